@@ -58,7 +58,9 @@ fn main() {
 
     println!("Before:\n\n{}", query_graph.explain());
     let mut opt_context = OptimizerContext::new();
-    optimizer.optimize(&mut opt_context, &mut query_graph);
+    if let Err(err) = optimizer.optimize(&mut opt_context, &mut query_graph) {
+        println!("Warning: {}", err);
+    }
     println!("After:\n\n{}", query_graph.explain());
 
     query_graph.garbage_collect();
@@ -0,0 +1,351 @@
+//! This module contains the system catalog, ie. the metadata about the tables
+//! the queries may refer to.
+use std::collections::HashMap;
+
+use crate::{
+    data_type::DataType,
+    scalar_expr::{visitor::collect_input_dependencies, ScalarExprRef},
+    value::Value,
+};
+
+/// Metadata about a column of a table.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ColumnMetadata {
+    pub name: String,
+    pub data_type: DataType,
+    pub nullable: bool,
+}
+
+impl ColumnMetadata {
+    pub fn new(name: &str, data_type: DataType, nullable: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            data_type,
+            nullable,
+        }
+    }
+}
+
+/// A foreign key from a set of columns of a table to a primary or unique key
+/// of another table (or the same one).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ForeignKey {
+    /// The referencing columns.
+    pub columns: Vec<usize>,
+    pub referenced_table_id: usize,
+    /// The referenced columns, in the same order as the referencing ones.
+    pub referenced_columns: Vec<usize>,
+}
+
+/// Equi-depth histogram over the non-null values of a column that are not among its
+/// most common values, ie. all the buckets contain the same number of rows.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Histogram {
+    /// The boundaries of the buckets in ascending order: the i-th bucket contains the
+    /// values between the i-th and the (i+1)-th boundaries.
+    pub bounds: Vec<Value>,
+}
+
+impl Histogram {
+    /// Builds a histogram with the given bucket boundaries, that must be sorted.
+    pub fn new(bounds: Vec<Value>) -> Self {
+        assert!(bounds.len() >= 2, "a histogram needs at least one bucket");
+        assert!(
+            bounds.windows(2).all(|pair| pair[0] <= pair[1]),
+            "unsorted histogram bounds"
+        );
+        Self { bounds }
+    }
+
+    pub fn num_buckets(&self) -> usize {
+        self.bounds.len() - 1
+    }
+}
+
+/// Statistics about the values stored in a column of a table.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ColumnStatistics {
+    /// Number of distinct non-null values in the column.
+    pub distinct_values: usize,
+    /// Fraction of the rows of the table where the column is NULL.
+    pub null_fraction: f64,
+    /// The minimum and the maximum non-null values in the column, if known.
+    pub min_value: Option<Value>,
+    pub max_value: Option<Value>,
+    /// The most frequent values in the column together with the fraction of the rows
+    /// of the table containing them.
+    pub most_common_values: Vec<(Value, f64)>,
+    /// The distribution of the rest of the non-null values, if known.
+    pub histogram: Option<Histogram>,
+}
+
+impl ColumnStatistics {
+    pub fn new(distinct_values: usize, null_fraction: f64) -> Self {
+        Self {
+            distinct_values,
+            null_fraction,
+            min_value: None,
+            max_value: None,
+            most_common_values: Vec::new(),
+            histogram: None,
+        }
+    }
+
+    pub fn with_range(mut self, min_value: Value, max_value: Value) -> Self {
+        self.min_value = Some(min_value);
+        self.max_value = Some(max_value);
+        self
+    }
+
+    pub fn with_most_common_values(mut self, most_common_values: Vec<(Value, f64)>) -> Self {
+        self.most_common_values = most_common_values;
+        self
+    }
+
+    pub fn with_histogram(mut self, histogram: Histogram) -> Self {
+        self.histogram = Some(histogram);
+        self
+    }
+}
+
+/// Statistics about the contents of a table, used for estimating the number of
+/// rows produced by the queries over it.
+#[derive(Clone, PartialEq, Debug)]
+pub struct TableStatistics {
+    pub row_count: usize,
+    /// The statistics of each column of the table, in the same order as the columns.
+    pub column_statistics: Vec<ColumnStatistics>,
+}
+
+/// Metadata about a table registered in the catalog.
+#[derive(Clone, PartialEq, Debug)]
+pub struct TableMetadata {
+    /// The ID used to refer to this table in `QueryNode::TableScan`.
+    pub id: usize,
+    pub name: String,
+    pub columns: Vec<ColumnMetadata>,
+    pub primary_key: Option<Vec<usize>>,
+    pub unique_keys: Vec<Vec<usize>>,
+    pub foreign_keys: Vec<ForeignKey>,
+    /// Boolean expressions, referencing the columns of the table via input refs,
+    /// that all the rows in the table satisfy.
+    pub check_constraints: Vec<ScalarExprRef>,
+    /// The minimum and the maximum number of rows the table is known to contain,
+    /// if any.
+    pub row_count_bounds: (usize, Option<usize>),
+    pub statistics: Option<TableStatistics>,
+}
+
+impl TableMetadata {
+    /// Returns the position of the column with the given name, if any.
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|column| column.name == name)
+    }
+
+    /// The types of the columns of the table.
+    pub fn row_type(&self) -> Vec<DataType> {
+        self.columns
+            .iter()
+            .map(|column| column.data_type.clone())
+            .collect()
+    }
+
+    /// All the unique keys of the table, starting with the primary key if any.
+    pub fn keys(&self) -> impl Iterator<Item = &Vec<usize>> {
+        self.primary_key.iter().chain(self.unique_keys.iter())
+    }
+
+    /// Whether the given columns form the primary key or one of the unique keys
+    /// of the table, in any order.
+    pub fn is_key(&self, columns: &[usize]) -> bool {
+        self.keys().any(|key| {
+            key.len() == columns.len() && key.iter().all(|column| columns.contains(column))
+        })
+    }
+
+    fn check_columns(&self, columns: &[usize]) {
+        assert!(!columns.is_empty(), "empty column list");
+        for column in columns.iter() {
+            assert!(
+                *column < self.columns.len(),
+                "column {} out of range for table {}",
+                column,
+                self.name
+            );
+        }
+    }
+}
+
+/// Collection of tables that can be referenced by name.
+#[derive(Clone, Default, Debug)]
+pub struct Catalog {
+    tables: HashMap<usize, TableMetadata>,
+    tables_by_name: HashMap<String, usize>,
+    next_table_id: usize,
+}
+
+impl Catalog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a new table with the given columns, returning the ID assigned to it.
+    ///
+    /// Panics if a table with the same name already exists.
+    pub fn add_table(&mut self, name: &str, columns: Vec<ColumnMetadata>) -> usize {
+        assert!(
+            !self.tables_by_name.contains_key(name),
+            "table {} already exists",
+            name
+        );
+        let id = self.next_table_id;
+        self.next_table_id += 1;
+        self.tables.insert(
+            id,
+            TableMetadata {
+                id,
+                name: name.to_string(),
+                columns,
+                primary_key: None,
+                unique_keys: Vec::new(),
+                foreign_keys: Vec::new(),
+                check_constraints: Vec::new(),
+                row_count_bounds: (0, None),
+                statistics: None,
+            },
+        );
+        self.tables_by_name.insert(name.to_string(), id);
+        id
+    }
+
+    /// Sets the primary key of the given table. The columns in the primary key
+    /// become non-nullable.
+    pub fn set_primary_key(&mut self, table_id: usize, columns: Vec<usize>) {
+        let table = self.table_mut(table_id);
+        table.check_columns(&columns);
+        assert!(
+            table.primary_key.is_none(),
+            "table {} already has a primary key",
+            table.name
+        );
+        for column in columns.iter() {
+            table.columns[*column].nullable = false;
+        }
+        table.primary_key = Some(columns);
+    }
+
+    pub fn add_unique_key(&mut self, table_id: usize, columns: Vec<usize>) {
+        let table = self.table_mut(table_id);
+        table.check_columns(&columns);
+        table.unique_keys.push(columns);
+    }
+
+    /// Adds a foreign key to the given table. The referenced columns must be
+    /// either the primary key or a unique key of the referenced table, and their
+    /// types must match the ones of the referencing columns.
+    pub fn add_foreign_key(
+        &mut self,
+        table_id: usize,
+        columns: Vec<usize>,
+        referenced_table_id: usize,
+        referenced_columns: Vec<usize>,
+    ) {
+        let table = self.table(table_id).expect("table not found");
+        let referenced_table = self
+            .table(referenced_table_id)
+            .expect("referenced table not found");
+        table.check_columns(&columns);
+        referenced_table.check_columns(&referenced_columns);
+        assert_eq!(columns.len(), referenced_columns.len());
+        assert!(
+            referenced_table.is_key(&referenced_columns),
+            "the referenced columns must be a key of table {}",
+            referenced_table.name
+        );
+        for (column, referenced_column) in columns.iter().zip(referenced_columns.iter()) {
+            assert_eq!(
+                table.columns[*column].data_type,
+                referenced_table.columns[*referenced_column].data_type,
+            );
+        }
+        self.table_mut(table_id).foreign_keys.push(ForeignKey {
+            columns,
+            referenced_table_id,
+            referenced_columns,
+        });
+    }
+
+    /// Adds a CHECK constraint to the given table. The constraint must only
+    /// reference columns of the table.
+    pub fn add_check_constraint(&mut self, table_id: usize, constraint: ScalarExprRef) {
+        let table = self.table_mut(table_id);
+        for column in collect_input_dependencies(&constraint) {
+            assert!(
+                column < table.columns.len(),
+                "column {} out of range for table {}",
+                column,
+                table.name
+            );
+        }
+        table.check_constraints.push(constraint);
+    }
+
+    /// Declares the minimum and the maximum number of rows the given table contains.
+    pub fn set_row_count_bounds(
+        &mut self,
+        table_id: usize,
+        lower_bound: usize,
+        upper_bound: Option<usize>,
+    ) {
+        if let Some(upper_bound) = upper_bound {
+            assert!(lower_bound <= upper_bound);
+        }
+        self.table_mut(table_id).row_count_bounds = (lower_bound, upper_bound);
+    }
+
+    /// Sets the statistics of the given table, replacing the previous ones if any.
+    /// The statistics must contain an entry for every column of the table.
+    pub fn set_table_statistics(&mut self, table_id: usize, statistics: TableStatistics) {
+        let table = self.table_mut(table_id);
+        assert_eq!(
+            statistics.column_statistics.len(),
+            table.columns.len(),
+            "column statistics mismatch for table {}",
+            table.name
+        );
+        for column_statistics in statistics.column_statistics.iter() {
+            assert!((0.0..=1.0).contains(&column_statistics.null_fraction));
+            assert!(column_statistics.distinct_values <= statistics.row_count);
+            assert!(
+                column_statistics.most_common_values.len() <= column_statistics.distinct_values
+            );
+            let most_common_fraction = column_statistics
+                .most_common_values
+                .iter()
+                .map(|(_, frequency)| *frequency)
+                .sum::<f64>();
+            assert!(
+                most_common_fraction <= 1.0 - column_statistics.null_fraction + f64::EPSILON,
+                "the most common values exceed the non-null rows of table {}",
+                table.name
+            );
+        }
+        table.statistics = Some(statistics);
+    }
+
+    /// Returns the metadata of the table with the given ID, if any.
+    pub fn table(&self, table_id: usize) -> Option<&TableMetadata> {
+        self.tables.get(&table_id)
+    }
+
+    /// Returns the metadata of the table with the given name, if any.
+    pub fn table_by_name(&self, name: &str) -> Option<&TableMetadata> {
+        self.tables_by_name
+            .get(name)
+            .and_then(|table_id| self.tables.get(table_id))
+    }
+
+    fn table_mut(&mut self, table_id: usize) -> &mut TableMetadata {
+        self.tables.get_mut(&table_id).expect("table not found")
+    }
+}
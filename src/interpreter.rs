@@ -0,0 +1,1160 @@
+//! This module contains a reference interpreter that evaluates query graphs over
+//! in-memory tables. It doesn't aim to be efficient, but to provide the ground-truth
+//! semantics of the plan representation, so that the results of a plan can be
+//! compared with the ones of its optimized version.
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fmt,
+    rc::Rc,
+};
+
+use itertools::Itertools;
+
+use crate::{
+    query_graph::{
+        properties::{num_columns, partitioned_ordering, row_type},
+        ApplyType, JoinType, NodeId, QueryGraph, QueryNode, SortKey, WindowFrame, WindowFrameBound,
+        WindowFrameUnits,
+    },
+    scalar_expr::{
+        AggregateExpr, AggregateOp, BinaryOp, NaryOp, ScalarExpr, ScalarExprRef,
+        ScalarSubqueryCmpOp, Subquery, UnaryOp, WindowExpr, WindowFunction,
+    },
+    value::Value,
+    visitor_utils::PreOrderVisitationResult,
+};
+
+pub type Row = Vec<Value>;
+
+/// The contents of the tables scanned by the queries, by table ID.
+#[derive(Clone, Default, Debug)]
+pub struct Database {
+    tables: HashMap<usize, Vec<Row>>,
+}
+
+impl Database {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the rows of the given table, replacing its previous contents.
+    pub fn insert_table(&mut self, table_id: usize, rows: Vec<Row>) {
+        self.tables.insert(table_id, rows);
+    }
+
+    pub fn table(&self, table_id: usize) -> Option<&Vec<Row>> {
+        self.tables.get(&table_id)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ExecutionError {
+    /// The plan scans a table not present in the database.
+    UnknownTable(usize),
+    /// The rows of a table in the database don't match the number of columns of a
+    /// scan over it: table ID, columns of the scan and columns of the row.
+    RowWidthMismatch(usize, usize, usize),
+    /// A scalar subquery returned more than one row.
+    ScalarSubqueryMultipleRows(NodeId),
+    /// A comparison between values of incompatible types.
+    IncomparableValues(Value, Value),
+    /// A boolean expression evaluated to a non-boolean value.
+    NonBooleanValue(Value),
+    /// A numeric operation applied to a non-numeric value.
+    NonNumericValue(Value),
+    /// A recursive reference evaluated outside of the recursive input of a recursive
+    /// union with the same ID.
+    UnboundRecursiveReference(usize),
+    /// A division or modulo operation with a zero divisor.
+    DivisionByZero,
+}
+
+impl fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExecutionError::UnknownTable(table_id) => write!(f, "unknown table {}", table_id),
+            ExecutionError::RowWidthMismatch(table_id, num_columns, row_width) => write!(
+                f,
+                "table {} scanned with {} columns contains a row with {} columns",
+                table_id, num_columns, row_width
+            ),
+            ExecutionError::ScalarSubqueryMultipleRows(subquery_root) => write!(
+                f,
+                "subquery {} returned more than one row in scalar context",
+                subquery_root
+            ),
+            ExecutionError::IncomparableValues(left, right) => {
+                write!(f, "cannot compare {:?} with {:?}", left, right)
+            }
+            ExecutionError::NonBooleanValue(value) => {
+                write!(f, "expected boolean value, found {:?}", value)
+            }
+            ExecutionError::NonNumericValue(value) => {
+                write!(f, "expected numeric value, found {:?}", value)
+            }
+            ExecutionError::UnboundRecursiveReference(recursion_id) => {
+                write!(f, "recursive reference {} not in scope", recursion_id)
+            }
+            ExecutionError::DivisionByZero => write!(f, "division by zero"),
+        }
+    }
+}
+
+/// Executes the plan hanging from the root node of the given query graph over the
+/// given database, returning the rows it produces.
+pub fn execute(query_graph: &QueryGraph, database: &Database) -> Result<Vec<Row>, ExecutionError> {
+    let mut interpreter = Interpreter {
+        query_graph,
+        database,
+        results: HashMap::new(),
+        recursive_tables: HashMap::new(),
+    };
+    interpreter
+        .execute_node(QueryGraph::ROOT_NODE_ID, &[])
+        .map(|rows| rows.as_ref().clone())
+}
+
+/// The values of the parameters of the correlation contexts in scope, with the
+/// innermost one last.
+type CorrelationScope = [Row];
+
+struct Interpreter<'a> {
+    query_graph: &'a QueryGraph,
+    database: &'a Database,
+    /// The rows produced by the nodes already executed under a given correlation
+    /// scope.
+    results: HashMap<(NodeId, Vec<Row>), Rc<Vec<Row>>>,
+    /// The rows produced by the previous iteration of the recursive unions being
+    /// executed, by recursion ID.
+    recursive_tables: HashMap<usize, Rc<Vec<Row>>>,
+}
+
+impl Interpreter<'_> {
+    fn execute_node(
+        &mut self,
+        node_id: NodeId,
+        scope: &CorrelationScope,
+    ) -> Result<Rc<Vec<Row>>, ExecutionError> {
+        let cache_key = (node_id, scope.to_vec());
+        if let Some(rows) = self.results.get(&cache_key) {
+            return Ok(rows.clone());
+        }
+        let rows = Rc::new(self.compute_node(node_id, scope)?);
+        self.results.insert(cache_key, rows.clone());
+        Ok(rows)
+    }
+
+    fn compute_node(
+        &mut self,
+        node_id: NodeId,
+        scope: &CorrelationScope,
+    ) -> Result<Vec<Row>, ExecutionError> {
+        let rows = match self.query_graph.node(node_id) {
+            QueryNode::QueryRoot { input } => match input {
+                Some(input) => self.execute_node(*input, scope)?.to_vec(),
+                None => Vec::new(),
+            },
+            QueryNode::SubqueryRoot { input } => self.execute_node(*input, scope)?.to_vec(),
+            QueryNode::TableScan { table_id, row_type } => {
+                let rows = self
+                    .database
+                    .table(*table_id)
+                    .ok_or(ExecutionError::UnknownTable(*table_id))?;
+                if let Some(row) = rows.iter().find(|row| row.len() != row_type.len()) {
+                    return Err(ExecutionError::RowWidthMismatch(
+                        *table_id,
+                        row_type.len(),
+                        row.len(),
+                    ));
+                }
+                rows.clone()
+            }
+            QueryNode::Values { rows, .. } => rows
+                .iter()
+                .map(|row| row.iter().map(|literal| literal.value.clone()).collect())
+                .collect(),
+            QueryNode::Project { outputs, input } => {
+                let input_rows = self.execute_node(*input, scope)?;
+                let mut rows = Vec::new();
+                for input_row in input_rows.iter() {
+                    rows.push(
+                        outputs
+                            .iter()
+                            .map(|expr| self.eval_expr(expr, input_row, scope))
+                            .collect::<Result<Vec<_>, _>>()?,
+                    );
+                }
+                rows
+            }
+            QueryNode::Filter { conditions, input } => {
+                let input_rows = self.execute_node(*input, scope)?;
+                let mut rows = Vec::new();
+                for input_row in input_rows.iter() {
+                    if self.eval_conditions(conditions, input_row, scope)? {
+                        rows.push(input_row.clone());
+                    }
+                }
+                rows
+            }
+            QueryNode::Join {
+                join_type,
+                conditions,
+                left,
+                right,
+            } => {
+                let left_rows = self.execute_node(*left, scope)?;
+                let right_rows = self.execute_node(*right, scope)?;
+                let left_nulls = vec![Value::Null; num_columns(self.query_graph, *left)];
+                let right_nulls = vec![Value::Null; num_columns(self.query_graph, *right)];
+                let mut right_matched = vec![false; right_rows.len()];
+                let mut rows = Vec::new();
+                for left_row in left_rows.iter() {
+                    let mut left_matched = false;
+                    for (right_idx, right_row) in right_rows.iter().enumerate() {
+                        let join_row = concat_rows(left_row, right_row);
+                        if self.eval_conditions(conditions, &join_row, scope)? {
+                            left_matched = true;
+                            right_matched[right_idx] = true;
+                            if join_type.projects_columns_from_right() {
+                                rows.push(join_row);
+                            }
+                        }
+                    }
+                    match join_type {
+                        JoinType::LeftOuter | JoinType::FullOuter if !left_matched => {
+                            rows.push(concat_rows(left_row, &right_nulls))
+                        }
+                        JoinType::Semi if left_matched => rows.push(left_row.clone()),
+                        JoinType::Anti if !left_matched => rows.push(left_row.clone()),
+                        _ => {}
+                    }
+                }
+                if let JoinType::RightOuter | JoinType::FullOuter = join_type {
+                    for (right_row, matched) in right_rows.iter().zip(right_matched.iter()) {
+                        if !matched {
+                            rows.push(concat_rows(&left_nulls, right_row));
+                        }
+                    }
+                }
+                rows
+            }
+            QueryNode::Aggregate {
+                group_key,
+                aggregates,
+                input,
+            } => {
+                let input_rows = self.execute_node(*input, scope)?;
+                // Groups in order of appearance
+                let mut groups: Vec<(Row, Vec<&Row>)> = Vec::new();
+                let mut group_index: HashMap<Row, usize> = HashMap::new();
+                for input_row in input_rows.iter() {
+                    let key = group_key
+                        .iter()
+                        .map(|column| input_row[*column].clone())
+                        .collect_vec();
+                    let idx = *group_index.entry(key.clone()).or_insert_with(|| {
+                        groups.push((key, Vec::new()));
+                        groups.len() - 1
+                    });
+                    groups[idx].1.push(input_row);
+                }
+                if groups.is_empty() && group_key.is_empty() {
+                    // A global aggregation returns a row even if its input is empty
+                    let input_row_type = row_type(self.query_graph, *input);
+                    vec![aggregates
+                        .iter()
+                        .map(|aggregate| aggregate.on_empty_group(&input_row_type).value)
+                        .collect()]
+                } else {
+                    let mut rows = Vec::new();
+                    for (key, group_rows) in groups.into_iter() {
+                        let mut row = key;
+                        for aggregate in aggregates.iter() {
+                            row.push(eval_aggregate(aggregate, &group_rows)?);
+                        }
+                        rows.push(row);
+                    }
+                    rows
+                }
+            }
+            QueryNode::Union { inputs } => {
+                let mut rows = Vec::new();
+                for input in inputs.iter() {
+                    rows.extend(self.execute_node(*input, scope)?.iter().cloned());
+                }
+                rows
+            }
+            QueryNode::RecursiveUnion {
+                recursion_id,
+                distinct,
+                max_iterations,
+                base,
+                recursive,
+            } => self.execute_recursive_union(
+                *recursion_id,
+                *distinct,
+                *max_iterations,
+                *base,
+                *recursive,
+                scope,
+            )?,
+            QueryNode::RecursiveReference { recursion_id, .. } => self
+                .recursive_tables
+                .get(recursion_id)
+                .ok_or(ExecutionError::UnboundRecursiveReference(*recursion_id))?
+                .to_vec(),
+            QueryNode::Intersect {
+                distinct,
+                left,
+                right,
+            } => {
+                let left_rows = self.execute_node(*left, scope)?;
+                let mut right_counts = row_counts(&self.execute_node(*right, scope)?);
+                let mut rows = Vec::new();
+                for row in left_rows.iter() {
+                    if let Some(count) = right_counts.get_mut(row) {
+                        if *count > 0 {
+                            // Each distinct row is returned once by draining its counter.
+                            *count = if *distinct { 0 } else { *count - 1 };
+                            rows.push(row.clone());
+                        }
+                    }
+                }
+                rows
+            }
+            QueryNode::Except {
+                distinct,
+                left,
+                right,
+            } => {
+                let left_rows = self.execute_node(*left, scope)?;
+                let mut right_counts = row_counts(&self.execute_node(*right, scope)?);
+                let mut rows = Vec::new();
+                for row in left_rows.iter() {
+                    match right_counts.get_mut(row) {
+                        Some(count) if *count > 0 => {
+                            if !*distinct {
+                                *count -= 1;
+                            }
+                        }
+                        _ => {
+                            if *distinct {
+                                // Subsequent occurrences of the row are skipped.
+                                right_counts.insert(row.clone(), 1);
+                            }
+                            rows.push(row.clone());
+                        }
+                    }
+                }
+                rows
+            }
+            QueryNode::Apply {
+                correlation,
+                left,
+                right,
+                apply_type,
+            } => {
+                let left_rows = self.execute_node(*left, scope)?;
+                let right_nulls = vec![Value::Null; num_columns(self.query_graph, *right)];
+                let mut rows = Vec::new();
+                for left_row in left_rows.iter() {
+                    let inner_scope =
+                        self.push_correlation_scope(&correlation.parameters, left_row, scope)?;
+                    let right_rows = self.execute_node(*right, &inner_scope)?;
+                    for right_row in right_rows.iter() {
+                        rows.push(concat_rows(left_row, right_row));
+                    }
+                    if right_rows.is_empty() && *apply_type == ApplyType::LeftOuter {
+                        rows.push(concat_rows(left_row, &right_nulls));
+                    }
+                }
+                rows
+            }
+            QueryNode::Sort { keys, input } => {
+                let mut rows = self.execute_node(*input, scope)?.to_vec();
+                sort_rows(&mut rows, keys);
+                rows
+            }
+            QueryNode::Limit {
+                offset,
+                limit,
+                input,
+            } => {
+                let input_rows = self.execute_node(*input, scope)?;
+                input_rows
+                    .iter()
+                    .skip(*offset)
+                    .take(limit.unwrap_or(usize::MAX))
+                    .cloned()
+                    .collect()
+            }
+            QueryNode::TopN {
+                partition_key,
+                keys,
+                offset,
+                limit,
+                input,
+            } => {
+                let mut rows = self.execute_node(*input, scope)?.to_vec();
+                sort_rows(&mut rows, &partitioned_ordering(partition_key, keys));
+                rows.chunk_by(|l, r| same_values(l, r, partition_key.iter()))
+                    .flat_map(|partition| partition.iter().skip(*offset).take(*limit).cloned())
+                    .collect()
+            }
+            QueryNode::Window {
+                partition_key,
+                order_key,
+                frame,
+                functions,
+                input,
+            } => {
+                let mut rows = self.execute_node(*input, scope)?.to_vec();
+                sort_rows(&mut rows, &partitioned_ordering(partition_key, order_key));
+                let mut result = Vec::with_capacity(rows.len());
+                for partition in rows.chunk_by(|l, r| same_values(l, r, partition_key.iter())) {
+                    // The first and last row of the peer group of each row, and the
+                    // index of the peer group within the partition.
+                    let mut peers = Vec::with_capacity(partition.len());
+                    let mut first = 0;
+                    for (group_idx, group) in partition
+                        .chunk_by(|l, r| same_values(l, r, order_key.iter().map(|key| &key.column)))
+                        .enumerate()
+                    {
+                        peers.extend(std::iter::repeat_n(
+                            (first, first + group.len() - 1, group_idx),
+                            group.len(),
+                        ));
+                        first += group.len();
+                    }
+                    for (current, row) in partition.iter().enumerate() {
+                        let mut output = row.clone();
+                        for function in functions.iter() {
+                            output.push(eval_window_function(
+                                function,
+                                frame,
+                                partition,
+                                current,
+                                peers[current],
+                            )?);
+                        }
+                        result.push(output);
+                    }
+                }
+                result
+            }
+        };
+        Ok(rows)
+    }
+
+    /// Evaluates the base input of a recursive union and then its recursive input over
+    /// the rows produced by the previous iteration until a fixpoint is reached.
+    fn execute_recursive_union(
+        &mut self,
+        recursion_id: usize,
+        distinct: bool,
+        max_iterations: Option<usize>,
+        base: NodeId,
+        recursive: NodeId,
+        scope: &CorrelationScope,
+    ) -> Result<Vec<Row>, ExecutionError> {
+        // The results of the nodes under the recursive input depend on the iteration.
+        let mut recursive_nodes = HashSet::new();
+        self.query_graph.visit_subgraph_pre(
+            &mut |_, node_id| {
+                if recursive_nodes.insert(node_id) {
+                    PreOrderVisitationResult::VisitInputs
+                } else {
+                    PreOrderVisitationResult::DoNotVisitInputs
+                }
+            },
+            recursive,
+        );
+        let mut seen_rows = HashSet::new();
+        let mut new_rows = self
+            .execute_node(base, scope)?
+            .iter()
+            .filter(|row| !distinct || seen_rows.insert((*row).clone()))
+            .cloned()
+            .collect_vec();
+        let mut rows = new_rows.clone();
+        // An enclosing recursive union with the same ID is shadowed by this one.
+        let outer_table = self.recursive_tables.remove(&recursion_id);
+        let mut iteration = 0;
+        while !new_rows.is_empty() && max_iterations.is_none_or(|max| iteration < max) {
+            self.recursive_tables
+                .insert(recursion_id, Rc::new(new_rows));
+            self.results
+                .retain(|(node_id, _), _| !recursive_nodes.contains(node_id));
+            new_rows = self
+                .execute_node(recursive, scope)?
+                .iter()
+                .filter(|row| !distinct || seen_rows.insert((*row).clone()))
+                .cloned()
+                .collect_vec();
+            rows.extend(new_rows.iter().cloned());
+            iteration += 1;
+        }
+        self.results
+            .retain(|(node_id, _), _| !recursive_nodes.contains(node_id));
+        self.recursive_tables.remove(&recursion_id);
+        if let Some(outer_table) = outer_table {
+            self.recursive_tables.insert(recursion_id, outer_table);
+        }
+        Ok(rows)
+    }
+
+    /// Evaluates the given parameters over the given row, returning the scope the
+    /// correlated subgraph must be evaluated in.
+    fn push_correlation_scope(
+        &mut self,
+        parameters: &[ScalarExprRef],
+        row: &Row,
+        scope: &CorrelationScope,
+    ) -> Result<Vec<Row>, ExecutionError> {
+        let parameter_values = parameters
+            .iter()
+            .map(|parameter| self.eval_expr(parameter, row, scope))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(scope
+            .iter()
+            .cloned()
+            .chain(std::iter::once(parameter_values))
+            .collect())
+    }
+
+    fn execute_subquery(
+        &mut self,
+        subquery: &Subquery<ScalarExpr>,
+        row: &Row,
+        scope: &CorrelationScope,
+    ) -> Result<Rc<Vec<Row>>, ExecutionError> {
+        match &subquery.correlation {
+            Some(correlation) => {
+                let inner_scope =
+                    self.push_correlation_scope(&correlation.parameters, row, scope)?;
+                self.execute_node(subquery.root, &inner_scope)
+            }
+            None => self.execute_node(subquery.root, scope),
+        }
+    }
+
+    /// Whether all the given conditions evaluate to TRUE.
+    fn eval_conditions(
+        &mut self,
+        conditions: &[ScalarExprRef],
+        row: &Row,
+        scope: &CorrelationScope,
+    ) -> Result<bool, ExecutionError> {
+        for condition in conditions.iter() {
+            if as_bool(self.eval_expr(condition, row, scope)?)? != Some(true) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn eval_expr(
+        &mut self,
+        expr: &ScalarExprRef,
+        row: &Row,
+        scope: &CorrelationScope,
+    ) -> Result<Value, ExecutionError> {
+        let value = match expr.as_ref() {
+            ScalarExpr::Literal(literal) => literal.value.clone(),
+            ScalarExpr::InputRef { index } => row[*index].clone(),
+            ScalarExpr::UnaryOp { op, operand } => {
+                let operand = self.eval_expr(operand, row, scope)?;
+                eval_unary_op(op, operand)?
+            }
+            ScalarExpr::BinaryOp { op, left, right } => {
+                let left = self.eval_expr(left, row, scope)?;
+                let right = self.eval_expr(right, row, scope)?;
+                eval_binary_op(op, &left, &right)?
+            }
+            ScalarExpr::NaryOp { op, operands } => {
+                let operands = operands
+                    .iter()
+                    .map(|operand| self.eval_expr(operand, row, scope))
+                    .collect::<Result<Vec<_>, _>>()?;
+                eval_nary_op(op, operands)?
+            }
+            ScalarExpr::ScalarSubquery { subquery } => {
+                let rows = self.execute_subquery(subquery, row, scope)?;
+                match rows.len() {
+                    0 => Value::Null,
+                    1 => rows[0][0].clone(),
+                    _ => return Err(ExecutionError::ScalarSubqueryMultipleRows(subquery.root)),
+                }
+            }
+            ScalarExpr::ExistsSubquery { subquery } => {
+                Value::Bool(!self.execute_subquery(subquery, row, scope)?.is_empty())
+            }
+            ScalarExpr::ScalarSubqueryCmp {
+                op,
+                scalar_operand,
+                subquery,
+            } => {
+                let scalar_value = self.eval_expr(scalar_operand, row, scope)?;
+                let rows = self.execute_subquery(subquery, row, scope)?;
+                let (cmp_op, is_any) = match op {
+                    ScalarSubqueryCmpOp::EqAny => (BinaryOp::Eq, true),
+                    ScalarSubqueryCmpOp::LtAny => (BinaryOp::Lt, true),
+                    ScalarSubqueryCmpOp::LteAny => (BinaryOp::Le, true),
+                    ScalarSubqueryCmpOp::GtAny => (BinaryOp::Gt, true),
+                    ScalarSubqueryCmpOp::GteAny => (BinaryOp::Ge, true),
+                    ScalarSubqueryCmpOp::EqAll => (BinaryOp::Eq, false),
+                    ScalarSubqueryCmpOp::LtAll => (BinaryOp::Lt, false),
+                    ScalarSubqueryCmpOp::LteAll => (BinaryOp::Le, false),
+                    ScalarSubqueryCmpOp::GtAll => (BinaryOp::Gt, false),
+                    ScalarSubqueryCmpOp::GteAll => (BinaryOp::Ge, false),
+                };
+                // ANY is a disjunction of comparisons and ALL a conjunction of them
+                let comparisons = rows
+                    .iter()
+                    .map(|subquery_row| eval_binary_op(&cmp_op, &scalar_value, &subquery_row[0]))
+                    .collect::<Result<Vec<_>, _>>()?;
+                eval_nary_op(if is_any { &NaryOp::Or } else { &NaryOp::And }, comparisons)?
+            }
+            ScalarExpr::CorrelatedInputRef {
+                context_offset,
+                index,
+                ..
+            } => scope[scope.len() - 1 - context_offset][*index].clone(),
+        };
+        Ok(value)
+    }
+}
+
+fn concat_rows(left: &Row, right: &Row) -> Row {
+    left.iter().chain(right.iter()).cloned().collect()
+}
+
+/// Converts a value of a boolean expression into an optional boolean, with None
+/// standing for NULL.
+fn as_bool(value: Value) -> Result<Option<bool>, ExecutionError> {
+    match value {
+        Value::Bool(value) => Ok(Some(value)),
+        Value::Null => Ok(None),
+        _ => Err(ExecutionError::NonBooleanValue(value)),
+    }
+}
+
+/// Sorts the given rows by the given keys, keeping the relative order of the rows
+/// with equal keys. NULL values are sorted after any other value.
+fn sort_rows(rows: &mut [Row], keys: &[SortKey]) {
+    rows.sort_by(|left, right| {
+        for key in keys.iter() {
+            let (l, r) = (&left[key.column], &right[key.column]);
+            let ordering = match (l.is_null(), r.is_null()) {
+                (false, false) => compare_values(l, r).unwrap_or_else(|_| l.cmp(r)),
+                (l_null, r_null) => l_null.cmp(&r_null),
+            };
+            let ordering = if key.ascending {
+                ordering
+            } else {
+                ordering.reverse()
+            };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        Ordering::Equal
+    });
+}
+
+/// Compares two non-null values, treating all integer types as comparable.
+fn compare_values(left: &Value, right: &Value) -> Result<Ordering, ExecutionError> {
+    match (left, right) {
+        (Value::Int(l), Value::BigInt(r)) => Ok(i64::from(*l).cmp(r)),
+        (Value::BigInt(l), Value::Int(r)) => Ok(l.cmp(&i64::from(*r))),
+        (Value::Bool(_), Value::Bool(_))
+        | (Value::Int(_), Value::Int(_))
+        | (Value::BigInt(_), Value::BigInt(_))
+        | (Value::String(_), Value::String(_))
+        | (Value::List(_), Value::List(_)) => Ok(left.cmp(right)),
+        _ => Err(ExecutionError::IncomparableValues(
+            left.clone(),
+            right.clone(),
+        )),
+    }
+}
+
+/// Converts a non-null integer value into an i64.
+fn as_bigint(value: &Value) -> Result<i64, ExecutionError> {
+    match value {
+        Value::Int(value) => Ok(i64::from(*value)),
+        Value::BigInt(value) => Ok(*value),
+        _ => Err(ExecutionError::NonNumericValue(value.clone())),
+    }
+}
+
+fn eval_unary_op(op: &UnaryOp, operand: Value) -> Result<Value, ExecutionError> {
+    Ok(match op {
+        UnaryOp::Not => as_bool(operand)?.map_or(Value::Null, |value| Value::Bool(!value)),
+        UnaryOp::Neg => match operand {
+            Value::Null => Value::Null,
+            Value::Int(value) => Value::Int(value.wrapping_neg()),
+            Value::BigInt(value) => Value::BigInt(value.wrapping_neg()),
+            _ => return Err(ExecutionError::NonNumericValue(operand)),
+        },
+        UnaryOp::IsNull => Value::Bool(operand.is_null()),
+        UnaryOp::IsNotNull => Value::Bool(!operand.is_null()),
+        UnaryOp::IsTrue => Value::Bool(as_bool(operand)? == Some(true)),
+    })
+}
+
+fn eval_binary_op(op: &BinaryOp, left: &Value, right: &Value) -> Result<Value, ExecutionError> {
+    if left.is_null() || right.is_null() {
+        return Ok(match op {
+            // IS NOT DISTINCT FROM never evaluates to NULL
+            BinaryOp::RawEq => Value::Bool(left.is_null() && right.is_null()),
+            BinaryOp::Eq
+            | BinaryOp::Ge
+            | BinaryOp::Gt
+            | BinaryOp::Le
+            | BinaryOp::Lt
+            | BinaryOp::Ne
+            | BinaryOp::Add
+            | BinaryOp::Sub
+            | BinaryOp::Mul
+            | BinaryOp::Div
+            | BinaryOp::Mod => Value::Null,
+        });
+    }
+    if let BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod = op {
+        return eval_arithmetic_op(op, left, right);
+    }
+    let ordering = compare_values(left, right)?;
+    Ok(Value::Bool(match op {
+        BinaryOp::Eq | BinaryOp::RawEq => ordering == Ordering::Equal,
+        BinaryOp::Ge => ordering != Ordering::Less,
+        BinaryOp::Gt => ordering == Ordering::Greater,
+        BinaryOp::Le => ordering != Ordering::Greater,
+        BinaryOp::Lt => ordering == Ordering::Less,
+        BinaryOp::Ne => ordering != Ordering::Equal,
+        BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => {
+            unreachable!()
+        }
+    }))
+}
+
+/// Evaluates an arithmetic operation over non-null integer values. The result is
+/// an int only if both operands are ints. Overflows wrap around.
+fn eval_arithmetic_op(op: &BinaryOp, left: &Value, right: &Value) -> Result<Value, ExecutionError> {
+    let (l, r) = (as_bigint(left)?, as_bigint(right)?);
+    let result = match op {
+        BinaryOp::Add => l.wrapping_add(r),
+        BinaryOp::Sub => l.wrapping_sub(r),
+        BinaryOp::Mul => l.wrapping_mul(r),
+        BinaryOp::Div | BinaryOp::Mod if r == 0 => return Err(ExecutionError::DivisionByZero),
+        BinaryOp::Div => l.wrapping_div(r),
+        BinaryOp::Mod => l.wrapping_rem(r),
+        _ => unreachable!(),
+    };
+    Ok(match (left, right) {
+        // The i64 result truncated to 32 bits is the wrapped around i32 result.
+        (Value::Int(_), Value::Int(_)) => Value::Int(result as i32),
+        _ => Value::BigInt(result),
+    })
+}
+
+/// Evaluates an n-ary operation following SQL three-valued logic for the boolean
+/// connectives.
+fn eval_nary_op(op: &NaryOp, operands: Vec<Value>) -> Result<Value, ExecutionError> {
+    match op {
+        NaryOp::And | NaryOp::Or => {
+            // The value that determines the result of the operation regardless of the
+            // rest of the operands.
+            let absorbing_value = matches!(op, NaryOp::Or);
+            let mut found_null = false;
+            for operand in operands.into_iter() {
+                match as_bool(operand)? {
+                    Some(value) if value == absorbing_value => return Ok(Value::Bool(value)),
+                    Some(_) => {}
+                    None => found_null = true,
+                }
+            }
+            Ok(if found_null {
+                Value::Null
+            } else {
+                Value::Bool(!absorbing_value)
+            })
+        }
+        NaryOp::Concat => {
+            let mut result = String::new();
+            for operand in operands.iter() {
+                match operand {
+                    Value::Null => return Ok(Value::Null),
+                    Value::String(value) => result.push_str(value),
+                    Value::Int(value) => result.push_str(&value.to_string()),
+                    Value::BigInt(value) => result.push_str(&value.to_string()),
+                    Value::Bool(value) => result.push_str(&value.to_string()),
+                    _ => result.push_str(&format!("{:?}", operand)),
+                }
+            }
+            Ok(Value::String(result))
+        }
+    }
+}
+
+/// Evaluates an aggregate over a non-empty group of rows. NULL values are ignored.
+fn eval_aggregate(aggregate: &AggregateExpr, rows: &[&Row]) -> Result<Value, ExecutionError> {
+    let non_null_rows = rows
+        .iter()
+        .filter(|row| {
+            aggregate
+                .operands
+                .iter()
+                .all(|operand| !row[*operand].is_null())
+        })
+        .collect_vec();
+    match aggregate.op {
+        AggregateOp::Count => Ok(Value::BigInt(non_null_rows.len() as i64)),
+        AggregateOp::Min | AggregateOp::Max => {
+            let mut result: Option<&Value> = None;
+            for row in non_null_rows.iter() {
+                let value = &row[aggregate.operands[0]];
+                result = match result {
+                    Some(current) => {
+                        let ordering = compare_values(value, current)?;
+                        if (aggregate.op == AggregateOp::Min && ordering == Ordering::Less)
+                            || (aggregate.op == AggregateOp::Max && ordering == Ordering::Greater)
+                        {
+                            Some(value)
+                        } else {
+                            Some(current)
+                        }
+                    }
+                    None => Some(value),
+                };
+            }
+            Ok(result.cloned().unwrap_or(Value::Null))
+        }
+    }
+}
+
+/// Counts the number of occurrences of each distinct row.
+fn row_counts(rows: &[Row]) -> HashMap<Row, usize> {
+    let mut counts = HashMap::new();
+    for row in rows.iter() {
+        *counts.entry(row.clone()).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Whether the given rows contain the same values in the given columns.
+fn same_values<'a>(left: &Row, right: &Row, mut columns: impl Iterator<Item = &'a usize>) -> bool {
+    columns.all(|column| left[*column] == right[*column])
+}
+
+/// Evaluates the given window function for the row at position `current` within its
+/// partition, given the first and last row of its peer group and the index of the
+/// peer group.
+fn eval_window_function(
+    function: &WindowExpr,
+    frame: &WindowFrame,
+    partition: &[Row],
+    current: usize,
+    (first_peer, last_peer, peer_group): (usize, usize, usize),
+) -> Result<Value, ExecutionError> {
+    let aggregate_op = match function.function {
+        WindowFunction::RowNumber => return Ok(Value::BigInt(current as i64 + 1)),
+        WindowFunction::Rank => return Ok(Value::BigInt(first_peer as i64 + 1)),
+        WindowFunction::DenseRank => return Ok(Value::BigInt(peer_group as i64 + 1)),
+        WindowFunction::Lag { offset } => {
+            return Ok(current.checked_sub(offset).map_or(Value::Null, |row| {
+                partition[row][function.operands[0]].clone()
+            }))
+        }
+        WindowFunction::Lead { offset } => {
+            return Ok(partition
+                .get(current + offset)
+                .map_or(Value::Null, |row| row[function.operands[0]].clone()))
+        }
+        WindowFunction::Sum => None,
+        WindowFunction::Count => Some(AggregateOp::Count),
+        WindowFunction::Min => Some(AggregateOp::Min),
+        WindowFunction::Max => Some(AggregateOp::Max),
+    };
+    let position = |bound: &WindowFrameBound, is_end: bool| match bound {
+        WindowFrameBound::UnboundedPreceding => 0,
+        WindowFrameBound::Preceding(n) => (current + is_end as usize).saturating_sub(*n),
+        WindowFrameBound::CurrentRow => match (frame.units, is_end) {
+            (WindowFrameUnits::Rows, _) => current + is_end as usize,
+            (WindowFrameUnits::Range, false) => first_peer,
+            (WindowFrameUnits::Range, true) => last_peer + 1,
+        },
+        WindowFrameBound::Following(n) => current + n + is_end as usize,
+        WindowFrameBound::UnboundedFollowing => partition.len(),
+    };
+    let end = position(&frame.end, true).min(partition.len());
+    let start = position(&frame.start, false).min(end);
+    let frame_rows = partition[start..end].iter().collect_vec();
+    match aggregate_op {
+        Some(op) => eval_aggregate(
+            &AggregateExpr {
+                op,
+                operands: function.operands.clone(),
+            },
+            &frame_rows,
+        ),
+        None => {
+            let mut sum: Option<i64> = None;
+            for row in frame_rows.iter() {
+                let value = match &row[function.operands[0]] {
+                    Value::Null => continue,
+                    Value::Int(value) => i64::from(*value),
+                    Value::BigInt(value) => *value,
+                    other => return Err(ExecutionError::NonNumericValue(other.clone())),
+                };
+                sum = Some(sum.unwrap_or(0).wrapping_add(value));
+            }
+            Ok(sum.map_or(Value::Null, Value::BigInt))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        catalog::{Catalog, ColumnMetadata},
+        data_type::DataType,
+        query_graph::{CorrelationContext, QueryGraph},
+        sql::build_query_graph,
+    };
+
+    use super::*;
+
+    fn int(value: i32) -> Value {
+        Value::Int(value)
+    }
+
+    fn big_int(value: i64) -> Value {
+        Value::BigInt(value)
+    }
+
+    /// Tables t1(a, b) and t2(c, d), with integer nullable columns.
+    fn test_database() -> (Rc<Catalog>, Database) {
+        let mut catalog = Catalog::new();
+        let mut database = Database::new();
+        let t1 = catalog.add_table(
+            "t1",
+            vec![
+                ColumnMetadata::new("a", DataType::Int, true),
+                ColumnMetadata::new("b", DataType::Int, true),
+            ],
+        );
+        let t2 = catalog.add_table(
+            "t2",
+            vec![
+                ColumnMetadata::new("c", DataType::Int, true),
+                ColumnMetadata::new("d", DataType::Int, true),
+            ],
+        );
+        database.insert_table(
+            t1,
+            vec![
+                vec![int(1), int(10)],
+                vec![int(2), Value::Null],
+                vec![Value::Null, int(30)],
+            ],
+        );
+        database.insert_table(
+            t2,
+            vec![
+                vec![int(1), int(100)],
+                vec![int(1), int(200)],
+                vec![Value::Null, int(300)],
+            ],
+        );
+        (Rc::new(catalog), database)
+    }
+
+    fn run(sql: &str) -> Result<Vec<Row>, ExecutionError> {
+        let (catalog, database) = test_database();
+        let query_graph = build_query_graph(&catalog, sql).unwrap();
+        execute(&query_graph, &database)
+    }
+
+    #[test]
+    fn test_three_valued_logic() {
+        assert_eq!(
+            run("select a from t1 where a = b or b = 10").unwrap(),
+            vec![vec![int(1)]]
+        );
+        assert_eq!(
+            run("select c from t2 join t1 on c = a").unwrap(),
+            vec![vec![int(1)], vec![int(1)]]
+        );
+        assert_eq!(
+            run("select c from t2 join t1 on c is not distinct from a").unwrap(),
+            vec![vec![int(1)], vec![int(1)], vec![Value::Null]]
+        );
+    }
+
+    #[test]
+    fn test_operators() {
+        assert_eq!(
+            run("select a + b, b - a, -a, b / a, b % 3 from t1").unwrap(),
+            vec![
+                vec![int(11), int(9), int(-1), int(10), int(1)],
+                vec![Value::Null, Value::Null, int(-2), Value::Null, Value::Null],
+                vec![Value::Null, Value::Null, Value::Null, Value::Null, int(0)],
+            ]
+        );
+        assert_eq!(
+            run("select a from t1 where a <> 1 or b is null").unwrap(),
+            vec![vec![int(2)]]
+        );
+        // NOT NULL is NULL, so the row is filtered out
+        assert_eq!(
+            run("select b from t1 where not (a = 1)").unwrap(),
+            vec![vec![Value::Null]]
+        );
+        assert_eq!(
+            run("select a is null, b is not null from t1 where a is null").unwrap(),
+            vec![vec![Value::Bool(true), Value::Bool(true)]]
+        );
+        assert_eq!(
+            run("select b / (a - 1) from t1"),
+            Err(ExecutionError::DivisionByZero)
+        );
+    }
+
+    #[test]
+    fn test_outer_joins() {
+        assert_eq!(
+            run("select a, d from t1 left join t2 on a = c").unwrap(),
+            vec![
+                vec![int(1), int(100)],
+                vec![int(1), int(200)],
+                vec![int(2), Value::Null],
+                vec![Value::Null, Value::Null],
+            ]
+        );
+        assert_eq!(
+            run("select a, d from t1 right join t2 on a = c").unwrap(),
+            vec![
+                vec![int(1), int(100)],
+                vec![int(1), int(200)],
+                vec![Value::Null, int(300)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_aggregates() {
+        assert_eq!(
+            run("select c, count(d), max(d) from t2 group by c").unwrap(),
+            vec![
+                vec![int(1), big_int(2), int(200)],
+                vec![Value::Null, big_int(1), int(300)],
+            ]
+        );
+        assert_eq!(
+            run("select count(b), min(b) from t1").unwrap(),
+            vec![vec![big_int(2), int(10)]]
+        );
+        assert_eq!(
+            run("select count(a), min(a) from t1 where a > 5").unwrap(),
+            vec![vec![big_int(0), Value::Null]]
+        );
+    }
+
+    #[test]
+    fn test_subqueries() {
+        assert_eq!(
+            run("select a from t1 where exists (select c from t2 where c = a)").unwrap(),
+            vec![vec![int(1)]]
+        );
+        assert_eq!(
+            run("select a, (select max(d) from t2 where c = a) from t1").unwrap(),
+            vec![
+                vec![int(1), int(200)],
+                vec![int(2), Value::Null],
+                vec![Value::Null, Value::Null],
+            ]
+        );
+        // NULL IN (...) is NULL, and x > ALL (empty set) is TRUE
+        assert_eq!(
+            run("select a from t1 where a in (select c from t2)").unwrap(),
+            vec![vec![int(1)]]
+        );
+        assert_eq!(
+            run("select a from t1 where a > all (select c from t2 where c > 5)").unwrap(),
+            vec![vec![int(1)], vec![int(2)], vec![Value::Null]]
+        );
+        assert_eq!(
+            run("select a, (select d from t2 where c = a) from t1"),
+            Err(ExecutionError::ScalarSubqueryMultipleRows(5))
+        );
+        // NOT EXISTS is TRUE for a NULL outer value, since the subquery returns no rows
+        assert_eq!(
+            run("select a from t1 where not exists (select c from t2 where c = a)").unwrap(),
+            vec![vec![int(2)], vec![Value::Null]]
+        );
+        // x NOT IN (...) is NULL if x is NULL, or if the subquery returns a NULL and
+        // no value equal to x
+        assert_eq!(
+            run("select a from t1 where a not in (select c from t2)").unwrap(),
+            Vec::<Row>::new()
+        );
+        assert_eq!(
+            run("select a from t1 where a not in (select c from t2 where c is not null)").unwrap(),
+            vec![vec![int(2)]]
+        );
+    }
+
+    #[test]
+    fn test_apply() {
+        let (catalog, database) = test_database();
+        let mut query_graph = QueryGraph::with_catalog(catalog);
+        let t1 = query_graph.catalog_table_scan(0);
+        let t2 = query_graph.catalog_table_scan(1);
+        let filter = query_graph.filter(
+            t2,
+            vec![ScalarExpr::input_ref(0)
+                .binary(
+                    BinaryOp::Eq,
+                    ScalarExpr::CorrelatedInputRef {
+                        context_offset: 0,
+                        index: 0,
+                        data_type: DataType::Int,
+                    }
+                    .into(),
+                )
+                .into()],
+        );
+        let apply = query_graph.add_node(QueryNode::Apply {
+            correlation: CorrelationContext {
+                parameters: vec![ScalarExpr::input_ref(0).into()],
+            },
+            left: t1,
+            right: filter,
+            apply_type: ApplyType::LeftOuter,
+        });
+        query_graph.set_entry_node(apply);
+        assert_eq!(
+            execute(&query_graph, &database).unwrap(),
+            vec![
+                vec![int(1), int(10), int(1), int(100)],
+                vec![int(1), int(10), int(1), int(200)],
+                vec![int(2), Value::Null, Value::Null, Value::Null],
+                vec![Value::Null, int(30), Value::Null, Value::Null],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_table_scan_row_width_mismatch() {
+        let (_, database) = test_database();
+        let mut query_graph = QueryGraph::new();
+        let table_scan = query_graph.table_scan(0, 1);
+        query_graph.set_entry_node(table_scan);
+        assert_eq!(
+            execute(&query_graph, &database),
+            Err(ExecutionError::RowWidthMismatch(0, 1, 2))
+        );
+    }
+}
@@ -4,8 +4,12 @@ extern crate lazy_static;
 #[macro_use]
 extern crate serde_derive;
 
+pub mod catalog;
 pub mod data_type;
+pub mod interpreter;
+pub mod physical_plan;
 pub mod query_graph;
 pub mod scalar_expr;
+pub mod sql;
 pub mod value;
 pub mod visitor_utils;
@@ -0,0 +1,162 @@
+use super::PhysicalNode;
+use crate::query_graph::JoinType;
+
+/// The estimations the lowering pass relies on to choose among the physical
+/// implementations of a logical node.
+pub trait CostModel {
+    /// Cost of executing the given operator on its own, ie. excluding the cost of its
+    /// inputs, given the estimated number of rows produced by each of its inputs and
+    /// by the operator itself.
+    fn operator_cost(
+        &self,
+        node: &PhysicalNode,
+        input_cardinalities: &[f64],
+        output_cardinality: f64,
+    ) -> f64;
+
+    /// Cost of a hash join of the given type, building the hash table over the right
+    /// input and probing it with the rows of the left one.
+    fn hash_join_cost(
+        &self,
+        join_type: JoinType,
+        left_cardinality: f64,
+        right_cardinality: f64,
+        output_cardinality: f64,
+    ) -> f64;
+
+    /// Cost of a hash aggregation grouping the rows of its input into the given
+    /// number of groups.
+    fn hash_aggregate_cost(&self, input_cardinality: f64, num_groups: f64) -> f64;
+
+    /// Cost of evaluating a list of expressions over every row of the input, as
+    /// filters and projections do.
+    fn expression_cost(&self, input_cardinality: f64, output_cardinality: f64) -> f64;
+
+    /// Cost of concatenating the rows of several inputs.
+    fn union_cost(&self, input_cardinality: f64, output_cardinality: f64) -> f64;
+}
+
+/// Cost of reading a row sequentially from a table.
+const SEQUENTIAL_ROW_COST: f64 = 1.0;
+/// Cost of reading a row from a table through an index.
+const INDEX_ROW_COST: f64 = 1.5;
+/// Cost of descending an index to the first row matching a lookup.
+const INDEX_LOOKUP_COST: f64 = 10.0;
+/// Cost of evaluating a list of expressions over a row.
+const EXPRESSION_ROW_COST: f64 = 0.1;
+/// Cost of inserting a row into a hash table.
+const HASH_BUILD_ROW_COST: f64 = 2.0;
+/// Cost of looking up a row in a hash table.
+const HASH_PROBE_ROW_COST: f64 = 1.0;
+/// Cost of comparing two rows.
+const COMPARISON_COST: f64 = 0.5;
+/// Cost of producing an output row.
+const OUTPUT_ROW_COST: f64 = 0.1;
+
+/// Cost model assuming all the inputs of the operators are in memory, where the cost
+/// of an operator is proportional to the number of rows it reads, compares, hashes
+/// and produces.
+#[derive(Default)]
+pub struct DefaultCostModel {}
+
+impl DefaultCostModel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl CostModel for DefaultCostModel {
+    fn operator_cost(
+        &self,
+        node: &PhysicalNode,
+        input_cardinalities: &[f64],
+        output_cardinality: f64,
+    ) -> f64 {
+        let input_cardinality = input_cardinalities.iter().sum::<f64>();
+        let operator_cost = match node {
+            PhysicalNode::TableScan { .. } | PhysicalNode::WorkTableScan { .. } => {
+                output_cardinality * SEQUENTIAL_ROW_COST
+            }
+            PhysicalNode::IndexScan { lookup, .. } => {
+                let lookup_cost = if lookup.is_empty() {
+                    0.0
+                } else {
+                    INDEX_LOOKUP_COST
+                };
+                lookup_cost + output_cardinality * INDEX_ROW_COST
+            }
+            PhysicalNode::Filter { .. } | PhysicalNode::Project { .. } => {
+                return self.expression_cost(input_cardinality, output_cardinality);
+            }
+            PhysicalNode::HashJoin { join_type, .. } => {
+                return self.hash_join_cost(
+                    *join_type,
+                    input_cardinalities[0],
+                    input_cardinalities[1],
+                    output_cardinality,
+                );
+            }
+            PhysicalNode::MergeJoin { .. } => input_cardinality * COMPARISON_COST,
+            PhysicalNode::NestedLoopJoin { .. } => {
+                input_cardinalities[0] * input_cardinalities[1] * COMPARISON_COST
+            }
+            PhysicalNode::HashAggregate { .. } => {
+                return self.hash_aggregate_cost(input_cardinality, output_cardinality);
+            }
+            PhysicalNode::StreamAggregate { .. } => input_cardinality * COMPARISON_COST,
+            PhysicalNode::Sort { .. } => {
+                input_cardinality * input_cardinality.max(2.0).log2() * COMPARISON_COST
+            }
+            PhysicalNode::Values { .. } | PhysicalNode::Limit { .. } => 0.0,
+            PhysicalNode::TopN { offset, limit, .. } => {
+                input_cardinality * ((offset + limit) as f64).max(2.0).log2() * COMPARISON_COST
+            }
+            PhysicalNode::Window { functions, .. } => {
+                input_cardinality * (COMPARISON_COST + functions.len() as f64 * EXPRESSION_ROW_COST)
+            }
+            PhysicalNode::Union { .. } => {
+                return self.union_cost(input_cardinality, output_cardinality);
+            }
+            PhysicalNode::HashIntersect { .. } | PhysicalNode::HashExcept { .. } => {
+                input_cardinalities[1] * HASH_BUILD_ROW_COST
+                    + input_cardinalities[0] * HASH_PROBE_ROW_COST
+            }
+            PhysicalNode::RecursiveUnion { distinct, .. } => {
+                if *distinct {
+                    input_cardinality * HASH_PROBE_ROW_COST
+                        + output_cardinality * HASH_BUILD_ROW_COST
+                } else {
+                    0.0
+                }
+            }
+            // The right input is evaluated once per row of the left input.
+            PhysicalNode::Apply { .. } => input_cardinalities[0] * input_cardinalities[1],
+        };
+        operator_cost + output_cardinality * OUTPUT_ROW_COST
+    }
+
+    fn hash_join_cost(
+        &self,
+        _join_type: JoinType,
+        left_cardinality: f64,
+        right_cardinality: f64,
+        output_cardinality: f64,
+    ) -> f64 {
+        right_cardinality * HASH_BUILD_ROW_COST
+            + left_cardinality * HASH_PROBE_ROW_COST
+            + output_cardinality * OUTPUT_ROW_COST
+    }
+
+    fn hash_aggregate_cost(&self, input_cardinality: f64, num_groups: f64) -> f64 {
+        input_cardinality * HASH_PROBE_ROW_COST
+            + num_groups * (HASH_BUILD_ROW_COST + OUTPUT_ROW_COST)
+    }
+
+    fn expression_cost(&self, input_cardinality: f64, output_cardinality: f64) -> f64 {
+        input_cardinality * EXPRESSION_ROW_COST + output_cardinality * OUTPUT_ROW_COST
+    }
+
+    fn union_cost(&self, _input_cardinality: f64, output_cardinality: f64) -> f64 {
+        output_cardinality * OUTPUT_ROW_COST
+    }
+}
@@ -0,0 +1,255 @@
+use std::collections::HashSet;
+
+use itertools::Itertools;
+
+use crate::{
+    query_graph::explain::{
+        explain_limit, explain_recursive_union, explain_scalar_expr_vec, explain_set_operation,
+        explain_top_n, explain_values, explain_window,
+    },
+    scalar_expr::ScalarExpr,
+};
+
+use super::{PhysicalNode, PhysicalNodeId, PhysicalPlan};
+
+/// Function returning the annotation of a node of a physical plan, if any.
+pub type PhysicalAnnotator = dyn Fn(&PhysicalPlan, PhysicalNodeId) -> Option<String>;
+
+/// Utility for explaining a physical plan.
+pub struct PhysicalExplainer<'a> {
+    plan: &'a PhysicalPlan,
+    annotators: Vec<&'a PhysicalAnnotator>,
+}
+
+impl<'a> PhysicalExplainer<'a> {
+    pub fn new(plan: &'a PhysicalPlan) -> Self {
+        Self {
+            plan,
+            annotators: Vec::new(),
+        }
+    }
+
+    pub fn with_all_annotators(self) -> Self {
+        Self {
+            plan: self.plan,
+            annotators: default_physical_annotators(),
+        }
+    }
+
+    pub fn with_annotators(self, annotators: Vec<&'a PhysicalAnnotator>) -> Self {
+        Self {
+            plan: self.plan,
+            annotators,
+        }
+    }
+
+    /// Generate the explain plan.
+    pub fn explain(&self) -> String {
+        let mut result = String::new();
+        let mut visited_nodes = HashSet::new();
+        self.explain_node(&mut result, &mut visited_nodes, self.plan.root(), 0);
+        for (subquery_root, node_id) in self.plan.subqueries() {
+            result += &format!("\nSubquery {}:\n", subquery_root);
+            self.explain_node(&mut result, &mut visited_nodes, *node_id, 0);
+        }
+        result
+    }
+
+    fn explain_node(
+        &self,
+        result: &mut String,
+        visited_nodes: &mut HashSet<PhysicalNodeId>,
+        node_id: PhysicalNodeId,
+        indentation: usize,
+    ) {
+        let line_prefix = (0..2 * indentation).map(|_| ' ').collect::<String>();
+        if !visited_nodes.insert(node_id) {
+            *result += &format!("{}Recurring node {}\n", line_prefix, node_id);
+            return;
+        }
+        let node = self.plan.node(node_id);
+        let description = match node {
+            PhysicalNode::TableScan { table_id, .. } => format!("TableScan id: {}", table_id),
+            PhysicalNode::IndexScan {
+                table_id,
+                index,
+                lookup,
+                ..
+            } => {
+                let mut description = format!(
+                    "IndexScan id: {}, index: [{}]",
+                    table_id,
+                    explain_columns(index)
+                );
+                if !lookup.is_empty() {
+                    description += &format!(", lookup: [{}]", explain_scalar_expr_vec(lookup));
+                }
+                description
+            }
+            PhysicalNode::Values { rows } => format!("Values [{}]", explain_values(rows)),
+            PhysicalNode::Filter { conditions, .. } => {
+                format!("Filter [{}]", explain_scalar_expr_vec(conditions))
+            }
+            PhysicalNode::Project { outputs, .. } => {
+                format!("Project [{}]", explain_scalar_expr_vec(outputs))
+            }
+            PhysicalNode::HashJoin {
+                join_type,
+                left_keys,
+                right_keys,
+                conditions,
+                ..
+            } => format!(
+                "{} HashJoin left key: [{}], right key: [{}], conditions: [{}]",
+                join_type,
+                explain_columns(left_keys),
+                explain_columns(right_keys),
+                explain_scalar_expr_vec(conditions)
+            ),
+            PhysicalNode::MergeJoin {
+                join_type,
+                left_keys,
+                right_keys,
+                conditions,
+                ..
+            } => format!(
+                "{} MergeJoin left key: [{}], right key: [{}], conditions: [{}]",
+                join_type,
+                explain_columns(left_keys),
+                explain_columns(right_keys),
+                explain_scalar_expr_vec(conditions)
+            ),
+            PhysicalNode::NestedLoopJoin {
+                join_type,
+                conditions,
+                ..
+            } => format!(
+                "{} NestedLoopJoin [{}]",
+                join_type,
+                explain_scalar_expr_vec(conditions)
+            ),
+            PhysicalNode::HashAggregate {
+                group_key,
+                aggregates,
+                ..
+            } => format!(
+                "HashAggregate key: [{}], aggregates: [{}]",
+                explain_columns(group_key),
+                aggregates.iter().join(", ")
+            ),
+            PhysicalNode::StreamAggregate {
+                group_key,
+                aggregates,
+                ..
+            } => format!(
+                "StreamAggregate key: [{}], aggregates: [{}]",
+                explain_columns(group_key),
+                aggregates.iter().join(", ")
+            ),
+            PhysicalNode::Sort { keys, .. } => format!("Sort [{}]", keys.iter().join(", ")),
+            PhysicalNode::Limit { offset, limit, .. } => {
+                format!("Limit {}", explain_limit(*offset, *limit))
+            }
+            PhysicalNode::TopN {
+                partition_key,
+                keys,
+                offset,
+                limit,
+                ..
+            } => format!(
+                "TopN {}",
+                explain_top_n(partition_key, keys, *offset, *limit)
+            ),
+            PhysicalNode::Window {
+                partition_key,
+                order_key,
+                frame,
+                functions,
+                ..
+            } => format!(
+                "Window {}",
+                explain_window(partition_key, order_key, frame, functions)
+            ),
+            PhysicalNode::Union { .. } => "Union".to_string(),
+            PhysicalNode::HashIntersect { distinct, .. } => {
+                explain_set_operation("HashIntersect", *distinct)
+            }
+            PhysicalNode::HashExcept { distinct, .. } => {
+                explain_set_operation("HashExcept", *distinct)
+            }
+            PhysicalNode::RecursiveUnion {
+                recursion_id,
+                distinct,
+                max_iterations,
+                ..
+            } => explain_recursive_union(*recursion_id, *distinct, *max_iterations),
+            PhysicalNode::WorkTableScan { recursion_id, .. } => {
+                format!("WorkTableScan id: {}", recursion_id)
+            }
+            PhysicalNode::Apply {
+                correlation,
+                apply_type,
+                ..
+            } => format!(
+                "{} Apply parameters: [{}]",
+                apply_type,
+                explain_scalar_expr_vec(&correlation.parameters)
+            ),
+        };
+        *result += &format!("{}[{}] {}\n", line_prefix, node_id, description);
+
+        for annotator in self.annotators.iter() {
+            if let Some(annotation) = (annotator)(self.plan, node_id) {
+                *result += &format!("{}    - {}\n", line_prefix, annotation);
+            }
+        }
+
+        for input in node.inputs() {
+            self.explain_node(result, visited_nodes, input, indentation + 1);
+        }
+    }
+}
+
+/// Explain functions.
+impl PhysicalPlan {
+    /// Returns a stringified version of the physical plan.
+    pub fn explain(&self) -> String {
+        PhysicalExplainer::new(self).explain()
+    }
+
+    /// Explains the physical plan annotated with all its properties.
+    pub fn fully_annotated_explain(&self) -> String {
+        PhysicalExplainer::new(self).with_all_annotators().explain()
+    }
+}
+
+fn explain_columns<'a>(columns: impl IntoIterator<Item = &'a usize>) -> String {
+    columns
+        .into_iter()
+        .map(|column| ScalarExpr::input_ref(*column))
+        .join(", ")
+}
+
+fn cardinality_annotator(plan: &PhysicalPlan, node_id: PhysicalNodeId) -> Option<String> {
+    Some(format!(
+        "Estimated Cardinality: {:.1}",
+        plan.properties(node_id).cardinality
+    ))
+}
+
+fn cost_annotator(plan: &PhysicalPlan, node_id: PhysicalNodeId) -> Option<String> {
+    Some(format!("Cost: {:.1}", plan.properties(node_id).cost))
+}
+
+fn ordering_annotator(plan: &PhysicalPlan, node_id: PhysicalNodeId) -> Option<String> {
+    let ordering = &plan.properties(node_id).ordering;
+    if ordering.is_empty() {
+        None
+    } else {
+        Some(format!("Ordering: [{}]", ordering.iter().join(", ")))
+    }
+}
+
+pub fn default_physical_annotators() -> Vec<&'static PhysicalAnnotator> {
+    vec![&cardinality_annotator, &cost_annotator, &ordering_annotator]
+}
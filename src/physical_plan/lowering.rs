@@ -0,0 +1,780 @@
+use std::collections::{HashMap, HashSet};
+
+use itertools::Itertools;
+
+use crate::{
+    query_graph::{
+        properties::{
+            cardinality_estimate, estimated_cardinality, estimated_selectivity, num_columns,
+            partitioned_ordering,
+        },
+        JoinType, NodeId, QueryGraph, QueryNode,
+    },
+    scalar_expr::{BinaryOp, ScalarExpr, ScalarExprRef},
+};
+
+use super::{
+    CostModel, Ordering, PhysicalNode, PhysicalNodeId, PhysicalPlan, PhysicalProperties, SortKey,
+};
+
+/// Lowers the given query graph into a physical plan, picking for every logical node
+/// the implementation leading to the cheapest plan according to the given cost model.
+///
+/// The lowering keeps, for every logical node, the cheapest physical candidate for
+/// each order of the rows the candidates produce, so that the operators requiring a
+/// sorted input, ie. merge joins and stream aggregates, may reuse the order delivered
+/// by their inputs instead of sorting them.
+pub fn lower(query_graph: &QueryGraph, cost_model: &dyn CostModel) -> PhysicalPlan {
+    let mut lowering = Lowering {
+        query_graph,
+        cost_model,
+        plan: PhysicalPlan::new(),
+        candidates: HashMap::new(),
+    };
+    let root = lowering.cheapest(QueryGraph::ROOT_NODE_ID);
+    let subqueries = query_graph
+        .subquery_roots()
+        .into_iter()
+        .map(|subquery_root| (subquery_root, lowering.cheapest(subquery_root)))
+        .collect_vec();
+    lowering.extract(root, subqueries)
+}
+
+struct Lowering<'a> {
+    query_graph: &'a QueryGraph,
+    cost_model: &'a dyn CostModel,
+    /// All the physical nodes built during the lowering, including the discarded ones.
+    plan: PhysicalPlan,
+    /// The candidates for every logical node lowered so far, none of them cheaper
+    /// than another one delivering the same or a more specific order.
+    candidates: HashMap<NodeId, Vec<PhysicalNodeId>>,
+}
+
+impl<'a> Lowering<'a> {
+    fn candidates(&mut self, node_id: NodeId) -> Vec<PhysicalNodeId> {
+        if let Some(candidates) = self.candidates.get(&node_id) {
+            return candidates.clone();
+        }
+        let candidates = self.lower_node(node_id);
+        self.candidates.insert(node_id, candidates.clone());
+        candidates
+    }
+
+    /// The cheapest candidate for the given logical node.
+    fn cheapest(&mut self, node_id: NodeId) -> PhysicalNodeId {
+        self.candidates(node_id)
+            .into_iter()
+            .min_by(|a, b| self.cost(*a).total_cmp(&self.cost(*b)))
+            .unwrap()
+    }
+
+    /// The cheapest candidate for the given logical node producing its rows in the
+    /// given order, either a candidate already delivering it or the cheapest one
+    /// followed by a sort.
+    fn ordered(&mut self, node_id: NodeId, ordering: &[SortKey]) -> PhysicalNodeId {
+        let cheapest = self.cheapest(node_id);
+        let ordered = self
+            .candidates(node_id)
+            .into_iter()
+            .filter(|candidate| self.ordering(*candidate).starts_with(ordering))
+            .min_by(|a, b| self.cost(*a).total_cmp(&self.cost(*b)));
+        let sort = self.add_node(
+            PhysicalNode::Sort {
+                keys: ordering.to_vec(),
+                input: cheapest,
+            },
+            self.plan.properties(cheapest).cardinality,
+            ordering.to_vec(),
+        );
+        match ordered {
+            Some(ordered) if self.cost(ordered) <= self.cost(sort) => ordered,
+            _ => sort,
+        }
+    }
+
+    fn cost(&self, node_id: PhysicalNodeId) -> f64 {
+        self.plan.properties(node_id).cost
+    }
+
+    fn ordering(&self, node_id: PhysicalNodeId) -> &Ordering {
+        &self.plan.properties(node_id).ordering
+    }
+
+    /// Adds the given physical node, computing its cost from the cost of its inputs.
+    fn add_node(
+        &mut self,
+        node: PhysicalNode,
+        cardinality: f64,
+        ordering: Ordering,
+    ) -> PhysicalNodeId {
+        let inputs = node.inputs();
+        let input_cardinalities = inputs
+            .iter()
+            .map(|input| self.plan.properties(*input).cardinality)
+            .collect_vec();
+        let cost = self
+            .cost_model
+            .operator_cost(&node, &input_cardinalities, cardinality)
+            + inputs.iter().map(|input| self.cost(*input)).sum::<f64>();
+        self.plan.add_node(
+            node,
+            PhysicalProperties {
+                cardinality,
+                cost,
+                ordering,
+            },
+        )
+    }
+
+    /// Adds the given physical node as a candidate, unless there is a candidate that
+    /// is as cheap and delivers the same order or a more specific one. The candidates
+    /// dominated by the new one are discarded.
+    fn add_candidate(
+        &mut self,
+        candidates: &mut Vec<PhysicalNodeId>,
+        node: PhysicalNode,
+        cardinality: f64,
+        ordering: Ordering,
+    ) {
+        let new = self.add_node(node, cardinality, ordering);
+        let dominates = |a: PhysicalNodeId, b: PhysicalNodeId| {
+            self.cost(a) <= self.cost(b) && self.ordering(a).starts_with(self.ordering(b))
+        };
+        if candidates.iter().any(|existing| dominates(*existing, new)) {
+            return;
+        }
+        candidates.retain(|existing| !dominates(new, *existing));
+        candidates.push(new);
+    }
+
+    fn lower_node(&mut self, node_id: NodeId) -> Vec<PhysicalNodeId> {
+        let query_graph = self.query_graph;
+        let cardinality = estimated_cardinality(query_graph, node_id);
+        let mut candidates = Vec::new();
+        match query_graph.node(node_id) {
+            QueryNode::QueryRoot { input: Some(input) } | QueryNode::SubqueryRoot { input } => {
+                return self.candidates(*input);
+            }
+            QueryNode::QueryRoot { input: None } => {
+                self.add_candidate(
+                    &mut candidates,
+                    PhysicalNode::Union { inputs: Vec::new() },
+                    0.0,
+                    Vec::new(),
+                );
+            }
+            QueryNode::Values { rows, .. } => {
+                self.add_candidate(
+                    &mut candidates,
+                    PhysicalNode::Values { rows: rows.clone() },
+                    cardinality,
+                    Vec::new(),
+                );
+            }
+            QueryNode::TableScan { table_id, row_type } => {
+                self.add_candidate(
+                    &mut candidates,
+                    PhysicalNode::TableScan {
+                        table_id: *table_id,
+                        num_columns: row_type.len(),
+                    },
+                    cardinality,
+                    Vec::new(),
+                );
+                for index in table_indexes(query_graph, *table_id) {
+                    let ordering = index.iter().map(|column| SortKey::asc(*column)).collect();
+                    self.add_candidate(
+                        &mut candidates,
+                        PhysicalNode::IndexScan {
+                            table_id: *table_id,
+                            num_columns: row_type.len(),
+                            index,
+                            lookup: Vec::new(),
+                        },
+                        cardinality,
+                        ordering,
+                    );
+                }
+            }
+            QueryNode::Filter { conditions, input } => {
+                for candidate in self.candidates(*input) {
+                    self.add_candidate(
+                        &mut candidates,
+                        PhysicalNode::Filter {
+                            conditions: conditions.clone(),
+                            input: candidate,
+                        },
+                        cardinality,
+                        self.ordering(candidate).clone(),
+                    );
+                }
+                self.lower_index_lookups(&mut candidates, conditions, *input, cardinality);
+            }
+            QueryNode::Project { outputs, input } => {
+                for candidate in self.candidates(*input) {
+                    let ordering = project_ordering(self.ordering(candidate), outputs);
+                    self.add_candidate(
+                        &mut candidates,
+                        PhysicalNode::Project {
+                            outputs: outputs.clone(),
+                            input: candidate,
+                        },
+                        cardinality,
+                        ordering,
+                    );
+                }
+            }
+            QueryNode::Join {
+                join_type,
+                conditions,
+                left,
+                right,
+            } => {
+                self.lower_join(
+                    &mut candidates,
+                    *join_type,
+                    conditions,
+                    *left,
+                    *right,
+                    cardinality,
+                );
+            }
+            QueryNode::Aggregate {
+                group_key,
+                aggregates,
+                input,
+            } => {
+                let cheapest = self.cheapest(*input);
+                self.add_candidate(
+                    &mut candidates,
+                    PhysicalNode::HashAggregate {
+                        group_key: group_key.clone(),
+                        aggregates: aggregates.clone(),
+                        input: cheapest,
+                    },
+                    cardinality,
+                    Vec::new(),
+                );
+                // Any order of the grouping key columns makes the rows of the same
+                // group contiguous.
+                let mut orderings = vec![group_key
+                    .iter()
+                    .map(|column| SortKey::asc(*column))
+                    .collect_vec()];
+                for candidate in self.candidates(*input) {
+                    let ordering = self.ordering(candidate);
+                    if ordering.len() >= group_key.len()
+                        && ordering[..group_key.len()]
+                            .iter()
+                            .all(|key| group_key.contains(&key.column))
+                    {
+                        orderings.push(ordering[..group_key.len()].to_vec());
+                    }
+                }
+                for ordering in orderings.into_iter().unique() {
+                    let input = self.ordered(*input, &ordering);
+                    let output_ordering = ordering
+                        .iter()
+                        .map(|key| SortKey {
+                            column: group_key.iter().position(|c| *c == key.column).unwrap(),
+                            ascending: key.ascending,
+                        })
+                        .collect();
+                    self.add_candidate(
+                        &mut candidates,
+                        PhysicalNode::StreamAggregate {
+                            group_key: group_key.clone(),
+                            aggregates: aggregates.clone(),
+                            input,
+                        },
+                        cardinality,
+                        output_ordering,
+                    );
+                }
+            }
+            QueryNode::Sort { keys, input } => {
+                return vec![self.ordered(*input, keys)];
+            }
+            QueryNode::Limit {
+                offset,
+                limit,
+                input,
+            } => {
+                for candidate in self.candidates(*input) {
+                    self.add_candidate(
+                        &mut candidates,
+                        PhysicalNode::Limit {
+                            offset: *offset,
+                            limit: *limit,
+                            input: candidate,
+                        },
+                        cardinality,
+                        self.ordering(candidate).clone(),
+                    );
+                }
+            }
+            QueryNode::TopN {
+                partition_key,
+                keys,
+                offset,
+                limit,
+                input,
+            } => {
+                let cheapest = self.cheapest(*input);
+                self.add_candidate(
+                    &mut candidates,
+                    PhysicalNode::TopN {
+                        partition_key: partition_key.clone(),
+                        keys: keys.clone(),
+                        offset: *offset,
+                        limit: *limit,
+                        input: cheapest,
+                    },
+                    cardinality,
+                    partitioned_ordering(partition_key, keys),
+                );
+                // A limit is enough over the candidates already delivering the order,
+                // unless the limit applies per partition.
+                for candidate in self.candidates(*input) {
+                    if partition_key.is_empty() && self.ordering(candidate).starts_with(keys) {
+                        self.add_candidate(
+                            &mut candidates,
+                            PhysicalNode::Limit {
+                                offset: *offset,
+                                limit: Some(*limit),
+                                input: candidate,
+                            },
+                            cardinality,
+                            self.ordering(candidate).clone(),
+                        );
+                    }
+                }
+            }
+            QueryNode::Window {
+                partition_key,
+                order_key,
+                frame,
+                functions,
+                input,
+            } => {
+                let ordering = partitioned_ordering(partition_key, order_key);
+                let input = self.ordered(*input, &ordering);
+                self.add_candidate(
+                    &mut candidates,
+                    PhysicalNode::Window {
+                        partition_key: partition_key.clone(),
+                        order_key: order_key.clone(),
+                        frame: *frame,
+                        functions: functions.clone(),
+                        input,
+                    },
+                    cardinality,
+                    ordering,
+                );
+            }
+            QueryNode::Union { inputs } => {
+                let inputs = inputs.iter().map(|input| self.cheapest(*input)).collect();
+                self.add_candidate(
+                    &mut candidates,
+                    PhysicalNode::Union { inputs },
+                    cardinality,
+                    Vec::new(),
+                );
+            }
+            QueryNode::Intersect {
+                distinct,
+                left,
+                right,
+            } => {
+                let (left, right) = (self.cheapest(*left), self.cheapest(*right));
+                self.add_candidate(
+                    &mut candidates,
+                    PhysicalNode::HashIntersect {
+                        distinct: *distinct,
+                        left,
+                        right,
+                    },
+                    cardinality,
+                    Vec::new(),
+                );
+            }
+            QueryNode::Except {
+                distinct,
+                left,
+                right,
+            } => {
+                let (left, right) = (self.cheapest(*left), self.cheapest(*right));
+                self.add_candidate(
+                    &mut candidates,
+                    PhysicalNode::HashExcept {
+                        distinct: *distinct,
+                        left,
+                        right,
+                    },
+                    cardinality,
+                    Vec::new(),
+                );
+            }
+            QueryNode::RecursiveUnion {
+                recursion_id,
+                distinct,
+                max_iterations,
+                base,
+                recursive,
+            } => {
+                let (base, recursive) = (self.cheapest(*base), self.cheapest(*recursive));
+                self.add_candidate(
+                    &mut candidates,
+                    PhysicalNode::RecursiveUnion {
+                        recursion_id: *recursion_id,
+                        distinct: *distinct,
+                        max_iterations: *max_iterations,
+                        base,
+                        recursive,
+                    },
+                    cardinality,
+                    Vec::new(),
+                );
+            }
+            QueryNode::RecursiveReference {
+                recursion_id,
+                row_type,
+            } => {
+                self.add_candidate(
+                    &mut candidates,
+                    PhysicalNode::WorkTableScan {
+                        recursion_id: *recursion_id,
+                        num_columns: row_type.len(),
+                    },
+                    cardinality,
+                    Vec::new(),
+                );
+            }
+            QueryNode::Apply {
+                correlation,
+                left,
+                right,
+                apply_type,
+            } => {
+                let right = self.cheapest(*right);
+                for candidate in self.candidates(*left) {
+                    self.add_candidate(
+                        &mut candidates,
+                        PhysicalNode::Apply {
+                            correlation: correlation.clone(),
+                            apply_type: *apply_type,
+                            left: candidate,
+                            right,
+                        },
+                        cardinality,
+                        self.ordering(candidate).clone(),
+                    );
+                }
+            }
+        }
+        candidates
+    }
+
+    /// Adds a candidate for a filter over a table scan for every index of the table
+    /// whose first columns are compared for equality with a literal by the filter.
+    fn lower_index_lookups(
+        &mut self,
+        candidates: &mut Vec<PhysicalNodeId>,
+        conditions: &[ScalarExprRef],
+        input: NodeId,
+        cardinality: f64,
+    ) {
+        let query_graph = self.query_graph;
+        let (table_id, num_columns) = match query_graph.node(input) {
+            QueryNode::TableScan { table_id, row_type } => (*table_id, row_type.len()),
+            _ => return,
+        };
+        let columns = cardinality_estimate(query_graph, input).columns.clone();
+        let table_cardinality = estimated_cardinality(query_graph, input);
+        for index in table_indexes(query_graph, table_id) {
+            let mut used_conditions = HashSet::new();
+            let mut lookup = Vec::new();
+            for column in index.iter() {
+                let Some((condition, value)) =
+                    conditions.iter().enumerate().find_map(|(i, condition)| {
+                        column_equals_literal(condition, *column).map(|value| (i, value))
+                    })
+                else {
+                    break;
+                };
+                used_conditions.insert(condition);
+                lookup.push(value);
+            }
+            if lookup.is_empty() {
+                continue;
+            }
+            let lookup_cardinality =
+                used_conditions
+                    .iter()
+                    .fold(table_cardinality, |cardinality, condition| {
+                        cardinality * estimated_selectivity(&conditions[*condition], &columns)
+                    });
+            let remaining_conditions = conditions
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !used_conditions.contains(i))
+                .map(|(_, condition)| condition.clone())
+                .collect_vec();
+            let ordering = index
+                .iter()
+                .map(|column| SortKey::asc(*column))
+                .collect_vec();
+            let index_scan = PhysicalNode::IndexScan {
+                table_id,
+                num_columns,
+                index,
+                lookup,
+            };
+            if remaining_conditions.is_empty() {
+                self.add_candidate(candidates, index_scan, lookup_cardinality, ordering);
+            } else {
+                let index_scan = self.add_node(index_scan, lookup_cardinality, ordering.clone());
+                self.add_candidate(
+                    candidates,
+                    PhysicalNode::Filter {
+                        conditions: remaining_conditions,
+                        input: index_scan,
+                    },
+                    cardinality,
+                    ordering,
+                );
+            }
+        }
+    }
+
+    fn lower_join(
+        &mut self,
+        candidates: &mut Vec<PhysicalNodeId>,
+        join_type: JoinType,
+        conditions: &[ScalarExprRef],
+        left: NodeId,
+        right: NodeId,
+        cardinality: f64,
+    ) {
+        let left_num_columns = num_columns(self.query_graph, left);
+        let (equalities, residual): (Vec<_>, Vec<_>) = conditions
+            .iter()
+            .map(|condition| (equi_join_key(condition, left_num_columns), condition))
+            .partition(|(key, _)| key.is_some());
+        let equalities = equalities
+            .into_iter()
+            .map(|(key, _)| key.unwrap())
+            .collect_vec();
+        let residual = residual
+            .into_iter()
+            .map(|(_, condition)| condition.clone())
+            .collect_vec();
+        let preserves_left_order = matches!(
+            join_type,
+            JoinType::Inner | JoinType::LeftOuter | JoinType::Semi | JoinType::Anti
+        );
+        let output_ordering = |ordering: &Ordering| {
+            if preserves_left_order {
+                ordering.clone()
+            } else {
+                Vec::new()
+            }
+        };
+
+        let cheapest_right = self.cheapest(right);
+        let left_candidates = self.candidates(left);
+        for candidate in left_candidates.iter() {
+            let ordering = output_ordering(self.ordering(*candidate));
+            self.add_candidate(
+                candidates,
+                PhysicalNode::NestedLoopJoin {
+                    join_type,
+                    conditions: conditions.to_vec(),
+                    left: *candidate,
+                    right: cheapest_right,
+                },
+                cardinality,
+                ordering.clone(),
+            );
+            if !equalities.is_empty() {
+                self.add_candidate(
+                    candidates,
+                    PhysicalNode::HashJoin {
+                        join_type,
+                        left_keys: equalities.iter().map(|(l, _)| *l).collect(),
+                        right_keys: equalities.iter().map(|(_, r)| *r).collect(),
+                        conditions: residual.clone(),
+                        left: *candidate,
+                        right: cheapest_right,
+                    },
+                    cardinality,
+                    ordering,
+                );
+            }
+        }
+
+        if equalities.is_empty() {
+            return;
+        }
+        // Merge joins may use the equalities in any order, so try the order the left
+        // candidates are already sorted by.
+        let mut key_orders = vec![equalities.clone()];
+        for candidate in left_candidates.iter() {
+            let ordering = self.ordering(*candidate);
+            if ordering.len() < equalities.len() || !ordering.iter().all(|key| key.ascending) {
+                continue;
+            }
+            let key_order = ordering[..equalities.len()]
+                .iter()
+                .filter_map(|key| equalities.iter().find(|(l, _)| *l == key.column).cloned())
+                .collect_vec();
+            if key_order.len() == equalities.len() && key_order.iter().all_unique() {
+                key_orders.push(key_order);
+            }
+        }
+        for key_order in key_orders.into_iter().unique() {
+            let left_keys = key_order.iter().map(|(l, _)| *l).collect_vec();
+            let right_keys = key_order.iter().map(|(_, r)| *r).collect_vec();
+            let left_ordering = left_keys.iter().map(|c| SortKey::asc(*c)).collect_vec();
+            let right_ordering = right_keys.iter().map(|c| SortKey::asc(*c)).collect_vec();
+            let left_input = self.ordered(left, &left_ordering);
+            let right_input = self.ordered(right, &right_ordering);
+            self.add_candidate(
+                candidates,
+                PhysicalNode::MergeJoin {
+                    join_type,
+                    left_keys,
+                    right_keys,
+                    conditions: residual.clone(),
+                    left: left_input,
+                    right: right_input,
+                },
+                cardinality,
+                output_ordering(&left_ordering),
+            );
+        }
+    }
+
+    /// Builds the final plan with the nodes reachable from the given roots.
+    fn extract(
+        &self,
+        root: PhysicalNodeId,
+        subqueries: Vec<(NodeId, PhysicalNodeId)>,
+    ) -> PhysicalPlan {
+        let mut plan = PhysicalPlan::new();
+        let mut node_map = HashMap::new();
+        plan.root = self.extract_node(&mut plan, &mut node_map, root);
+        plan.subqueries = subqueries
+            .into_iter()
+            .map(|(subquery_root, node_id)| {
+                (
+                    subquery_root,
+                    self.extract_node(&mut plan, &mut node_map, node_id),
+                )
+            })
+            .collect();
+        plan
+    }
+
+    fn extract_node(
+        &self,
+        plan: &mut PhysicalPlan,
+        node_map: &mut HashMap<PhysicalNodeId, PhysicalNodeId>,
+        node_id: PhysicalNodeId,
+    ) -> PhysicalNodeId {
+        if let Some(new_node_id) = node_map.get(&node_id) {
+            return *new_node_id;
+        }
+        let node = self.plan.node(node_id);
+        let inputs = node
+            .inputs()
+            .into_iter()
+            .map(|input| self.extract_node(plan, node_map, input))
+            .collect_vec();
+        let new_node_id = plan.add_node(
+            node.with_inputs(&inputs),
+            self.plan.properties(node_id).clone(),
+        );
+        node_map.insert(node_id, new_node_id);
+        new_node_id
+    }
+}
+
+/// The key columns of the given table, which are assumed to be indexed.
+pub(crate) fn table_indexes(query_graph: &QueryGraph, table_id: usize) -> Vec<Vec<usize>> {
+    query_graph
+        .table_metadata(table_id)
+        .map(|table| table.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// If the given condition compares the given column for equality with a literal,
+/// returns the literal.
+pub(crate) fn column_equals_literal(
+    condition: &ScalarExprRef,
+    column: usize,
+) -> Option<ScalarExprRef> {
+    if let ScalarExpr::BinaryOp {
+        op: BinaryOp::Eq,
+        left,
+        right,
+    } = condition.as_ref()
+    {
+        for (a, b) in [(left, right), (right, left)] {
+            if let (ScalarExpr::InputRef { index }, ScalarExpr::Literal(_)) =
+                (a.as_ref(), b.as_ref())
+            {
+                if *index == column {
+                    return Some(b.clone());
+                }
+            }
+        }
+    }
+    None
+}
+
+/// If the given join condition is an equality between a column of the left input and
+/// a column of the right input, returns both columns, the right one relative to the
+/// right input.
+pub(crate) fn equi_join_key(
+    condition: &ScalarExprRef,
+    left_num_columns: usize,
+) -> Option<(usize, usize)> {
+    if let ScalarExpr::BinaryOp {
+        op: BinaryOp::Eq,
+        left,
+        right,
+    } = condition.as_ref()
+    {
+        if let (ScalarExpr::InputRef { index: a }, ScalarExpr::InputRef { index: b }) =
+            (left.as_ref(), right.as_ref())
+        {
+            if *a < left_num_columns && *b >= left_num_columns {
+                return Some((*a, *b - left_num_columns));
+            }
+            if *b < left_num_columns && *a >= left_num_columns {
+                return Some((*b, *a - left_num_columns));
+            }
+        }
+    }
+    None
+}
+
+/// The order of the rows of a projection over an input with the given order: the
+/// longest prefix of the input order whose columns are projected.
+fn project_ordering(ordering: &Ordering, outputs: &[ScalarExprRef]) -> Ordering {
+    ordering
+        .iter()
+        .map_while(|key| {
+            outputs
+                .iter()
+                .position(|output| {
+                    matches!(output.as_ref(), ScalarExpr::InputRef { index } if *index == key.column)
+                })
+                .map(|column| SortKey {
+                    column,
+                    ascending: key.ascending,
+                })
+        })
+        .collect()
+}
@@ -0,0 +1,300 @@
+//! Physical query plans.
+//!
+//! A physical plan is a tree of operators, each of them implementing a node of the
+//! logical query graph with a specific algorithm. Physical plans are obtained by
+//! lowering an optimized query graph, picking for each logical node the cheapest
+//! implementation according to a cost model.
+use std::collections::BTreeSet;
+
+use crate::{
+    query_graph::{ApplyType, CorrelationContext, JoinType, NodeId, WindowFrame},
+    scalar_expr::{AggregateExprRef, ScalarExpr, ScalarExprRef, WindowExprRef},
+    value::Literal,
+};
+
+pub mod cost;
+mod explain;
+mod lowering;
+
+pub use cost::{CostModel, DefaultCostModel};
+pub use explain::{default_physical_annotators, PhysicalAnnotator, PhysicalExplainer};
+pub use lowering::lower;
+pub(crate) use lowering::{column_equals_literal, equi_join_key, table_indexes};
+
+pub use crate::query_graph::SortKey;
+
+pub type PhysicalNodeId = usize;
+
+/// The order of the rows produced by an operator, as a list of sort keys. Empty for
+/// operators that do not guarantee any order.
+pub type Ordering = Vec<SortKey>;
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub enum PhysicalNode {
+    /// Full scan of a table.
+    TableScan {
+        table_id: usize,
+        num_columns: usize,
+    },
+    /// Scan of a table through the index built over the given key columns, returning
+    /// the rows sorted by them. If `lookup` is not empty, only the rows where the first
+    /// columns of the index are equal to the given values are returned.
+    IndexScan {
+        table_id: usize,
+        num_columns: usize,
+        index: Vec<usize>,
+        lookup: Vec<ScalarExprRef>,
+    },
+    /// Returns the given rows of literals.
+    Values {
+        rows: Vec<Vec<Literal>>,
+    },
+    Filter {
+        conditions: Vec<ScalarExprRef>,
+        input: PhysicalNodeId,
+    },
+    Project {
+        outputs: Vec<ScalarExprRef>,
+        input: PhysicalNodeId,
+    },
+    /// Join that builds a hash table with the rows of its right input, keyed by the
+    /// right key columns, and probes it with the left key columns of the rows of its
+    /// left input. The rest of the conditions are evaluated over the matching pairs.
+    HashJoin {
+        join_type: JoinType,
+        left_keys: Vec<usize>,
+        right_keys: Vec<usize>,
+        conditions: Vec<ScalarExprRef>,
+        left: PhysicalNodeId,
+        right: PhysicalNodeId,
+    },
+    /// Join of two inputs sorted by their key columns, in the given order.
+    MergeJoin {
+        join_type: JoinType,
+        left_keys: Vec<usize>,
+        right_keys: Vec<usize>,
+        conditions: Vec<ScalarExprRef>,
+        left: PhysicalNodeId,
+        right: PhysicalNodeId,
+    },
+    /// Join evaluating the conditions over every pair of rows from its inputs.
+    NestedLoopJoin {
+        join_type: JoinType,
+        conditions: Vec<ScalarExprRef>,
+        left: PhysicalNodeId,
+        right: PhysicalNodeId,
+    },
+    /// Aggregation keeping a hash table with a group per distinct grouping key.
+    HashAggregate {
+        group_key: BTreeSet<usize>,
+        aggregates: Vec<AggregateExprRef>,
+        input: PhysicalNodeId,
+    },
+    /// Aggregation over an input sorted by the grouping key, so that the rows of the
+    /// same group are contiguous.
+    StreamAggregate {
+        group_key: BTreeSet<usize>,
+        aggregates: Vec<AggregateExprRef>,
+        input: PhysicalNodeId,
+    },
+    Sort {
+        keys: Vec<SortKey>,
+        input: PhysicalNodeId,
+    },
+    /// Returns at most `limit` rows of its input after skipping the first `offset`
+    /// ones, stopping as soon as they have been produced.
+    Limit {
+        offset: usize,
+        limit: Option<usize>,
+        input: PhysicalNodeId,
+    },
+    /// Sort keeping only the first `offset + limit` rows in a bounded heap, per
+    /// partition if a partition key is given.
+    TopN {
+        partition_key: Vec<usize>,
+        keys: Vec<SortKey>,
+        offset: usize,
+        limit: usize,
+        input: PhysicalNodeId,
+    },
+    /// Computes the window functions over an input sorted by the partition key and
+    /// the order key, so that the rows of the same partition are contiguous.
+    Window {
+        partition_key: Vec<usize>,
+        order_key: Vec<SortKey>,
+        frame: WindowFrame,
+        functions: Vec<WindowExprRef>,
+        input: PhysicalNodeId,
+    },
+    Union {
+        inputs: Vec<PhysicalNodeId>,
+    },
+    /// Builds a hash table counting the occurrences of the rows of its right input and
+    /// probes it with the rows of its left input, returning the ones found.
+    HashIntersect {
+        distinct: bool,
+        left: PhysicalNodeId,
+        right: PhysicalNodeId,
+    },
+    /// Builds a hash table counting the occurrences of the rows of its right input and
+    /// probes it with the rows of its left input, returning the ones not found.
+    HashExcept {
+        distinct: bool,
+        left: PhysicalNodeId,
+        right: PhysicalNodeId,
+    },
+    /// Evaluates its base input and then its recursive input repeatedly, over the rows
+    /// produced by the previous iteration, until a fixpoint is reached. If `distinct`
+    /// is true, it keeps a hash table with the rows already produced.
+    RecursiveUnion {
+        recursion_id: usize,
+        distinct: bool,
+        max_iterations: Option<usize>,
+        base: PhysicalNodeId,
+        recursive: PhysicalNodeId,
+    },
+    /// Scan of the rows produced by the previous iteration of the enclosing recursive
+    /// union with the same ID.
+    WorkTableScan {
+        recursion_id: usize,
+        num_columns: usize,
+    },
+    /// Evaluates its right input once per row of its left input, binding the
+    /// correlation parameters.
+    Apply {
+        correlation: CorrelationContext<ScalarExpr>,
+        apply_type: ApplyType,
+        left: PhysicalNodeId,
+        right: PhysicalNodeId,
+    },
+}
+
+impl PhysicalNode {
+    pub fn inputs(&self) -> Vec<PhysicalNodeId> {
+        match self {
+            PhysicalNode::TableScan { .. }
+            | PhysicalNode::IndexScan { .. }
+            | PhysicalNode::Values { .. }
+            | PhysicalNode::WorkTableScan { .. } => Vec::new(),
+            PhysicalNode::Filter { input, .. }
+            | PhysicalNode::Project { input, .. }
+            | PhysicalNode::HashAggregate { input, .. }
+            | PhysicalNode::StreamAggregate { input, .. }
+            | PhysicalNode::Sort { input, .. }
+            | PhysicalNode::Limit { input, .. }
+            | PhysicalNode::TopN { input, .. }
+            | PhysicalNode::Window { input, .. } => vec![*input],
+            PhysicalNode::HashJoin { left, right, .. }
+            | PhysicalNode::MergeJoin { left, right, .. }
+            | PhysicalNode::NestedLoopJoin { left, right, .. }
+            | PhysicalNode::Apply { left, right, .. }
+            | PhysicalNode::HashIntersect { left, right, .. }
+            | PhysicalNode::HashExcept { left, right, .. }
+            | PhysicalNode::RecursiveUnion {
+                base: left,
+                recursive: right,
+                ..
+            } => vec![*left, *right],
+            PhysicalNode::Union { inputs } => inputs.clone(),
+        }
+    }
+
+    /// Returns a copy of this node with its inputs replaced with the given ones.
+    pub(crate) fn with_inputs(&self, new_inputs: &[PhysicalNodeId]) -> Self {
+        let mut node = self.clone();
+        match &mut node {
+            PhysicalNode::TableScan { .. }
+            | PhysicalNode::IndexScan { .. }
+            | PhysicalNode::Values { .. }
+            | PhysicalNode::WorkTableScan { .. } => {}
+            PhysicalNode::Filter { input, .. }
+            | PhysicalNode::Project { input, .. }
+            | PhysicalNode::HashAggregate { input, .. }
+            | PhysicalNode::StreamAggregate { input, .. }
+            | PhysicalNode::Sort { input, .. }
+            | PhysicalNode::Limit { input, .. }
+            | PhysicalNode::TopN { input, .. }
+            | PhysicalNode::Window { input, .. } => *input = new_inputs[0],
+            PhysicalNode::HashJoin { left, right, .. }
+            | PhysicalNode::MergeJoin { left, right, .. }
+            | PhysicalNode::NestedLoopJoin { left, right, .. }
+            | PhysicalNode::Apply { left, right, .. }
+            | PhysicalNode::HashIntersect { left, right, .. }
+            | PhysicalNode::HashExcept { left, right, .. }
+            | PhysicalNode::RecursiveUnion {
+                base: left,
+                recursive: right,
+                ..
+            } => {
+                *left = new_inputs[0];
+                *right = new_inputs[1];
+            }
+            PhysicalNode::Union { inputs } => *inputs = new_inputs.to_vec(),
+        }
+        node
+    }
+}
+
+/// Properties of a physical node computed during lowering.
+#[derive(Clone, Debug)]
+pub struct PhysicalProperties {
+    /// Estimated number of rows produced by the node.
+    pub cardinality: f64,
+    /// Estimated cost of the node, including the cost of its inputs.
+    pub cost: f64,
+    /// The order of the rows produced by the node.
+    pub ordering: Ordering,
+}
+
+/// A physical plan, with the plan for the main query and one plan for each of its
+/// subqueries.
+pub struct PhysicalPlan {
+    nodes: Vec<PhysicalNode>,
+    properties: Vec<PhysicalProperties>,
+    pub(crate) root: PhysicalNodeId,
+    /// The root of the plan of each subquery, keyed by the ID of the logical subquery
+    /// root node it was lowered from, which is the ID scalar subquery expressions
+    /// refer to.
+    pub(crate) subqueries: Vec<(NodeId, PhysicalNodeId)>,
+}
+
+impl PhysicalPlan {
+    pub(crate) fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            properties: Vec::new(),
+            root: 0,
+            subqueries: Vec::new(),
+        }
+    }
+
+    pub(crate) fn add_node(
+        &mut self,
+        node: PhysicalNode,
+        properties: PhysicalProperties,
+    ) -> PhysicalNodeId {
+        self.nodes.push(node);
+        self.properties.push(properties);
+        self.nodes.len() - 1
+    }
+
+    pub fn root(&self) -> PhysicalNodeId {
+        self.root
+    }
+
+    pub fn subqueries(&self) -> &[(NodeId, PhysicalNodeId)] {
+        &self.subqueries
+    }
+
+    pub fn node(&self, node_id: PhysicalNodeId) -> &PhysicalNode {
+        &self.nodes[node_id]
+    }
+
+    pub fn properties(&self, node_id: PhysicalNodeId) -> &PhysicalProperties {
+        &self.properties[node_id]
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+}
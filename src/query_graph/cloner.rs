@@ -63,7 +63,9 @@ where
             QueryNode::TableScan {
                 table_id: _,
                 row_type: _,
-            } => {}
+            }
+            | QueryNode::Values { .. }
+            | QueryNode::RecursiveReference { .. } => {}
             QueryNode::Join {
                 join_type: _,
                 conditions,
@@ -80,11 +82,22 @@ where
                 input,
             } => *input = inputs[0],
             QueryNode::Union { inputs: inputs_ref } => *inputs_ref = inputs.to_vec(),
+            QueryNode::Sort { input, .. }
+            | QueryNode::Limit { input, .. }
+            | QueryNode::TopN { input, .. }
+            | QueryNode::Window { input, .. } => *input = inputs[0],
             QueryNode::Apply {
                 correlation: _,
                 left,
                 right,
                 apply_type: _,
+            }
+            | QueryNode::Intersect { left, right, .. }
+            | QueryNode::Except { left, right, .. }
+            | QueryNode::RecursiveUnion {
+                base: left,
+                recursive: right,
+                ..
             } => {
                 *left = inputs[0];
                 *right = inputs[1];
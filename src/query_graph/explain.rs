@@ -1,6 +1,6 @@
 use crate::query_graph::visitor::*;
 use crate::query_graph::*;
-use crate::scalar_expr::ScalarExpr;
+use crate::scalar_expr::{ScalarExpr, WindowExprRef};
 use crate::visitor_utils::PreOrderVisitationResult;
 
 use super::properties::default_annotators;
@@ -135,6 +135,9 @@ impl<'a> QueryGraphPrePostVisitor for ExplainVisitor<'a> {
             QueryNode::TableScan { table_id, .. } => {
                 format!("{}TableScan id: {}\n", prefix, table_id)
             }
+            QueryNode::Values { rows, .. } => {
+                format!("{}Values [{}]\n", prefix, explain_values(rows))
+            }
             QueryNode::Join {
                 join_type,
                 conditions,
@@ -166,6 +169,29 @@ impl<'a> QueryGraphPrePostVisitor for ExplainVisitor<'a> {
                     .join(", "),
             ),
             QueryNode::Union { .. } => format!("{}Union\n", prefix),
+            QueryNode::Intersect { distinct, .. } => {
+                format!(
+                    "{}{}\n",
+                    prefix,
+                    explain_set_operation("Intersect", *distinct)
+                )
+            }
+            QueryNode::Except { distinct, .. } => {
+                format!("{}{}\n", prefix, explain_set_operation("Except", *distinct))
+            }
+            QueryNode::RecursiveUnion {
+                recursion_id,
+                distinct,
+                max_iterations,
+                ..
+            } => format!(
+                "{}{}\n",
+                prefix,
+                explain_recursive_union(*recursion_id, *distinct, *max_iterations)
+            ),
+            QueryNode::RecursiveReference { recursion_id, .. } => {
+                format!("{}RecursiveReference id: {}\n", prefix, recursion_id)
+            }
             QueryNode::SubqueryRoot { .. } => format!("{}SubqueryRoot\n", prefix),
             QueryNode::Apply {
                 correlation,
@@ -179,6 +205,34 @@ impl<'a> QueryGraphPrePostVisitor for ExplainVisitor<'a> {
                     explain_scalar_expr_vec(&correlation.parameters),
                 )
             }
+            QueryNode::Sort { keys, .. } => {
+                format!("{}Sort [{}]\n", prefix, explain_sort_keys(keys))
+            }
+            QueryNode::Limit { offset, limit, .. } => {
+                format!("{}Limit {}\n", prefix, explain_limit(*offset, *limit))
+            }
+            QueryNode::TopN {
+                partition_key,
+                keys,
+                offset,
+                limit,
+                ..
+            } => format!(
+                "{}TopN {}\n",
+                prefix,
+                explain_top_n(partition_key, keys, *offset, *limit)
+            ),
+            QueryNode::Window {
+                partition_key,
+                order_key,
+                frame,
+                functions,
+                ..
+            } => format!(
+                "{}Window {}\n",
+                prefix,
+                explain_window(partition_key, order_key, frame, functions)
+            ),
         };
         self.result += &node;
 
@@ -200,6 +254,95 @@ impl<'a> QueryGraphPrePostVisitor for ExplainVisitor<'a> {
     }
 }
 
+pub(crate) fn explain_sort_keys(keys: &[SortKey]) -> String {
+    keys.iter()
+        .map(|key| format!("{}", key))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub(crate) fn explain_limit(offset: usize, limit: Option<usize>) -> String {
+    match limit {
+        Some(limit) => format!("offset: {}, limit: {}", offset, limit),
+        None => format!("offset: {}", offset),
+    }
+}
+
+pub(crate) fn explain_set_operation(name: &str, distinct: bool) -> String {
+    format!("{} {}", name, if distinct { "Distinct" } else { "All" })
+}
+
+pub(crate) fn explain_values(rows: &[Vec<Literal>]) -> String {
+    rows.iter()
+        .map(|row| format!("({})", row.iter().join(", ")))
+        .join(", ")
+}
+
+pub(crate) fn explain_recursive_union(
+    recursion_id: usize,
+    distinct: bool,
+    max_iterations: Option<usize>,
+) -> String {
+    let mut result = format!(
+        "{} id: {}",
+        explain_set_operation("RecursiveUnion", distinct),
+        recursion_id
+    );
+    if let Some(max_iterations) = max_iterations {
+        result += &format!(", max iterations: {}", max_iterations);
+    }
+    result
+}
+
+pub(crate) fn explain_top_n(
+    partition_key: &[usize],
+    keys: &[SortKey],
+    offset: usize,
+    limit: usize,
+) -> String {
+    if partition_key.is_empty() {
+        format!(
+            "[{}], {}",
+            explain_sort_keys(keys),
+            explain_limit(offset, Some(limit))
+        )
+    } else {
+        format!(
+            "partition: [{}], order: [{}], {}",
+            explain_column_list(partition_key),
+            explain_sort_keys(keys),
+            explain_limit(offset, Some(limit))
+        )
+    }
+}
+
+pub(crate) fn explain_window(
+    partition_key: &[usize],
+    order_key: &[SortKey],
+    frame: &WindowFrame,
+    functions: &[WindowExprRef],
+) -> String {
+    format!(
+        "partition: [{}], order: [{}], frame: {}, functions: [{}]",
+        explain_column_list(partition_key),
+        explain_sort_keys(order_key),
+        frame,
+        functions
+            .iter()
+            .map(|f| format!("{}", f))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+pub(crate) fn explain_column_list(columns: &[usize]) -> String {
+    columns
+        .iter()
+        .map(|c| format!("{}", ScalarExpr::input_ref(*c)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 pub(crate) fn explain_scalar_expr_vec(vec: &Vec<ScalarExprRef>) -> String {
     vec.iter()
         .map(|e| format!("{}", e))
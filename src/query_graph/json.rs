@@ -2,7 +2,13 @@
 use std::collections::VecDeque;
 
 use crate::{
-    query_graph::{explain::explain_scalar_expr_vec, *},
+    query_graph::{
+        explain::{
+            explain_limit, explain_recursive_union, explain_scalar_expr_vec, explain_set_operation,
+            explain_sort_keys, explain_top_n, explain_values, explain_window,
+        },
+        *,
+    },
     scalar_expr::ScalarExpr,
     visitor_utils::PreOrderVisitationResult,
 };
@@ -82,6 +88,9 @@ impl<'a> QueryGraphPrePostVisitor for JsonSerializer<'a> {
             QueryNode::TableScan { table_id, .. } => {
                 format!("{}TableScan id: {}", prefix, table_id)
             }
+            QueryNode::Values { rows, .. } => {
+                format!("{}Values [{}]", prefix, explain_values(rows))
+            }
             QueryNode::Join {
                 join_type,
                 conditions,
@@ -113,6 +122,29 @@ impl<'a> QueryGraphPrePostVisitor for JsonSerializer<'a> {
                     .join(", "),
             ),
             QueryNode::Union { .. } => format!("{}Union", prefix),
+            QueryNode::Intersect { distinct, .. } => {
+                format!(
+                    "{}{}",
+                    prefix,
+                    explain_set_operation("Intersect", *distinct)
+                )
+            }
+            QueryNode::Except { distinct, .. } => {
+                format!("{}{}", prefix, explain_set_operation("Except", *distinct))
+            }
+            QueryNode::RecursiveUnion {
+                recursion_id,
+                distinct,
+                max_iterations,
+                ..
+            } => format!(
+                "{}{}",
+                prefix,
+                explain_recursive_union(*recursion_id, *distinct, *max_iterations)
+            ),
+            QueryNode::RecursiveReference { recursion_id, .. } => {
+                format!("{}RecursiveReference id: {}", prefix, recursion_id)
+            }
             QueryNode::SubqueryRoot { .. } => format!("{}SubqueryRoot", prefix),
             QueryNode::Apply {
                 correlation,
@@ -126,6 +158,34 @@ impl<'a> QueryGraphPrePostVisitor for JsonSerializer<'a> {
                     explain_scalar_expr_vec(&correlation.parameters),
                 )
             }
+            QueryNode::Sort { keys, .. } => {
+                format!("{}Sort [{}]", prefix, explain_sort_keys(keys))
+            }
+            QueryNode::Limit { offset, limit, .. } => {
+                format!("{}Limit {}", prefix, explain_limit(*offset, *limit))
+            }
+            QueryNode::TopN {
+                partition_key,
+                keys,
+                offset,
+                limit,
+                ..
+            } => format!(
+                "{}TopN {}",
+                prefix,
+                explain_top_n(partition_key, keys, *offset, *limit)
+            ),
+            QueryNode::Window {
+                partition_key,
+                order_key,
+                frame,
+                functions,
+                ..
+            } => format!(
+                "{}Window {}",
+                prefix,
+                explain_window(partition_key, order_key, frame, functions)
+            ),
         };
         let mut annotations = Vec::new();
         for annotator in self.annotators.iter() {
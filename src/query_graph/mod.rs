@@ -1,11 +1,13 @@
 use itertools::Itertools;
 
 use crate::{
+    catalog::{Catalog, TableMetadata},
     data_type::DataType,
     scalar_expr::{
         rewrite::RewritableExpr, visitor::visit_expr_pre, AggregateExprRef, ScalarExpr,
-        ScalarExprRef, VisitableExpr,
+        ScalarExprRef, VisitableExpr, WindowExprRef,
     },
+    value::Literal,
     visitor_utils::PreOrderVisitationResult,
 };
 use std::{
@@ -22,11 +24,12 @@ pub mod explain;
 pub mod json;
 pub mod optimizer;
 pub mod properties;
+pub mod validator;
 pub mod visitor;
 
 pub type NodeId = usize;
 
-#[derive(Clone, PartialEq, Eq, Copy)]
+#[derive(Clone, PartialEq, Eq, Copy, Hash)]
 pub enum JoinType {
     Inner,
     LeftOuter,
@@ -38,18 +41,146 @@ pub enum JoinType {
     Anti,
 }
 
-#[derive(Clone, PartialEq, Eq, Copy)]
+#[derive(Clone, PartialEq, Eq, Copy, Hash)]
 pub enum ApplyType {
     Inner,
     LeftOuter,
 }
 
+/// A column a relation is sorted by.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct SortKey {
+    pub column: usize,
+    pub ascending: bool,
+}
+
+impl SortKey {
+    pub fn asc(column: usize) -> Self {
+        Self {
+            column,
+            ascending: true,
+        }
+    }
+}
+
+impl fmt::Display for SortKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {}",
+            ScalarExpr::input_ref(self.column),
+            if self.ascending { "ASC" } else { "DESC" }
+        )
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub enum WindowFrameUnits {
+    /// The bounds of the frame are given in number of rows.
+    Rows,
+    /// The bounds of the frame are given in terms of the values of the order key.
+    /// The current row includes all its peers.
+    Range,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub enum WindowFrameBound {
+    UnboundedPreceding,
+    Preceding(usize),
+    CurrentRow,
+    Following(usize),
+    UnboundedFollowing,
+}
+
+/// The set of rows within the partition of the current row aggregate window
+/// functions are computed over.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct WindowFrame {
+    pub units: WindowFrameUnits,
+    pub start: WindowFrameBound,
+    pub end: WindowFrameBound,
+}
+
+impl WindowFrameBound {
+    /// The position of the bound relative to the current row.
+    fn relative_position(&self) -> i128 {
+        match self {
+            WindowFrameBound::UnboundedPreceding => i128::MIN,
+            WindowFrameBound::Preceding(n) => -(*n as i128),
+            WindowFrameBound::CurrentRow => 0,
+            WindowFrameBound::Following(n) => *n as i128,
+            WindowFrameBound::UnboundedFollowing => i128::MAX,
+        }
+    }
+}
+
+impl WindowFrame {
+    /// Whether the frame is well-formed: its start doesn't come after its end, it
+    /// doesn't start at the end or end at the start of the partition, and RANGE frames
+    /// don't use offsets, since they are not supported.
+    pub fn is_valid(&self) -> bool {
+        self.start != WindowFrameBound::UnboundedFollowing
+            && self.end != WindowFrameBound::UnboundedPreceding
+            && self.start.relative_position() <= self.end.relative_position()
+            && (self.units == WindowFrameUnits::Rows
+                || [self.start, self.end].iter().all(|bound| {
+                    !matches!(
+                        bound,
+                        WindowFrameBound::Preceding(_) | WindowFrameBound::Following(_)
+                    )
+                }))
+    }
+
+    /// Whether the current row is always part of the frame.
+    pub fn includes_current_row(&self) -> bool {
+        self.start <= WindowFrameBound::CurrentRow && self.end >= WindowFrameBound::CurrentRow
+    }
+}
+
+impl Default for WindowFrame {
+    /// `RANGE BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW`, the default frame in SQL.
+    fn default() -> Self {
+        Self {
+            units: WindowFrameUnits::Range,
+            start: WindowFrameBound::UnboundedPreceding,
+            end: WindowFrameBound::CurrentRow,
+        }
+    }
+}
+
+impl fmt::Display for WindowFrameBound {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WindowFrameBound::UnboundedPreceding => write!(f, "UNBOUNDED PRECEDING"),
+            WindowFrameBound::Preceding(n) => write!(f, "{} PRECEDING", n),
+            WindowFrameBound::CurrentRow => write!(f, "CURRENT ROW"),
+            WindowFrameBound::Following(n) => write!(f, "{} FOLLOWING", n),
+            WindowFrameBound::UnboundedFollowing => write!(f, "UNBOUNDED FOLLOWING"),
+        }
+    }
+}
+
+impl fmt::Display for WindowFrame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} BETWEEN {} AND {}",
+            match self.units {
+                WindowFrameUnits::Rows => "ROWS",
+                WindowFrameUnits::Range => "RANGE",
+            },
+            self.start,
+            self.end
+        )
+    }
+}
+
 #[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub struct CorrelationContext<E: VisitableExpr + RewritableExpr> {
     pub parameters: Vec<Rc<E>>,
 }
 
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub enum QueryNode {
     QueryRoot {
         input: Option<NodeId>,
@@ -66,6 +197,12 @@ pub enum QueryNode {
         table_id: usize,
         row_type: Rc<Vec<DataType>>,
     },
+    /// Relation made of the given rows of literals. An empty list of rows represents
+    /// the empty relation.
+    Values {
+        row_type: Rc<Vec<DataType>>,
+        rows: Vec<Vec<Literal>>,
+    },
     Join {
         join_type: JoinType,
         conditions: Vec<ScalarExprRef>,
@@ -80,6 +217,24 @@ pub enum QueryNode {
     Union {
         inputs: Vec<NodeId>,
     },
+    /// Returns the rows of its left input that are also present in its right input.
+    /// Rows are compared as in `IS NOT DISTINCT FROM`. If `distinct` is false, each row
+    /// is returned as many times as the minimum number of times it appears in both
+    /// inputs. Otherwise, duplicated rows are returned only once.
+    Intersect {
+        distinct: bool,
+        left: NodeId,
+        right: NodeId,
+    },
+    /// Returns the rows of its left input that are not present in its right input.
+    /// Rows are compared as in `IS NOT DISTINCT FROM`. If `distinct` is false, each row
+    /// of the right input cancels one occurrence of the same row in the left input.
+    /// Otherwise, duplicated rows are returned only once.
+    Except {
+        distinct: bool,
+        left: NodeId,
+        right: NodeId,
+    },
     /// Subgraph root.
     SubqueryRoot {
         input: NodeId,
@@ -90,6 +245,64 @@ pub enum QueryNode {
         right: NodeId,
         apply_type: ApplyType,
     },
+    /// Sorts the rows of its input by the given keys.
+    Sort {
+        keys: Vec<SortKey>,
+        input: NodeId,
+    },
+    /// Skips the first `offset` rows of its input and returns at most `limit` rows
+    /// among the remaining ones.
+    Limit {
+        offset: usize,
+        limit: Option<usize>,
+        input: NodeId,
+    },
+    /// Returns the rows of its input between `offset` and `offset + limit` when
+    /// sorted by the given keys. Equivalent to a limit over a sort.
+    ///
+    /// If a partition key is given, the offset and the limit are applied to each group
+    /// of rows with the same values for the partition key, and the rows are returned
+    /// sorted by the partition key first.
+    TopN {
+        partition_key: Vec<usize>,
+        keys: Vec<SortKey>,
+        offset: usize,
+        limit: usize,
+        input: NodeId,
+    },
+    /// Computes the given window functions over the rows of its input grouped by the
+    /// partition key and sorted by the order key. Projects the columns of its input
+    /// followed by one column per window function. The rows are returned sorted by the
+    /// partition key and then by the order key.
+    Window {
+        partition_key: Vec<usize>,
+        order_key: Vec<SortKey>,
+        frame: WindowFrame,
+        functions: Vec<WindowExprRef>,
+        input: NodeId,
+    },
+    /// Returns the rows of its base input followed by the rows produced by repeatedly
+    /// evaluating its recursive input, until no new rows are produced or `max_iterations`
+    /// is reached. Within the recursive input, the `RecursiveReference` nodes with the
+    /// same `recursion_id` return the rows produced by the previous iteration.
+    ///
+    /// If `distinct` is true, duplicated rows are removed, and only the rows that were
+    /// not returned before are fed into the next iteration.
+    RecursiveUnion {
+        recursion_id: usize,
+        distinct: bool,
+        max_iterations: Option<usize>,
+        base: NodeId,
+        recursive: NodeId,
+    },
+    /// Leaf node returning the rows produced by the previous iteration of the closest
+    /// enclosing `RecursiveUnion` with the same `recursion_id`. Since it refers back to
+    /// the recursive union by ID rather than through its inputs, the graph remains
+    /// acyclic.
+    RecursiveReference {
+        recursion_id: usize,
+        row_type: Rc<Vec<DataType>>,
+    },
 }
 
 pub struct QueryGraph {
@@ -103,6 +316,8 @@ pub struct QueryGraph {
     parents: HashMap<NodeId, BTreeSet<NodeId>>,
     /// Subqueries
     subqueries: Vec<NodeId>,
+    /// The catalog the tables scanned by the query belong to, if any.
+    catalog: Option<Rc<Catalog>>,
     /// Keeps track of the number of node replacements the query graph has gone through.
     pub gen_number: usize,
     pub property_cache: RefCell<PropertyCache>,
@@ -113,9 +328,18 @@ impl QueryNode {
     pub fn num_inputs(&self) -> usize {
         match self {
             Self::QueryRoot { input } => input.map(|_| 1).unwrap_or(0),
-            Self::Project { .. } | Self::Filter { .. } | Self::Aggregate { .. } => 1,
-            Self::TableScan { .. } => 0,
-            Self::Join { .. } => 2,
+            Self::Project { .. }
+            | Self::Filter { .. }
+            | Self::Aggregate { .. }
+            | Self::Sort { .. }
+            | Self::Limit { .. }
+            | Self::TopN { .. }
+            | Self::Window { .. } => 1,
+            Self::TableScan { .. } | Self::Values { .. } | Self::RecursiveReference { .. } => 0,
+            Self::Join { .. }
+            | Self::Intersect { .. }
+            | Self::Except { .. }
+            | Self::RecursiveUnion { .. } => 2,
             Self::Union { inputs } => inputs.len(),
             Self::SubqueryRoot { .. } => 1,
             Self::Apply { .. } => 2,
@@ -131,9 +355,23 @@ impl QueryNode {
             Self::Project { input, .. }
             | Self::Filter { input, .. }
             | Self::Aggregate { input, .. }
+            | Self::Sort { input, .. }
+            | Self::Limit { input, .. }
+            | Self::TopN { input, .. }
+            | Self::Window { input, .. }
             | Self::SubqueryRoot { input } => *input,
-            Self::TableScan { .. } => panic!(),
-            Self::Join { left, right, .. } | Self::Apply { left, right, .. } => {
+            Self::TableScan { .. } | Self::Values { .. } | Self::RecursiveReference { .. } => {
+                panic!()
+            }
+            Self::Join { left, right, .. }
+            | Self::Apply { left, right, .. }
+            | Self::Intersect { left, right, .. }
+            | Self::Except { left, right, .. }
+            | Self::RecursiveUnion {
+                base: left,
+                recursive: right,
+                ..
+            } => {
                 if input_idx == 0 {
                     *left
                 } else {
@@ -145,7 +383,7 @@ impl QueryNode {
     }
 
     /// Private method to set an input of this node. It is only meant to be called within
-    /// `QueryGraph::replace_node`.
+    /// `QueryGraph::replace_node` or on detached copies of the nodes in the graph.
     fn set_input(&mut self, input_idx: usize, node_id: NodeId) {
         assert!(input_idx < self.num_inputs());
 
@@ -154,9 +392,23 @@ impl QueryNode {
             Self::Project { input, .. }
             | Self::Filter { input, .. }
             | Self::Aggregate { input, .. }
+            | Self::Sort { input, .. }
+            | Self::Limit { input, .. }
+            | Self::TopN { input, .. }
+            | Self::Window { input, .. }
             | Self::SubqueryRoot { input } => *input = node_id,
-            Self::TableScan { .. } => panic!(),
-            Self::Join { left, right, .. } | Self::Apply { left, right, .. } => {
+            Self::TableScan { .. } | Self::Values { .. } | Self::RecursiveReference { .. } => {
+                panic!()
+            }
+            Self::Join { left, right, .. }
+            | Self::Apply { left, right, .. }
+            | Self::Intersect { left, right, .. }
+            | Self::Except { left, right, .. }
+            | Self::RecursiveUnion {
+                base: left,
+                recursive: right,
+                ..
+            } => {
                 if input_idx == 0 {
                     *left = node_id
                 } else {
@@ -186,9 +438,18 @@ impl QueryNode {
             }
             QueryNode::QueryRoot { .. }
             | QueryNode::TableScan { .. }
+            | QueryNode::Values { .. }
             | QueryNode::Aggregate { .. }
             | QueryNode::Union { .. }
-            | QueryNode::SubqueryRoot { .. } => {}
+            | QueryNode::Intersect { .. }
+            | QueryNode::Except { .. }
+            | QueryNode::SubqueryRoot { .. }
+            | QueryNode::Sort { .. }
+            | QueryNode::Limit { .. }
+            | QueryNode::TopN { .. }
+            | QueryNode::Window { .. }
+            | QueryNode::RecursiveUnion { .. }
+            | QueryNode::RecursiveReference { .. } => {}
             QueryNode::Apply { correlation, .. } => {
                 for expr in correlation.parameters.iter() {
                     visitor(expr);
@@ -208,6 +469,7 @@ impl QueryNode {
                 match curr_expr.as_ref() {
                     ScalarExpr::Literal(_)
                     | ScalarExpr::InputRef { .. }
+                    | ScalarExpr::UnaryOp { .. }
                     | ScalarExpr::BinaryOp { .. }
                     | ScalarExpr::NaryOp { .. }
                     | ScalarExpr::CorrelatedInputRef { .. } => {}
@@ -234,10 +496,31 @@ impl QueryGraph {
             gen_number: 0,
             parents: HashMap::new(),
             subqueries: Vec::new(),
+            catalog: None,
             property_cache: RefCell::new(PropertyCache::new()),
         }
     }
 
+    /// Creates an empty query graph whose table scans refer to the tables in
+    /// the given catalog.
+    pub fn with_catalog(catalog: Rc<Catalog>) -> QueryGraph {
+        let mut query_graph = Self::new();
+        query_graph.catalog = Some(catalog);
+        query_graph
+    }
+
+    pub fn catalog(&self) -> Option<&Rc<Catalog>> {
+        self.catalog.as_ref()
+    }
+
+    /// Returns the catalog metadata of the given table, if the query graph has
+    /// a catalog and the table is registered in it.
+    pub fn table_metadata(&self, table_id: usize) -> Option<&TableMetadata> {
+        self.catalog
+            .as_ref()
+            .and_then(|catalog| catalog.table(table_id))
+    }
+
     pub fn set_entry_node(&mut self, entry_node: NodeId) {
         match self.nodes.get_mut(&Self::ROOT_NODE_ID).unwrap() {
             QueryNode::QueryRoot { input } => {
@@ -450,7 +733,6 @@ impl QueryGraph {
         referenced_subqueries
     }
 
-    #[allow(dead_code)]
     fn collect_attached_nodes(&self) -> HashSet<NodeId> {
         let mut attached_nodes = HashSet::new();
         let mut queue = VecDeque::from([Self::ROOT_NODE_ID]);
@@ -487,6 +769,70 @@ impl QueryGraph {
             detached_nodes.iter().join(", "),
             self.explain()
         );
+        // A recursive reference is also detached if it is no longer under the
+        // recursive union it refers to.
+        let unbound_references = self.unbound_recursive_references();
+        assert!(
+            unbound_references.is_empty(),
+            "Unbound recursive references {}\n{}",
+            unbound_references.iter().join(", "),
+            self.explain()
+        );
+    }
+
+    /// Returns the recursive references bound to the given recursive union, ie. the
+    /// ones with the same recursion ID under its recursive input, except for the ones
+    /// shadowed by a nested recursive union with the same recursion ID.
+    pub fn recursive_references(&self, node_id: NodeId) -> BTreeSet<NodeId> {
+        let mut references = BTreeSet::new();
+        let QueryNode::RecursiveUnion {
+            recursion_id,
+            recursive,
+            ..
+        } = self.node(node_id)
+        else {
+            return references;
+        };
+        let mut visited_nodes = HashSet::new();
+        let mut stack = vec![*recursive];
+        while let Some(current) = stack.pop() {
+            if !visited_nodes.insert(current) {
+                continue;
+            }
+            match self.node(current) {
+                QueryNode::RecursiveReference {
+                    recursion_id: current_recursion_id,
+                    ..
+                } if current_recursion_id == recursion_id => {
+                    references.insert(current);
+                }
+                QueryNode::RecursiveUnion {
+                    recursion_id: current_recursion_id,
+                    base,
+                    ..
+                } if current_recursion_id == recursion_id => stack.push(*base),
+                node => stack.extend((0..node.num_inputs()).map(|input| node.get_input(input))),
+            }
+        }
+        references
+    }
+
+    /// Returns the recursive references attached to the plan that are not bound to
+    /// any recursive union, sorted by node ID.
+    pub(crate) fn unbound_recursive_references(&self) -> Vec<NodeId> {
+        let attached_nodes = self.collect_attached_nodes();
+        let bound_references = attached_nodes
+            .iter()
+            .flat_map(|node_id| self.recursive_references(*node_id))
+            .collect::<HashSet<_>>();
+        attached_nodes
+            .into_iter()
+            .filter(|node_id| {
+                matches!(self.node(*node_id), QueryNode::RecursiveReference { .. })
+                    && !bound_references.contains(node_id)
+            })
+            .sorted()
+            .collect()
     }
 }
 
@@ -499,6 +845,30 @@ impl QueryGraph {
         })
     }
 
+    /// Adds a scan of a table registered in the catalog of the query graph,
+    /// using the column types from the table metadata.
+    ///
+    /// Panics if the table is not in the catalog.
+    pub fn catalog_table_scan(&mut self, table_id: usize) -> NodeId {
+        let row_type = match self.table_metadata(table_id) {
+            Some(table) => table.row_type(),
+            None => panic!("table {} not in catalog", table_id),
+        };
+        self.add_node(QueryNode::TableScan {
+            table_id,
+            row_type: Rc::new(row_type),
+        })
+    }
+
+    pub fn values(&mut self, row_type: Rc<Vec<DataType>>, rows: Vec<Vec<Literal>>) -> NodeId {
+        self.add_node(QueryNode::Values { row_type, rows })
+    }
+
+    /// Adds a relation with the given row type that never produces any row.
+    pub fn empty_relation(&mut self, row_type: Rc<Vec<DataType>>) -> NodeId {
+        self.values(row_type, Vec::new())
+    }
+
     pub fn filter(&mut self, input: NodeId, conditions: Vec<ScalarExprRef>) -> NodeId {
         if conditions.is_empty() {
             input
@@ -511,6 +881,35 @@ impl QueryGraph {
         self.add_node(QueryNode::Project { outputs, input })
     }
 
+    pub fn sort(&mut self, input: NodeId, keys: Vec<SortKey>) -> NodeId {
+        self.add_node(QueryNode::Sort { keys, input })
+    }
+
+    pub fn limit(&mut self, input: NodeId, offset: usize, limit: Option<usize>) -> NodeId {
+        self.add_node(QueryNode::Limit {
+            offset,
+            limit,
+            input,
+        })
+    }
+
+    pub fn window(
+        &mut self,
+        input: NodeId,
+        partition_key: Vec<usize>,
+        order_key: Vec<SortKey>,
+        frame: WindowFrame,
+        functions: Vec<WindowExprRef>,
+    ) -> NodeId {
+        self.add_node(QueryNode::Window {
+            partition_key,
+            order_key,
+            frame,
+            functions,
+            input,
+        })
+    }
+
     pub fn inner_join(
         &mut self,
         left: NodeId,
@@ -534,6 +933,46 @@ impl QueryGraph {
             conditions,
         })
     }
+
+    pub fn intersect(&mut self, left: NodeId, right: NodeId, distinct: bool) -> NodeId {
+        self.add_node(QueryNode::Intersect {
+            distinct,
+            left,
+            right,
+        })
+    }
+
+    pub fn except(&mut self, left: NodeId, right: NodeId, distinct: bool) -> NodeId {
+        self.add_node(QueryNode::Except {
+            distinct,
+            left,
+            right,
+        })
+    }
+
+    pub fn recursive_union(
+        &mut self,
+        recursion_id: usize,
+        base: NodeId,
+        recursive: NodeId,
+        distinct: bool,
+        max_iterations: Option<usize>,
+    ) -> NodeId {
+        self.add_node(QueryNode::RecursiveUnion {
+            recursion_id,
+            distinct,
+            max_iterations,
+            base,
+            recursive,
+        })
+    }
+
+    pub fn recursive_reference(&mut self, recursion_id: usize, row_type: Vec<DataType>) -> NodeId {
+        self.add_node(QueryNode::RecursiveReference {
+            recursion_id,
+            row_type: Rc::new(row_type),
+        })
+    }
 }
 
 impl Clone for QueryGraph {
@@ -544,6 +983,7 @@ impl Clone for QueryGraph {
             gen_number: self.gen_number,
             parents: self.parents.clone(),
             subqueries: self.subqueries.clone(),
+            catalog: self.catalog.clone(),
             // Cached metadata is not cloned
             property_cache: RefCell::new(PropertyCache::new()),
         }
@@ -665,10 +1105,30 @@ impl fmt::Display for ApplyType {
 
 #[cfg(test)]
 mod tests {
-    use crate::scalar_expr::ScalarExpr;
+    use crate::{catalog::ColumnMetadata, scalar_expr::ScalarExpr};
 
     use super::*;
 
+    #[test]
+    fn test_catalog_table_scan() {
+        let mut catalog = Catalog::new();
+        let table_id = catalog.add_table(
+            "t",
+            vec![
+                ColumnMetadata::new("a", DataType::Int, false),
+                ColumnMetadata::new("b", DataType::String, true),
+            ],
+        );
+        let mut query_graph = QueryGraph::with_catalog(Rc::new(catalog));
+        let table_scan_id = query_graph.catalog_table_scan(table_id);
+        assert_eq!(
+            properties::row_type(&query_graph, table_scan_id).as_ref(),
+            &vec![DataType::Int, DataType::String]
+        );
+        assert_eq!(query_graph.table_metadata(table_id).unwrap().name, "t");
+        assert!(query_graph.clone().table_metadata(table_id).is_some());
+    }
+
     #[test]
     fn test_add_node() {
         let mut query_graph = QueryGraph::new();
@@ -0,0 +1,62 @@
+use itertools::Itertools;
+
+use crate::{
+    query_graph::{
+        properties::{cardinality_estimate, estimated_cardinality, estimated_selectivity},
+        NodeId, QueryGraph,
+    },
+    scalar_expr::ScalarExprRef,
+};
+
+/// The estimations the join enumerator relies on to compare join orders.
+pub trait JoinCostModel {
+    /// Estimated number of rows produced by the given node, which is a relation of
+    /// the join graph.
+    fn relation_cardinality(&self, query_graph: &QueryGraph, node_id: NodeId) -> f64;
+
+    /// Estimated fraction of rows satisfying the given predicate, which references the
+    /// columns of the given relations as if they were all concatenated.
+    fn selectivity(
+        &self,
+        query_graph: &QueryGraph,
+        relations: &[NodeId],
+        predicate: &ScalarExprRef,
+    ) -> f64;
+
+    /// Cost of joining two sub-plans with the given costs, producing the given number
+    /// of rows. By default, the cost of a plan is the sum of the cardinalities of its
+    /// joins, ie. the C_out cost function.
+    fn join_cost(&self, left_cost: f64, right_cost: f64, output_cardinality: f64) -> f64 {
+        left_cost + right_cost + output_cardinality
+    }
+}
+
+/// Cost model based on the cardinality estimation property, which relies on the
+/// statistics of the tables in the catalog.
+#[derive(Default)]
+pub struct DefaultJoinCostModel {}
+
+impl DefaultJoinCostModel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl JoinCostModel for DefaultJoinCostModel {
+    fn relation_cardinality(&self, query_graph: &QueryGraph, node_id: NodeId) -> f64 {
+        estimated_cardinality(query_graph, node_id)
+    }
+
+    fn selectivity(
+        &self,
+        query_graph: &QueryGraph,
+        relations: &[NodeId],
+        predicate: &ScalarExprRef,
+    ) -> f64 {
+        let columns = relations
+            .iter()
+            .flat_map(|node_id| cardinality_estimate(query_graph, *node_id).columns.clone())
+            .collect_vec();
+        estimated_selectivity(predicate, &columns)
+    }
+}
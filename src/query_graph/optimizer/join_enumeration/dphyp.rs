@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+
+use crate::query_graph::QueryGraph;
+
+use super::{
+    cost::JoinCostModel,
+    join_graph::{JoinGraph, JoinTree, RelationSet},
+};
+
+/// The best plan found for a connected set of relations.
+struct PlanEntry {
+    cost: f64,
+    cardinality: f64,
+    /// The sets of relations joined by the top-level join of the plan, or None for
+    /// single relations.
+    children: Option<(RelationSet, RelationSet)>,
+}
+
+/// Join enumerator implementing the DPhyp algorithm described in "Dynamic Programming
+/// Strikes Back" by Guido Moerkotte and Thomas Neumann.
+///
+/// It enumerates the pairs of connected sub-graphs of the join graph, in an order
+/// that ensures that the best plan for every sub-graph is known before being used
+/// for building the plans of bigger sub-graphs. Cross products are only considered
+/// between the disconnected components of the graph.
+pub struct DPhyp<'a> {
+    join_graph: &'a JoinGraph,
+    cost_model: &'a dyn JoinCostModel,
+    /// The estimated cardinality of each relation.
+    relation_cardinalities: Vec<f64>,
+    /// The estimated selectivity of each predicate.
+    selectivities: Vec<f64>,
+    plans: HashMap<RelationSet, PlanEntry>,
+}
+
+impl<'a> DPhyp<'a> {
+    pub fn new(
+        query_graph: &QueryGraph,
+        join_graph: &'a JoinGraph,
+        cost_model: &'a dyn JoinCostModel,
+    ) -> Self {
+        let relations = join_graph
+            .relations
+            .iter()
+            .map(|relation| relation.node_id)
+            .collect::<Vec<_>>();
+        Self {
+            join_graph,
+            cost_model,
+            relation_cardinalities: relations
+                .iter()
+                .map(|node_id| cost_model.relation_cardinality(query_graph, *node_id))
+                .collect(),
+            selectivities: join_graph
+                .predicates
+                .iter()
+                .map(|predicate| cost_model.selectivity(query_graph, &relations, &predicate.expr))
+                .collect(),
+            plans: HashMap::new(),
+        }
+    }
+
+    /// Returns the cheapest join tree for all the relations in the join graph and its
+    /// cost, or None if the graph cannot be fully joined without cross products not
+    /// present in the join graph.
+    pub fn solve(mut self) -> Option<(JoinTree, f64)> {
+        let num_relations = self.join_graph.relations.len();
+        for relation in 0..num_relations {
+            let set = 1 << relation;
+            self.plans.insert(
+                set,
+                PlanEntry {
+                    cost: 0.0,
+                    cardinality: self.cardinality(set),
+                    children: None,
+                },
+            );
+        }
+        for relation in (0..num_relations).rev() {
+            let set = 1 << relation;
+            self.emit_csg(set);
+            self.enumerate_csg_rec(set, lower_or_equal(relation));
+        }
+        let all_relations = (1 << num_relations) - 1;
+        let cost = self.plans.get(&all_relations)?.cost;
+        Some((self.join_tree(all_relations), cost))
+    }
+
+    /// Returns the cost of the given join tree.
+    pub fn tree_cost(&self, tree: &JoinTree) -> f64 {
+        match tree {
+            JoinTree::Relation(_) => 0.0,
+            JoinTree::Join(left, right) => self.cost_model.join_cost(
+                self.tree_cost(left),
+                self.tree_cost(right),
+                self.cardinality(tree.relations()),
+            ),
+        }
+    }
+
+    fn enumerate_csg_rec(&mut self, set: RelationSet, excluded: RelationSet) {
+        let neighborhood = self.join_graph.neighborhood(set, excluded);
+        for subset in subsets(neighborhood) {
+            if self.plans.contains_key(&(set | subset)) {
+                self.emit_csg(set | subset);
+            }
+        }
+        for subset in subsets(neighborhood) {
+            self.enumerate_csg_rec(set | subset, excluded | neighborhood);
+        }
+    }
+
+    fn emit_csg(&mut self, set: RelationSet) {
+        let excluded = set | lower_or_equal(set.trailing_zeros() as usize);
+        let neighborhood = self.join_graph.neighborhood(set, excluded);
+        for relation in elements(neighborhood).rev() {
+            let complement = 1 << relation;
+            if self.join_graph.are_connected(set, complement) {
+                self.emit_csg_cmp(set, complement);
+            }
+            self.enumerate_cmp_rec(
+                set,
+                complement,
+                excluded | (lower_or_equal(relation) & neighborhood),
+            );
+        }
+    }
+
+    fn enumerate_cmp_rec(
+        &mut self,
+        set: RelationSet,
+        complement: RelationSet,
+        excluded: RelationSet,
+    ) {
+        let neighborhood = self.join_graph.neighborhood(complement, excluded);
+        for subset in subsets(neighborhood) {
+            let new_complement = complement | subset;
+            if self.plans.contains_key(&new_complement)
+                && self.join_graph.are_connected(set, new_complement)
+            {
+                self.emit_csg_cmp(set, new_complement);
+            }
+        }
+        for subset in subsets(neighborhood) {
+            self.enumerate_cmp_rec(set, complement | subset, excluded | neighborhood);
+        }
+    }
+
+    /// Considers the two possible joins between the given pair of connected sub-graphs.
+    /// On ties, the input with the highest cardinality is kept on the left hand side.
+    fn emit_csg_cmp(&mut self, a: RelationSet, b: RelationSet) {
+        let set = a | b;
+        let (a, b) = if self.plans[&a].cardinality >= self.plans[&b].cardinality {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        let cardinality = self.cardinality(set);
+        for (left, right) in [(a, b), (b, a)] {
+            let cost = self.cost_model.join_cost(
+                self.plans[&left].cost,
+                self.plans[&right].cost,
+                cardinality,
+            );
+            if self.plans.get(&set).is_none_or(|plan| cost < plan.cost) {
+                self.plans.insert(
+                    set,
+                    PlanEntry {
+                        cost,
+                        cardinality,
+                        children: Some((left, right)),
+                    },
+                );
+            }
+        }
+    }
+
+    /// Estimated number of rows produced by joining the given set of relations, ie.
+    /// the product of their cardinalities and of the selectivities of the predicates
+    /// among them.
+    fn cardinality(&self, set: RelationSet) -> f64 {
+        let relations = elements(set)
+            .map(|relation| self.relation_cardinalities[relation])
+            .product::<f64>();
+        let selectivity = self
+            .join_graph
+            .predicates
+            .iter()
+            .zip(self.selectivities.iter())
+            .filter(|(predicate, _)| {
+                predicate.relations != 0 && predicate.relations & set == predicate.relations
+            })
+            .map(|(_, selectivity)| selectivity)
+            .product::<f64>();
+        relations * selectivity
+    }
+
+    fn join_tree(&self, set: RelationSet) -> JoinTree {
+        match self.plans[&set].children {
+            Some((left, right)) => JoinTree::Join(
+                Box::new(self.join_tree(left)),
+                Box::new(self.join_tree(right)),
+            ),
+            None => JoinTree::Relation(set.trailing_zeros() as usize),
+        }
+    }
+}
+
+/// The set of relations with an index lower or equal than the given one.
+fn lower_or_equal(relation: usize) -> RelationSet {
+    (1 << (relation + 1)) - 1
+}
+
+/// Iterates over the indexes of the relations in the given set, in ascending order.
+fn elements(set: RelationSet) -> impl DoubleEndedIterator<Item = usize> {
+    (0..RelationSet::BITS as usize).filter(move |relation| set & (1 << relation) != 0)
+}
+
+/// Iterates over the non-empty subsets of the given set, in ascending order.
+fn subsets(set: RelationSet) -> impl Iterator<Item = RelationSet> {
+    let mut subset: RelationSet = 0;
+    std::iter::from_fn(move || {
+        subset = subset.wrapping_sub(set) & set;
+        if subset == 0 {
+            None
+        } else {
+            Some(subset)
+        }
+    })
+}
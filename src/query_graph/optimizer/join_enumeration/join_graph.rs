@@ -0,0 +1,314 @@
+use crate::{
+    query_graph::{properties::num_columns, JoinType, NodeId, QueryGraph, QueryNode},
+    scalar_expr::{
+        rewrite::dereference_scalar_expr, visitor::collect_input_dependencies, ScalarExpr,
+        ScalarExprRef,
+    },
+};
+
+/// A set of relations of a join graph, represented as a bitmap where the i-th bit
+/// stands for the i-th relation.
+pub type RelationSet = u64;
+
+/// The maximum number of relations a join graph may contain, so that the enumeration
+/// of the join orders remains tractable.
+pub const MAX_RELATIONS: usize = 12;
+
+/// An input of the flattened join tree, ie. any node that is not an inner join or a
+/// filter or projection between two inner joins.
+pub struct Relation {
+    pub node_id: NodeId,
+    /// The position of the first column of the relation within the concatenation of
+    /// the columns of all the relations.
+    pub column_offset: usize,
+    pub num_columns: usize,
+}
+
+/// A predicate of the flattened join tree, either a join condition or a condition
+/// of a filter between two joins.
+pub struct JoinPredicate {
+    /// The predicate itself, referencing the columns of the relations, as if they were
+    /// all concatenated.
+    pub expr: ScalarExprRef,
+    /// The relations the predicate references.
+    pub relations: RelationSet,
+}
+
+/// An edge connecting two disjoint sets of relations. Predicates referencing more
+/// than two relations lead to hyperedges, where either side contains more than one
+/// relation.
+pub struct Hyperedge {
+    pub left: RelationSet,
+    pub right: RelationSet,
+}
+
+/// The shape of a join tree over the relations of a join graph.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum JoinTree {
+    Relation(usize),
+    Join(Box<JoinTree>, Box<JoinTree>),
+}
+
+impl JoinTree {
+    /// The relations joined by this tree.
+    pub fn relations(&self) -> RelationSet {
+        match self {
+            JoinTree::Relation(relation) => 1 << relation,
+            JoinTree::Join(left, right) => left.relations() | right.relations(),
+        }
+    }
+}
+
+/// The result of flattening a tree of inner joins: the relations being joined and
+/// the predicates among them.
+pub struct JoinGraph {
+    pub relations: Vec<Relation>,
+    pub predicates: Vec<JoinPredicate>,
+    pub edges: Vec<Hyperedge>,
+    /// The shape of the flattened join tree.
+    pub original_tree: JoinTree,
+    /// The columns of the root of the flattened join tree, as expressions over the
+    /// columns of the relations.
+    pub outputs: Vec<ScalarExprRef>,
+    /// The total number of columns of the relations.
+    pub num_columns: usize,
+}
+
+impl JoinGraph {
+    /// Flattens the tree of inner joins rooted at the given node, which must be an
+    /// inner join. Returns None if the tree contains too many relations.
+    ///
+    /// Inner joins with a single parent are flattened, together with the filters and
+    /// projections with a single parent in between them, while any other node becomes
+    /// a relation of the join graph.
+    pub fn build(query_graph: &QueryGraph, node_id: NodeId) -> Option<JoinGraph> {
+        let mut join_graph = JoinGraph {
+            relations: Vec::new(),
+            predicates: Vec::new(),
+            edges: Vec::new(),
+            original_tree: JoinTree::Relation(0),
+            outputs: Vec::new(),
+            num_columns: 0,
+        };
+        let mut predicates = Vec::new();
+        let (original_tree, outputs) =
+            join_graph.flatten(query_graph, node_id, true, &mut predicates)?;
+        join_graph.original_tree = original_tree;
+        join_graph.outputs = outputs;
+        join_graph.predicates = predicates
+            .into_iter()
+            .map(|expr| JoinPredicate {
+                relations: join_graph.referenced_relations(&expr),
+                expr,
+            })
+            .collect();
+        join_graph.build_edges();
+        Some(join_graph)
+    }
+
+    /// Flattens the given node, returning its shape and its columns as expressions
+    /// over the columns of the relations.
+    fn flatten(
+        &mut self,
+        query_graph: &QueryGraph,
+        node_id: NodeId,
+        is_root: bool,
+        predicates: &mut Vec<ScalarExprRef>,
+    ) -> Option<(JoinTree, Vec<ScalarExprRef>)> {
+        match query_graph.node(node_id) {
+            QueryNode::Join {
+                join_type: JoinType::Inner,
+                conditions,
+                left,
+                right,
+            } if is_root || query_graph.num_parents(node_id) == 1 => {
+                let (left, mut columns) = self.flatten(query_graph, *left, false, predicates)?;
+                let (right, right_columns) =
+                    self.flatten(query_graph, *right, false, predicates)?;
+                columns.extend(right_columns);
+                predicates.extend(
+                    conditions
+                        .iter()
+                        .map(|c| dereference_scalar_expr(c, &columns)),
+                );
+                Some((JoinTree::Join(Box::new(left), Box::new(right)), columns))
+            }
+            QueryNode::Filter { conditions, input }
+                if !is_root && is_flattenable_join(query_graph, node_id) =>
+            {
+                let (tree, columns) = self.flatten(query_graph, *input, false, predicates)?;
+                predicates.extend(
+                    conditions
+                        .iter()
+                        .map(|c| dereference_scalar_expr(c, &columns)),
+                );
+                Some((tree, columns))
+            }
+            QueryNode::Project { outputs, input }
+                if !is_root && is_flattenable_join(query_graph, node_id) =>
+            {
+                let (tree, columns) = self.flatten(query_graph, *input, false, predicates)?;
+                let columns = outputs
+                    .iter()
+                    .map(|e| dereference_scalar_expr(e, &columns))
+                    .collect();
+                Some((tree, columns))
+            }
+            _ => {
+                if self.relations.len() == MAX_RELATIONS {
+                    return None;
+                }
+                let column_offset = self.num_columns;
+                let num_columns = num_columns(query_graph, node_id);
+                self.relations.push(Relation {
+                    node_id,
+                    column_offset,
+                    num_columns,
+                });
+                self.num_columns += num_columns;
+                let columns = (column_offset..column_offset + num_columns)
+                    .map(|i| ScalarExpr::input_ref(i).into())
+                    .collect();
+                Some((JoinTree::Relation(self.relations.len() - 1), columns))
+            }
+        }
+    }
+
+    /// The relations whose columns are referenced by the given expression.
+    fn referenced_relations(&self, expr: &ScalarExprRef) -> RelationSet {
+        collect_input_dependencies(expr)
+            .iter()
+            .fold(0, |set, column| set | (1 << self.column_relation(*column)))
+    }
+
+    /// The relation the given column belongs to.
+    fn column_relation(&self, column: usize) -> usize {
+        self.relations
+            .iter()
+            .position(|relation| {
+                column >= relation.column_offset
+                    && column < relation.column_offset + relation.num_columns
+            })
+            .unwrap()
+    }
+
+    /// Adds an edge for every predicate referencing more than one relation. Comparisons
+    /// lead to an edge between the relations referenced by each of their operands,
+    /// while for any other predicate, the relation with the lowest index is connected
+    /// with the rest of them.
+    ///
+    /// The join enumerator only considers joins between connected sets of relations,
+    /// so the disconnected components of the graph are connected with edges without
+    /// any predicate, ie. cross products.
+    fn build_edges(&mut self) {
+        for predicate in self.predicates.iter() {
+            if predicate.relations.count_ones() < 2 {
+                continue;
+            }
+            let edge = match predicate.expr.as_ref() {
+                ScalarExpr::BinaryOp { left, right, .. } => {
+                    let left = self.referenced_relations(left);
+                    let right = self.referenced_relations(right);
+                    if left != 0 && right != 0 && left & right == 0 {
+                        Some(Hyperedge { left, right })
+                    } else {
+                        None
+                    }
+                }
+                _ => None,
+            };
+            self.edges.push(edge.unwrap_or_else(|| {
+                let lowest = predicate.relations & predicate.relations.wrapping_neg();
+                Hyperedge {
+                    left: lowest,
+                    right: predicate.relations & !lowest,
+                }
+            }));
+        }
+
+        let mut components: Vec<RelationSet> = Vec::new();
+        for relation in 0..self.relations.len() {
+            components.push(1 << relation);
+        }
+        for edge in self.edges.iter() {
+            let connected = edge.left | edge.right;
+            let (merged, mut rest): (Vec<_>, Vec<_>) = components
+                .into_iter()
+                .partition(|component| component & connected != 0);
+            rest.push(merged.into_iter().fold(0, |set, component| set | component));
+            components = rest;
+        }
+        components.sort_by_key(|component| component.trailing_zeros());
+        for (a, b) in components.iter().zip(components.iter().skip(1)) {
+            self.edges.push(Hyperedge {
+                left: a & a.wrapping_neg(),
+                right: b & b.wrapping_neg(),
+            });
+        }
+    }
+
+    /// Returns the neighborhood of the given set of relations, ie. for every edge
+    /// leading from the set to relations not in the set nor in the exclusion set, the
+    /// lowest relation at its other end.
+    pub fn neighborhood(&self, set: RelationSet, excluded: RelationSet) -> RelationSet {
+        let mut neighborhood = 0;
+        for edge in self.edges.iter() {
+            for (from, to) in [(edge.left, edge.right), (edge.right, edge.left)] {
+                if from & set == from && to & (set | excluded) == 0 {
+                    neighborhood |= to & to.wrapping_neg();
+                }
+            }
+        }
+        neighborhood
+    }
+
+    /// Whether there is an edge between the two given disjoint sets of relations.
+    pub fn are_connected(&self, a: RelationSet, b: RelationSet) -> bool {
+        self.edges.iter().any(|edge| {
+            (edge.left & a == edge.left && edge.right & b == edge.right)
+                || (edge.left & b == edge.left && edge.right & a == edge.right)
+        })
+    }
+}
+
+/// Whether the given node has a single parent and is either an inner join or a chain
+/// of filters and projections over an inner join with a single parent, so that it
+/// would be flattened together with its parent.
+fn is_flattenable_join(query_graph: &QueryGraph, node_id: NodeId) -> bool {
+    if query_graph.num_parents(node_id) != 1 {
+        return false;
+    }
+    match query_graph.node(node_id) {
+        QueryNode::Join {
+            join_type: JoinType::Inner,
+            ..
+        } => true,
+        QueryNode::Filter { input, .. } | QueryNode::Project { input, .. } => {
+            is_flattenable_join(query_graph, *input)
+        }
+        _ => false,
+    }
+}
+
+/// Whether the given inner join is part of a bigger join tree, that would be flattened
+/// starting from some other join.
+pub fn is_flattened_by_parent(query_graph: &QueryGraph, node_id: NodeId) -> bool {
+    let mut current_id = node_id;
+    while query_graph.num_parents(current_id) == 1 {
+        let parent_id = *query_graph
+            .get_parents(current_id)
+            .unwrap()
+            .iter()
+            .next()
+            .unwrap();
+        match query_graph.node(parent_id) {
+            QueryNode::Join {
+                join_type: JoinType::Inner,
+                ..
+            } => return true,
+            QueryNode::Filter { .. } | QueryNode::Project { .. } => current_id = parent_id,
+            _ => return false,
+        }
+    }
+    false
+}
@@ -0,0 +1,130 @@
+//! Cost-based join enumeration for trees of inner joins.
+//!
+//! A connected tree of inner joins, together with the filters and projections in
+//! between them, is flattened into a join graph, whose nodes are the relations being
+//! joined and whose edges are the predicates among them. The join orders allowed by
+//! the graph are then enumerated with dynamic programming, and the cheapest one
+//! according to a cost model is rebuilt as a new tree of joins.
+use std::collections::HashMap;
+
+use crate::{
+    query_graph::{JoinType, NodeId, QueryGraph},
+    scalar_expr::{rewrite::apply_column_map, ScalarExprRef},
+};
+
+use self::{
+    dphyp::DPhyp,
+    join_graph::{JoinGraph, JoinTree, RelationSet},
+};
+
+pub mod cost;
+mod dphyp;
+mod join_graph;
+
+pub use cost::{DefaultJoinCostModel, JoinCostModel};
+pub(crate) use join_graph::is_flattened_by_parent;
+
+/// Minimum relative cost reduction for a join tree to be replaced with a cheaper one.
+/// Avoids replacing a tree with an equivalent one due to rounding errors.
+const MIN_COST_REDUCTION: f64 = 1e-9;
+
+/// Replaces the tree of inner joins rooted at the given node with the cheapest join
+/// order found according to the given cost model, followed by a projection restoring
+/// the original order of the columns.
+///
+/// Returns None if the given node is not the root of a tree of inner joins, or if no
+/// join order cheaper than the current one was found.
+pub fn reorder_joins(
+    query_graph: &mut QueryGraph,
+    node_id: NodeId,
+    cost_model: &dyn JoinCostModel,
+) -> Option<NodeId> {
+    let join_graph = JoinGraph::build(query_graph, node_id)?;
+    if join_graph.relations.len() < 3 {
+        return None;
+    }
+    let enumerator = DPhyp::new(query_graph, &join_graph, cost_model);
+    let current_cost = enumerator.tree_cost(&join_graph.original_tree);
+    let (best_tree, best_cost) = enumerator.solve()?;
+    if best_cost >= current_cost * (1.0 - MIN_COST_REDUCTION) {
+        return None;
+    }
+
+    let (join, column_map) = build_join_tree(query_graph, &join_graph, &best_tree, true);
+    let outputs = join_graph
+        .outputs
+        .iter()
+        .map(|output| apply_column_map(output, &column_map).unwrap())
+        .collect();
+    Some(query_graph.project(join, outputs))
+}
+
+/// Builds the nodes for the given join tree, placing every predicate in the lowest
+/// node where all the relations it references are available. Returns the top-level
+/// node and the map from the columns of the flattened join tree to the columns of
+/// the node.
+fn build_join_tree(
+    query_graph: &mut QueryGraph,
+    join_graph: &JoinGraph,
+    tree: &JoinTree,
+    is_root: bool,
+) -> (NodeId, HashMap<usize, usize>) {
+    match tree {
+        JoinTree::Relation(index) => {
+            let relation = &join_graph.relations[*index];
+            let column_map = (0..relation.num_columns)
+                .map(|i| (relation.column_offset + i, i))
+                .collect::<HashMap<_, _>>();
+            let conditions =
+                rewrite_predicates(join_graph, &column_map, |relations| relations == 1 << index);
+            (query_graph.filter(relation.node_id, conditions), column_map)
+        }
+        JoinTree::Join(left, right) => {
+            let (left_node, left_map) = build_join_tree(query_graph, join_graph, left, false);
+            let (right_node, right_map) = build_join_tree(query_graph, join_graph, right, false);
+            let left_num_columns = left_map.len();
+            let column_map = left_map
+                .into_iter()
+                .chain(
+                    right_map
+                        .into_iter()
+                        .map(|(column, i)| (column, left_num_columns + i)),
+                )
+                .collect::<HashMap<_, _>>();
+            let left_relations = left.relations();
+            let right_relations = right.relations();
+            let mut conditions = rewrite_predicates(join_graph, &column_map, |relations| {
+                relations & left_relations != 0
+                    && relations & right_relations != 0
+                    && relations & !(left_relations | right_relations) == 0
+            });
+            if is_root {
+                conditions.extend(rewrite_predicates(join_graph, &column_map, |relations| {
+                    relations == 0
+                }));
+            }
+            (
+                query_graph.join(JoinType::Inner, left_node, right_node, conditions),
+                column_map,
+            )
+        }
+    }
+}
+
+/// Returns the predicates whose set of referenced relations satisfies the given
+/// filter, rewritten with the given column map.
+fn rewrite_predicates<F>(
+    join_graph: &JoinGraph,
+    column_map: &HashMap<usize, usize>,
+    filter: F,
+) -> Vec<ScalarExprRef>
+where
+    F: Fn(RelationSet) -> bool,
+{
+    join_graph
+        .predicates
+        .iter()
+        .filter(|predicate| filter(predicate.relations))
+        .map(|predicate| apply_column_map(&predicate.expr, column_map).unwrap())
+        .collect()
+}
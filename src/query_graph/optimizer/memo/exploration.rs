@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use crate::{
+    query_graph::{JoinType, QueryNode},
+    scalar_expr::{
+        rewrite::{apply_column_map, shift_left_input_refs},
+        visitor::collect_input_dependencies,
+        ScalarExpr,
+    },
+};
+
+use super::{Memo, MemoExprId};
+
+/// Trait for rules adding logically equivalent alternatives to the group of a memo
+/// expression.
+pub trait ExplorationRule: Sync {
+    /// Returns the alternatives for the given expression, whose inputs are groups. The
+    /// rule may add new groups to the memo for the inputs of the alternatives.
+    fn explore(&self, memo: &mut Memo, expr_id: MemoExprId) -> Vec<QueryNode>;
+
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>().split("::").last().unwrap()
+    }
+}
+
+/// Swaps the inputs of inner joins, restoring the original order of the columns with
+/// a projection.
+pub struct JoinCommutativityRule {}
+
+impl ExplorationRule for JoinCommutativityRule {
+    fn explore(&self, memo: &mut Memo, expr_id: MemoExprId) -> Vec<QueryNode> {
+        if let QueryNode::Join {
+            join_type: JoinType::Inner,
+            conditions,
+            left,
+            right,
+        } = &memo.expr(expr_id).node
+        {
+            let (conditions, left, right) = (conditions.clone(), *left, *right);
+            let left_num_columns = memo.num_columns(left);
+            let right_num_columns = memo.num_columns(right);
+            let column_map = (0..left_num_columns + right_num_columns)
+                .map(|column| {
+                    if column < left_num_columns {
+                        (column, column + right_num_columns)
+                    } else {
+                        (column, column - left_num_columns)
+                    }
+                })
+                .collect::<HashMap<_, _>>();
+            let swapped_join = memo.add_expr(
+                QueryNode::Join {
+                    join_type: JoinType::Inner,
+                    conditions: conditions
+                        .iter()
+                        .map(|c| apply_column_map(c, &column_map).unwrap())
+                        .collect(),
+                    left: right,
+                    right: left,
+                },
+                None,
+            );
+            let outputs = (0..left_num_columns + right_num_columns)
+                .map(|column| ScalarExpr::input_ref(column_map[&column]).into())
+                .collect();
+            return vec![QueryNode::Project {
+                outputs,
+                input: swapped_join,
+            }];
+        }
+        Vec::new()
+    }
+}
+
+/// Turns `(A join B) join C` into `A join (B join C)` for inner joins, as long as the
+/// new join between `B` and `C` is not a cross product.
+pub struct JoinAssociativityRule {}
+
+impl ExplorationRule for JoinAssociativityRule {
+    fn explore(&self, memo: &mut Memo, expr_id: MemoExprId) -> Vec<QueryNode> {
+        let (top_conditions, left, right) = match &memo.expr(expr_id).node {
+            QueryNode::Join {
+                join_type: JoinType::Inner,
+                conditions,
+                left,
+                right,
+            } => (conditions.clone(), *left, *right),
+            _ => return Vec::new(),
+        };
+        let left_joins = memo
+            .group_exprs(left)
+            .filter_map(|node| match node {
+                QueryNode::Join {
+                    join_type: JoinType::Inner,
+                    conditions,
+                    left,
+                    right,
+                } => Some((conditions.clone(), *left, *right)),
+                _ => None,
+            })
+            .collect_vec();
+        let mut alternatives = Vec::new();
+        for (bottom_conditions, a, b) in left_joins {
+            // The columns of A, B and C remain in the same positions, but the conditions
+            // only referencing B and C are moved to the new join.
+            let a_num_columns = memo.num_columns(a);
+            let (new_bottom_conditions, new_top_conditions): (Vec<_>, Vec<_>) = top_conditions
+                .iter()
+                .chain(bottom_conditions.iter())
+                .cloned()
+                .partition(|c| {
+                    let dependencies = collect_input_dependencies(c);
+                    !dependencies.is_empty()
+                        && dependencies.iter().all(|column| *column >= a_num_columns)
+                });
+            if new_bottom_conditions.is_empty() {
+                continue;
+            }
+            let new_bottom = memo.add_expr(
+                QueryNode::Join {
+                    join_type: JoinType::Inner,
+                    conditions: new_bottom_conditions
+                        .iter()
+                        .map(|c| shift_left_input_refs(c, a_num_columns))
+                        .collect(),
+                    left: b,
+                    right,
+                },
+                None,
+            );
+            alternatives.push(QueryNode::Join {
+                join_type: JoinType::Inner,
+                conditions: new_top_conditions,
+                left: a,
+                right: new_bottom,
+            });
+        }
+        alternatives
+    }
+}
@@ -0,0 +1,595 @@
+use itertools::Itertools;
+
+use crate::{
+    physical_plan::{
+        column_equals_literal, equi_join_key, table_indexes, Ordering, PhysicalNode, SortKey,
+    },
+    query_graph::{
+        properties::{ordering, partitioned_ordering},
+        JoinType, QueryNode,
+    },
+    scalar_expr::ScalarExpr,
+};
+
+use super::{Memo, MemoExprId};
+
+/// A physical implementation of a logical expression in the memo.
+#[derive(Clone)]
+pub struct PhysicalExpr {
+    /// The physical operator, with the IDs of its input groups in place of the IDs of
+    /// its input nodes.
+    pub node: PhysicalNode,
+    /// The order of the rows required from each of the inputs.
+    pub input_orderings: Vec<Ordering>,
+    /// The order of the rows produced by the operator.
+    pub ordering: Ordering,
+}
+
+impl PhysicalExpr {
+    fn unordered(node: PhysicalNode) -> Self {
+        let input_orderings = node.inputs().iter().map(|_| Vec::new()).collect();
+        Self {
+            node,
+            input_orderings,
+            ordering: Vec::new(),
+        }
+    }
+}
+
+/// Trait for rules giving the physical operators implementing a logical expression.
+pub trait ImplementationRule: Sync {
+    /// Returns the implementations of the given logical expression. The order of the
+    /// rows required by the parent is given so that the rule may request an order
+    /// from the inputs that leads to it. Implementations not delivering the required
+    /// order are not discarded by the rule, but by the search, which sorts the output
+    /// of the cheapest implementation if needed.
+    fn implement(&self, memo: &Memo, expr_id: MemoExprId, required: &Ordering)
+        -> Vec<PhysicalExpr>;
+
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>().split("::").last().unwrap()
+    }
+}
+
+/// Implements table scans as full scans or as full scans of the indexes of the table.
+pub struct TableScanImplementationRule {}
+
+impl ImplementationRule for TableScanImplementationRule {
+    fn implement(&self, memo: &Memo, expr_id: MemoExprId, _: &Ordering) -> Vec<PhysicalExpr> {
+        let QueryNode::TableScan { table_id, row_type } = &memo.expr(expr_id).node else {
+            return Vec::new();
+        };
+        let mut implementations = vec![PhysicalExpr::unordered(PhysicalNode::TableScan {
+            table_id: *table_id,
+            num_columns: row_type.len(),
+        })];
+        for index in table_indexes(memo.query_graph(), *table_id) {
+            let ordering = index.iter().map(|column| SortKey::asc(*column)).collect();
+            implementations.push(PhysicalExpr {
+                node: PhysicalNode::IndexScan {
+                    table_id: *table_id,
+                    num_columns: row_type.len(),
+                    index,
+                    lookup: Vec::new(),
+                },
+                input_orderings: Vec::new(),
+                ordering,
+            });
+        }
+        implementations
+    }
+}
+
+pub struct ValuesImplementationRule {}
+
+impl ImplementationRule for ValuesImplementationRule {
+    fn implement(&self, memo: &Memo, expr_id: MemoExprId, _: &Ordering) -> Vec<PhysicalExpr> {
+        let QueryNode::Values { rows, .. } = &memo.expr(expr_id).node else {
+            return Vec::new();
+        };
+        vec![PhysicalExpr::unordered(PhysicalNode::Values {
+            rows: rows.clone(),
+        })]
+    }
+}
+
+/// Implements filters over table scans as index lookups, when all the conditions of
+/// the filter compare the first columns of an index with literals.
+pub struct IndexLookupRule {}
+
+impl ImplementationRule for IndexLookupRule {
+    fn implement(&self, memo: &Memo, expr_id: MemoExprId, _: &Ordering) -> Vec<PhysicalExpr> {
+        let QueryNode::Filter { conditions, input } = &memo.expr(expr_id).node else {
+            return Vec::new();
+        };
+        let Some((table_id, num_columns)) = memo.group_exprs(*input).find_map(|node| match node {
+            QueryNode::TableScan { table_id, row_type } => Some((*table_id, row_type.len())),
+            _ => None,
+        }) else {
+            return Vec::new();
+        };
+        let mut implementations = Vec::new();
+        for index in table_indexes(memo.query_graph(), table_id) {
+            let lookup = index
+                .iter()
+                .map_while(|column| {
+                    conditions
+                        .iter()
+                        .find_map(|condition| column_equals_literal(condition, *column))
+                })
+                .collect_vec();
+            if lookup.len() != conditions.len() {
+                continue;
+            }
+            let ordering = index.iter().map(|column| SortKey::asc(*column)).collect();
+            implementations.push(PhysicalExpr {
+                node: PhysicalNode::IndexScan {
+                    table_id,
+                    num_columns,
+                    index,
+                    lookup,
+                },
+                input_orderings: Vec::new(),
+                ordering,
+            });
+        }
+        implementations
+    }
+}
+
+pub struct FilterImplementationRule {}
+
+impl ImplementationRule for FilterImplementationRule {
+    fn implement(
+        &self,
+        memo: &Memo,
+        expr_id: MemoExprId,
+        required: &Ordering,
+    ) -> Vec<PhysicalExpr> {
+        let QueryNode::Filter { conditions, input } = &memo.expr(expr_id).node else {
+            return Vec::new();
+        };
+        vec![PhysicalExpr {
+            node: PhysicalNode::Filter {
+                conditions: conditions.clone(),
+                input: *input,
+            },
+            input_orderings: vec![required.clone()],
+            ordering: required.clone(),
+        }]
+    }
+}
+
+pub struct ProjectImplementationRule {}
+
+impl ImplementationRule for ProjectImplementationRule {
+    fn implement(
+        &self,
+        memo: &Memo,
+        expr_id: MemoExprId,
+        required: &Ordering,
+    ) -> Vec<PhysicalExpr> {
+        let QueryNode::Project { outputs, input } = &memo.expr(expr_id).node else {
+            return Vec::new();
+        };
+        let node = PhysicalNode::Project {
+            outputs: outputs.clone(),
+            input: *input,
+        };
+        // The required order can only be obtained from the input if all the columns
+        // it is made of are passed through.
+        let input_ordering = required
+            .iter()
+            .map(|key| match outputs[key.column].as_ref() {
+                ScalarExpr::InputRef { index } => Some(SortKey {
+                    column: *index,
+                    ascending: key.ascending,
+                }),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>();
+        match input_ordering {
+            Some(input_ordering) => vec![PhysicalExpr {
+                node,
+                input_orderings: vec![input_ordering],
+                ordering: required.clone(),
+            }],
+            None => vec![PhysicalExpr::unordered(node)],
+        }
+    }
+}
+
+/// Implements joins as nested loop joins and, if they contain equalities between
+/// columns of both inputs, as hash joins and merge joins.
+pub struct JoinImplementationRule {}
+
+impl ImplementationRule for JoinImplementationRule {
+    fn implement(
+        &self,
+        memo: &Memo,
+        expr_id: MemoExprId,
+        required: &Ordering,
+    ) -> Vec<PhysicalExpr> {
+        let QueryNode::Join {
+            join_type,
+            conditions,
+            left,
+            right,
+        } = &memo.expr(expr_id).node
+        else {
+            return Vec::new();
+        };
+        let join_type = *join_type;
+        let left_num_columns = memo.num_columns(*left);
+        let preserves_left_order = matches!(
+            join_type,
+            JoinType::Inner | JoinType::LeftOuter | JoinType::Semi | JoinType::Anti
+        );
+        // Nested loop and hash joins deliver the order of their left input.
+        let left_ordering =
+            if preserves_left_order && required.iter().all(|key| key.column < left_num_columns) {
+                required.clone()
+            } else {
+                Vec::new()
+            };
+        let mut implementations = vec![PhysicalExpr {
+            node: PhysicalNode::NestedLoopJoin {
+                join_type,
+                conditions: conditions.clone(),
+                left: *left,
+                right: *right,
+            },
+            input_orderings: vec![left_ordering.clone(), Vec::new()],
+            ordering: left_ordering.clone(),
+        }];
+
+        let (equalities, residual): (Vec<_>, Vec<_>) = conditions
+            .iter()
+            .partition(|condition| equi_join_key(condition, left_num_columns).is_some());
+        if equalities.is_empty() {
+            return implementations;
+        }
+        let mut equalities = equalities
+            .iter()
+            .map(|condition| equi_join_key(condition, left_num_columns).unwrap())
+            .collect_vec();
+        let residual = residual.into_iter().cloned().collect_vec();
+        implementations.push(PhysicalExpr {
+            node: PhysicalNode::HashJoin {
+                join_type,
+                left_keys: equalities.iter().map(|(l, _)| *l).collect(),
+                right_keys: equalities.iter().map(|(_, r)| *r).collect(),
+                conditions: residual.clone(),
+                left: *left,
+                right: *right,
+            },
+            input_orderings: vec![left_ordering.clone(), Vec::new()],
+            ordering: left_ordering,
+        });
+
+        // Merge joins may use the equalities in any order, so use the required one
+        // for the equalities whose left column is part of it.
+        let position_in_required = |column: usize| {
+            required
+                .iter()
+                .position(|key| key.ascending && key.column == column)
+                .unwrap_or(required.len())
+        };
+        equalities.sort_by_key(|(l, _)| position_in_required(*l));
+        let left_ordering = equalities
+            .iter()
+            .map(|(l, _)| SortKey::asc(*l))
+            .collect_vec();
+        let right_ordering = equalities
+            .iter()
+            .map(|(_, r)| SortKey::asc(*r))
+            .collect_vec();
+        implementations.push(PhysicalExpr {
+            node: PhysicalNode::MergeJoin {
+                join_type,
+                left_keys: equalities.iter().map(|(l, _)| *l).collect(),
+                right_keys: equalities.iter().map(|(_, r)| *r).collect(),
+                conditions: residual,
+                left: *left,
+                right: *right,
+            },
+            input_orderings: vec![left_ordering.clone(), right_ordering],
+            ordering: if preserves_left_order {
+                left_ordering
+            } else {
+                Vec::new()
+            },
+        });
+        implementations
+    }
+}
+
+/// Implements aggregations as hash aggregations and as stream aggregations.
+pub struct AggregateImplementationRule {}
+
+impl ImplementationRule for AggregateImplementationRule {
+    fn implement(
+        &self,
+        memo: &Memo,
+        expr_id: MemoExprId,
+        required: &Ordering,
+    ) -> Vec<PhysicalExpr> {
+        let QueryNode::Aggregate {
+            group_key,
+            aggregates,
+            input,
+        } = &memo.expr(expr_id).node
+        else {
+            return Vec::new();
+        };
+        let hash_aggregate = PhysicalExpr::unordered(PhysicalNode::HashAggregate {
+            group_key: group_key.clone(),
+            aggregates: aggregates.clone(),
+            input: *input,
+        });
+        let stream_aggregate = PhysicalNode::StreamAggregate {
+            group_key: group_key.clone(),
+            aggregates: aggregates.clone(),
+            input: *input,
+        };
+        // Any order of the grouping key columns makes the rows of the same group
+        // contiguous, so start with the required one if it only contains grouping
+        // key columns.
+        let key_columns = group_key.iter().cloned().collect_vec();
+        let mut output_ordering = Vec::new();
+        if required.iter().all(|key| key.column < key_columns.len()) {
+            output_ordering.extend(required.iter().cloned().unique_by(|key| key.column));
+        }
+        for column in 0..key_columns.len() {
+            if !output_ordering.iter().any(|key| key.column == column) {
+                output_ordering.push(SortKey::asc(column));
+            }
+        }
+        let input_ordering = output_ordering
+            .iter()
+            .map(|key| SortKey {
+                column: key_columns[key.column],
+                ascending: key.ascending,
+            })
+            .collect();
+        vec![
+            hash_aggregate,
+            PhysicalExpr {
+                node: stream_aggregate,
+                input_orderings: vec![input_ordering],
+                ordering: output_ordering,
+            },
+        ]
+    }
+}
+
+pub struct SortImplementationRule {}
+
+impl ImplementationRule for SortImplementationRule {
+    fn implement(&self, memo: &Memo, expr_id: MemoExprId, _: &Ordering) -> Vec<PhysicalExpr> {
+        let QueryNode::Sort { keys, input } = &memo.expr(expr_id).node else {
+            return Vec::new();
+        };
+        vec![PhysicalExpr {
+            node: PhysicalNode::Sort {
+                keys: keys.clone(),
+                input: *input,
+            },
+            input_orderings: vec![Vec::new()],
+            ordering: keys.clone(),
+        }]
+    }
+}
+
+/// Implements limits, requiring from their input the order it guarantees, since it
+/// determines which rows are returned.
+pub struct LimitImplementationRule {}
+
+impl ImplementationRule for LimitImplementationRule {
+    fn implement(&self, memo: &Memo, expr_id: MemoExprId, _: &Ordering) -> Vec<PhysicalExpr> {
+        let QueryNode::Limit {
+            offset,
+            limit,
+            input,
+        } = &memo.expr(expr_id).node
+        else {
+            return Vec::new();
+        };
+        let input_ordering = ordering(memo.query_graph(), memo.group(*input).representative);
+        vec![PhysicalExpr {
+            node: PhysicalNode::Limit {
+                offset: *offset,
+                limit: *limit,
+                input: *input,
+            },
+            input_orderings: vec![input_ordering.to_vec()],
+            ordering: input_ordering.to_vec(),
+        }]
+    }
+}
+
+/// Implements top-N operators as bounded sorts and, if they are not partitioned, as
+/// limits over an input sorted by the keys of the top-N.
+pub struct TopNImplementationRule {}
+
+impl ImplementationRule for TopNImplementationRule {
+    fn implement(&self, memo: &Memo, expr_id: MemoExprId, _: &Ordering) -> Vec<PhysicalExpr> {
+        let QueryNode::TopN {
+            partition_key,
+            keys,
+            offset,
+            limit,
+            input,
+        } = &memo.expr(expr_id).node
+        else {
+            return Vec::new();
+        };
+        let mut alternatives = vec![PhysicalExpr {
+            node: PhysicalNode::TopN {
+                partition_key: partition_key.clone(),
+                keys: keys.clone(),
+                offset: *offset,
+                limit: *limit,
+                input: *input,
+            },
+            input_orderings: vec![Vec::new()],
+            ordering: partitioned_ordering(partition_key, keys),
+        }];
+        if partition_key.is_empty() {
+            alternatives.push(PhysicalExpr {
+                node: PhysicalNode::Limit {
+                    offset: *offset,
+                    limit: Some(*limit),
+                    input: *input,
+                },
+                input_orderings: vec![keys.clone()],
+                ordering: keys.clone(),
+            });
+        }
+        alternatives
+    }
+}
+
+/// Implements window operators over an input sorted by the partition key and the
+/// order key.
+pub struct WindowImplementationRule {}
+
+impl ImplementationRule for WindowImplementationRule {
+    fn implement(&self, memo: &Memo, expr_id: MemoExprId, _: &Ordering) -> Vec<PhysicalExpr> {
+        let QueryNode::Window {
+            partition_key,
+            order_key,
+            frame,
+            functions,
+            input,
+        } = &memo.expr(expr_id).node
+        else {
+            return Vec::new();
+        };
+        let ordering = partitioned_ordering(partition_key, order_key);
+        vec![PhysicalExpr {
+            node: PhysicalNode::Window {
+                partition_key: partition_key.clone(),
+                order_key: order_key.clone(),
+                frame: *frame,
+                functions: functions.clone(),
+                input: *input,
+            },
+            input_orderings: vec![ordering.clone()],
+            ordering,
+        }]
+    }
+}
+
+pub struct UnionImplementationRule {}
+
+impl ImplementationRule for UnionImplementationRule {
+    fn implement(&self, memo: &Memo, expr_id: MemoExprId, _: &Ordering) -> Vec<PhysicalExpr> {
+        let QueryNode::Union { inputs } = &memo.expr(expr_id).node else {
+            return Vec::new();
+        };
+        vec![PhysicalExpr::unordered(PhysicalNode::Union {
+            inputs: inputs.clone(),
+        })]
+    }
+}
+
+/// Implements recursive unions and the scans of the rows produced by their previous
+/// iteration.
+pub struct RecursiveUnionImplementationRule {}
+
+impl ImplementationRule for RecursiveUnionImplementationRule {
+    fn implement(&self, memo: &Memo, expr_id: MemoExprId, _: &Ordering) -> Vec<PhysicalExpr> {
+        let node = match &memo.expr(expr_id).node {
+            QueryNode::RecursiveUnion {
+                recursion_id,
+                distinct,
+                max_iterations,
+                base,
+                recursive,
+            } => PhysicalNode::RecursiveUnion {
+                recursion_id: *recursion_id,
+                distinct: *distinct,
+                max_iterations: *max_iterations,
+                base: *base,
+                recursive: *recursive,
+            },
+            QueryNode::RecursiveReference {
+                recursion_id,
+                row_type,
+            } => PhysicalNode::WorkTableScan {
+                recursion_id: *recursion_id,
+                num_columns: row_type.len(),
+            },
+            _ => return Vec::new(),
+        };
+        vec![PhysicalExpr::unordered(node)]
+    }
+}
+
+/// Implements intersect and except operators with hash tables.
+pub struct SetOperationImplementationRule {}
+
+impl ImplementationRule for SetOperationImplementationRule {
+    fn implement(&self, memo: &Memo, expr_id: MemoExprId, _: &Ordering) -> Vec<PhysicalExpr> {
+        let node = match &memo.expr(expr_id).node {
+            QueryNode::Intersect {
+                distinct,
+                left,
+                right,
+            } => PhysicalNode::HashIntersect {
+                distinct: *distinct,
+                left: *left,
+                right: *right,
+            },
+            QueryNode::Except {
+                distinct,
+                left,
+                right,
+            } => PhysicalNode::HashExcept {
+                distinct: *distinct,
+                left: *left,
+                right: *right,
+            },
+            _ => return Vec::new(),
+        };
+        vec![PhysicalExpr::unordered(node)]
+    }
+}
+
+pub struct ApplyImplementationRule {}
+
+impl ImplementationRule for ApplyImplementationRule {
+    fn implement(
+        &self,
+        memo: &Memo,
+        expr_id: MemoExprId,
+        required: &Ordering,
+    ) -> Vec<PhysicalExpr> {
+        let QueryNode::Apply {
+            correlation,
+            left,
+            right,
+            apply_type,
+        } = &memo.expr(expr_id).node
+        else {
+            return Vec::new();
+        };
+        let left_num_columns = memo.num_columns(*left);
+        let left_ordering = if required.iter().all(|key| key.column < left_num_columns) {
+            required.clone()
+        } else {
+            Vec::new()
+        };
+        vec![PhysicalExpr {
+            node: PhysicalNode::Apply {
+                correlation: correlation.clone(),
+                apply_type: *apply_type,
+                left: *left,
+                right: *right,
+            },
+            input_orderings: vec![left_ordering.clone(), Vec::new()],
+            ordering: left_ordering,
+        }]
+    }
+}
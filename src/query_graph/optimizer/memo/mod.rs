@@ -0,0 +1,357 @@
+//! Memo-based, Cascades-style search for the cheapest physical plan of a query.
+//!
+//! The memo is made of groups of logically equivalent expressions, where the inputs
+//! of each expression are groups rather than nodes, so that all the alternatives
+//! for a sub-plan are shared by all its parents. Exploration rules add alternatives
+//! to the groups instead of replacing nodes, while implementation rules give the
+//! physical operators implementing each logical expression. The cheapest plan is
+//! then extracted from the memo, optimizing every group for each order of the rows
+//! required by its parents.
+use std::collections::HashMap;
+
+use crate::query_graph::{
+    explain::{
+        explain_limit, explain_recursive_union, explain_scalar_expr_vec, explain_set_operation,
+        explain_sort_keys, explain_top_n, explain_values, explain_window,
+    },
+    properties::{estimated_cardinality, num_columns},
+    NodeId, QueryGraph, QueryNode,
+};
+
+mod exploration;
+mod implementation;
+mod search;
+
+pub use exploration::{ExplorationRule, JoinAssociativityRule, JoinCommutativityRule};
+pub use implementation::{
+    AggregateImplementationRule, ApplyImplementationRule, FilterImplementationRule,
+    ImplementationRule, IndexLookupRule, JoinImplementationRule, LimitImplementationRule,
+    PhysicalExpr, ProjectImplementationRule, RecursiveUnionImplementationRule,
+    SetOperationImplementationRule, SortImplementationRule, TableScanImplementationRule,
+    TopNImplementationRule, UnionImplementationRule, ValuesImplementationRule,
+    WindowImplementationRule,
+};
+pub use search::{MemoOptimizer, DEFAULT_MEMO_OPTIMIZER};
+
+pub type GroupId = usize;
+pub type MemoExprId = usize;
+
+/// A logical expression in the memo.
+pub struct MemoExpr {
+    /// The logical operator, with the IDs of its input groups in place of the IDs
+    /// of its input nodes.
+    pub node: QueryNode,
+    pub group: GroupId,
+}
+
+/// A set of logically equivalent expressions.
+pub struct Group {
+    pub exprs: Vec<MemoExprId>,
+    /// A node of the query graph owned by the memo, equivalent to the expressions in
+    /// the group, from which the logical properties of the group are derived.
+    pub representative: NodeId,
+}
+
+pub struct Memo {
+    /// Copy of the query graph the memo was built from. New nodes are added to it
+    /// for the new groups found during the exploration.
+    query_graph: QueryGraph,
+    exprs: Vec<MemoExpr>,
+    groups: Vec<Group>,
+    expr_ids: HashMap<QueryNode, MemoExprId>,
+    /// The group each node of the query graph belongs to.
+    node_groups: HashMap<NodeId, GroupId>,
+}
+
+impl Memo {
+    pub fn new(query_graph: &QueryGraph) -> Self {
+        Self {
+            query_graph: query_graph.clone(),
+            exprs: Vec::new(),
+            groups: Vec::new(),
+            expr_ids: HashMap::new(),
+            node_groups: HashMap::new(),
+        }
+    }
+
+    pub fn query_graph(&self) -> &QueryGraph {
+        &self.query_graph
+    }
+
+    pub fn expr(&self, expr_id: MemoExprId) -> &MemoExpr {
+        &self.exprs[expr_id]
+    }
+
+    pub fn group(&self, group_id: GroupId) -> &Group {
+        &self.groups[group_id]
+    }
+
+    pub fn num_exprs(&self) -> usize {
+        self.exprs.len()
+    }
+
+    pub fn num_groups(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// The logical expressions in the given group.
+    pub fn group_exprs(&self, group_id: GroupId) -> impl Iterator<Item = &QueryNode> {
+        self.groups[group_id]
+            .exprs
+            .iter()
+            .map(|expr_id| &self.exprs[*expr_id].node)
+    }
+
+    pub fn num_columns(&self, group_id: GroupId) -> usize {
+        num_columns(&self.query_graph, self.groups[group_id].representative)
+    }
+
+    /// Estimated number of rows produced by the expressions in the given group.
+    pub fn cardinality(&self, group_id: GroupId) -> f64 {
+        estimated_cardinality(&self.query_graph, self.groups[group_id].representative)
+    }
+
+    /// Adds the sub-graph rooted at the given node of the query graph, returning the
+    /// group of the given node. Query and subquery roots are skipped.
+    pub fn add_subgraph(&mut self, node_id: NodeId) -> GroupId {
+        if let Some(group_id) = self.node_groups.get(&node_id) {
+            return *group_id;
+        }
+        let mut node = self.query_graph.node(node_id).clone();
+        if let QueryNode::QueryRoot { input: Some(input) } | QueryNode::SubqueryRoot { input } =
+            node
+        {
+            return self.add_subgraph(input);
+        }
+        for i in 0..node.num_inputs() {
+            let input_group = self.add_subgraph(node.get_input(i));
+            node.set_input(i, input_group);
+        }
+        let group_id = match self.expr_ids.get(&node) {
+            Some(expr_id) => self.exprs[*expr_id].group,
+            None => {
+                let group_id = self.add_group(node_id);
+                self.insert_expr(node, group_id);
+                group_id
+            }
+        };
+        self.node_groups.insert(node_id, group_id);
+        group_id
+    }
+
+    /// Adds the given logical expression, whose inputs are groups, to the given group,
+    /// or to a new group if none is given. Returns the group the expression belongs to.
+    ///
+    /// Groups are never merged: if the expression already exists in the memo, it is not
+    /// added again and its existing group is returned.
+    pub fn add_expr(&mut self, node: QueryNode, group_id: Option<GroupId>) -> GroupId {
+        if let Some(expr_id) = self.expr_ids.get(&node) {
+            return self.exprs[*expr_id].group;
+        }
+        let group_id = match group_id {
+            Some(group_id) => group_id,
+            None => {
+                let mut representative = node.clone();
+                for i in 0..node.num_inputs() {
+                    representative.set_input(i, self.groups[node.get_input(i)].representative);
+                }
+                let node_id = self.query_graph.add_node(representative);
+                match self.node_groups.get(&node_id) {
+                    Some(group_id) => *group_id,
+                    None => {
+                        let group_id = self.add_group(node_id);
+                        self.node_groups.insert(node_id, group_id);
+                        group_id
+                    }
+                }
+            }
+        };
+        self.insert_expr(node, group_id);
+        group_id
+    }
+
+    fn add_group(&mut self, representative: NodeId) -> GroupId {
+        self.groups.push(Group {
+            exprs: Vec::new(),
+            representative,
+        });
+        self.groups.len() - 1
+    }
+
+    fn insert_expr(&mut self, node: QueryNode, group_id: GroupId) {
+        let expr_id = self.exprs.len();
+        self.expr_ids.insert(node.clone(), expr_id);
+        self.exprs.push(MemoExpr {
+            node,
+            group: group_id,
+        });
+        self.groups[group_id].exprs.push(expr_id);
+    }
+
+    /// Returns a stringified version of the memo, listing the expressions of every
+    /// group.
+    pub fn explain(&self) -> String {
+        let mut result = String::new();
+        for (group_id, group) in self.groups.iter().enumerate() {
+            result += &format!(
+                "Group {} (Estimated Cardinality: {:.1})\n",
+                group_id,
+                self.cardinality(group_id)
+            );
+            for expr_id in group.exprs.iter() {
+                let node = &self.exprs[*expr_id].node;
+                let inputs = (0..node.num_inputs())
+                    .map(|i| node.get_input(i).to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                result += &format!(
+                    "  [{}] {} inputs: [{}]\n",
+                    expr_id,
+                    explain_node(node),
+                    inputs
+                );
+            }
+        }
+        result
+    }
+}
+
+fn explain_node(node: &QueryNode) -> String {
+    match node {
+        QueryNode::QueryRoot { .. } => "QueryRoot".to_string(),
+        QueryNode::Project { outputs, .. } => {
+            format!("Project [{}]", explain_scalar_expr_vec(outputs))
+        }
+        QueryNode::Filter { conditions, .. } => {
+            format!("Filter [{}]", explain_scalar_expr_vec(conditions))
+        }
+        QueryNode::TableScan { table_id, .. } => format!("TableScan id: {}", table_id),
+        QueryNode::Values { rows, .. } => format!("Values [{}]", explain_values(rows)),
+        QueryNode::Join {
+            join_type,
+            conditions,
+            ..
+        } => format!(
+            "{} Join [{}]",
+            join_type,
+            explain_scalar_expr_vec(conditions)
+        ),
+        QueryNode::Aggregate {
+            group_key,
+            aggregates,
+            ..
+        } => format!(
+            "Aggregate key: [{}], aggregates: [{}]",
+            group_key
+                .iter()
+                .map(|e| format!("ref_{}", e))
+                .collect::<Vec<_>>()
+                .join(", "),
+            aggregates
+                .iter()
+                .map(|e| format!("{}", e))
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        QueryNode::Union { .. } => "Union".to_string(),
+        QueryNode::Intersect { distinct, .. } => explain_set_operation("Intersect", *distinct),
+        QueryNode::Except { distinct, .. } => explain_set_operation("Except", *distinct),
+        QueryNode::RecursiveUnion {
+            recursion_id,
+            distinct,
+            max_iterations,
+            ..
+        } => explain_recursive_union(*recursion_id, *distinct, *max_iterations),
+        QueryNode::RecursiveReference { recursion_id, .. } => {
+            format!("RecursiveReference id: {}", recursion_id)
+        }
+        QueryNode::SubqueryRoot { .. } => "SubqueryRoot".to_string(),
+        QueryNode::Apply {
+            correlation,
+            apply_type,
+            ..
+        } => format!(
+            "{} Apply parameters: [{}]",
+            apply_type,
+            explain_scalar_expr_vec(&correlation.parameters)
+        ),
+        QueryNode::Sort { keys, .. } => format!("Sort [{}]", explain_sort_keys(keys)),
+        QueryNode::Limit { offset, limit, .. } => {
+            format!("Limit {}", explain_limit(*offset, *limit))
+        }
+        QueryNode::TopN {
+            partition_key,
+            keys,
+            offset,
+            limit,
+            ..
+        } => format!(
+            "TopN {}",
+            explain_top_n(partition_key, keys, *offset, *limit)
+        ),
+        QueryNode::Window {
+            partition_key,
+            order_key,
+            frame,
+            functions,
+            ..
+        } => format!(
+            "Window {}",
+            explain_window(partition_key, order_key, frame, functions)
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        query_graph::JoinType,
+        scalar_expr::{BinaryOp, ScalarExpr},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_join_exploration() {
+        // (t0 join t1) join t2, with t0.ref_0 = t1.ref_0 and t1.ref_1 = t2.ref_0
+        let mut query_graph = QueryGraph::new();
+        let t0 = query_graph.table_scan(0, 2);
+        let t1 = query_graph.table_scan(1, 2);
+        let t2 = query_graph.table_scan(2, 2);
+        let eq = |a: usize, b: usize| {
+            ScalarExpr::input_ref(a)
+                .binary(BinaryOp::Eq, ScalarExpr::input_ref(b).into())
+                .into()
+        };
+        let join_01 = query_graph.join(JoinType::Inner, t0, t1, vec![eq(0, 2)]);
+        let join_012 = query_graph.join(JoinType::Inner, join_01, t2, vec![eq(3, 4)]);
+
+        let mut memo = Memo::new(&query_graph);
+        let root = memo.add_subgraph(join_012);
+        assert_eq!(memo.num_groups(), 5);
+        DEFAULT_MEMO_OPTIMIZER.explore(&mut memo);
+
+        // t1 join t2 was found by associativity
+        let t1_group = memo.add_subgraph(t1);
+        let t2_group = memo.add_subgraph(t2);
+        assert!((0..memo.num_groups()).any(|group_id| {
+            memo.group_exprs(group_id).any(|node| {
+                matches!(node, QueryNode::Join { left, right, .. }
+                    if *left == t1_group && *right == t2_group)
+            })
+        }));
+        // Every alternative of the root group has 6 columns
+        assert!(memo.group(root).exprs.len() > 1);
+        for expr_id in memo.group(root).exprs.iter() {
+            let node = &memo.expr(*expr_id).node;
+            let num_columns = match node {
+                QueryNode::Join { left, right, .. } => {
+                    memo.num_columns(*left) + memo.num_columns(*right)
+                }
+                QueryNode::Project { outputs, .. } => outputs.len(),
+                _ => panic!("unexpected expression"),
+            };
+            assert_eq!(num_columns, 6);
+        }
+    }
+}
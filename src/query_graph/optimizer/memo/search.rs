@@ -0,0 +1,273 @@
+use std::collections::{HashMap, HashSet};
+
+use itertools::Itertools;
+
+use crate::{
+    physical_plan::{
+        CostModel, Ordering, PhysicalNode, PhysicalNodeId, PhysicalPlan, PhysicalProperties,
+    },
+    query_graph::{
+        optimizer::{Optimizer, OptimizerContext, OptimizerError, DEFAULT_OPTIMIZER},
+        QueryGraph, QueryNode,
+    },
+};
+
+use super::{
+    exploration::*, implementation::*, ExplorationRule, GroupId, ImplementationRule, Memo,
+    PhysicalExpr,
+};
+
+/// Optimizer finding the cheapest physical plan for a query graph through the
+/// exploration of a memo.
+///
+/// The query graph is first normalized with a rewrite-to-fixpoint optimizer. The
+/// exploration rules are then applied to all the expressions in the memo until no new
+/// expressions are found, or until the memo reaches its maximum size.
+pub struct MemoOptimizer {
+    normalization: &'static Optimizer,
+    exploration_rules: Vec<Box<dyn ExplorationRule>>,
+    implementation_rules: Vec<Box<dyn ImplementationRule>>,
+    max_exprs: usize,
+}
+
+impl MemoOptimizer {
+    /// Default value for the maximum number of expressions in the memo, after which
+    /// no further alternatives are explored.
+    pub const DEFAULT_MAX_EXPRS: usize = 5000;
+
+    pub fn new(
+        normalization: &'static Optimizer,
+        exploration_rules: Vec<Box<dyn ExplorationRule>>,
+        implementation_rules: Vec<Box<dyn ImplementationRule>>,
+    ) -> Self {
+        Self {
+            normalization,
+            exploration_rules,
+            implementation_rules,
+            max_exprs: Self::DEFAULT_MAX_EXPRS,
+        }
+    }
+
+    pub fn with_max_exprs(self, max_exprs: usize) -> Self {
+        Self { max_exprs, ..self }
+    }
+
+    /// Normalizes the given query graph and returns its cheapest physical plan
+    /// according to the given cost model.
+    pub fn optimize(
+        &self,
+        context: &mut OptimizerContext,
+        query_graph: &mut QueryGraph,
+        cost_model: &dyn CostModel,
+    ) -> Result<PhysicalPlan, OptimizerError> {
+        self.normalization.optimize(context, query_graph)?;
+        let mut memo = Memo::new(query_graph);
+        let root = match query_graph.node(QueryGraph::ROOT_NODE_ID) {
+            QueryNode::QueryRoot { input: Some(input) } => Some(memo.add_subgraph(*input)),
+            _ => None,
+        };
+        let subqueries = query_graph
+            .subquery_roots()
+            .into_iter()
+            .map(|subquery_root| (subquery_root, memo.add_subgraph(subquery_root)))
+            .collect_vec();
+        self.explore(&mut memo);
+
+        let mut search = Search {
+            optimizer: self,
+            memo: &memo,
+            cost_model,
+            winners: HashMap::new(),
+            in_progress: HashSet::new(),
+        };
+        let mut plan = PhysicalPlan::new();
+        let mut extracted = HashMap::new();
+        plan.root = match root {
+            Some(root) => search.extract(&mut plan, &mut extracted, root, &Vec::new()),
+            None => plan.add_node(
+                PhysicalNode::Union { inputs: Vec::new() },
+                PhysicalProperties {
+                    cardinality: 0.0,
+                    cost: 0.0,
+                    ordering: Vec::new(),
+                },
+            ),
+        };
+        plan.subqueries = subqueries
+            .into_iter()
+            .map(|(subquery_root, group_id)| {
+                (
+                    subquery_root,
+                    search.extract(&mut plan, &mut extracted, group_id, &Vec::new()),
+                )
+            })
+            .collect();
+        Ok(plan)
+    }
+
+    /// Applies the exploration rules to every expression in the memo until a full
+    /// pass doesn't add any new expression.
+    pub fn explore(&self, memo: &mut Memo) {
+        loop {
+            let num_exprs = memo.num_exprs();
+            let mut expr_id = 0;
+            while expr_id < memo.num_exprs() && memo.num_exprs() < self.max_exprs {
+                let group_id = memo.expr(expr_id).group;
+                for rule in self.exploration_rules.iter() {
+                    for alternative in rule.explore(memo, expr_id) {
+                        memo.add_expr(alternative, Some(group_id));
+                    }
+                }
+                expr_id += 1;
+            }
+            if memo.num_exprs() == num_exprs || memo.num_exprs() >= self.max_exprs {
+                return;
+            }
+        }
+    }
+}
+
+/// The cheapest implementation found for a group and a required order.
+struct Winner {
+    expr: PhysicalExpr,
+    /// The cost of the implementation, including the cost of its inputs.
+    cost: f64,
+}
+
+struct Search<'a> {
+    optimizer: &'a MemoOptimizer,
+    memo: &'a Memo,
+    cost_model: &'a dyn CostModel,
+    winners: HashMap<(GroupId, Ordering), Option<Winner>>,
+    /// The groups being optimized, used for skipping the expressions that lead to
+    /// cycles in the memo.
+    in_progress: HashSet<(GroupId, Ordering)>,
+}
+
+impl<'a> Search<'a> {
+    /// Finds the cheapest implementation of the given group delivering the given order,
+    /// returning its cost. Returns None if the group can only be implemented through
+    /// groups that are already being optimized.
+    fn optimize_group(&mut self, group_id: GroupId, required: &Ordering) -> Option<f64> {
+        let key = (group_id, required.clone());
+        if let Some(winner) = self.winners.get(&key) {
+            return winner.as_ref().map(|winner| winner.cost);
+        }
+        if !self.in_progress.insert(key.clone()) {
+            return None;
+        }
+        let mut best: Option<Winner> = None;
+        for expr_id in self.memo.group(group_id).exprs.iter() {
+            for rule in self.optimizer.implementation_rules.iter() {
+                for expr in rule.implement(self.memo, *expr_id, required) {
+                    if expr.ordering.starts_with(required) {
+                        self.consider(&mut best, group_id, expr);
+                    }
+                }
+            }
+        }
+        if !required.is_empty() {
+            // Enforce the required order by sorting the output of the cheapest
+            // implementation.
+            let sort = PhysicalExpr {
+                node: PhysicalNode::Sort {
+                    keys: required.clone(),
+                    input: group_id,
+                },
+                input_orderings: vec![Vec::new()],
+                ordering: required.clone(),
+            };
+            self.consider(&mut best, group_id, sort);
+        }
+        self.in_progress.remove(&key);
+        let cost = best.as_ref().map(|winner| winner.cost);
+        self.winners.insert(key, best);
+        cost
+    }
+
+    fn consider(&mut self, best: &mut Option<Winner>, group_id: GroupId, expr: PhysicalExpr) {
+        let mut input_cost = 0.0;
+        let mut input_cardinalities = Vec::new();
+        for (input, ordering) in expr.node.inputs().iter().zip(expr.input_orderings.iter()) {
+            match self.optimize_group(*input, ordering) {
+                Some(cost) => input_cost += cost,
+                None => return,
+            }
+            input_cardinalities.push(self.memo.cardinality(*input));
+        }
+        let cost = input_cost
+            + self.cost_model.operator_cost(
+                &expr.node,
+                &input_cardinalities,
+                self.memo.cardinality(group_id),
+            );
+        if best.as_ref().is_none_or(|winner| cost < winner.cost) {
+            *best = Some(Winner { expr, cost });
+        }
+    }
+
+    /// Adds the cheapest implementation of the given group delivering the given order
+    /// to the physical plan.
+    fn extract(
+        &mut self,
+        plan: &mut PhysicalPlan,
+        extracted: &mut HashMap<(GroupId, Ordering), PhysicalNodeId>,
+        group_id: GroupId,
+        required: &Ordering,
+    ) -> PhysicalNodeId {
+        let key = (group_id, required.clone());
+        if let Some(node_id) = extracted.get(&key) {
+            return *node_id;
+        }
+        self.optimize_group(group_id, required)
+            .expect("every group has at least one implementation without cycles");
+        let (expr, cost) = match &self.winners[&key] {
+            Some(winner) => (winner.expr.clone(), winner.cost),
+            None => unreachable!(),
+        };
+        let inputs = expr
+            .node
+            .inputs()
+            .iter()
+            .zip(expr.input_orderings.iter())
+            .map(|(input, ordering)| self.extract(plan, extracted, *input, ordering))
+            .collect_vec();
+        let node_id = plan.add_node(
+            expr.node.with_inputs(&inputs),
+            PhysicalProperties {
+                cardinality: self.memo.cardinality(group_id),
+                cost,
+                ordering: expr.ordering,
+            },
+        );
+        extracted.insert(key, node_id);
+        node_id
+    }
+}
+
+lazy_static! {
+    pub static ref DEFAULT_MEMO_OPTIMIZER: MemoOptimizer = MemoOptimizer::new(
+        &DEFAULT_OPTIMIZER,
+        vec![
+            Box::new(JoinAssociativityRule {}),
+            Box::new(JoinCommutativityRule {}),
+        ],
+        vec![
+            Box::new(AggregateImplementationRule {}),
+            Box::new(ApplyImplementationRule {}),
+            Box::new(FilterImplementationRule {}),
+            Box::new(IndexLookupRule {}),
+            Box::new(JoinImplementationRule {}),
+            Box::new(LimitImplementationRule {}),
+            Box::new(ProjectImplementationRule {}),
+            Box::new(RecursiveUnionImplementationRule {}),
+            Box::new(SetOperationImplementationRule {}),
+            Box::new(SortImplementationRule {}),
+            Box::new(TableScanImplementationRule {}),
+            Box::new(TopNImplementationRule {}),
+            Box::new(UnionImplementationRule {}),
+            Box::new(ValuesImplementationRule {}),
+            Box::new(WindowImplementationRule {}),
+        ],
+    );
+}
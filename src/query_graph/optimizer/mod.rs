@@ -1,7 +1,17 @@
+use std::{collections::HashMap, fmt};
+
 use crate::visitor_utils::{PostOrderVisitationResult, PreOrderVisitationResult};
 
-use super::{visitor::QueryGraphPrePostVisitorMut, NodeId, QueryGraph};
+use itertools::Itertools;
+
+use super::{
+    validator::{validate, ValidationError},
+    visitor::{QueryGraphPrePostVisitor, QueryGraphPrePostVisitorMut},
+    NodeId, QueryGraph, QueryNode,
+};
 
+pub mod join_enumeration;
+pub mod memo;
 pub mod rules;
 pub(crate) mod utils;
 
@@ -77,9 +87,74 @@ pub trait OptimizerListener {
     );
 }
 
+/// Error returned by the optimizer when an optimization loop was stopped before reaching
+/// a fix-point, which is usually caused by a bug in some rule or by rules undoing each
+/// other's work.
+///
+/// Unless a rule led to an invalid query graph, the query graph is left in a valid
+/// state, as the loop is only stopped in between rule applications, but it may not be
+/// fully optimized.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum OptimizerError {
+    /// The optimization loop went back to a state it had already been in. Contains the
+    /// names of the rules applied since that state was first seen.
+    Cycle { rules: Vec<&'static str> },
+    /// The maximum number of rule applications allowed for an optimization loop was
+    /// reached. Contains the names of the rules applied during the last pass over the
+    /// query graph.
+    BudgetExhausted {
+        rule_applications: usize,
+        rules: Vec<&'static str>,
+    },
+    /// The given rule led to an invalid query graph. Only reported when the query graph
+    /// is validated after every rule application.
+    InvalidQueryGraph {
+        rule: &'static str,
+        errors: Vec<ValidationError>,
+    },
+}
+
+impl fmt::Display for OptimizerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OptimizerError::Cycle { rules } => {
+                write!(
+                    f,
+                    "optimization cycle detected, rules involved: {}",
+                    rules.join(", ")
+                )
+            }
+            OptimizerError::BudgetExhausted {
+                rule_applications,
+                rules,
+            } => write!(
+                f,
+                "optimization stopped after {} rule applications, last rules applied: {}",
+                rule_applications,
+                rules.join(", ")
+            ),
+            OptimizerError::InvalidQueryGraph { rule, errors } => write!(
+                f,
+                "{} led to an invalid query graph: {}",
+                rule,
+                errors.iter().join(", ")
+            ),
+        }
+    }
+}
+
 /// Structure for passing parameters to the optimizer.
 pub struct OptimizerContext<'a> {
     listeners: Vec<&'a mut dyn OptimizerListener>,
+    /// Whether the query graph must be validated after every rule application.
+    validate_after_each_rule: bool,
+    /// Maximum number of rule applications allowed within a single optimization loop.
+    max_rule_applications: usize,
+    /// The names of the rules applied during the current optimization loop, in order.
+    applied_rules: Vec<&'static str>,
+    /// The error found when validating the query graph after the last rule application,
+    /// which stops the current optimization loop.
+    validation_error: Option<OptimizerError>,
 }
 
 /// Helper visitor to apply the optimization rules in an optimizer instance during a mutating
@@ -90,15 +165,45 @@ struct OptimizationVisitor<'a, 'b, 'c> {
 }
 
 impl<'a> OptimizerContext<'a> {
+    /// Default value for the maximum number of rule applications within a single
+    /// optimization loop.
+    pub const DEFAULT_MAX_RULE_APPLICATIONS: usize = 10000;
+
     pub fn new() -> Self {
         Self {
             listeners: Vec::new(),
+            validate_after_each_rule: false,
+            max_rule_applications: Self::DEFAULT_MAX_RULE_APPLICATIONS,
+            applied_rules: Vec::new(),
+            validation_error: None,
         }
     }
 
     pub fn append_listener(&mut self, listener: &'a mut dyn OptimizerListener) {
         self.listeners.push(listener)
     }
+
+    /// Makes the optimizer validate the query graph after every rule application,
+    /// failing with `OptimizerError::InvalidQueryGraph` as soon as a rule leads to
+    /// an invalid query graph.
+    pub fn set_validate_after_each_rule(&mut self, validate_after_each_rule: bool) {
+        self.validate_after_each_rule = validate_after_each_rule
+    }
+
+    /// Sets the maximum number of rule applications allowed within a single optimization
+    /// loop, after which the optimizer gives up with `OptimizerError::BudgetExhausted`.
+    pub fn set_max_rule_applications(&mut self, max_rule_applications: usize) {
+        self.max_rule_applications = max_rule_applications
+    }
+
+    fn budget_exhausted(&self) -> bool {
+        self.applied_rules.len() >= self.max_rule_applications
+    }
+
+    /// Whether no more rules must be applied in the current optimization loop.
+    fn must_stop(&self) -> bool {
+        self.budget_exhausted() || self.validation_error.is_some()
+    }
 }
 
 impl Optimizer {
@@ -127,10 +232,17 @@ impl Optimizer {
     }
 
     /// Optimize the given query graph by applying the rules in this optimizer instance.
-    pub fn optimize(&self, context: &mut OptimizerContext, query_graph: &mut QueryGraph) {
+    ///
+    /// Fails if any of the optimization loops had to be stopped before reaching a
+    /// fix-point. See `OptimizerError`.
+    pub fn optimize(
+        &self,
+        context: &mut OptimizerContext,
+        query_graph: &mut QueryGraph,
+    ) -> Result<(), OptimizerError> {
         self.optimization_loop(context, query_graph, true, |query_graph| {
             query_graph.node(QueryGraph::ROOT_NODE_ID).get_input(0)
-        });
+        })?;
 
         // Optimize the subqueries in the query graph
         // Note: optimizing a subquery may result on some other subquery being removed.
@@ -143,23 +255,35 @@ impl Optimizer {
         {
             self.optimization_loop(context, query_graph, false, |query_graph| {
                 query_graph.node(next_subquery).get_input(0)
-            });
+            })?;
             last_subquery = Some(next_subquery);
         }
+        Ok(())
     }
 
+    /// Applies the rules in this optimizer instance to the sub-graph returned by
+    /// `get_node_id` until a fix-point is reached.
+    ///
+    /// In order to detect infinite loops due to bugs, the loop is stopped if the
+    /// structure of the sub-graph after a full pass over it is identical to the one
+    /// after any previous pass, or if the rule application budget is exhausted.
     pub fn optimization_loop<F>(
         &self,
         context: &mut OptimizerContext,
         query_graph: &mut QueryGraph,
         is_query_root: bool,
         get_node_id: F,
-    ) where
+    ) -> Result<(), OptimizerError>
+    where
         F: Fn(&QueryGraph) -> NodeId,
     {
-        // TODO(asenac) add mechanism to detect infinite loops due to bugs
+        context.applied_rules.clear();
+        // Number of rule applications at the moment each plan state was first seen.
+        let mut seen_states = HashMap::new();
+        seen_states.insert(canonical_subgraph(query_graph, get_node_id(query_graph)), 0);
         loop {
             let last_gen_number = query_graph.gen_number;
+            let pass_start = context.applied_rules.len();
 
             let mut node_id = get_node_id(query_graph);
             if is_query_root {
@@ -174,9 +298,27 @@ impl Optimizer {
 
             query_graph.visit_subgraph_mut(&mut visitor, node_id);
 
+            if let Some(err) = context.validation_error.take() {
+                return Err(err);
+            }
+
             if last_gen_number == query_graph.gen_number {
                 // Fix-point was reached. A full plan traversal without modifications.
-                break;
+                return Ok(());
+            }
+
+            if context.budget_exhausted() {
+                return Err(OptimizerError::BudgetExhausted {
+                    rule_applications: context.applied_rules.len(),
+                    rules: sorted_rule_names(&context.applied_rules[pass_start..]),
+                });
+            }
+
+            let state = canonical_subgraph(query_graph, get_node_id(query_graph));
+            if let Some(first_seen) = seen_states.insert(state, context.applied_rules.len()) {
+                return Err(OptimizerError::Cycle {
+                    rules: sorted_rule_names(&context.applied_rules[first_seen..]),
+                });
             }
         }
     }
@@ -192,9 +334,13 @@ impl Optimizer {
             .iter()
             .map(|id| self.rules.get(*id).unwrap())
         {
+            if context.must_stop() {
+                break;
+            }
             if let Some(replacements) = rule.apply(query_graph, *node_id) {
                 Self::notify_replacements(context, &**rule, query_graph, &replacements);
                 query_graph.replace_nodes(&replacements);
+                Self::validate_after_rule(context, &**rule, query_graph);
                 for (original_node, replacement_node) in replacements {
                     if original_node == *node_id {
                         *node_id = replacement_node;
@@ -210,11 +356,27 @@ impl Optimizer {
         query_graph: &QueryGraph,
         replacements: &Vec<(NodeId, NodeId)>,
     ) {
+        context.applied_rules.push(rule.name());
         for listener in context.listeners.iter_mut() {
             listener.node_replacements(rule, query_graph, replacements);
         }
     }
 
+    fn validate_after_rule(
+        context: &mut OptimizerContext,
+        rule: &dyn Rule,
+        query_graph: &QueryGraph,
+    ) {
+        if context.validate_after_each_rule {
+            if let Err(errors) = validate(query_graph) {
+                context.validation_error = Some(OptimizerError::InvalidQueryGraph {
+                    rule: rule.name(),
+                    errors,
+                });
+            }
+        }
+    }
+
     /// Apply a set of rules to the given node. It returns early if any of the rules
     /// replaces any node that is not the current one, as that invalidates the current
     /// traversal stack.
@@ -222,7 +384,9 @@ impl Optimizer {
     /// If the given node is replaced, `node_id` is updated to point to the replacement
     /// node.
     ///
-    /// Returns whether the current traversal can continue or must be aborted.
+    /// Returns whether the current traversal can continue or must be aborted, either
+    /// because the traversal stack was invalidated, because the rule application
+    /// budget was exhausted or because the last rule led to an invalid query graph.
     fn apply_rule_list(
         &self,
         context: &mut OptimizerContext,
@@ -232,11 +396,18 @@ impl Optimizer {
     ) -> bool {
         let mut can_continue = true;
         for rule in rules.iter().map(|id| self.rules.get(*id).unwrap()) {
+            if context.must_stop() {
+                return false;
+            }
             if let Some(replacements) = rule.apply(query_graph, *node_id) {
                 Optimizer::notify_replacements(context, &**rule, query_graph, &replacements);
                 // Replace the node in the graph and apply the remaining rules to the
                 // returned one.
                 query_graph.replace_nodes(&replacements);
+                Optimizer::validate_after_rule(context, &**rule, query_graph);
+                if context.validation_error.is_some() {
+                    return false;
+                }
                 for (original_node, replacement_node) in replacements {
                     if original_node == *node_id {
                         // Make the visitation logic aware of the replacement, so the inputs of
@@ -292,6 +463,64 @@ impl QueryGraphPrePostVisitorMut for OptimizationVisitor<'_, '_, '_> {
     }
 }
 
+/// Returns the given rule names sorted and without duplicates.
+fn sorted_rule_names(rules: &[&'static str]) -> Vec<&'static str> {
+    rules.iter().cloned().sorted().dedup().collect()
+}
+
+/// Returns a canonical form of the structure of the sub-graph under the given node,
+/// which, unlike the node IDs, remains the same when a rule re-creates nodes that had
+/// already been replaced. Two sub-graphs are structurally equal if and only if their
+/// canonical forms are equal.
+///
+/// The canonical form is the list of nodes in post-order, where the nodes are numbered
+/// in the order they are visited and point to the numbers given to their inputs, so
+/// that it also reflects which nodes are shared.
+///
+/// The sub-graphs under the subquery roots are included as well, since some rules, such
+/// as `RootOnly` ones, may modify them.
+fn canonical_subgraph(query_graph: &QueryGraph, node_id: NodeId) -> Vec<QueryNode> {
+    let mut visitor = CanonicalSubgraphVisitor {
+        node_numbers: HashMap::new(),
+        nodes: Vec::new(),
+    };
+    query_graph.visit_subgraph(&mut visitor, node_id);
+    for subquery_root in query_graph.subqueries.iter() {
+        query_graph.visit_subgraph(&mut visitor, *subquery_root);
+    }
+    visitor.nodes
+}
+
+struct CanonicalSubgraphVisitor {
+    node_numbers: HashMap<NodeId, usize>,
+    nodes: Vec<QueryNode>,
+}
+
+impl QueryGraphPrePostVisitor for CanonicalSubgraphVisitor {
+    fn visit_pre(&mut self, _: &QueryGraph, node_id: NodeId) -> PreOrderVisitationResult {
+        if self.node_numbers.contains_key(&node_id) {
+            PreOrderVisitationResult::DoNotVisitInputs
+        } else {
+            PreOrderVisitationResult::VisitInputs
+        }
+    }
+
+    fn visit_post(&mut self, query_graph: &QueryGraph, node_id: NodeId) {
+        if self.node_numbers.contains_key(&node_id) {
+            return;
+        }
+        // Store a copy of the node pointing to the numbers given to its inputs instead
+        // of to their IDs.
+        let mut node = query_graph.node(node_id).clone();
+        for input_idx in 0..node.num_inputs() {
+            let input_number = self.node_numbers[&node.get_input(input_idx)];
+            node.set_input(input_idx, input_number);
+        }
+        self.nodes.push(node);
+        self.node_numbers.insert(node_id, self.node_numbers.len());
+    }
+}
+
 pub fn build_rule(rule_name: &str) -> Result<Box<dyn Rule>, ()> {
     use self::rules::*;
     match rule_name {
@@ -299,25 +528,45 @@ pub fn build_rule(rule_name: &str) -> Result<Box<dyn Rule>, ()> {
         "AggregatePruningRule" => Ok(Box::new(AggregatePruningRule {})),
         "AggregateRemoveRule" => Ok(Box::new(AggregateRemoveRule {})),
         "AggregateSimplifierRule" => Ok(Box::new(AggregateSimplifierRule {})),
+        "ApplyAggregateTransposeRule" => Ok(Box::new(ApplyAggregateTransposeRule {})),
+        "ApplyFilterTransposeRule" => Ok(Box::new(ApplyFilterTransposeRule {})),
+        "ApplyJoinTransposeRule" => Ok(Box::new(ApplyJoinTransposeRule {})),
+        "ApplyProjectTransposeRule" => Ok(Box::new(ApplyProjectTransposeRule {})),
         "ApplyPruningRule" => Ok(Box::new(ApplyPruningRule {})),
+        "ApplyToJoinRule" => Ok(Box::new(ApplyToJoinRule {})),
+        "ApplyUnionTransposeRule" => Ok(Box::new(ApplyUnionTransposeRule {})),
         "CommonAggregateDiscoveryRule" => Ok(Box::new(CommonAggregateDiscoveryRule {})),
         "CteDiscoveryRule" => Ok(Box::new(CteDiscoveryRule {})),
+        "EmptyRelationPropagationRule" => Ok(Box::new(EmptyRelationPropagationRule {})),
         "EqualityPropagationRule" => Ok(Box::new(EqualityPropagationRule {})),
         "ExpressionReductionRule" => Ok(Box::new(ExpressionReductionRule {})),
+        "ForeignKeyJoinEliminationRule" => Ok(Box::new(ForeignKeyJoinEliminationRule {})),
         "FilterAggregateTransposeRule" => Ok(Box::new(FilterAggregateTransposeRule {})),
         "FilterApplyTransposeRule" => Ok(Box::new(FilterApplyTransposeRule {})),
         "FilterJoinTransposeRule" => Ok(Box::new(FilterJoinTransposeRule {})),
         "FilterMergeRule" => Ok(Box::new(FilterMergeRule {})),
         "FilterNormalizationRule" => Ok(Box::new(FilterNormalizationRule {})),
         "FilterProjectTransposeRule" => Ok(Box::new(FilterProjectTransposeRule {})),
+        "FilterSubqueryUnnestingRule" => Ok(Box::new(FilterSubqueryUnnestingRule {})),
+        "FilterWindowToTopNRule" => Ok(Box::new(FilterWindowToTopNRule {})),
+        "FilterWindowTransposeRule" => Ok(Box::new(FilterWindowTransposeRule {})),
         "IdentityJoinRule" => Ok(Box::new(IdentityJoinRule {})),
         "JoinProjectTransposeRule" => Ok(Box::new(JoinProjectTransposeRule {})),
         "JoinPruningRule" => Ok(Box::new(JoinPruningRule {})),
+        "JoinReorderingRule" => Ok(Box::new(JoinReorderingRule {})),
+        "LimitProjectTransposeRule" => Ok(Box::new(LimitProjectTransposeRule {})),
+        "LimitSortToTopNRule" => Ok(Box::new(LimitSortToTopNRule {})),
+        "LimitUnionTransposeRule" => Ok(Box::new(LimitUnionTransposeRule {})),
+        "OuterApplyToInnerRule" => Ok(Box::new(OuterApplyToInnerRule {})),
         "OuterToInnerJoinRule" => Ok(Box::new(OuterToInnerJoinRule {})),
         "ProjectMergeRule" => Ok(Box::new(ProjectMergeRule {})),
         "ProjectNormalizationRule" => Ok(Box::new(ProjectNormalizationRule {})),
         "PruneAggregateInputRule" => Ok(Box::new(PruneAggregateInputRule {})),
         "RemovePassthroughProjectRule" => Ok(Box::new(RemovePassthroughProjectRule {})),
+        "ScalarSubqueryUnnestingRule" => Ok(Box::new(ScalarSubqueryUnnestingRule {})),
+        "SetOperationLoweringRule" => Ok(Box::new(SetOperationLoweringRule {})),
+        "SetOperationSimplifierRule" => Ok(Box::new(SetOperationSimplifierRule {})),
+        "SortRemoveRule" => Ok(Box::new(SortRemoveRule {})),
         "TopProjectionRule" => Ok(Box::new(TopProjectionRule {})),
         "UnionMergeRule" => Ok(Box::new(UnionMergeRule {})),
         "UnionPruningRule" => Ok(Box::new(UnionPruningRule {})),
@@ -333,25 +582,45 @@ lazy_static! {
             Box::new(AggregatePruningRule {}),
             Box::new(AggregateRemoveRule {}),
             Box::new(AggregateSimplifierRule {}),
+            Box::new(ApplyAggregateTransposeRule {}),
+            Box::new(ApplyFilterTransposeRule {}),
+            Box::new(ApplyJoinTransposeRule {}),
+            Box::new(ApplyProjectTransposeRule {}),
             Box::new(ApplyPruningRule {}),
+            Box::new(ApplyToJoinRule {}),
+            Box::new(ApplyUnionTransposeRule {}),
             Box::new(CteDiscoveryRule {}),
             Box::new(CommonAggregateDiscoveryRule {}),
+            Box::new(EmptyRelationPropagationRule {}),
             Box::new(EqualityPropagationRule {}),
             Box::new(ExpressionReductionRule {}),
+            Box::new(ForeignKeyJoinEliminationRule {}),
             Box::new(FilterAggregateTransposeRule {}),
             Box::new(FilterApplyTransposeRule {}),
             Box::new(FilterJoinTransposeRule {}),
             Box::new(FilterMergeRule {}),
             Box::new(FilterNormalizationRule {}),
             Box::new(FilterProjectTransposeRule {}),
+            Box::new(FilterSubqueryUnnestingRule {}),
+            Box::new(FilterWindowToTopNRule {}),
+            Box::new(FilterWindowTransposeRule {}),
             Box::new(IdentityJoinRule {}),
             Box::new(JoinProjectTransposeRule {}),
             Box::new(JoinPruningRule {}),
+            Box::new(JoinReorderingRule {}),
+            Box::new(LimitProjectTransposeRule {}),
+            Box::new(LimitSortToTopNRule {}),
+            Box::new(LimitUnionTransposeRule {}),
+            Box::new(OuterApplyToInnerRule {}),
             Box::new(OuterToInnerJoinRule {}),
             Box::new(ProjectMergeRule {}),
             Box::new(ProjectNormalizationRule {}),
             Box::new(PruneAggregateInputRule {}),
             Box::new(RemovePassthroughProjectRule {}),
+            Box::new(ScalarSubqueryUnnestingRule {}),
+            Box::new(SetOperationLoweringRule {}),
+            Box::new(SetOperationSimplifierRule {}),
+            Box::new(SortRemoveRule {}),
             Box::new(TopProjectionRule {}),
             Box::new(UnionMergeRule {}),
             Box::new(UnionPruningRule {}),
@@ -359,3 +628,203 @@ lazy_static! {
         optimizer
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        query_graph::{JoinType, QueryNode},
+        scalar_expr::{BinaryOp, ScalarExpr, ScalarExprRef},
+    };
+
+    use super::*;
+
+    /// Sorts the conditions of filter nodes.
+    struct SortConditionsRule {}
+
+    impl SingleReplacementRule for SortConditionsRule {
+        fn rule_type(&self) -> OptRuleType {
+            OptRuleType::TopDown
+        }
+
+        fn apply(&self, query_graph: &mut QueryGraph, node_id: NodeId) -> Option<NodeId> {
+            if let QueryNode::Filter { conditions, input } = query_graph.node(node_id) {
+                let sorted = conditions.iter().cloned().sorted().collect_vec();
+                if sorted != *conditions {
+                    let input = *input;
+                    return Some(query_graph.filter(input, sorted));
+                }
+            }
+            None
+        }
+    }
+
+    /// Reverses the conditions of filter nodes whose conditions are sorted, undoing
+    /// `SortConditionsRule`.
+    struct ReverseConditionsRule {}
+
+    impl SingleReplacementRule for ReverseConditionsRule {
+        fn rule_type(&self) -> OptRuleType {
+            OptRuleType::TopDown
+        }
+
+        fn apply(&self, query_graph: &mut QueryGraph, node_id: NodeId) -> Option<NodeId> {
+            if let QueryNode::Filter { conditions, input } = query_graph.node(node_id) {
+                if conditions.len() > 1 && conditions.iter().tuple_windows().all(|(a, b)| a < b) {
+                    let reversed = conditions.iter().rev().cloned().collect_vec();
+                    let input = *input;
+                    return Some(query_graph.filter(input, reversed));
+                }
+            }
+            None
+        }
+    }
+
+    /// Duplicates the first condition of filter nodes, growing them forever.
+    struct GrowConditionsRule {}
+
+    impl SingleReplacementRule for GrowConditionsRule {
+        fn rule_type(&self) -> OptRuleType {
+            OptRuleType::BottomUp
+        }
+
+        fn apply(&self, query_graph: &mut QueryGraph, node_id: NodeId) -> Option<NodeId> {
+            if let QueryNode::Filter { conditions, input } = query_graph.node(node_id) {
+                let mut conditions = conditions.clone();
+                conditions.push(conditions[0].clone());
+                let input = *input;
+                return Some(query_graph.filter(input, conditions));
+            }
+            None
+        }
+    }
+
+    /// Replaces the conditions of filter nodes with one referencing a column their
+    /// input doesn't project.
+    struct OutOfBoundsConditionRule {}
+
+    impl SingleReplacementRule for OutOfBoundsConditionRule {
+        fn rule_type(&self) -> OptRuleType {
+            OptRuleType::TopDown
+        }
+
+        fn apply(&self, query_graph: &mut QueryGraph, node_id: NodeId) -> Option<NodeId> {
+            if let QueryNode::Filter { input, .. } = query_graph.node(node_id) {
+                let input = *input;
+                let condition = ScalarExpr::input_ref(4)
+                    .binary(BinaryOp::Eq, ScalarExpr::input_ref(0).into())
+                    .into();
+                return Some(query_graph.filter(input, vec![condition]));
+            }
+            None
+        }
+    }
+
+    fn filter_conditions() -> Vec<ScalarExprRef> {
+        vec![
+            ScalarExpr::input_ref(0)
+                .binary(BinaryOp::Eq, ScalarExpr::input_ref(2).into())
+                .into(),
+            ScalarExpr::input_ref(1)
+                .binary(BinaryOp::Eq, ScalarExpr::input_ref(3).into())
+                .into(),
+        ]
+    }
+
+    fn filter_query_graph() -> QueryGraph {
+        let mut query_graph = QueryGraph::new();
+        let left = query_graph.table_scan(0, 2);
+        let right = query_graph.table_scan(1, 2);
+        let join = query_graph.join(JoinType::Inner, left, right, Vec::new());
+        let filter = query_graph.filter(join, filter_conditions());
+        query_graph.set_entry_node(filter);
+        query_graph
+    }
+
+    #[test]
+    fn test_cycle_detection() {
+        let optimizer = Optimizer::new(vec![
+            Box::new(SortConditionsRule {}),
+            Box::new(ReverseConditionsRule {}),
+        ]);
+        let mut query_graph = filter_query_graph();
+        let mut context = OptimizerContext::new();
+        assert_eq!(
+            optimizer.optimize(&mut context, &mut query_graph),
+            Err(OptimizerError::Cycle {
+                rules: vec!["ReverseConditionsRule", "SortConditionsRule"]
+            })
+        );
+    }
+
+    #[test]
+    fn test_rule_application_budget() {
+        let optimizer = Optimizer::new(vec![Box::new(GrowConditionsRule {})]);
+        let mut query_graph = filter_query_graph();
+        let mut context = OptimizerContext::new();
+        context.set_max_rule_applications(50);
+        assert_eq!(
+            optimizer.optimize(&mut context, &mut query_graph),
+            Err(OptimizerError::BudgetExhausted {
+                rule_applications: 50,
+                rules: vec!["GrowConditionsRule"]
+            })
+        );
+        // The query graph is left in a valid state
+        assert!(validate(&query_graph).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_query_graph_after_rule() {
+        let optimizer = Optimizer::new(vec![Box::new(OutOfBoundsConditionRule {})]);
+        let mut query_graph = filter_query_graph();
+        let mut context = OptimizerContext::new();
+        context.set_validate_after_each_rule(true);
+        let filter = query_graph.node(QueryGraph::ROOT_NODE_ID).get_input(0);
+        let Err(OptimizerError::InvalidQueryGraph { rule, errors }) =
+            optimizer.optimize(&mut context, &mut query_graph)
+        else {
+            panic!("expected an invalid query graph error");
+        };
+        assert_eq!(rule, "OutOfBoundsConditionRule");
+        assert_eq!(
+            errors,
+            vec![ValidationError::ColumnOutOfBounds {
+                node_id: query_graph.node(QueryGraph::ROOT_NODE_ID).get_input(0),
+                index: 4,
+                num_columns: 4,
+            }]
+        );
+        assert_ne!(
+            filter,
+            query_graph.node(QueryGraph::ROOT_NODE_ID).get_input(0)
+        );
+    }
+
+    #[test]
+    fn test_canonical_subgraph() {
+        let query_graph = filter_query_graph();
+        // Same plan with different node IDs
+        let mut renumbered_query_graph = QueryGraph::new();
+        renumbered_query_graph.table_scan(2, 2);
+        let left = renumbered_query_graph.table_scan(0, 2);
+        let right = renumbered_query_graph.table_scan(1, 2);
+        let join = renumbered_query_graph.join(JoinType::Inner, left, right, Vec::new());
+        let filter = renumbered_query_graph.filter(join, filter_conditions());
+        renumbered_query_graph.set_entry_node(filter);
+        assert!(
+            canonical_subgraph(&query_graph, QueryGraph::ROOT_NODE_ID)
+                == canonical_subgraph(&renumbered_query_graph, QueryGraph::ROOT_NODE_ID)
+        );
+        // Same nodes with the join inputs swapped
+        let mut swapped_query_graph = QueryGraph::new();
+        let left = swapped_query_graph.table_scan(1, 2);
+        let right = swapped_query_graph.table_scan(0, 2);
+        let join = swapped_query_graph.join(JoinType::Inner, left, right, Vec::new());
+        let filter = swapped_query_graph.filter(join, filter_conditions());
+        swapped_query_graph.set_entry_node(filter);
+        assert!(
+            canonical_subgraph(&query_graph, QueryGraph::ROOT_NODE_ID)
+                != canonical_subgraph(&swapped_query_graph, QueryGraph::ROOT_NODE_ID)
+        );
+    }
+}
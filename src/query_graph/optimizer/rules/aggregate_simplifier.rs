@@ -9,6 +9,7 @@ use crate::{
     scalar_expr::{
         equivalence_class::{find_class, EquivalenceClasses},
         rewrite::{rewrite_expr_post, rewrite_expr_vec},
+        visitor::collect_input_dependencies,
         ScalarExpr, ScalarExprRef,
     },
 };
@@ -80,8 +81,13 @@ fn find_redundant_key(
         let input_ref = ScalarExpr::input_ref(out_col).into();
         if let Some(class_id) = find_class(&classes, &input_ref) {
             let class = &classes[class_id];
-            // TODO(asenac) verify that other doesn't reference input_ref
-            if let Some(other) = class.members.iter().find(|x| **x != input_ref).cloned() {
+            // The replacement expression must not reference the column being removed
+            if let Some(other) = class
+                .members
+                .iter()
+                .find(|x| **x != input_ref && !collect_input_dependencies(x).contains(&out_col))
+                .cloned()
+            {
                 return Some((out_col, *in_col, other));
             }
         }
@@ -109,3 +115,34 @@ fn update_project_after_pruning_column(
         )
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::scalar_expr::{
+        equivalence_class::extract_equivalence_classes, BinaryOp, NaryOp, ScalarExpr,
+    };
+
+    use super::find_redundant_key;
+
+    #[test]
+    fn test_find_redundant_key_self_reference() {
+        // ref_0 cannot be replaced with an expression depending on itself
+        let classes = extract_equivalence_classes(&vec![ScalarExpr::input_ref(0)
+            .binary(
+                BinaryOp::RawEq,
+                ScalarExpr::nary(
+                    NaryOp::Concat,
+                    vec![
+                        ScalarExpr::input_ref(0).into(),
+                        ScalarExpr::input_ref(1).into(),
+                    ],
+                )
+                .into(),
+            )
+            .into()]);
+        let group_key = BTreeSet::from([0, 1]);
+        assert_eq!(find_redundant_key(&group_key, &classes), None);
+    }
+}
@@ -0,0 +1,145 @@
+use std::rc::Rc;
+
+use crate::{
+    query_graph::{
+        optimizer::{
+            utils::{domain_apply, domain_join_conditions},
+            OptRuleType, SingleReplacementRule,
+        },
+        properties::{num_columns, subgraph_correlated_input_refs},
+        ApplyType, JoinType, NodeId, QueryGraph, QueryNode,
+    },
+    scalar_expr::{AggregateExpr, AggregateOp, ScalarExpr},
+};
+
+/// Rule that pushes an apply through the aggregation at its right input, by evaluating the
+/// aggregate input for each distinct value of the parameters of the apply, ie. over its
+/// domain, and grouping the result by the columns of the domain. The result of the
+/// aggregation is then joined with the left input of the apply.
+///
+/// ```txt
+/// Apply parameters: [ref_1]
+///   A
+///   Aggregate key: [ref_0], aggregates: [min(ref_1)]
+///     R
+/// ```
+///
+/// becomes:
+///
+/// ```txt
+/// Project [ref_0, ..., ref_n-1, ref_n+1, ref_n+2]
+///   Join [raw_eq(ref_1, ref_n)]
+///     A
+///     Aggregate key: [ref_0, ref_1], aggregates: [min(ref_2)]
+///       Apply parameters: [ref_0]
+///         Aggregate key: [ref_0]
+///           Project [ref_1]
+///             A
+///         R
+/// ```
+///
+/// Global aggregations always return one row, even for the values of the domain for
+/// which the aggregate input is empty. In that case, the aggregate input is evaluated
+/// with a left outer apply, and `count(*)` aggregates are turned into counts of a
+/// non-nullable marker column projected on top of it, so that the NULL-padded rows
+/// are not counted.
+pub struct ApplyAggregateTransposeRule {}
+
+impl SingleReplacementRule for ApplyAggregateTransposeRule {
+    fn rule_type(&self) -> OptRuleType {
+        OptRuleType::TopDown
+    }
+
+    fn apply(&self, query_graph: &mut QueryGraph, node_id: NodeId) -> Option<NodeId> {
+        if let QueryNode::Apply {
+            correlation,
+            left,
+            right,
+            apply_type,
+        } = query_graph.node(node_id)
+        {
+            if let QueryNode::Aggregate {
+                group_key,
+                aggregates,
+                input,
+            } = query_graph.node(*right)
+            {
+                if subgraph_correlated_input_refs(query_graph, *right).is_empty() {
+                    return None;
+                }
+                let parameters = correlation.parameters.clone();
+                let num_parameters = parameters.len();
+                let left = *left;
+                let apply_type = *apply_type;
+                let input = *input;
+                let group_key = group_key.clone();
+                let aggregates = aggregates.clone();
+                let left_num_columns = num_columns(query_graph, left);
+                let num_aggregate_columns = group_key.len() + aggregates.len();
+
+                let (inner_apply_type, join_type, input, count_operand) = if group_key.is_empty() {
+                    let input_num_columns = num_columns(query_graph, input);
+                    let has_count_star = aggregates
+                        .iter()
+                        .any(|a| a.op == AggregateOp::Count && a.operands.is_empty());
+                    let (input, count_operand) = if has_count_star {
+                        let outputs = (0..input_num_columns)
+                            .map(|i| ScalarExpr::input_ref(i).into())
+                            .chain(std::iter::once(ScalarExpr::true_literal().into()))
+                            .collect();
+                        let marked_input = query_graph.project(input, outputs);
+                        (marked_input, Some(num_parameters + input_num_columns))
+                    } else {
+                        (input, None)
+                    };
+                    (ApplyType::LeftOuter, JoinType::Inner, input, count_operand)
+                } else {
+                    let join_type = match apply_type {
+                        ApplyType::Inner => JoinType::Inner,
+                        ApplyType::LeftOuter => JoinType::LeftOuter,
+                    };
+                    (ApplyType::Inner, join_type, input, None)
+                };
+
+                let new_apply =
+                    domain_apply(query_graph, inner_apply_type, left, &parameters, input);
+                let new_aggregate = query_graph.add_node(QueryNode::Aggregate {
+                    group_key: (0..num_parameters)
+                        .chain(group_key.iter().map(|k| k + num_parameters))
+                        .collect(),
+                    aggregates: aggregates
+                        .iter()
+                        .map(|a| {
+                            let operands = match count_operand {
+                                Some(count_operand) if a.operands.is_empty() => {
+                                    vec![count_operand]
+                                }
+                                _ => a.operands.iter().map(|o| o + num_parameters).collect(),
+                            };
+                            Rc::new(AggregateExpr {
+                                op: a.op.clone(),
+                                operands,
+                            })
+                        })
+                        .collect(),
+                    input: new_apply,
+                });
+                let join = query_graph.join(
+                    join_type,
+                    left,
+                    new_aggregate,
+                    domain_join_conditions(&parameters, left_num_columns),
+                );
+                let outputs = (0..left_num_columns)
+                    .chain(
+                        left_num_columns + num_parameters
+                            ..left_num_columns + num_parameters + num_aggregate_columns,
+                    )
+                    .map(|i| ScalarExpr::input_ref(i).into())
+                    .collect();
+                return Some(query_graph.project(join, outputs));
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,61 @@
+use crate::query_graph::{
+    optimizer::{utils::pull_up_correlated_expr, OptRuleType, SingleReplacementRule},
+    properties::{expr_correlated_input_refs, num_columns},
+    ApplyType, NodeId, QueryGraph, QueryNode,
+};
+
+/// Rule that pushes an inner apply through the filter at its right input, by pulling the
+/// correlated conditions of the filter on top of the apply.
+///
+/// The uncorrelated conditions are left in place, since `FilterApplyTransposeRule` would
+/// push them down again.
+pub struct ApplyFilterTransposeRule {}
+
+impl SingleReplacementRule for ApplyFilterTransposeRule {
+    fn rule_type(&self) -> OptRuleType {
+        OptRuleType::TopDown
+    }
+
+    fn apply(&self, query_graph: &mut QueryGraph, node_id: NodeId) -> Option<NodeId> {
+        if let QueryNode::Apply {
+            correlation,
+            left,
+            right,
+            apply_type: ApplyType::Inner,
+        } = query_graph.node(node_id)
+        {
+            if let QueryNode::Filter { conditions, input } = query_graph.node(*right) {
+                let left_num_columns = num_columns(query_graph, *left);
+                let mut pulled_up_conditions = Vec::new();
+                let mut remaining_conditions = Vec::new();
+                for condition in conditions.iter() {
+                    if !expr_correlated_input_refs(query_graph, condition).is_empty() {
+                        if let Some(pulled_up_condition) = pull_up_correlated_expr(
+                            condition,
+                            &correlation.parameters,
+                            left_num_columns,
+                        ) {
+                            pulled_up_conditions.push(pulled_up_condition);
+                            continue;
+                        }
+                    }
+                    remaining_conditions.push(condition.clone());
+                }
+                if !pulled_up_conditions.is_empty() {
+                    let correlation = correlation.clone();
+                    let left = *left;
+                    let input = *input;
+                    let new_right = query_graph.filter(input, remaining_conditions);
+                    let new_apply = query_graph.add_node(QueryNode::Apply {
+                        correlation,
+                        left,
+                        right: new_right,
+                        apply_type: ApplyType::Inner,
+                    });
+                    return Some(query_graph.filter(new_apply, pulled_up_conditions));
+                }
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use crate::{
+    query_graph::{
+        optimizer::{utils::pull_up_correlated_expr, OptRuleType, SingleReplacementRule},
+        properties::{num_columns, subgraph_correlated_input_refs},
+        ApplyType, JoinType, NodeId, QueryGraph, QueryNode,
+    },
+    scalar_expr::{rewrite::apply_column_map, ScalarExpr},
+};
+
+/// Rule that pushes an inner apply through the join at its right input when only one
+/// of the inputs of the join is correlated. The apply is pushed into the correlated
+/// input and the conditions of the join are pulled up.
+///
+/// When the correlated input is the right one, the inputs of the new join are swapped,
+/// so that the apply remains at the preserving side of outer joins, and a projection
+/// restoring the original order of the columns is added on top of it.
+pub struct ApplyJoinTransposeRule {}
+
+impl SingleReplacementRule for ApplyJoinTransposeRule {
+    fn rule_type(&self) -> OptRuleType {
+        OptRuleType::TopDown
+    }
+
+    fn apply(&self, query_graph: &mut QueryGraph, node_id: NodeId) -> Option<NodeId> {
+        if let QueryNode::Apply {
+            correlation,
+            left,
+            right,
+            apply_type: ApplyType::Inner,
+        } = query_graph.node(node_id)
+        {
+            if let QueryNode::Join {
+                join_type,
+                conditions,
+                left: join_left,
+                right: join_right,
+            } = query_graph.node(*right)
+            {
+                if subgraph_correlated_input_refs(query_graph, *right).is_empty() {
+                    return None;
+                }
+                let left_num_columns = num_columns(query_graph, *left);
+                let conditions = conditions
+                    .iter()
+                    .map(|c| pull_up_correlated_expr(c, &correlation.parameters, left_num_columns))
+                    .collect::<Option<Vec<_>>>()?;
+                let correlation = correlation.clone();
+                let left = *left;
+                let join_type = *join_type;
+                let join_left = *join_left;
+                let join_right = *join_right;
+                if subgraph_correlated_input_refs(query_graph, join_right).is_empty() {
+                    match join_type {
+                        JoinType::Inner | JoinType::LeftOuter | JoinType::Semi | JoinType::Anti => {
+                            let new_apply = query_graph.add_node(QueryNode::Apply {
+                                correlation,
+                                left,
+                                right: join_left,
+                                apply_type: ApplyType::Inner,
+                            });
+                            return Some(
+                                query_graph.join(join_type, new_apply, join_right, conditions),
+                            );
+                        }
+                        JoinType::RightOuter | JoinType::FullOuter => return None,
+                    }
+                }
+                if subgraph_correlated_input_refs(query_graph, join_left).is_empty() {
+                    let new_join_type = match join_type {
+                        JoinType::Inner => JoinType::Inner,
+                        JoinType::RightOuter => JoinType::LeftOuter,
+                        _ => return None,
+                    };
+                    let join_left_num_columns = num_columns(query_graph, join_left);
+                    let join_right_num_columns = num_columns(query_graph, join_right);
+                    // Columns from the left input of the apply stay in place, while the
+                    // ones from the inputs of the join are swapped
+                    let column_map = (0..left_num_columns)
+                        .map(|i| (i, i))
+                        .chain((0..join_left_num_columns).map(|i| {
+                            (
+                                left_num_columns + i,
+                                left_num_columns + join_right_num_columns + i,
+                            )
+                        }))
+                        .chain((0..join_right_num_columns).map(|i| {
+                            (
+                                left_num_columns + join_left_num_columns + i,
+                                left_num_columns + i,
+                            )
+                        }))
+                        .collect::<HashMap<_, _>>();
+                    let conditions = conditions
+                        .iter()
+                        .map(|c| apply_column_map(c, &column_map).unwrap())
+                        .collect();
+                    let new_apply = query_graph.add_node(QueryNode::Apply {
+                        correlation,
+                        left,
+                        right: join_right,
+                        apply_type: ApplyType::Inner,
+                    });
+                    let new_join =
+                        query_graph.join(new_join_type, new_apply, join_left, conditions);
+                    let outputs = (0..left_num_columns)
+                        .chain(
+                            left_num_columns + join_right_num_columns
+                                ..left_num_columns + join_right_num_columns + join_left_num_columns,
+                        )
+                        .chain(left_num_columns..left_num_columns + join_right_num_columns)
+                        .map(|i| ScalarExpr::input_ref(i).into())
+                        .collect();
+                    return Some(query_graph.project(new_join, outputs));
+                }
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,64 @@
+use crate::{
+    query_graph::{
+        optimizer::{utils::pull_up_correlated_expr, OptRuleType, SingleReplacementRule},
+        properties::{is_null_if_input_is_null, num_columns, subgraph_correlated_input_refs},
+        ApplyType, NodeId, QueryGraph, QueryNode,
+    },
+    scalar_expr::ScalarExpr,
+};
+
+/// Rule that pushes an apply with a correlated right input through the projection at
+/// its right input, by evaluating the projected expressions on top of the apply.
+///
+/// For left outer applies, the expressions must evaluate to NULL when the input of the
+/// projection is NULL, since they are evaluated after padding with NULLs the rows from
+/// the left input without matches.
+pub struct ApplyProjectTransposeRule {}
+
+impl SingleReplacementRule for ApplyProjectTransposeRule {
+    fn rule_type(&self) -> OptRuleType {
+        OptRuleType::TopDown
+    }
+
+    fn apply(&self, query_graph: &mut QueryGraph, node_id: NodeId) -> Option<NodeId> {
+        if let QueryNode::Apply {
+            correlation,
+            left,
+            right,
+            apply_type,
+        } = query_graph.node(node_id)
+        {
+            if let QueryNode::Project { outputs, input } = query_graph.node(*right) {
+                if subgraph_correlated_input_refs(query_graph, *right).is_empty() {
+                    return None;
+                }
+                if *apply_type == ApplyType::LeftOuter
+                    && !outputs.iter().all(is_null_if_input_is_null)
+                {
+                    return None;
+                }
+                let left_num_columns = num_columns(query_graph, *left);
+                let pulled_up_outputs = outputs
+                    .iter()
+                    .map(|e| pull_up_correlated_expr(e, &correlation.parameters, left_num_columns))
+                    .collect::<Option<Vec<_>>>()?;
+                let correlation = correlation.clone();
+                let apply_type = *apply_type;
+                let left = *left;
+                let input = *input;
+                let new_apply = query_graph.add_node(QueryNode::Apply {
+                    correlation,
+                    left,
+                    right: input,
+                    apply_type,
+                });
+                let project = (0..left_num_columns)
+                    .map(|i| ScalarExpr::input_ref(i).into())
+                    .chain(pulled_up_outputs)
+                    .collect();
+                return Some(query_graph.project(new_apply, project));
+            }
+        }
+        None
+    }
+}
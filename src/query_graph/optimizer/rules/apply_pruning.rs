@@ -9,10 +9,10 @@ use crate::{
             },
             OptRuleType, Rule,
         },
-        properties::num_columns,
-        NodeId, QueryGraph, QueryNode,
+        properties::{num_columns, subgraph_correlated_input_refs},
+        CorrelationContext, NodeId, QueryGraph, QueryNode,
     },
-    scalar_expr::{visitor::store_input_dependencies, ScalarExpr},
+    scalar_expr::{rewrite::apply_column_map, visitor::store_input_dependencies, ScalarExpr},
 };
 
 /// Rule that given a shared apply where all its parents are pruning projections, computes
@@ -44,12 +44,17 @@ impl Rule for ApplyPruningRule {
                 for parameter in correlation.parameters.iter() {
                     store_input_dependencies(parameter, &mut required_columns);
                 }
+                let left_num_columns = num_columns(query_graph, *left);
+                // A pruning projection on top of a correlated RHS would be pulled up
+                // again by ApplyProjectTransposeRule
+                if !subgraph_correlated_input_refs(query_graph, *right).is_empty() {
+                    required_columns.extend(left_num_columns..num_columns(query_graph, node_id));
+                }
                 if required_columns.len() == num_columns(&query_graph, node_id) {
                     // All columns are referenced, nothing to prune
                     return None;
                 }
                 let column_map = required_columns_to_column_map(&required_columns);
-                let left_num_columns = num_columns(query_graph, *left);
                 let (left_columns, right_columns): (Vec<usize>, Vec<usize>) = required_columns
                     .iter()
                     .sorted()
@@ -67,7 +72,14 @@ impl Rule for ApplyPruningRule {
                         .into()
                     })
                     .collect::<Vec<_>>();
-                let correlation = correlation.clone();
+                // The parameters must refer to the columns of the pruned LHS
+                let correlation = CorrelationContext {
+                    parameters: correlation
+                        .parameters
+                        .iter()
+                        .map(|e| apply_column_map(e, &column_map).unwrap())
+                        .collect(),
+                };
                 let apply_type = *apply_type;
                 let left = *left;
                 let right = *right;
@@ -92,3 +104,77 @@ impl Rule for ApplyPruningRule {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        data_type::DataType,
+        query_graph::{optimizer::Rule, ApplyType, CorrelationContext, QueryGraph, QueryNode},
+        scalar_expr::{BinaryOp, ScalarExpr, ScalarExprRef},
+    };
+
+    use super::ApplyPruningRule;
+
+    #[test]
+    fn test_parameters_refer_to_pruned_left_input() {
+        let mut query_graph = QueryGraph::new();
+        let table_scan_1 = query_graph.table_scan(1, 5);
+        let filter_1 = query_graph.filter(
+            table_scan_1,
+            vec![ScalarExpr::input_ref(0)
+                .binary(
+                    BinaryOp::Eq,
+                    ScalarExpr::CorrelatedInputRef {
+                        context_offset: 0,
+                        index: 0,
+                        data_type: DataType::String,
+                    }
+                    .into(),
+                )
+                .into()],
+        );
+        let table_scan_2 = query_graph.table_scan(2, 5);
+        let apply_1 = query_graph.add_node(QueryNode::Apply {
+            correlation: CorrelationContext {
+                parameters: vec![ScalarExpr::input_ref(1).into()],
+            },
+            left: table_scan_2,
+            right: filter_1,
+            apply_type: ApplyType::LeftOuter,
+        });
+        let project = query_graph.project(
+            apply_1,
+            vec![
+                ScalarExpr::input_ref(4).into(),
+                ScalarExpr::input_ref(6).into(),
+                ScalarExpr::input_ref(7).into(),
+            ],
+        );
+        query_graph.set_entry_node(project);
+
+        let replacements = ApplyPruningRule {}
+            .apply(&mut query_graph, apply_1)
+            .unwrap();
+        assert_eq!(replacements.len(), 1);
+        let new_apply = query_graph.node(replacements[0].1).get_input(0);
+        let QueryNode::Apply {
+            correlation, left, ..
+        } = query_graph.node(new_apply)
+        else {
+            panic!();
+        };
+        // ref_1 from the original LHS is the first column of the pruned one
+        let QueryNode::Project { outputs, .. } = query_graph.node(*left) else {
+            panic!();
+        };
+        let expected_outputs: Vec<ScalarExprRef> = vec![
+            ScalarExpr::input_ref(1).into(),
+            ScalarExpr::input_ref(4).into(),
+        ];
+        assert_eq!(*outputs, expected_outputs);
+        assert_eq!(
+            correlation.parameters,
+            vec![ScalarExprRef::from(ScalarExpr::input_ref(0))]
+        );
+    }
+}
@@ -0,0 +1,69 @@
+use crate::query_graph::{
+    optimizer::{utils::pull_up_correlated_expr, OptRuleType, SingleReplacementRule},
+    properties::{num_columns, subgraph_correlated_input_refs},
+    ApplyType, JoinType, NodeId, QueryGraph, QueryNode,
+};
+
+/// Rule that replaces an apply node whose right input is not correlated with a join.
+///
+/// If the right input is a filter whose input is not correlated, the conditions of the
+/// filter, correlated or not, become the conditions of the join, which is required for
+/// preserving the semantics of left outer applies:
+///
+/// ```txt
+/// Left Outer Apply parameters: [ref_1]
+///   A
+///   Filter [eq(ref_0, ctx_0.ref_0)]
+///     B
+/// ```
+///
+/// becomes:
+///
+/// ```txt
+/// Left Outer Join [eq(ref_n, ref_1)]
+///   A
+///   B
+/// ```
+pub struct ApplyToJoinRule {}
+
+impl SingleReplacementRule for ApplyToJoinRule {
+    fn rule_type(&self) -> OptRuleType {
+        OptRuleType::TopDown
+    }
+
+    fn apply(&self, query_graph: &mut QueryGraph, node_id: NodeId) -> Option<NodeId> {
+        if let QueryNode::Apply {
+            correlation,
+            left,
+            right,
+            apply_type,
+        } = query_graph.node(node_id)
+        {
+            let join_type = match apply_type {
+                ApplyType::Inner => JoinType::Inner,
+                ApplyType::LeftOuter => JoinType::LeftOuter,
+            };
+            let left = *left;
+            if let QueryNode::Filter { conditions, input } = query_graph.node(*right) {
+                if subgraph_correlated_input_refs(query_graph, *input).is_empty() {
+                    let left_num_columns = num_columns(query_graph, left);
+                    if let Some(conditions) = conditions
+                        .iter()
+                        .map(|c| {
+                            pull_up_correlated_expr(c, &correlation.parameters, left_num_columns)
+                        })
+                        .collect::<Option<Vec<_>>>()
+                    {
+                        let input = *input;
+                        return Some(query_graph.join(join_type, left, input, conditions));
+                    }
+                }
+            }
+            if subgraph_correlated_input_refs(query_graph, *right).is_empty() {
+                let right = *right;
+                return Some(query_graph.join(join_type, left, right, Vec::new()));
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,47 @@
+use crate::query_graph::{
+    optimizer::{OptRuleType, SingleReplacementRule},
+    properties::subgraph_correlated_input_refs,
+    ApplyType, NodeId, QueryGraph, QueryNode,
+};
+
+/// Rule that pushes an inner apply through the union at its right input, by applying
+/// each branch of the union separately.
+pub struct ApplyUnionTransposeRule {}
+
+impl SingleReplacementRule for ApplyUnionTransposeRule {
+    fn rule_type(&self) -> OptRuleType {
+        OptRuleType::TopDown
+    }
+
+    fn apply(&self, query_graph: &mut QueryGraph, node_id: NodeId) -> Option<NodeId> {
+        if let QueryNode::Apply {
+            correlation,
+            left,
+            right,
+            apply_type: ApplyType::Inner,
+        } = query_graph.node(node_id)
+        {
+            if let QueryNode::Union { inputs } = query_graph.node(*right) {
+                if subgraph_correlated_input_refs(query_graph, *right).is_empty() {
+                    return None;
+                }
+                let correlation = correlation.clone();
+                let left = *left;
+                let inputs = inputs.clone();
+                let new_inputs = inputs
+                    .into_iter()
+                    .map(|input| {
+                        query_graph.add_node(QueryNode::Apply {
+                            correlation: correlation.clone(),
+                            left,
+                            right: input,
+                            apply_type: ApplyType::Inner,
+                        })
+                    })
+                    .collect();
+                return Some(query_graph.add_node(QueryNode::Union { inputs: new_inputs }));
+            }
+        }
+        None
+    }
+}
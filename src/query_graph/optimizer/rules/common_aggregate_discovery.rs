@@ -5,6 +5,7 @@ use itertools::Itertools;
 use crate::{
     query_graph::{
         optimizer::{OptRuleType, Rule},
+        properties::node_correlated_input_refs,
         NodeId, QueryGraph, QueryNode,
     },
     scalar_expr::{
@@ -69,8 +70,14 @@ impl Rule for CommonAggregateDiscoveryRule {
                 // Aggregate key[ref_0], Aggregates[max(ref_1), max(ref_2]
                 //   Project ref_0, ref_1 + ref_2, ref_1 + ref_3
                 //     Shared node X
+                //
+                // Projections containing correlated references are not absorbed, since
+                // the aggregates may belong to different correlation scopes.
                 while let QueryNode::Project { outputs, input } = query_graph.node(normalized_input)
                 {
+                    if !node_correlated_input_refs(query_graph, normalized_input).is_empty() {
+                        break;
+                    }
                     let extended_outputs =
                         outputs.iter().map(|e| e.to_extended_expr()).collect_vec();
                     for key in group_key.iter_mut() {
@@ -169,3 +176,83 @@ fn append_to_vector_if_not_present<E: Eq>(vec: &mut Vec<E>, e: E) -> usize {
         vec.len() - 1
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::{
+        data_type::DataType,
+        query_graph::{
+            optimizer::Rule, validator::validate, ApplyType, CorrelationContext, NodeId,
+            QueryGraph, QueryNode,
+        },
+        scalar_expr::{AggregateExpr, AggregateOp, ScalarExpr},
+    };
+
+    use super::CommonAggregateDiscoveryRule;
+
+    /// Adds `Apply(left, Aggregate key: [ref_0], aggregates: [max(ref_1)])` over
+    /// `Project [ref_0, ctx_0.ref_<param>]` over the given right input.
+    fn correlated_aggregate(
+        query_graph: &mut QueryGraph,
+        left: NodeId,
+        right: NodeId,
+        num_parameters: usize,
+        param: usize,
+    ) -> NodeId {
+        let project = query_graph.project(
+            right,
+            vec![
+                ScalarExpr::input_ref(0).into(),
+                ScalarExpr::CorrelatedInputRef {
+                    context_offset: 0,
+                    index: param,
+                    data_type: DataType::String,
+                }
+                .into(),
+            ],
+        );
+        let aggregate = query_graph.add_node(QueryNode::Aggregate {
+            group_key: BTreeSet::from([0]),
+            aggregates: vec![AggregateExpr {
+                op: AggregateOp::Max,
+                operands: vec![1],
+            }
+            .into()],
+            input: project,
+        });
+        query_graph.add_node(QueryNode::Apply {
+            correlation: CorrelationContext {
+                parameters: (0..num_parameters)
+                    .map(|i| ScalarExpr::input_ref(i).into())
+                    .collect(),
+            },
+            left,
+            right: aggregate,
+            apply_type: ApplyType::Inner,
+        })
+    }
+
+    #[test]
+    fn test_correlated_projections_are_not_absorbed() {
+        let mut query_graph = QueryGraph::new();
+        let table_scan_1 = query_graph.table_scan(1, 2);
+        let table_scan_2 = query_graph.table_scan(2, 2);
+        let apply_1 = correlated_aggregate(&mut query_graph, table_scan_1, table_scan_2, 1, 0);
+        let apply_2 = correlated_aggregate(&mut query_graph, table_scan_1, table_scan_2, 2, 1);
+        let union = query_graph.add_node(QueryNode::Union {
+            inputs: vec![apply_1, apply_2],
+        });
+        query_graph.set_entry_node(union);
+        assert!(validate(&query_graph).is_ok());
+
+        // Folding both aggregates would make the first apply see a reference to
+        // the second parameter of the other one.
+        let rule = CommonAggregateDiscoveryRule {};
+        if let Some(replacements) = rule.apply(&mut query_graph, QueryGraph::ROOT_NODE_ID) {
+            query_graph.replace_nodes(&replacements);
+        }
+        assert!(validate(&query_graph).is_ok());
+    }
+}
@@ -0,0 +1,146 @@
+use itertools::Itertools;
+
+use crate::{
+    query_graph::{
+        optimizer::{OptRuleType, SingleReplacementRule},
+        properties::{is_empty_relation, num_columns, row_type},
+        JoinType, NodeId, QueryGraph, QueryNode,
+    },
+    scalar_expr::{ScalarExpr, ScalarExprRef},
+};
+
+/// Replaces any relation that is known to never produce any row with an empty
+/// `Values` node with the same row type.
+///
+/// Also simplifies the nodes with empty inputs:
+///
+/// - Inner and semi joins with an empty input are empty.
+/// - Anti joins with an empty right input return their left input.
+/// - Outer joins with an empty non-preserving side become a projection of the
+///   preserving side padded with NULLs. They are empty if all their preserving
+///   sides are empty.
+/// - Empty branches of a union are removed.
+/// - Grouping aggregates over an empty input are empty, while global aggregates
+///   return a single row with the result of each aggregate for an empty group.
+pub struct EmptyRelationPropagationRule {}
+
+impl SingleReplacementRule for EmptyRelationPropagationRule {
+    fn rule_type(&self) -> OptRuleType {
+        OptRuleType::Always
+    }
+
+    fn apply(&self, query_graph: &mut QueryGraph, node_id: NodeId) -> Option<NodeId> {
+        match query_graph.node(node_id) {
+            QueryNode::Values { .. } => return None,
+            // The top projection is added back by `TopProjectionRule`.
+            QueryNode::Project { .. }
+                if query_graph
+                    .get_parents(node_id)
+                    .is_some_and(|parents| parents.contains(&QueryGraph::ROOT_NODE_ID)) =>
+            {
+                return None
+            }
+            _ => {}
+        }
+        if is_empty_relation(query_graph, node_id) {
+            return Some(empty_relation(query_graph, node_id));
+        }
+        match query_graph.node(node_id) {
+            QueryNode::Join {
+                join_type,
+                left,
+                right,
+                ..
+            } => {
+                let (join_type, left, right) = (*join_type, *left, *right);
+                let left_is_empty = is_empty_relation(query_graph, left);
+                let right_is_empty = is_empty_relation(query_graph, right);
+                match join_type {
+                    JoinType::Inner | JoinType::Semi if left_is_empty || right_is_empty => {
+                        Some(empty_relation(query_graph, node_id))
+                    }
+                    JoinType::Anti if left_is_empty => Some(empty_relation(query_graph, node_id)),
+                    JoinType::Anti if right_is_empty => Some(left),
+                    JoinType::LeftOuter if left_is_empty => {
+                        Some(empty_relation(query_graph, node_id))
+                    }
+                    JoinType::RightOuter if right_is_empty => {
+                        Some(empty_relation(query_graph, node_id))
+                    }
+                    JoinType::FullOuter if left_is_empty && right_is_empty => {
+                        Some(empty_relation(query_graph, node_id))
+                    }
+                    JoinType::LeftOuter | JoinType::FullOuter if right_is_empty => {
+                        let outputs = input_refs(query_graph, left)
+                            .chain(null_literals(query_graph, right))
+                            .collect();
+                        Some(query_graph.project(left, outputs))
+                    }
+                    JoinType::RightOuter | JoinType::FullOuter if left_is_empty => {
+                        let outputs = null_literals(query_graph, left)
+                            .chain(input_refs(query_graph, right))
+                            .collect();
+                        Some(query_graph.project(right, outputs))
+                    }
+                    _ => None,
+                }
+            }
+            QueryNode::Union { inputs } => {
+                let inputs = inputs.clone();
+                let non_empty_inputs = inputs
+                    .iter()
+                    .filter(|input| !is_empty_relation(query_graph, **input))
+                    .cloned()
+                    .collect_vec();
+                if non_empty_inputs.len() == inputs.len() {
+                    None
+                } else if non_empty_inputs.is_empty() {
+                    Some(empty_relation(query_graph, node_id))
+                } else if non_empty_inputs.len() == 1 {
+                    Some(non_empty_inputs[0])
+                } else {
+                    Some(query_graph.add_node(QueryNode::Union {
+                        inputs: non_empty_inputs,
+                    }))
+                }
+            }
+            QueryNode::Aggregate {
+                group_key,
+                aggregates,
+                input,
+            } if is_empty_relation(query_graph, *input) => {
+                if !group_key.is_empty() {
+                    return Some(empty_relation(query_graph, node_id));
+                }
+                let input_row_type = row_type(query_graph, *input);
+                let row = aggregates
+                    .iter()
+                    .map(|aggregate| aggregate.on_empty_group(&input_row_type))
+                    .collect_vec();
+                let row_type = row_type(query_graph, node_id);
+                Some(query_graph.values(row_type, vec![row]))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Adds an empty relation with the row type of the given node.
+fn empty_relation(query_graph: &mut QueryGraph, node_id: NodeId) -> NodeId {
+    let row_type = row_type(query_graph, node_id);
+    query_graph.empty_relation(row_type)
+}
+
+/// Returns a reference to each column of the given node.
+fn input_refs(query_graph: &QueryGraph, node_id: NodeId) -> impl Iterator<Item = ScalarExprRef> {
+    (0..num_columns(query_graph, node_id)).map(|i| ScalarExpr::input_ref(i).into())
+}
+
+/// Returns a NULL literal for each column of the given node.
+fn null_literals(query_graph: &QueryGraph, node_id: NodeId) -> impl Iterator<Item = ScalarExprRef> {
+    row_type(query_graph, node_id)
+        .iter()
+        .map(|data_type| ScalarExpr::null_literal(data_type.clone()).into())
+        .collect_vec()
+        .into_iter()
+}
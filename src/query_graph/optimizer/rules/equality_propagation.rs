@@ -10,13 +10,14 @@ use crate::{
         JoinType, NodeId, QueryGraph, QueryNode,
     },
     scalar_expr::{
-        equivalence_class::extract_equivalence_classes,
+        equivalence_class::{extract_equivalence_classes, to_replacement_map},
         reduction::reduce_expr_recursively,
         rewrite::{
-            rewrite_expr_pre, rewrite_expr_vec, shift_left_input_refs, shift_right_input_refs,
+            replace_sub_expressions_pre, rewrite_expr_pre, rewrite_expr_vec, shift_left_input_refs,
+            shift_right_input_refs,
         },
         visitor::collect_input_dependencies,
-        ScalarExprRef,
+        ScalarExpr, ScalarExprRef,
     },
 };
 
@@ -177,6 +178,16 @@ impl EqualityPropagationRule {
     where
         F: Fn(usize) -> bool,
     {
+        // Predicates implied by the ones known on the other side, for example `ref_1` when
+        // `raw_eq(ref_1, TRUE)` is known, don't need to be propagated either.
+        let true_literal: ScalarExprRef = ScalarExpr::true_literal().into();
+        let mut known_predicates =
+            to_replacement_map(&extract_equivalence_classes(other_side_predicates));
+        known_predicates.extend(
+            other_side_predicates
+                .iter()
+                .map(|predicate| (predicate.clone(), true_literal.clone())),
+        );
         let mut propagated_predicates = Vec::new();
         for predicate in predicates.iter() {
             let rewritten_predicate = rewrite_expr_pre(
@@ -193,7 +204,12 @@ impl EqualityPropagationRule {
             let rewritten_predicate =
                 reduce_expr_recursively(&rewritten_predicate, query_graph, cross_product_row_type);
 
-            if !other_side_predicates.contains(&rewritten_predicate)
+            let normalized_predicate = reduce_expr_recursively(
+                &replace_sub_expressions_pre(&rewritten_predicate, &known_predicates),
+                query_graph,
+                cross_product_row_type,
+            );
+            if normalized_predicate != true_literal
                 && collect_input_dependencies(&rewritten_predicate)
                     .iter()
                     .all(|col| validate_input_ref(*col))
@@ -6,7 +6,10 @@ use crate::{
         properties::pulled_up_predicates,
         NodeId, QueryGraph, QueryNode,
     },
-    scalar_expr::rewrite::{apply_column_map, to_column_map_for_expr_push_down},
+    scalar_expr::{
+        rewrite::{apply_column_map, to_column_map_for_expr_push_down},
+        visitor::collect_input_dependencies,
+    },
 };
 
 pub struct FilterAggregateTransposeRule {}
@@ -31,7 +34,15 @@ impl SingleReplacementRule for FilterAggregateTransposeRule {
                     .enumerate()
                     .filter_map(|(i, expr)| {
                         if let Some(condition) = apply_column_map(expr, &column_map) {
-                            if !known_predicates.contains(&condition) {
+                            // Literal conditions are not included among the pulled up
+                            // predicates, so the parent filter would never be removed
+                            // and they would be pushed down over and over again. The
+                            // same happens with conditions not referencing any column,
+                            // since they may be rewritten in terms of some equivalent
+                            // column once pushed down.
+                            if !collect_input_dependencies(&condition).is_empty()
+                                && !known_predicates.contains(&condition)
+                            {
                                 return Some((i, condition));
                             }
                         }
@@ -62,3 +73,36 @@ impl SingleReplacementRule for FilterAggregateTransposeRule {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::{
+        query_graph::{optimizer::SingleReplacementRule, QueryGraph, QueryNode},
+        scalar_expr::{AggregateExpr, AggregateOp, ScalarExpr},
+    };
+
+    use super::FilterAggregateTransposeRule;
+
+    #[test]
+    fn test_literal_conditions_are_not_pushed_down() {
+        let mut query_graph = QueryGraph::new();
+        let table_scan = query_graph.table_scan(0, 2);
+        let aggregate = query_graph.add_node(QueryNode::Aggregate {
+            group_key: BTreeSet::from([0]),
+            aggregates: vec![AggregateExpr {
+                op: AggregateOp::Max,
+                operands: vec![1],
+            }
+            .into()],
+            input: table_scan,
+        });
+        let filter = query_graph.filter(aggregate, vec![ScalarExpr::false_literal().into()]);
+        query_graph.set_entry_node(filter);
+
+        // Literal conditions are never pushed down below the aggregate
+        let rule = FilterAggregateTransposeRule {};
+        assert!(rule.apply(&mut query_graph, aggregate).is_none());
+    }
+}
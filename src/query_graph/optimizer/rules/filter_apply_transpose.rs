@@ -1,7 +1,7 @@
 use crate::{
     query_graph::{
         optimizer::{utils::common_parent_filters, OptRuleType, SingleReplacementRule},
-        properties::{num_columns, pulled_up_predicates},
+        properties::{expr_correlated_input_refs, num_columns, pulled_up_predicates},
         ApplyType, NodeId, QueryGraph, QueryNode,
     },
     scalar_expr::{rewrite::shift_left_input_refs, visitor::collect_input_dependencies},
@@ -49,6 +49,10 @@ impl SingleReplacementRule for FilterApplyTransposeRule {
                             left_predicates.push(condition.clone());
                         } else if allowed_right_pushdown
                             && dependencies.iter().all(|x| *x >= left_num_columns)
+                            // The right input is evaluated within the correlation
+                            // context of the apply, so correlated references
+                            // would point to a different context
+                            && expr_correlated_input_refs(query_graph, condition).is_empty()
                         {
                             right_predicates
                                 .push(shift_left_input_refs(condition, left_num_columns));
@@ -76,3 +80,52 @@ impl SingleReplacementRule for FilterApplyTransposeRule {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        data_type::DataType,
+        query_graph::{
+            optimizer::SingleReplacementRule, ApplyType, CorrelationContext, QueryGraph, QueryNode,
+        },
+        scalar_expr::{BinaryOp, ScalarExpr},
+    };
+
+    use super::FilterApplyTransposeRule;
+
+    #[test]
+    fn test_correlated_conditions_are_not_pushed_to_the_right() {
+        let mut query_graph = QueryGraph::new();
+        let table_scan_1 = query_graph.table_scan(1, 2);
+        let table_scan_2 = query_graph.table_scan(2, 1);
+        let apply = query_graph.add_node(QueryNode::Apply {
+            correlation: CorrelationContext {
+                parameters: vec![ScalarExpr::input_ref(0).into()],
+            },
+            left: table_scan_1,
+            right: table_scan_2,
+            apply_type: ApplyType::Inner,
+        });
+        // ctx_0 refers to an outer context. Below the apply it would refer to the
+        // parameters of the apply instead.
+        let filter = query_graph.filter(
+            apply,
+            vec![ScalarExpr::input_ref(2)
+                .binary(
+                    BinaryOp::Eq,
+                    ScalarExpr::CorrelatedInputRef {
+                        context_offset: 0,
+                        index: 0,
+                        data_type: DataType::String,
+                    }
+                    .into(),
+                )
+                .into()],
+        );
+        query_graph.set_entry_node(filter);
+
+        assert!(FilterApplyTransposeRule {}
+            .apply(&mut query_graph, apply)
+            .is_none());
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{
     query_graph::{
         optimizer::{utils::common_parent_filters, OptRuleType, SingleReplacementRule},
-        properties::{num_columns, pulled_up_predicates},
+        properties::{is_empty_relation, num_columns, pulled_up_predicates},
         JoinType, NodeId, QueryGraph, QueryNode,
     },
     scalar_expr::{rewrite::shift_left_input_refs, visitor::collect_input_dependencies},
@@ -33,16 +33,17 @@ impl SingleReplacementRule for FilterJoinTransposeRule {
 
                 let known_predicates = pulled_up_predicates(query_graph, node_id);
 
+                // There is no point in filtering an empty input.
                 let allowed_left_pushdown = match join_type {
                     JoinType::Semi | JoinType::Anti | JoinType::Inner | JoinType::LeftOuter => true,
                     JoinType::RightOuter | JoinType::FullOuter => false,
-                };
+                } && !is_empty_relation(query_graph, *left);
                 let allowed_right_pushdown = match join_type {
                     JoinType::Inner | JoinType::RightOuter => true,
                     JoinType::Semi | JoinType::Anti | JoinType::LeftOuter | JoinType::FullOuter => {
                         false
                     }
-                };
+                } && !is_empty_relation(query_graph, *right);
 
                 for condition in common_conditions.iter() {
                     if known_predicates.contains(condition) {
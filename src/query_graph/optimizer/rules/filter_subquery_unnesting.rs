@@ -0,0 +1,173 @@
+use crate::{
+    query_graph::{
+        optimizer::{utils::unnest_subquery, OptRuleType, SingleReplacementRule},
+        properties::row_type,
+        JoinType, NodeId, QueryGraph, QueryNode,
+    },
+    scalar_expr::{
+        BinaryOp, NaryOp, ScalarExpr, ScalarExprRef, ScalarSubqueryCmpOp, Subquery, UnaryOp,
+    },
+};
+
+/// Rule that unnests the subqueries in the conditions of a filter node that can be
+/// evaluated as semi or anti joins:
+///
+/// * `exists(S)` becomes a semi join with `S`.
+/// * `not(exists(S))` or `raw_eq(exists(S), FALSE)`, ie. `NOT EXISTS`, becomes an anti
+///   join with `S`.
+/// * `x op ANY(S)` becomes a semi join with `S` with `x op s` as join condition, since
+///   a filter discards the rows where the comparison evaluates to NULL as well.
+/// * `x op ALL(S)` becomes an anti join with `S` discarding the rows for which the
+///   comparison doesn't evaluate to TRUE for some row of `S`, either because it
+///   evaluates to FALSE or because `x` or `s` are NULL.
+pub struct FilterSubqueryUnnestingRule {}
+
+/// The comparison operation of a subquery comparison and its scalar operand.
+type SubqueryComparison = (ScalarSubqueryCmpOp, ScalarExprRef);
+
+impl SingleReplacementRule for FilterSubqueryUnnestingRule {
+    fn rule_type(&self) -> OptRuleType {
+        OptRuleType::TopDown
+    }
+
+    fn apply(&self, query_graph: &mut QueryGraph, node_id: NodeId) -> Option<NodeId> {
+        if let QueryNode::Filter { conditions, input } = query_graph.node(node_id) {
+            let (position, join_type, subquery, comparison) =
+                conditions.iter().enumerate().find_map(|(i, condition)| {
+                    Self::match_condition(condition).map(|(join_type, subquery, comparison)| {
+                        (i, join_type, subquery, comparison)
+                    })
+                })?;
+            let input = *input;
+            let remaining_conditions = conditions
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i != position)
+                .map(|(_, c)| c.clone())
+                .collect();
+            let (right, mut join_conditions, subquery_columns) =
+                unnest_subquery(query_graph, input, &subquery);
+            if let Some((op, scalar_operand)) = comparison {
+                join_conditions.push(Self::comparison(
+                    query_graph,
+                    input,
+                    right,
+                    &op,
+                    scalar_operand,
+                    subquery_columns[0].clone(),
+                ));
+            }
+            let join = query_graph.join(join_type, input, right, join_conditions);
+            return Some(query_graph.filter(join, remaining_conditions));
+        }
+        None
+    }
+}
+
+impl FilterSubqueryUnnestingRule {
+    /// Returns the type of join the given condition can be unnested into, the subquery and,
+    /// for comparisons with subqueries, the comparison operation and its scalar operand.
+    fn match_condition(
+        condition: &ScalarExprRef,
+    ) -> Option<(JoinType, Subquery<ScalarExpr>, Option<SubqueryComparison>)> {
+        match condition.as_ref() {
+            ScalarExpr::ExistsSubquery { subquery } => {
+                Some((JoinType::Semi, subquery.clone(), None))
+            }
+            ScalarExpr::UnaryOp {
+                op: UnaryOp::Not,
+                operand,
+            } => {
+                if let ScalarExpr::ExistsSubquery { subquery } = operand.as_ref() {
+                    Some((JoinType::Anti, subquery.clone(), None))
+                } else {
+                    None
+                }
+            }
+            ScalarExpr::BinaryOp {
+                op: BinaryOp::Eq | BinaryOp::RawEq,
+                left,
+                right,
+            } => {
+                let false_literal = ScalarExpr::false_literal();
+                let exists = if **right == false_literal {
+                    left
+                } else if **left == false_literal {
+                    right
+                } else {
+                    return None;
+                };
+                if let ScalarExpr::ExistsSubquery { subquery } = exists.as_ref() {
+                    Some((JoinType::Anti, subquery.clone(), None))
+                } else {
+                    None
+                }
+            }
+            ScalarExpr::ScalarSubqueryCmp {
+                op,
+                scalar_operand,
+                subquery,
+            } => {
+                let join_type = if op.is_all() {
+                    JoinType::Anti
+                } else {
+                    JoinType::Semi
+                };
+                Some((
+                    join_type,
+                    subquery.clone(),
+                    Some((op.clone(), scalar_operand.clone())),
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Builds the join condition for the given comparison. For `ALL` comparisons, the
+    /// condition matches the rows of the subquery for which the comparison is not TRUE.
+    fn comparison(
+        query_graph: &QueryGraph,
+        left: NodeId,
+        right: NodeId,
+        op: &ScalarSubqueryCmpOp,
+        scalar_operand: ScalarExprRef,
+        subquery_column: ScalarExprRef,
+    ) -> ScalarExprRef {
+        let binary = |op: BinaryOp| -> ScalarExprRef {
+            ScalarExpr::BinaryOp {
+                op,
+                left: scalar_operand.clone(),
+                right: subquery_column.clone(),
+            }
+            .into()
+        };
+        let mut disjuncts = match op {
+            ScalarSubqueryCmpOp::EqAny => return binary(BinaryOp::Eq),
+            ScalarSubqueryCmpOp::LtAny => return binary(BinaryOp::Lt),
+            ScalarSubqueryCmpOp::LteAny => return binary(BinaryOp::Le),
+            ScalarSubqueryCmpOp::GtAny => return binary(BinaryOp::Gt),
+            ScalarSubqueryCmpOp::GteAny => return binary(BinaryOp::Ge),
+            ScalarSubqueryCmpOp::EqAll => vec![binary(BinaryOp::Lt), binary(BinaryOp::Gt)],
+            ScalarSubqueryCmpOp::LtAll => vec![binary(BinaryOp::Ge)],
+            ScalarSubqueryCmpOp::LteAll => vec![binary(BinaryOp::Gt)],
+            ScalarSubqueryCmpOp::GtAll => vec![binary(BinaryOp::Le)],
+            ScalarSubqueryCmpOp::GteAll => vec![binary(BinaryOp::Lt)],
+        };
+        let row_type = row_type(query_graph, left)
+            .iter()
+            .chain(row_type(query_graph, right).iter())
+            .cloned()
+            .collect::<Vec<_>>();
+        let is_null = |operand: &ScalarExprRef| -> ScalarExprRef {
+            ScalarExpr::BinaryOp {
+                op: BinaryOp::RawEq,
+                left: operand.clone(),
+                right: ScalarExpr::null_literal(operand.data_type(query_graph, &row_type)).into(),
+            }
+            .into()
+        };
+        disjuncts.push(is_null(&scalar_operand));
+        disjuncts.push(is_null(&subquery_column));
+        ScalarExpr::nary(NaryOp::Or, disjuncts).into()
+    }
+}
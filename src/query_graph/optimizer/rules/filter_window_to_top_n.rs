@@ -0,0 +1,110 @@
+use crate::{
+    query_graph::{
+        optimizer::{OptRuleType, SingleReplacementRule},
+        properties::num_columns,
+        NodeId, QueryGraph, QueryNode,
+    },
+    scalar_expr::{BinaryOp, ScalarExpr, WindowFunction},
+    value::Value,
+};
+
+/// Given a Filter node restricting the row number computed by the Window node
+/// beneath it to be at most `k`, ie. `row_number() <= k`, it removes that condition
+/// and inserts a TopN node under the window that only keeps the first `k` rows of
+/// each partition.
+///
+/// This is only valid if the values of the window functions for the remaining rows
+/// only depend on the rows preceding them within their partition.
+pub struct FilterWindowToTopNRule {}
+
+impl SingleReplacementRule for FilterWindowToTopNRule {
+    fn rule_type(&self) -> OptRuleType {
+        OptRuleType::TopDown
+    }
+
+    fn apply(&self, query_graph: &mut QueryGraph, node_id: NodeId) -> Option<NodeId> {
+        if let QueryNode::Filter { conditions, input } = query_graph.node(node_id) {
+            if let QueryNode::Window {
+                partition_key,
+                order_key,
+                frame,
+                functions,
+                input: window_input,
+            } = query_graph.node(*input)
+            {
+                if !functions
+                    .iter()
+                    .all(|function| function.depends_only_on_preceding_rows(frame))
+                {
+                    return None;
+                }
+                let input_num_columns = num_columns(query_graph, *window_input);
+                let is_row_number = |column: usize| {
+                    column >= input_num_columns
+                        && functions[column - input_num_columns].function
+                            == WindowFunction::RowNumber
+                };
+                // All the row number functions of a window node produce the same
+                // values, so the tightest limit makes the rest of bounds redundant.
+                let mut limit: Option<usize> = None;
+                let mut remaining_conditions = Vec::new();
+                for condition in conditions.iter() {
+                    match row_number_upper_bound(condition) {
+                        Some((column, bound)) if is_row_number(column) => {
+                            limit = Some(limit.map_or(bound, |limit| limit.min(bound)));
+                        }
+                        _ => remaining_conditions.push(condition.clone()),
+                    }
+                }
+                let limit = limit?;
+                let new_partition_key = partition_key.clone();
+                let new_order_key = order_key.clone();
+                let new_frame = *frame;
+                let new_functions = functions.clone();
+                let top_n = query_graph.add_node(QueryNode::TopN {
+                    partition_key: new_partition_key.clone(),
+                    keys: new_order_key.clone(),
+                    offset: 0,
+                    limit,
+                    input: *window_input,
+                });
+                let new_window = query_graph.window(
+                    top_n,
+                    new_partition_key,
+                    new_order_key,
+                    new_frame,
+                    new_functions,
+                );
+                return Some(query_graph.filter(new_window, remaining_conditions));
+            }
+        }
+        None
+    }
+}
+
+/// If the given condition is a comparison of the form `ref_n <= k` or `ref_n < k`,
+/// or their symmetric forms, with `k` an integer literal, returns the column and the
+/// maximum value it may take, ie. the number of rows to keep.
+fn row_number_upper_bound(condition: &ScalarExpr) -> Option<(usize, usize)> {
+    if let ScalarExpr::BinaryOp { op, left, right } = condition {
+        let (column, literal, inclusive) = match (op, left.as_ref(), right.as_ref()) {
+            (BinaryOp::Le, ScalarExpr::InputRef { index }, ScalarExpr::Literal(literal))
+            | (BinaryOp::Ge, ScalarExpr::Literal(literal), ScalarExpr::InputRef { index }) => {
+                (*index, literal, true)
+            }
+            (BinaryOp::Lt, ScalarExpr::InputRef { index }, ScalarExpr::Literal(literal))
+            | (BinaryOp::Gt, ScalarExpr::Literal(literal), ScalarExpr::InputRef { index }) => {
+                (*index, literal, false)
+            }
+            _ => return None,
+        };
+        let value = match literal.value {
+            Value::Int(value) => i64::from(value),
+            Value::BigInt(value) => value,
+            _ => return None,
+        };
+        let bound = if inclusive { value } else { value - 1 };
+        return Some((column, bound.max(0) as usize));
+    }
+    None
+}
@@ -0,0 +1,67 @@
+use crate::{
+    query_graph::{
+        optimizer::{utils::common_parent_filters, OptRuleType, SingleReplacementRule},
+        properties::pulled_up_predicates,
+        NodeId, QueryGraph, QueryNode,
+    },
+    scalar_expr::visitor::collect_input_dependencies,
+};
+
+/// Pushes the conditions of the filters on top of a Window node that only reference
+/// columns of its partition key down through it. Since these conditions either keep
+/// or discard entire partitions, the window functions evaluated for the remaining
+/// rows are not affected.
+///
+/// As it happens with the rest of the filter push down rules, the parent filters
+/// are left untouched, as the pushed conditions will be removed from them later
+/// since they become known predicates of the window.
+pub struct FilterWindowTransposeRule {}
+
+impl SingleReplacementRule for FilterWindowTransposeRule {
+    fn rule_type(&self) -> OptRuleType {
+        OptRuleType::TopDown
+    }
+
+    fn apply(&self, query_graph: &mut QueryGraph, node_id: NodeId) -> Option<NodeId> {
+        if let QueryNode::Window {
+            partition_key,
+            order_key,
+            frame,
+            functions,
+            input: window_input,
+        } = query_graph.node(node_id)
+        {
+            if let Some(conditions) = common_parent_filters(query_graph, node_id) {
+                let known_predicates = pulled_up_predicates(query_graph, *window_input);
+                // The partition key columns are passed through, so the conditions
+                // don't need to be rewritten.
+                let pushable_conditions = conditions
+                    .into_iter()
+                    .filter(|condition| {
+                        let dependencies = collect_input_dependencies(condition);
+                        !dependencies.is_empty()
+                            && dependencies.iter().all(|col| partition_key.contains(col))
+                            && !known_predicates.contains(condition)
+                    })
+                    .collect::<Vec<_>>();
+
+                if !pushable_conditions.is_empty() {
+                    let new_partition_key = partition_key.clone();
+                    let new_order_key = order_key.clone();
+                    let new_frame = *frame;
+                    let new_functions = functions.clone();
+                    let new_filter = query_graph.filter(*window_input, pushable_conditions);
+                    let new_window = query_graph.window(
+                        new_filter,
+                        new_partition_key,
+                        new_order_key,
+                        new_frame,
+                        new_functions,
+                    );
+                    return Some(new_window);
+                }
+            }
+        }
+        None
+    }
+}
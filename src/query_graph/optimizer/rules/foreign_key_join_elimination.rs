@@ -0,0 +1,230 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    query_graph::{
+        optimizer::{
+            utils::{apply_map_to_parents_and_replace_input, required_columns_from_parents},
+            OptRuleType, Rule,
+        },
+        properties::{keys, num_columns},
+        JoinType, NodeId, QueryGraph, QueryNode,
+    },
+    scalar_expr::{BinaryOp, ScalarExpr, ScalarExprRef},
+};
+
+/// Rule that removes joins whose parents only use the columns from one of its inputs,
+/// when the join is known to produce exactly one row for each row of that input.
+///
+/// That is the case of:
+///
+/// * inner joins where the preserved input references the other one through a foreign
+///   key with non-nullable columns, and the referenced input is a scan of the referenced
+///   table, possibly under projections, ie. every referenced row is present, exactly
+///   once,
+/// * left outer joins where the right input has a unique key equated to columns from
+///   the left input.
+///
+/// Like `JoinPruningRule`, all the parents of the join must be pruning projections, or
+/// chains of filters leading to them, which are rewritten on top of the preserved input.
+pub struct ForeignKeyJoinEliminationRule {}
+
+impl Rule for ForeignKeyJoinEliminationRule {
+    fn rule_type(&self) -> OptRuleType {
+        OptRuleType::TopDown
+    }
+
+    fn apply(
+        &self,
+        query_graph: &mut QueryGraph,
+        node_id: NodeId,
+    ) -> Option<Vec<(NodeId, NodeId)>> {
+        if let QueryNode::Join {
+            join_type,
+            conditions,
+            left,
+            right,
+        } = query_graph.node(node_id)
+        {
+            let required_columns = required_columns_from_parents(query_graph, node_id)?;
+            let left_num_columns = num_columns(query_graph, *left);
+            let (equalities, only_equalities) =
+                extract_equi_join_columns(conditions, left_num_columns);
+            let only_left_columns = required_columns.iter().all(|col| *col < left_num_columns);
+            let only_right_columns = required_columns.iter().all(|col| *col >= left_num_columns);
+            let (preserved_input, column_offset) = match join_type {
+                JoinType::Inner if only_equalities && !equalities.is_empty() => {
+                    if only_left_columns
+                        && is_foreign_key_join(query_graph, *left, *right, &equalities)
+                    {
+                        (*left, 0)
+                    } else if only_right_columns
+                        && is_foreign_key_join(
+                            query_graph,
+                            *right,
+                            *left,
+                            &equalities.iter().map(|(l, r)| (*r, *l)).collect(),
+                        )
+                    {
+                        (*right, left_num_columns)
+                    } else {
+                        return None;
+                    }
+                }
+                JoinType::LeftOuter
+                    if only_left_columns
+                        && has_unique_key_within(
+                            query_graph,
+                            *right,
+                            &equalities.iter().map(|(_, r)| *r).collect(),
+                        ) =>
+                {
+                    (*left, 0)
+                }
+                _ => return None,
+            };
+            let column_map = required_columns
+                .iter()
+                .map(|col| (*col, *col - column_offset))
+                .collect::<HashMap<_, _>>();
+            return Some(apply_map_to_parents_and_replace_input(
+                query_graph,
+                node_id,
+                &column_map,
+                preserved_input,
+            ));
+        }
+        None
+    }
+}
+
+/// Returns the pairs of columns, from the left and the right input respectively, equated
+/// by the given join conditions, and whether all the conditions are such equalities.
+fn extract_equi_join_columns(
+    conditions: &[ScalarExprRef],
+    left_num_columns: usize,
+) -> (HashSet<(usize, usize)>, bool) {
+    let mut equalities = HashSet::new();
+    let mut only_equalities = true;
+    for condition in conditions.iter() {
+        if let ScalarExpr::BinaryOp {
+            op: BinaryOp::Eq | BinaryOp::RawEq,
+            left,
+            right,
+        } = condition.as_ref()
+        {
+            if let (ScalarExpr::InputRef { index: i }, ScalarExpr::InputRef { index: j }) =
+                (left.as_ref(), right.as_ref())
+            {
+                if *i < left_num_columns && *j >= left_num_columns {
+                    equalities.insert((*i, *j - left_num_columns));
+                    continue;
+                }
+                if *j < left_num_columns && *i >= left_num_columns {
+                    equalities.insert((*j, *i - left_num_columns));
+                    continue;
+                }
+            }
+        }
+        only_equalities = false;
+    }
+    (equalities, only_equalities)
+}
+
+/// Whether the given equalities between the columns of the referencing input and the
+/// columns of the referenced one match a foreign key between the underlying tables, so
+/// that every row from the referencing input joins exactly one row from the referenced
+/// one.
+fn is_foreign_key_join(
+    query_graph: &QueryGraph,
+    referencing: NodeId,
+    referenced: NodeId,
+    equalities: &HashSet<(usize, usize)>,
+) -> bool {
+    // The referencing input may be filtered, but all the rows of the referenced table
+    // must be present.
+    let (
+        Some((referencing_table_id, referencing_columns)),
+        Some((referenced_table_id, referenced_columns)),
+    ) = (
+        table_columns(query_graph, referencing, true),
+        table_columns(query_graph, referenced, false),
+    )
+    else {
+        return false;
+    };
+    let Some(referencing_table) = query_graph.table_metadata(referencing_table_id) else {
+        return false;
+    };
+    let Some(table_equalities) = equalities
+        .iter()
+        .map(|(l, r)| Some((referencing_columns[*l]?, referenced_columns[*r]?)))
+        .collect::<Option<HashSet<_>>>()
+    else {
+        return false;
+    };
+    referencing_table.foreign_keys.iter().any(|foreign_key| {
+        foreign_key.referenced_table_id == referenced_table_id
+            && foreign_key
+                .columns
+                .iter()
+                .all(|col| !referencing_table.columns[*col].nullable)
+            && foreign_key
+                .columns
+                .iter()
+                .cloned()
+                .zip(foreign_key.referenced_columns.iter().cloned())
+                .collect::<HashSet<_>>()
+                == table_equalities
+    }) && has_unique_key_within(
+        query_graph,
+        referenced,
+        &equalities.iter().map(|(_, r)| *r).collect(),
+    )
+}
+
+/// Whether the given relation has a unique key only containing the given columns.
+fn has_unique_key_within(
+    query_graph: &QueryGraph,
+    node_id: NodeId,
+    columns: &HashSet<usize>,
+) -> bool {
+    keys(query_graph, node_id).iter().any(|key| {
+        key.upper_bound == Some(1)
+            && key.key.iter().all(|e| match e.as_ref() {
+                ScalarExpr::InputRef { index } => columns.contains(index),
+                _ => false,
+            })
+    })
+}
+
+/// If the given node is a scan of a table, optionally under projections, and filters if
+/// `allow_filters` is set, returns the ID of the table and, for each column of the given
+/// node, the column of the table it projects, if any.
+fn table_columns(
+    query_graph: &QueryGraph,
+    node_id: NodeId,
+    allow_filters: bool,
+) -> Option<(usize, Vec<Option<usize>>)> {
+    match query_graph.node(node_id) {
+        QueryNode::TableScan { table_id, row_type } => {
+            Some((*table_id, (0..row_type.len()).map(Some).collect()))
+        }
+        QueryNode::Project { outputs, input } => {
+            let (table_id, input_columns) = table_columns(query_graph, *input, allow_filters)?;
+            Some((
+                table_id,
+                outputs
+                    .iter()
+                    .map(|e| match e.as_ref() {
+                        ScalarExpr::InputRef { index } => input_columns[*index],
+                        _ => None,
+                    })
+                    .collect(),
+            ))
+        }
+        QueryNode::Filter { input, .. } if allow_filters => {
+            table_columns(query_graph, *input, allow_filters)
+        }
+        _ => None,
+    }
+}
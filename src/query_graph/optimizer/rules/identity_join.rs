@@ -1,11 +1,17 @@
-use crate::query_graph::{
-    optimizer::{OptRuleType, SingleReplacementRule},
-    properties::{empty_key, num_columns},
-    JoinType, NodeId, QueryGraph, QueryNode,
+use crate::{
+    query_graph::{
+        optimizer::{OptRuleType, SingleReplacementRule},
+        properties::{empty_key, num_columns},
+        JoinType, NodeId, QueryGraph, QueryNode,
+    },
+    scalar_expr::{ScalarExpr, ScalarExprRef},
 };
 
 /// Removes joins where one of the inputs is a relation always projecting a single
 /// row and has no columns.
+///
+/// Inner joins with a `Values` node containing a single row are also removed,
+/// replacing the columns of the `Values` node with its literals.
 pub struct IdentityJoinRule;
 
 impl SingleReplacementRule for IdentityJoinRule {
@@ -39,7 +45,45 @@ impl SingleReplacementRule for IdentityJoinRule {
             if let Some(non_identity_relation) = non_identity_relation {
                 return Some(query_graph.filter(non_identity_relation, conditions.clone()));
             }
+
+            let (left, right, conditions) = (*left, *right, conditions.clone());
+            let (input, outputs): (NodeId, Vec<ScalarExprRef>) =
+                if let Some(literals) = single_row_values(query_graph, left) {
+                    let outputs = literals
+                        .chain((0..right_num_columns).map(|i| ScalarExpr::input_ref(i).into()))
+                        .collect();
+                    (right, outputs)
+                } else if let Some(literals) = single_row_values(query_graph, right) {
+                    let outputs = (0..left_num_columns)
+                        .map(|i| ScalarExpr::input_ref(i).into())
+                        .chain(literals)
+                        .collect();
+                    (left, outputs)
+                } else {
+                    return None;
+                };
+            let project = query_graph.project(input, outputs);
+            return Some(query_graph.filter(project, conditions));
         }
         None
     }
 }
+
+/// If the given node is a `Values` node with a single row, returns its literals
+/// as scalar expressions.
+fn single_row_values(
+    query_graph: &QueryGraph,
+    node_id: NodeId,
+) -> Option<impl Iterator<Item = ScalarExprRef>> {
+    if let QueryNode::Values { rows, .. } = query_graph.node(node_id) {
+        if rows.len() == 1 {
+            return Some(
+                rows[0]
+                    .clone()
+                    .into_iter()
+                    .map(|literal| ScalarExpr::Literal(literal).into()),
+            );
+        }
+    }
+    None
+}
@@ -32,6 +32,7 @@ impl SingleReplacementRule for JoinProjectTransposeRule {
             conditions,
         } = query_graph.node(node_id)
         {
+            let join_num_columns = num_columns(query_graph, node_id);
             // Lifting projection from the LHS
             if let QueryNode::Project {
                 outputs,
@@ -55,10 +56,13 @@ impl SingleReplacementRule for JoinProjectTransposeRule {
                         .map(|c| apply_column_map(c, &column_map).unwrap())
                         .collect_vec();
 
+                    // Each column of the original join is taken from its position in
+                    // the new one
                     let final_project = column_map
                         .iter()
-                        .sorted_by_key(|(_, j)| *j)
-                        .map(|(i, _)| ScalarExpr::input_ref(*i).into())
+                        .sorted_by_key(|(i, _)| *i)
+                        .take(join_num_columns)
+                        .map(|(_, j)| ScalarExpr::input_ref(*j).into())
                         .collect_vec();
 
                     let join_type = *join_type;
@@ -93,10 +97,13 @@ impl SingleReplacementRule for JoinProjectTransposeRule {
                         .map(|c| apply_column_map(c, &column_map).unwrap())
                         .collect_vec();
 
+                    // Each column of the original join is taken from its position in
+                    // the new one
                     let final_project = column_map
                         .iter()
-                        .sorted_by_key(|(_, j)| *j)
-                        .map(|(i, _)| ScalarExpr::input_ref(*i).into())
+                        .sorted_by_key(|(i, _)| *i)
+                        .take(join_num_columns)
+                        .map(|(_, j)| ScalarExpr::input_ref(*j).into())
                         .collect_vec();
 
                     let join_type = *join_type;
@@ -116,3 +123,97 @@ impl SingleReplacementRule for JoinProjectTransposeRule {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        query_graph::{optimizer::SingleReplacementRule, JoinType, QueryGraph, QueryNode},
+        scalar_expr::{BinaryOp, ScalarExpr, ScalarExprRef},
+    };
+
+    use super::JoinProjectTransposeRule;
+
+    fn input_refs(indexes: &[usize]) -> Vec<ScalarExprRef> {
+        indexes
+            .iter()
+            .map(|i| ScalarExpr::input_ref(*i).into())
+            .collect()
+    }
+
+    fn final_project_outputs(query_graph: &QueryGraph, node_id: usize) -> Vec<ScalarExprRef> {
+        if let QueryNode::Project { outputs, .. } = query_graph.node(node_id) {
+            outputs.clone()
+        } else {
+            panic!();
+        }
+    }
+
+    #[test]
+    fn test_lifted_projection_preserves_column_order() {
+        let condition: ScalarExprRef = ScalarExpr::input_ref(0)
+            .binary(BinaryOp::Eq, ScalarExpr::input_ref(3).into())
+            .into();
+
+        // Projection at the LHS
+        let mut query_graph = QueryGraph::new();
+        let table_scan_1 = query_graph.table_scan(1, 3);
+        let project = query_graph.project(table_scan_1, input_refs(&[1, 2, 0]));
+        let table_scan_2 = query_graph.table_scan(2, 1);
+        let join = query_graph.join(
+            JoinType::Inner,
+            project,
+            table_scan_2,
+            vec![condition.clone()],
+        );
+        query_graph.set_entry_node(join);
+
+        let new_project = JoinProjectTransposeRule {}
+            .apply(&mut query_graph, join)
+            .unwrap();
+        assert_eq!(
+            final_project_outputs(&query_graph, new_project),
+            input_refs(&[1, 2, 0, 3])
+        );
+
+        // Projection at the RHS
+        let mut query_graph = QueryGraph::new();
+        let table_scan_1 = query_graph.table_scan(1, 1);
+        let table_scan_2 = query_graph.table_scan(2, 3);
+        let project = query_graph.project(table_scan_2, input_refs(&[1, 2, 0]));
+        let join = query_graph.join(JoinType::Inner, table_scan_1, project, vec![condition]);
+        query_graph.set_entry_node(join);
+
+        let new_project = JoinProjectTransposeRule {}
+            .apply(&mut query_graph, join)
+            .unwrap();
+        assert_eq!(
+            final_project_outputs(&query_graph, new_project),
+            input_refs(&[0, 2, 3, 1])
+        );
+    }
+
+    #[test]
+    fn test_semi_join_only_projects_left_columns() {
+        let mut query_graph = QueryGraph::new();
+        let table_scan_1 = query_graph.table_scan(1, 2);
+        let project = query_graph.project(table_scan_1, input_refs(&[1, 0]));
+        let table_scan_2 = query_graph.table_scan(2, 1);
+        let join = query_graph.join(
+            JoinType::Semi,
+            project,
+            table_scan_2,
+            vec![ScalarExpr::input_ref(0)
+                .binary(BinaryOp::Eq, ScalarExpr::input_ref(2).into())
+                .into()],
+        );
+        query_graph.set_entry_node(join);
+
+        let new_project = JoinProjectTransposeRule {}
+            .apply(&mut query_graph, join)
+            .unwrap();
+        assert_eq!(
+            final_project_outputs(&query_graph, new_project),
+            input_refs(&[1, 0])
+        );
+    }
+}
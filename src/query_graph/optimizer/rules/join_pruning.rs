@@ -54,7 +54,12 @@ impl Rule for JoinPruningRule {
                 for condition in conditions.iter() {
                     store_input_dependencies(condition, &mut required_columns_including_join);
                 }
-                if required_columns_including_join.len() == num_columns(&query_graph, node_id) {
+                // Note: semi and anti joins don't project the columns from their right
+                // input, but they may be referenced by the join conditions
+                let left_num_columns = num_columns(query_graph, *left);
+                if required_columns_including_join.len()
+                    == left_num_columns + num_columns(query_graph, *right)
+                {
                     return None;
                 }
                 let join_column_map =
@@ -64,7 +69,6 @@ impl Rule for JoinPruningRule {
                 });
 
                 // Prune the branches
-                let left_num_columns = num_columns(query_graph, *left);
                 let (left_columns, right_columns): (Vec<usize>, Vec<usize>) =
                     required_columns_including_join
                         .iter()
@@ -112,3 +116,34 @@ impl Rule for JoinPruningRule {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        query_graph::{optimizer::Rule, JoinType, QueryGraph},
+        scalar_expr::{BinaryOp, ScalarExpr},
+    };
+
+    use super::JoinPruningRule;
+
+    #[test]
+    fn test_semi_join_with_all_columns_used() {
+        let mut query_graph = QueryGraph::new();
+        let table_scan_1 = query_graph.table_scan(1, 2);
+        let table_scan_2 = query_graph.table_scan(2, 1);
+        let join = query_graph.join(
+            JoinType::Semi,
+            table_scan_1,
+            table_scan_2,
+            vec![ScalarExpr::input_ref(1)
+                .binary(BinaryOp::Eq, ScalarExpr::input_ref(2).into())
+                .into()],
+        );
+        let project = query_graph.project(join, vec![ScalarExpr::input_ref(0).into()]);
+        query_graph.set_entry_node(project);
+
+        // The column not required by the parent and the column from the right input
+        // are used by the join condition, so there is nothing to prune
+        assert!(JoinPruningRule {}.apply(&mut query_graph, join).is_none());
+    }
+}
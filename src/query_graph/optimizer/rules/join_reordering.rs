@@ -0,0 +1,35 @@
+use crate::query_graph::{
+    optimizer::{
+        join_enumeration::{is_flattened_by_parent, reorder_joins, DefaultJoinCostModel},
+        OptRuleType, SingleReplacementRule,
+    },
+    JoinType, NodeId, QueryGraph, QueryNode,
+};
+
+/// Rule that reorders the trees of inner joins, replacing them with the cheapest join
+/// order found by the join enumerator. See `join_enumeration` module.
+///
+/// The rule is applied on the top-level join of the tree, and only if some join order
+/// cheaper than the current one is found, so that the rule is not applied again over
+/// the resulting tree.
+pub struct JoinReorderingRule {}
+
+impl SingleReplacementRule for JoinReorderingRule {
+    fn rule_type(&self) -> OptRuleType {
+        OptRuleType::TopDown
+    }
+
+    fn apply(&self, query_graph: &mut QueryGraph, node_id: NodeId) -> Option<NodeId> {
+        if let QueryNode::Join {
+            join_type: JoinType::Inner,
+            ..
+        } = query_graph.node(node_id)
+        {
+            if is_flattened_by_parent(query_graph, node_id) {
+                return None;
+            }
+            return reorder_joins(query_graph, node_id, &DefaultJoinCostModel::new());
+        }
+        None
+    }
+}
@@ -0,0 +1,35 @@
+use crate::query_graph::{
+    optimizer::{OptRuleType, SingleReplacementRule},
+    NodeId, QueryGraph, QueryNode,
+};
+
+/// Given a Limit node on top of a Project node, it transposes them so that the
+/// expressions of the projection are only evaluated for the rows returned by the
+/// limit.
+pub struct LimitProjectTransposeRule {}
+
+impl SingleReplacementRule for LimitProjectTransposeRule {
+    fn rule_type(&self) -> OptRuleType {
+        OptRuleType::TopDown
+    }
+
+    fn apply(&self, query_graph: &mut QueryGraph, node_id: NodeId) -> Option<NodeId> {
+        if let QueryNode::Limit {
+            offset,
+            limit,
+            input,
+        } = query_graph.node(node_id)
+        {
+            if let QueryNode::Project {
+                outputs,
+                input: proj_input,
+            } = query_graph.node(*input)
+            {
+                let outputs = outputs.clone();
+                let new_limit = query_graph.limit(*proj_input, *offset, *limit);
+                return Some(query_graph.project(new_limit, outputs));
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,38 @@
+use crate::query_graph::{
+    optimizer::{OptRuleType, SingleReplacementRule},
+    NodeId, QueryGraph, QueryNode,
+};
+
+/// Merges a Limit node with a bounded number of rows on top of a Sort node into a
+/// TopN node, which doesn't need to sort its entire input.
+pub struct LimitSortToTopNRule {}
+
+impl SingleReplacementRule for LimitSortToTopNRule {
+    fn rule_type(&self) -> OptRuleType {
+        OptRuleType::TopDown
+    }
+
+    fn apply(&self, query_graph: &mut QueryGraph, node_id: NodeId) -> Option<NodeId> {
+        if let QueryNode::Limit {
+            offset,
+            limit: Some(limit),
+            input,
+        } = query_graph.node(node_id)
+        {
+            if let QueryNode::Sort {
+                keys,
+                input: sort_input,
+            } = query_graph.node(*input)
+            {
+                return Some(query_graph.add_node(QueryNode::TopN {
+                    partition_key: Vec::new(),
+                    keys: keys.clone(),
+                    offset: *offset,
+                    limit: *limit,
+                    input: *sort_input,
+                }));
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,55 @@
+use itertools::Itertools;
+
+use crate::query_graph::{
+    optimizer::{OptRuleType, SingleReplacementRule},
+    properties::empty_key,
+    NodeId, QueryGraph, QueryNode,
+};
+
+/// Given a Limit node on top of a Union node, it adds a limit to the branches of the
+/// union, since none of them needs to produce more than `offset + limit` rows. The
+/// top Limit node is preserved.
+///
+/// Branches already known to produce at most `offset + limit` rows are left untouched.
+pub struct LimitUnionTransposeRule {}
+
+impl SingleReplacementRule for LimitUnionTransposeRule {
+    fn rule_type(&self) -> OptRuleType {
+        OptRuleType::TopDown
+    }
+
+    fn apply(&self, query_graph: &mut QueryGraph, node_id: NodeId) -> Option<NodeId> {
+        if let QueryNode::Limit {
+            offset,
+            limit: Some(limit),
+            input,
+        } = query_graph.node(node_id)
+        {
+            if let QueryNode::Union { inputs } = query_graph.node(*input) {
+                let (offset, limit, inputs) = (*offset, *limit, inputs.clone());
+                let branch_limit = offset + limit;
+                let is_bounded = |query_graph: &QueryGraph, branch: NodeId| {
+                    empty_key(query_graph, branch)
+                        .and_then(|key| key.upper_bound)
+                        .is_some_and(|upper_bound| upper_bound <= branch_limit)
+                };
+                if inputs.iter().all(|branch| is_bounded(query_graph, *branch)) {
+                    return None;
+                }
+                let new_inputs = inputs
+                    .iter()
+                    .map(|branch| {
+                        if is_bounded(query_graph, *branch) {
+                            *branch
+                        } else {
+                            query_graph.limit(*branch, 0, Some(branch_limit))
+                        }
+                    })
+                    .collect_vec();
+                let new_union = query_graph.add_node(QueryNode::Union { inputs: new_inputs });
+                return Some(query_graph.limit(new_union, offset, Some(limit)));
+            }
+        }
+        None
+    }
+}
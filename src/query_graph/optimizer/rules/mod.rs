@@ -2,9 +2,16 @@ mod aggregate_project_transpose;
 mod aggregate_pruning;
 mod aggregate_remove;
 mod aggregate_simplifier;
+mod apply_aggregate_transpose;
+mod apply_filter_transpose;
+mod apply_join_transpose;
+mod apply_project_transpose;
 mod apply_pruning;
+mod apply_to_join;
+mod apply_union_transpose;
 mod common_aggregate_discovery;
 mod cte_discovery;
+mod empty_relation_propagation;
 mod equality_propagation;
 mod expression_reduction;
 mod filter_aggregate_transpose;
@@ -13,14 +20,27 @@ mod filter_join_transpose;
 mod filter_merge;
 mod filter_normalization;
 mod filter_project_transpose;
+mod filter_subquery_unnesting;
+mod filter_window_to_top_n;
+mod filter_window_transpose;
+mod foreign_key_join_elimination;
 mod identity_join;
 mod join_project_transpose;
 mod join_pruning;
+mod join_reordering;
+mod limit_project_transpose;
+mod limit_sort_to_top_n;
+mod limit_union_transpose;
+mod outer_apply_to_inner;
 mod outer_to_inner_join;
 mod project_merge;
 mod project_normalization;
 mod prune_aggregate_input;
 mod remove_passthrough_project;
+mod scalar_subquery_unnesting;
+mod set_operation_lowering;
+mod set_operation_simplifier;
+mod sort_remove;
 mod top_projection;
 mod union_merge;
 mod union_pruning;
@@ -29,9 +49,16 @@ pub use aggregate_project_transpose::AggregateProjectTransposeRule;
 pub use aggregate_pruning::AggregatePruningRule;
 pub use aggregate_remove::AggregateRemoveRule;
 pub use aggregate_simplifier::AggregateSimplifierRule;
+pub use apply_aggregate_transpose::ApplyAggregateTransposeRule;
+pub use apply_filter_transpose::ApplyFilterTransposeRule;
+pub use apply_join_transpose::ApplyJoinTransposeRule;
+pub use apply_project_transpose::ApplyProjectTransposeRule;
 pub use apply_pruning::ApplyPruningRule;
+pub use apply_to_join::ApplyToJoinRule;
+pub use apply_union_transpose::ApplyUnionTransposeRule;
 pub use common_aggregate_discovery::CommonAggregateDiscoveryRule;
 pub use cte_discovery::CteDiscoveryRule;
+pub use empty_relation_propagation::EmptyRelationPropagationRule;
 pub use equality_propagation::EqualityPropagationRule;
 pub use expression_reduction::ExpressionReductionRule;
 pub use filter_aggregate_transpose::FilterAggregateTransposeRule;
@@ -40,14 +67,27 @@ pub use filter_join_transpose::FilterJoinTransposeRule;
 pub use filter_merge::FilterMergeRule;
 pub use filter_normalization::FilterNormalizationRule;
 pub use filter_project_transpose::FilterProjectTransposeRule;
+pub use filter_subquery_unnesting::FilterSubqueryUnnestingRule;
+pub use filter_window_to_top_n::FilterWindowToTopNRule;
+pub use filter_window_transpose::FilterWindowTransposeRule;
+pub use foreign_key_join_elimination::ForeignKeyJoinEliminationRule;
 pub use identity_join::IdentityJoinRule;
 pub use join_project_transpose::JoinProjectTransposeRule;
 pub use join_pruning::JoinPruningRule;
+pub use join_reordering::JoinReorderingRule;
+pub use limit_project_transpose::LimitProjectTransposeRule;
+pub use limit_sort_to_top_n::LimitSortToTopNRule;
+pub use limit_union_transpose::LimitUnionTransposeRule;
+pub use outer_apply_to_inner::OuterApplyToInnerRule;
 pub use outer_to_inner_join::OuterToInnerJoinRule;
 pub use project_merge::ProjectMergeRule;
 pub use project_normalization::ProjectNormalizationRule;
 pub use prune_aggregate_input::PruneAggregateInputRule;
 pub use remove_passthrough_project::RemovePassthroughProjectRule;
+pub use scalar_subquery_unnesting::ScalarSubqueryUnnestingRule;
+pub use set_operation_lowering::SetOperationLoweringRule;
+pub use set_operation_simplifier::SetOperationSimplifierRule;
+pub use sort_remove::SortRemoveRule;
 pub use top_projection::TopProjectionRule;
 pub use union_merge::UnionMergeRule;
 pub use union_pruning::UnionPruningRule;
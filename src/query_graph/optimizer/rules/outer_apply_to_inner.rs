@@ -0,0 +1,93 @@
+use crate::{
+    query_graph::{
+        optimizer::{
+            utils::{domain_apply, domain_join_conditions},
+            OptRuleType, SingleReplacementRule,
+        },
+        properties::{is_null_if_input_is_null, num_columns, subgraph_correlated_input_refs},
+        ApplyType, JoinType, NodeId, QueryGraph, QueryNode,
+    },
+    scalar_expr::ScalarExpr,
+};
+
+/// Rule that replaces a left outer apply with a left outer join between its left input
+/// and an inner apply evaluating its right input over the domain of its parameters, so
+/// that the inner apply can be decorrelated by the rules that only support inner applies.
+///
+/// ```txt
+/// Left Outer Apply parameters: [ref_1]
+///   A
+///   R
+/// ```
+///
+/// becomes:
+///
+/// ```txt
+/// Project [ref_0, ..., ref_n-1, ref_n+1, ...]
+///   Left Outer Join [raw_eq(ref_1, ref_n)]
+///     A
+///     Apply parameters: [ref_0]
+///       Aggregate key: [ref_0]
+///         Project [ref_1]
+///           A
+///       R
+/// ```
+///
+/// The rule is not applied when some other rule is able to handle the left outer apply
+/// directly.
+pub struct OuterApplyToInnerRule {}
+
+impl SingleReplacementRule for OuterApplyToInnerRule {
+    fn rule_type(&self) -> OptRuleType {
+        OptRuleType::TopDown
+    }
+
+    fn apply(&self, query_graph: &mut QueryGraph, node_id: NodeId) -> Option<NodeId> {
+        if let QueryNode::Apply {
+            correlation,
+            left,
+            right,
+            apply_type: ApplyType::LeftOuter,
+        } = query_graph.node(node_id)
+        {
+            if subgraph_correlated_input_refs(query_graph, *right).is_empty() {
+                return None;
+            }
+            let handled_elsewhere = match query_graph.node(*right) {
+                // ApplyAggregateTransposeRule
+                QueryNode::Aggregate { .. } => true,
+                // ApplyProjectTransposeRule
+                QueryNode::Project { outputs, .. } => outputs.iter().all(is_null_if_input_is_null),
+                // ApplyToJoinRule
+                QueryNode::Filter { input, .. } => {
+                    subgraph_correlated_input_refs(query_graph, *input).is_empty()
+                }
+                _ => false,
+            };
+            if handled_elsewhere {
+                return None;
+            }
+            let parameters = correlation.parameters.clone();
+            let left = *left;
+            let right = *right;
+            let left_num_columns = num_columns(query_graph, left);
+            let right_num_columns = num_columns(query_graph, right);
+            let new_apply = domain_apply(query_graph, ApplyType::Inner, left, &parameters, right);
+            let join = query_graph.join(
+                JoinType::LeftOuter,
+                left,
+                new_apply,
+                domain_join_conditions(&parameters, left_num_columns),
+            );
+            let outputs = (0..left_num_columns)
+                .chain(
+                    left_num_columns + parameters.len()
+                        ..left_num_columns + parameters.len() + right_num_columns,
+                )
+                .map(|i| ScalarExpr::input_ref(i).into())
+                .collect();
+            return Some(query_graph.project(join, outputs));
+        }
+        None
+    }
+}
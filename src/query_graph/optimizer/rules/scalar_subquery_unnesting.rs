@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+
+use crate::{
+    query_graph::{
+        optimizer::{OptRuleType, SingleReplacementRule},
+        properties::{empty_key, num_columns, subgraph_correlated_input_refs},
+        ApplyType, JoinType, NodeId, QueryGraph, QueryNode,
+    },
+    scalar_expr::{
+        rewrite::replace_sub_expressions_pre, visitor::visit_expr_pre, ScalarExpr, ScalarExprRef,
+    },
+    visitor_utils::PreOrderVisitationResult,
+};
+
+/// Rule that unnests the scalar subqueries in the expressions of filter and project nodes,
+/// by evaluating the subquery with a left outer join, or a left outer apply if it is
+/// correlated, on top of the input of the node. The subquery expression is then replaced
+/// with a reference to the first column of the subquery.
+///
+/// A scalar subquery returning more than one row must raise an error, which the joins
+/// don't do. Hence, only the subqueries known to return one row at most are unnested.
+pub struct ScalarSubqueryUnnestingRule {}
+
+impl SingleReplacementRule for ScalarSubqueryUnnestingRule {
+    fn rule_type(&self) -> OptRuleType {
+        OptRuleType::TopDown
+    }
+
+    fn apply(&self, query_graph: &mut QueryGraph, node_id: NodeId) -> Option<NodeId> {
+        let (exprs, input) = match query_graph.node(node_id) {
+            QueryNode::Project { outputs, input } => (outputs, *input),
+            QueryNode::Filter { conditions, input } => (conditions, *input),
+            _ => return None,
+        };
+        let subquery_expr = Self::find_scalar_subquery(query_graph, exprs)?;
+        let subquery = match subquery_expr.as_ref() {
+            ScalarExpr::ScalarSubquery { subquery } => subquery.clone(),
+            _ => panic!("expected scalar subquery"),
+        };
+        let input_num_columns = num_columns(query_graph, input);
+        let replacement_map = HashMap::from([(
+            subquery_expr,
+            ScalarExpr::input_ref(input_num_columns).into(),
+        )]);
+        let new_exprs = exprs
+            .iter()
+            .map(|e| replace_sub_expressions_pre(e, &replacement_map))
+            .collect();
+
+        let subquery_input = query_graph.node(subquery.root).get_input(0);
+        let new_input = match subquery.correlation {
+            Some(correlation)
+                if !subgraph_correlated_input_refs(query_graph, subquery_input).is_empty() =>
+            {
+                query_graph.add_node(QueryNode::Apply {
+                    correlation,
+                    left: input,
+                    right: subquery_input,
+                    apply_type: ApplyType::LeftOuter,
+                })
+            }
+            _ => query_graph.join(JoinType::LeftOuter, input, subquery_input, Vec::new()),
+        };
+        if let QueryNode::Project { .. } = query_graph.node(node_id) {
+            Some(query_graph.project(new_input, new_exprs))
+        } else {
+            let filter = query_graph.filter(new_input, new_exprs);
+            let outputs = (0..input_num_columns)
+                .map(|i| ScalarExpr::input_ref(i).into())
+                .collect();
+            Some(query_graph.project(filter, outputs))
+        }
+    }
+}
+
+impl ScalarSubqueryUnnestingRule {
+    /// Finds the first scalar subquery among the given expressions that is known to return
+    /// one row at most.
+    fn find_scalar_subquery(
+        query_graph: &QueryGraph,
+        exprs: &[ScalarExprRef],
+    ) -> Option<ScalarExprRef> {
+        let mut result = None;
+        for expr in exprs.iter() {
+            visit_expr_pre(expr, &mut |curr_expr: &ScalarExprRef| {
+                if result.is_some() {
+                    return PreOrderVisitationResult::Abort;
+                }
+                if let ScalarExpr::ScalarSubquery { subquery } = curr_expr.as_ref() {
+                    if let Some(key) = empty_key(query_graph, subquery.root) {
+                        if key.upper_bound.is_some_and(|upper_bound| upper_bound <= 1) {
+                            result = Some(curr_expr.clone());
+                            return PreOrderVisitationResult::Abort;
+                        }
+                    }
+                }
+                PreOrderVisitationResult::VisitInputs
+            });
+        }
+        result
+    }
+}
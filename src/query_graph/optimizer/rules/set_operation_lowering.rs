@@ -0,0 +1,187 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    data_type::DataType,
+    physical_plan::{CostModel, DefaultCostModel},
+    query_graph::{
+        optimizer::{OptRuleType, SingleReplacementRule},
+        properties::{
+            estimated_cardinality, estimated_distinct_rows, is_empty_relation, num_columns,
+            unique_key,
+        },
+        JoinType, NodeId, QueryGraph, QueryNode,
+    },
+    scalar_expr::{AggregateExpr, AggregateOp, BinaryOp, ScalarExpr, ScalarExprRef},
+    value::{Literal, Value},
+};
+
+/// Lowers Intersect and Except nodes into joins or aggregations.
+///
+/// If the left input is known to be unique, `distinct` doesn't make any difference
+/// and the node can be replaced with a semi-join or an anti-join, matching the rows of
+/// both inputs with null-safe equalities, which doesn't need any aggregation.
+///
+/// The distinct variants can also be rewritten as an aggregation over the union of
+/// both inputs, counting the rows coming from each side. This requires a single hash
+/// table instead of one for the join and another one for removing the duplicated rows
+/// afterwards:
+///
+/// ```text
+/// Project [ref_0, ..., ref_n-1]
+///   Filter [gt(ref_n, 0), gt(ref_n+1, 0)]  -- eq(ref_n+1, 0) for Except
+///     Aggregate key: [ref_0, ..., ref_n-1], aggregates: [count(ref_n), count(ref_n+1)]
+///       Union
+///         Project [ref_0, ..., ref_n-1, TRUE, NULL]
+///           left
+///         Project [ref_0, ..., ref_n-1, NULL, TRUE]
+///           right
+/// ```
+///
+/// When both rewrites are possible, the one with the lowest estimated cost is chosen:
+/// the join inserts all the rows of the right input into its hash table, while the
+/// aggregation hashes the rows of both inputs but only inserts the distinct ones, which
+/// makes it cheaper when the right input is large and contains many duplicates.
+///
+/// The all variants of non-unique inputs are left untouched, since the number of
+/// copies of each row they return can't be expressed with these operators. Nodes
+/// with an empty input are left untouched as well, since they can be simplified.
+pub struct SetOperationLoweringRule {}
+
+impl SingleReplacementRule for SetOperationLoweringRule {
+    fn rule_type(&self) -> OptRuleType {
+        OptRuleType::TopDown
+    }
+
+    fn apply(&self, query_graph: &mut QueryGraph, node_id: NodeId) -> Option<NodeId> {
+        let (is_intersect, distinct, left, right) = match query_graph.node(node_id) {
+            QueryNode::Intersect {
+                distinct,
+                left,
+                right,
+            } => (true, *distinct, *left, *right),
+            QueryNode::Except {
+                distinct,
+                left,
+                right,
+            } => (false, *distinct, *left, *right),
+            _ => return None,
+        };
+        // Empty inputs are handled by `SetOperationSimplifierRule`.
+        if is_empty_relation(query_graph, left) || is_empty_relation(query_graph, right) {
+            return None;
+        }
+        let num_columns = num_columns(query_graph, left);
+        let join_type = if is_intersect {
+            JoinType::Semi
+        } else {
+            JoinType::Anti
+        };
+        if unique_key(query_graph, left).is_some()
+            && !(distinct
+                && Self::aggregation_is_cheaper(query_graph, node_id, left, right, join_type))
+        {
+            let conditions = (0..num_columns)
+                .map(|col| {
+                    ScalarExpr::input_ref(col)
+                        .binary(
+                            BinaryOp::RawEq,
+                            ScalarExpr::input_ref(num_columns + col).into(),
+                        )
+                        .into()
+                })
+                .collect();
+            return Some(query_graph.join(join_type, left, right, conditions));
+        }
+        if !distinct {
+            return None;
+        }
+        let columns = (0..num_columns)
+            .map(|col| ScalarExpr::input_ref(col).into())
+            .collect::<Vec<ScalarExprRef>>();
+        let marker = |present: bool| -> ScalarExprRef {
+            if present {
+                ScalarExpr::true_literal().into()
+            } else {
+                ScalarExpr::null_literal(DataType::Bool).into()
+            }
+        };
+        let branch = |query_graph: &mut QueryGraph, input: NodeId, from_left: bool| {
+            let mut outputs = columns.clone();
+            outputs.push(marker(from_left));
+            outputs.push(marker(!from_left));
+            query_graph.project(input, outputs)
+        };
+        let left_branch = branch(query_graph, left, true);
+        let right_branch = branch(query_graph, right, false);
+        let union_ = query_graph.add_node(QueryNode::Union {
+            inputs: vec![left_branch, right_branch],
+        });
+        let aggregate = query_graph.add_node(QueryNode::Aggregate {
+            group_key: (0..num_columns).collect::<BTreeSet<_>>(),
+            aggregates: [num_columns, num_columns + 1]
+                .into_iter()
+                .map(|col| {
+                    AggregateExpr {
+                        op: AggregateOp::Count,
+                        operands: vec![col],
+                    }
+                    .into()
+                })
+                .collect(),
+            input: union_,
+        });
+        let zero: ScalarExprRef =
+            ScalarExpr::Literal(Literal::new(Value::BigInt(0), DataType::BigInt)).into();
+        let left_count = ScalarExpr::input_ref(num_columns);
+        let right_count = ScalarExpr::input_ref(num_columns + 1);
+        let filter = query_graph.filter(
+            aggregate,
+            vec![
+                left_count.binary(BinaryOp::Gt, zero.clone()).into(),
+                right_count
+                    .binary(
+                        if is_intersect {
+                            BinaryOp::Gt
+                        } else {
+                            BinaryOp::Eq
+                        },
+                        zero,
+                    )
+                    .into(),
+            ],
+        );
+        Some(query_graph.project(filter, columns))
+    }
+}
+
+impl SetOperationLoweringRule {
+    /// Returns whether rewriting the given distinct set operation as an aggregation
+    /// is estimated to be cheaper than rewriting it as the given type of join,
+    /// according to the default cost model of the physical operators they would be
+    /// implemented with.
+    fn aggregation_is_cheaper(
+        query_graph: &QueryGraph,
+        node_id: NodeId,
+        left: NodeId,
+        right: NodeId,
+        join_type: JoinType,
+    ) -> bool {
+        let left_rows = estimated_cardinality(query_graph, left);
+        let right_rows = estimated_cardinality(query_graph, right);
+        let output_rows = estimated_cardinality(query_graph, node_id);
+        // The groups are the distinct rows of the union of both inputs
+        let groups = (estimated_distinct_rows(query_graph, left)
+            + estimated_distinct_rows(query_graph, right))
+        .min(left_rows + right_rows);
+
+        let cost_model = DefaultCostModel::new();
+        let join_cost = cost_model.hash_join_cost(join_type, left_rows, right_rows, output_rows);
+        let aggregation_cost = cost_model.expression_cost(left_rows, left_rows)
+            + cost_model.expression_cost(right_rows, right_rows)
+            + cost_model.union_cost(left_rows + right_rows, left_rows + right_rows)
+            + cost_model.hash_aggregate_cost(left_rows + right_rows, groups)
+            + cost_model.expression_cost(groups, output_rows)
+            + cost_model.expression_cost(output_rows, output_rows);
+        aggregation_cost < join_cost
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::BTreeSet;
+
+use crate::query_graph::{
+    optimizer::{OptRuleType, SingleReplacementRule},
+    properties::{is_empty_relation, num_columns, row_type, unique_key},
+    NodeId, QueryGraph, QueryNode,
+};
+
+/// Simplifies Intersect and Except nodes with an empty input:
+///
+/// - The intersection with an empty relation is empty.
+/// - Nothing can be removed from an empty relation.
+/// - Removing an empty relation from the left input just returns the left input,
+///   without duplicates in the case of the distinct variant.
+pub struct SetOperationSimplifierRule {}
+
+impl SingleReplacementRule for SetOperationSimplifierRule {
+    fn rule_type(&self) -> OptRuleType {
+        OptRuleType::Always
+    }
+
+    fn apply(&self, query_graph: &mut QueryGraph, node_id: NodeId) -> Option<NodeId> {
+        match query_graph.node(node_id) {
+            QueryNode::Intersect { left, right, .. } => {
+                let left = *left;
+                if is_empty_relation(query_graph, left) {
+                    Some(left)
+                } else if is_empty_relation(query_graph, *right) {
+                    let row_type = row_type(query_graph, left);
+                    Some(query_graph.empty_relation(row_type))
+                } else {
+                    None
+                }
+            }
+            QueryNode::Except {
+                distinct,
+                left,
+                right,
+            } => {
+                let (distinct, left) = (*distinct, *left);
+                if is_empty_relation(query_graph, left) {
+                    Some(left)
+                } else if is_empty_relation(query_graph, *right) {
+                    if !distinct || unique_key(query_graph, left).is_some() {
+                        Some(left)
+                    } else {
+                        let num_columns = num_columns(query_graph, left);
+                        Some(query_graph.add_node(QueryNode::Aggregate {
+                            group_key: (0..num_columns).collect::<BTreeSet<_>>(),
+                            aggregates: Vec::new(),
+                            input: left,
+                        }))
+                    }
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use crate::query_graph::{
+    optimizer::{OptRuleType, SingleReplacementRule},
+    properties::ordering,
+    NodeId, QueryGraph, QueryNode,
+};
+
+/// Removes the Sort nodes whose order is not observable by any of their ancestors, ie.
+/// when all their parents produce the same rows regardless of the order of their
+/// input, and the ones sorting an input that is already guaranteed to be sorted by
+/// their keys.
+pub struct SortRemoveRule {}
+
+impl SingleReplacementRule for SortRemoveRule {
+    fn rule_type(&self) -> OptRuleType {
+        OptRuleType::TopDown
+    }
+
+    fn apply(&self, query_graph: &mut QueryGraph, node_id: NodeId) -> Option<NodeId> {
+        if let QueryNode::Sort { keys, input } = query_graph.node(node_id) {
+            if ordering(query_graph, *input).starts_with(keys)
+                || !is_order_observable(query_graph, node_id)
+            {
+                return Some(*input);
+            }
+        }
+        None
+    }
+}
+
+/// Whether the order of the rows of the given node may affect the rows produced by
+/// any of its ancestors. Projections and filters preserve the order of their input,
+/// so it is observable if their own order is.
+fn is_order_observable(query_graph: &QueryGraph, node_id: NodeId) -> bool {
+    query_graph.get_parents(node_id).is_none_or(|parents| {
+        parents
+            .iter()
+            .any(|parent| match query_graph.node(*parent) {
+                QueryNode::Join { .. }
+                | QueryNode::Aggregate { .. }
+                | QueryNode::Union { .. }
+                | QueryNode::Intersect { .. }
+                | QueryNode::Except { .. }
+                | QueryNode::RecursiveUnion { .. }
+                | QueryNode::Apply { .. }
+                | QueryNode::Sort { .. }
+                | QueryNode::TopN { .. } => false,
+                QueryNode::Project { .. } | QueryNode::Filter { .. } => {
+                    is_order_observable(query_graph, *parent)
+                }
+                QueryNode::QueryRoot { .. }
+                | QueryNode::SubqueryRoot { .. }
+                | QueryNode::TableScan { .. }
+                | QueryNode::Values { .. }
+                | QueryNode::RecursiveReference { .. }
+                | QueryNode::Limit { .. } => true,
+                // The order of the peers within a partition determines their row
+                // numbers.
+                QueryNode::Window { .. } => true,
+            })
+    })
+}
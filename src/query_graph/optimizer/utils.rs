@@ -4,13 +4,16 @@ use std::collections::{HashMap, HashSet};
 
 use itertools::Itertools;
 
-use crate::query_graph::{NodeId, QueryGraph, QueryNode};
-use crate::scalar_expr::rewrite::{apply_column_map, rewrite_expr_vec};
+use crate::query_graph::{ApplyType, CorrelationContext, NodeId, QueryGraph, QueryNode};
+use crate::scalar_expr::rewrite::{apply_column_map, rewrite_expr_pre, rewrite_expr_vec};
 use crate::scalar_expr::visitor::store_input_dependencies;
 use crate::visitor_utils::PreOrderVisitationResult;
 use crate::{
-    query_graph::{properties::num_columns, visitor::QueryGraphPrePostVisitor},
-    scalar_expr::{AggregateExpr, ScalarExprRef},
+    query_graph::{
+        properties::{num_columns, subgraph_correlated_input_refs},
+        visitor::QueryGraphPrePostVisitor,
+    },
+    scalar_expr::{AggregateExpr, BinaryOp, ScalarExpr, ScalarExprRef, Subquery},
 };
 
 /// Get the filters that are common to all parents on the given node.
@@ -276,3 +279,185 @@ pub(crate) fn sort_projection(
         None
     }
 }
+
+/// Rewrites an expression from the right input of an apply node, so that it can be
+/// evaluated on top of the apply, where the columns of its right input come after the
+/// `left_num_columns` columns of its left input.
+///
+/// The references to the correlation context of the apply are replaced with the
+/// corresponding parameters, and the ones to outer contexts are adjusted, since the
+/// context of the apply is no longer in scope.
+///
+/// Returns None if the expression contains subqueries, as the references to outer
+/// contexts within them would need to be adjusted as well.
+pub(crate) fn pull_up_correlated_expr(
+    expr: &ScalarExprRef,
+    parameters: &[ScalarExprRef],
+    left_num_columns: usize,
+) -> Option<ScalarExprRef> {
+    rewrite_expr_pre(
+        &mut |expr: &ScalarExprRef| match expr.as_ref() {
+            ScalarExpr::InputRef { index } => {
+                Ok(Some(ScalarExpr::input_ref(index + left_num_columns).into()))
+            }
+            ScalarExpr::CorrelatedInputRef {
+                context_offset,
+                index,
+                data_type,
+            } => {
+                if *context_offset == 0 {
+                    Ok(Some(parameters[*index].clone()))
+                } else {
+                    Ok(Some(
+                        ScalarExpr::CorrelatedInputRef {
+                            context_offset: context_offset - 1,
+                            index: *index,
+                            data_type: data_type.clone(),
+                        }
+                        .into(),
+                    ))
+                }
+            }
+            ScalarExpr::ScalarSubquery { .. }
+            | ScalarExpr::ExistsSubquery { .. }
+            | ScalarExpr::ScalarSubqueryCmp { .. } => Err(()),
+            _ => Ok(None),
+        },
+        expr,
+    )
+}
+
+/// Builds an apply node evaluating `right` for each distinct value of the given
+/// parameters over `left`, ie. over the domain of the parameters. The parameters of the
+/// new apply are the columns of the domain, so `right` doesn't need to be modified.
+///
+/// Returns the new apply node, which projects the columns of the domain followed by the
+/// ones of `right`.
+pub(crate) fn domain_apply(
+    query_graph: &mut QueryGraph,
+    apply_type: ApplyType,
+    left: NodeId,
+    parameters: &[ScalarExprRef],
+    right: NodeId,
+) -> NodeId {
+    let project = query_graph.project(left, parameters.to_vec());
+    let domain = query_graph.add_node(QueryNode::Aggregate {
+        group_key: (0..parameters.len()).collect(),
+        aggregates: Vec::new(),
+        input: project,
+    });
+    query_graph.add_node(QueryNode::Apply {
+        correlation: CorrelationContext {
+            parameters: (0..parameters.len())
+                .map(|i| ScalarExpr::input_ref(i).into())
+                .collect(),
+        },
+        left: domain,
+        right,
+        apply_type,
+    })
+}
+
+/// Returns the conditions for joining the left input of an apply node with the result of
+/// a `domain_apply` built for its parameters, placed after the `left_num_columns` columns
+/// of the left input. NULL parameters must match their NULL value in the domain.
+pub(crate) fn domain_join_conditions(
+    parameters: &[ScalarExprRef],
+    left_num_columns: usize,
+) -> Vec<ScalarExprRef> {
+    parameters
+        .iter()
+        .enumerate()
+        .map(|(i, parameter)| {
+            ScalarExpr::BinaryOp {
+                op: BinaryOp::RawEq,
+                left: parameter.clone(),
+                right: ScalarExpr::input_ref(left_num_columns + i).into(),
+            }
+            .into()
+        })
+        .collect()
+}
+
+/// Builds the relation to be joined with `left` for unnesting the given subquery, whose
+/// parameters, if any, are evaluated over the rows of `left`.
+///
+/// Returns the right input of the join, the conditions of the join for correlating it
+/// with `left` and the columns of the subquery, as expressions over the output of the
+/// join. The correlated conditions of a filter at the top of the subquery, optionally
+/// under a projection, are pulled up as join conditions. Otherwise, the subquery is
+/// evaluated over the domain of its parameters with an apply.
+pub(crate) fn unnest_subquery(
+    query_graph: &mut QueryGraph,
+    left: NodeId,
+    subquery: &Subquery<ScalarExpr>,
+) -> (NodeId, Vec<ScalarExprRef>, Vec<ScalarExprRef>) {
+    let subquery_input = query_graph.node(subquery.root).get_input(0);
+    let left_num_columns = num_columns(query_graph, left);
+    let columns_from = |offset: usize, num_columns: usize| -> Vec<ScalarExprRef> {
+        (offset..offset + num_columns)
+            .map(|i| ScalarExpr::input_ref(i).into())
+            .collect()
+    };
+    let subquery_num_columns = num_columns(query_graph, subquery_input);
+    let parameters = match &subquery.correlation {
+        // The subquery is evaluated in the same context as the join
+        None => {
+            return (
+                subquery_input,
+                Vec::new(),
+                columns_from(left_num_columns, subquery_num_columns),
+            )
+        }
+        Some(correlation) => correlation.parameters.clone(),
+    };
+    if subgraph_correlated_input_refs(query_graph, subquery_input).is_empty() {
+        return (
+            subquery_input,
+            Vec::new(),
+            columns_from(left_num_columns, subquery_num_columns),
+        );
+    }
+    let pull_up_vec = |exprs: &[ScalarExprRef]| {
+        exprs
+            .iter()
+            .map(|e| pull_up_correlated_expr(e, &parameters, left_num_columns))
+            .collect::<Option<Vec<_>>>()
+    };
+    let (outputs, filter) = match query_graph.node(subquery_input) {
+        QueryNode::Project { outputs, input } => (Some(outputs), *input),
+        _ => (None, subquery_input),
+    };
+    let (conditions, input) = match query_graph.node(filter) {
+        QueryNode::Filter { conditions, input } => (Some(conditions), *input),
+        _ => (None, filter),
+    };
+    if subgraph_correlated_input_refs(query_graph, input).is_empty() {
+        let conditions = match conditions {
+            Some(conditions) => pull_up_vec(conditions),
+            None => Some(Vec::new()),
+        };
+        let columns = match outputs {
+            Some(outputs) => pull_up_vec(outputs),
+            None => Some(columns_from(
+                left_num_columns,
+                num_columns(query_graph, input),
+            )),
+        };
+        if let (Some(conditions), Some(columns)) = (conditions, columns) {
+            return (input, conditions, columns);
+        }
+    }
+    let apply = domain_apply(
+        query_graph,
+        ApplyType::Inner,
+        left,
+        &parameters,
+        subquery_input,
+    );
+    (
+        apply,
+        domain_join_conditions(&parameters, left_num_columns),
+        columns_from(left_num_columns + parameters.len(), subquery_num_columns),
+    )
+}
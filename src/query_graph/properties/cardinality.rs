@@ -0,0 +1,609 @@
+use std::{any::TypeId, cmp::Ordering, rc::Rc};
+
+use itertools::Itertools;
+
+use crate::{
+    catalog::Histogram,
+    query_graph::{visitor::QueryGraphPrePostVisitor, *},
+    scalar_expr::{BinaryOp, ScalarExpr},
+    value::{Literal, Value},
+    visitor_utils::PreOrderVisitationResult,
+};
+
+use super::{
+    keys,
+    selectivity::{compare_values, estimated_conjunction_selectivity, DEFAULT_SELECTIVITY},
+};
+
+/// Number of rows assumed for the tables without statistics whose size is unknown.
+const DEFAULT_TABLE_ROW_COUNT: f64 = 1000.0;
+
+/// Number of iterations assumed for the recursive unions without an iteration limit.
+const DEFAULT_RECURSIVE_ITERATIONS: f64 = 10.0;
+
+/// Estimated statistics of a column of a relation.
+#[derive(Clone, Default, Debug)]
+pub struct ColumnEstimate {
+    /// Estimated number of distinct non-null values in the column, if known.
+    pub distinct_values: Option<f64>,
+    /// Estimated fraction of rows where the column is NULL.
+    pub null_fraction: f64,
+    /// The minimum and the maximum non-null values of the column, if known.
+    pub min_value: Option<Value>,
+    pub max_value: Option<Value>,
+    /// The most frequent values of the column and the fraction of rows containing them.
+    pub most_common_values: Vec<(Value, f64)>,
+    /// The distribution of the rest of non-null values of the column, if known.
+    pub histogram: Option<Histogram>,
+}
+
+impl ColumnEstimate {
+    /// The estimate of a column containing the given literal in all its rows.
+    fn literal(literal: &Literal) -> Self {
+        if literal.is_null() {
+            Self {
+                distinct_values: Some(0.0),
+                null_fraction: 1.0,
+                ..Default::default()
+            }
+        } else {
+            Self {
+                distinct_values: Some(1.0),
+                null_fraction: 0.0,
+                min_value: Some(literal.value.clone()),
+                max_value: Some(literal.value.clone()),
+                ..Default::default()
+            }
+        }
+    }
+
+    /// The estimate of a column with the same values as this one, but where each
+    /// value may appear in a different number of rows.
+    fn without_distribution(&self) -> Self {
+        Self {
+            most_common_values: Vec::new(),
+            histogram: None,
+            ..self.clone()
+        }
+    }
+}
+
+/// Estimated number of rows produced by a relation and estimated statistics of its
+/// columns.
+#[derive(Clone, Debug)]
+pub struct CardinalityEstimate {
+    pub row_count: f64,
+    pub columns: Vec<ColumnEstimate>,
+}
+
+/// Property computed in a bottom-up manner with the estimated number of rows produced
+/// by the given relation and the estimated statistics of its columns, derived from
+/// the statistics of the tables in the catalog.
+pub fn cardinality_estimate(query_graph: &QueryGraph, node_id: NodeId) -> Rc<CardinalityEstimate> {
+    Cardinality::cardinality_estimate(query_graph, node_id)
+}
+
+/// Estimated number of rows produced by the given relation.
+pub fn estimated_cardinality(query_graph: &QueryGraph, node_id: NodeId) -> f64 {
+    cardinality_estimate(query_graph, node_id).row_count
+}
+
+/// Estimated number of distinct rows produced by the given relation.
+pub fn estimated_distinct_rows(query_graph: &QueryGraph, node_id: NodeId) -> f64 {
+    let estimate = cardinality_estimate(query_graph, node_id);
+    if estimate.columns.is_empty() {
+        return estimate.row_count.min(1.0);
+    }
+    Cardinality::group_count(&estimate, 0..estimate.columns.len())
+}
+
+/// Helper function to include the estimated cardinality when explaining the plan.
+pub fn estimated_cardinality_annotator(
+    query_graph: &QueryGraph,
+    node_id: NodeId,
+) -> Option<String> {
+    Some(format!(
+        "Estimated Cardinality: {:.1}",
+        estimated_cardinality(query_graph, node_id)
+    ))
+}
+
+struct Cardinality {}
+
+impl Cardinality {
+    fn cardinality_estimate(query_graph: &QueryGraph, node_id: NodeId) -> Rc<CardinalityEstimate> {
+        let mut visitor = Cardinality {};
+        query_graph.visit_subgraph(&mut visitor, node_id);
+        visitor.cardinality_estimate_unchecked(query_graph, node_id)
+    }
+
+    fn cardinality_estimate_unchecked(
+        &self,
+        query_graph: &QueryGraph,
+        node_id: NodeId,
+    ) -> Rc<CardinalityEstimate> {
+        query_graph
+            .property_cache
+            .borrow_mut()
+            .node_bottom_up_properties(node_id)
+            .get(&Self::metadata_type_id())
+            .unwrap()
+            .downcast_ref::<Rc<CardinalityEstimate>>()
+            .unwrap()
+            .clone()
+    }
+
+    fn metadata_type_id() -> TypeId {
+        TypeId::of::<Self>()
+    }
+
+    fn compute_cardinality_estimate_for_node(
+        &self,
+        query_graph: &QueryGraph,
+        node_id: NodeId,
+    ) -> Rc<CardinalityEstimate> {
+        let estimate = match query_graph.node(node_id) {
+            QueryNode::QueryRoot { input } => {
+                if let Some(input) = input {
+                    return self.cardinality_estimate_unchecked(query_graph, *input);
+                }
+                CardinalityEstimate {
+                    row_count: 0.0,
+                    columns: Vec::new(),
+                }
+            }
+            QueryNode::SubqueryRoot { input } | QueryNode::Sort { input, .. } => {
+                return self.cardinality_estimate_unchecked(query_graph, *input)
+            }
+            QueryNode::Limit {
+                offset,
+                limit,
+                input,
+            } => Self::limit_estimate(
+                &self.cardinality_estimate_unchecked(query_graph, *input),
+                1.0,
+                *offset,
+                *limit,
+            ),
+            QueryNode::TopN {
+                partition_key,
+                offset,
+                limit,
+                input,
+                ..
+            } => {
+                let input = self.cardinality_estimate_unchecked(query_graph, *input);
+                let partitions = if partition_key.is_empty() {
+                    1.0
+                } else {
+                    Self::group_count(&input, partition_key.iter().cloned())
+                };
+                Self::limit_estimate(&input, partitions, *offset, Some(*limit))
+            }
+            QueryNode::Intersect {
+                distinct,
+                left,
+                right,
+            } => {
+                let left = self.cardinality_estimate_unchecked(query_graph, *left);
+                let right = self.cardinality_estimate_unchecked(query_graph, *right);
+                let mut row_count = left.row_count.min(right.row_count);
+                if *distinct {
+                    row_count = row_count.min(Self::group_count(&left, 0..left.columns.len()));
+                }
+                Self::subset_estimate(&left, row_count)
+            }
+            QueryNode::Except { distinct, left, .. } => {
+                let left = self.cardinality_estimate_unchecked(query_graph, *left);
+                let row_count = if *distinct {
+                    Self::group_count(&left, 0..left.columns.len())
+                } else {
+                    left.row_count
+                };
+                Self::subset_estimate(&left, row_count)
+            }
+            QueryNode::RecursiveUnion {
+                distinct,
+                max_iterations,
+                base,
+                recursive,
+                ..
+            } => {
+                let base = self.cardinality_estimate_unchecked(query_graph, *base);
+                let recursive = self.cardinality_estimate_unchecked(query_graph, *recursive);
+                let iterations = max_iterations
+                    .map(|max_iterations| max_iterations as f64)
+                    .unwrap_or(DEFAULT_RECURSIVE_ITERATIONS);
+                let inputs = [
+                    base.clone(),
+                    Rc::new(Self::subset_estimate(
+                        &recursive,
+                        recursive.row_count * iterations,
+                    )),
+                ];
+                let row_count = inputs.iter().map(|input| input.row_count).sum::<f64>();
+                let estimate = CardinalityEstimate {
+                    row_count,
+                    columns: (0..base.columns.len())
+                        .map(|i| Self::union_column_estimate(&inputs, i, row_count))
+                        .collect(),
+                };
+                if *distinct {
+                    let row_count = Self::group_count(&estimate, 0..estimate.columns.len());
+                    Self::subset_estimate(&estimate, row_count)
+                } else {
+                    estimate
+                }
+            }
+            // The rows of the previous iteration, whose size is unknown.
+            QueryNode::RecursiveReference { row_type, .. } => CardinalityEstimate {
+                row_count: DEFAULT_TABLE_ROW_COUNT,
+                columns: vec![ColumnEstimate::default(); row_type.len()],
+            },
+            QueryNode::Window {
+                functions, input, ..
+            } => {
+                let input = self.cardinality_estimate_unchecked(query_graph, *input);
+                CardinalityEstimate {
+                    row_count: input.row_count,
+                    columns: input
+                        .columns
+                        .iter()
+                        .cloned()
+                        .chain(functions.iter().map(|_| ColumnEstimate::default()))
+                        .collect(),
+                }
+            }
+            QueryNode::TableScan { table_id, row_type } => match query_graph
+                .table_metadata(*table_id)
+                .and_then(|table| table.statistics.as_ref())
+            {
+                Some(statistics) => CardinalityEstimate {
+                    row_count: statistics.row_count as f64,
+                    columns: statistics
+                        .column_statistics
+                        .iter()
+                        .map(|column| ColumnEstimate {
+                            distinct_values: Some(column.distinct_values as f64),
+                            null_fraction: column.null_fraction,
+                            min_value: column.min_value.clone(),
+                            max_value: column.max_value.clone(),
+                            most_common_values: column.most_common_values.clone(),
+                            histogram: column.histogram.clone(),
+                        })
+                        .collect(),
+                },
+                None => CardinalityEstimate {
+                    row_count: DEFAULT_TABLE_ROW_COUNT,
+                    columns: vec![ColumnEstimate::default(); row_type.len()],
+                },
+            },
+            QueryNode::Values { row_type, rows } => CardinalityEstimate {
+                row_count: rows.len() as f64,
+                columns: (0..row_type.len())
+                    .map(|column| Self::values_column_estimate(rows, column))
+                    .collect(),
+            },
+            QueryNode::Project { outputs, input } => {
+                let input = self.cardinality_estimate_unchecked(query_graph, *input);
+                CardinalityEstimate {
+                    row_count: input.row_count,
+                    columns: outputs
+                        .iter()
+                        .map(|output| match output.as_ref() {
+                            ScalarExpr::InputRef { index } => input.columns[*index].clone(),
+                            ScalarExpr::Literal(literal) => ColumnEstimate::literal(literal),
+                            _ => ColumnEstimate::default(),
+                        })
+                        .collect(),
+                }
+            }
+            QueryNode::Filter { conditions, input } => {
+                let input = self.cardinality_estimate_unchecked(query_graph, *input);
+                let selectivity = estimated_conjunction_selectivity(conditions, &input.columns);
+                let mut columns = input.columns.clone();
+                // Columns compared for equality with a literal only contain that value
+                for condition in conditions.iter() {
+                    if let ScalarExpr::BinaryOp {
+                        op: BinaryOp::Eq | BinaryOp::RawEq,
+                        left,
+                        right,
+                    } = condition.as_ref()
+                    {
+                        match (left.as_ref(), right.as_ref()) {
+                            (ScalarExpr::InputRef { index }, ScalarExpr::Literal(literal))
+                            | (ScalarExpr::Literal(literal), ScalarExpr::InputRef { index }) => {
+                                columns[*index] = ColumnEstimate::literal(literal);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                CardinalityEstimate {
+                    row_count: input.row_count * selectivity,
+                    columns,
+                }
+            }
+            QueryNode::Join {
+                join_type,
+                conditions,
+                left,
+                right,
+            } => {
+                let left = self.cardinality_estimate_unchecked(query_graph, *left);
+                let right = self.cardinality_estimate_unchecked(query_graph, *right);
+                let columns = left
+                    .columns
+                    .iter()
+                    .chain(right.columns.iter())
+                    .cloned()
+                    .collect_vec();
+                let inner_row_count = left.row_count
+                    * right.row_count
+                    * estimated_conjunction_selectivity(conditions, &columns);
+                match join_type {
+                    JoinType::Inner => CardinalityEstimate {
+                        row_count: inner_row_count,
+                        columns,
+                    },
+                    JoinType::LeftOuter => CardinalityEstimate {
+                        row_count: inner_row_count.max(left.row_count),
+                        columns,
+                    },
+                    JoinType::RightOuter => CardinalityEstimate {
+                        row_count: inner_row_count.max(right.row_count),
+                        columns,
+                    },
+                    JoinType::FullOuter => CardinalityEstimate {
+                        row_count: inner_row_count.max(left.row_count).max(right.row_count),
+                        columns,
+                    },
+                    JoinType::Semi | JoinType::Anti => CardinalityEstimate {
+                        row_count: left.row_count * DEFAULT_SELECTIVITY,
+                        columns: left.columns.clone(),
+                    },
+                }
+            }
+            QueryNode::Aggregate {
+                group_key,
+                aggregates,
+                input,
+            } => {
+                let input = self.cardinality_estimate_unchecked(query_graph, *input);
+                let row_count = if group_key.is_empty() {
+                    1.0
+                } else {
+                    Self::group_count(&input, group_key.iter().cloned())
+                };
+                CardinalityEstimate {
+                    row_count,
+                    columns: group_key
+                        .iter()
+                        .map(|column| input.columns[*column].without_distribution())
+                        .chain(aggregates.iter().map(|_| ColumnEstimate::default()))
+                        .collect(),
+                }
+            }
+            QueryNode::Union { inputs } => {
+                let inputs = inputs
+                    .iter()
+                    .map(|input| self.cardinality_estimate_unchecked(query_graph, *input))
+                    .collect_vec();
+                let row_count = inputs.iter().map(|input| input.row_count).sum::<f64>();
+                let num_columns = inputs.first().map_or(0, |input| input.columns.len());
+                CardinalityEstimate {
+                    row_count,
+                    columns: (0..num_columns)
+                        .map(|i| Self::union_column_estimate(&inputs, i, row_count))
+                        .collect(),
+                }
+            }
+            QueryNode::Apply {
+                left,
+                right,
+                apply_type,
+                ..
+            } => {
+                let left = self.cardinality_estimate_unchecked(query_graph, *left);
+                let right = self.cardinality_estimate_unchecked(query_graph, *right);
+                // The right hand side is assumed to produce the same number of rows for
+                // every row of the left hand side.
+                let row_count = match apply_type {
+                    ApplyType::Inner => left.row_count * right.row_count,
+                    ApplyType::LeftOuter => left.row_count * right.row_count.max(1.0),
+                };
+                CardinalityEstimate {
+                    row_count,
+                    columns: left
+                        .columns
+                        .iter()
+                        .chain(right.columns.iter())
+                        .cloned()
+                        .collect(),
+                }
+            }
+        };
+        Rc::new(Self::apply_keys(query_graph, node_id, estimate))
+    }
+
+    /// Estimate of a relation returning at most `limit` rows of the given input after
+    /// skipping the first `offset` ones.
+    /// Estimates the number of groups of rows with the same values for the given
+    /// non-empty key.
+    fn group_count(input: &CardinalityEstimate, key: impl Iterator<Item = usize>) -> f64 {
+        // Each distinct combination of values of the key, including NULL, leads to
+        // a group.
+        key.map(|column| {
+            let column = &input.columns[column];
+            column.distinct_values.map(|distinct_values| {
+                distinct_values + if column.null_fraction > 0.0 { 1.0 } else { 0.0 }
+            })
+        })
+        .product::<Option<f64>>()
+        .unwrap_or(input.row_count * DEFAULT_SELECTIVITY)
+        .min(input.row_count)
+    }
+
+    /// The offset and the limit are applied to each of the given number of groups.
+    fn limit_estimate(
+        input: &CardinalityEstimate,
+        groups: f64,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> CardinalityEstimate {
+        let row_count = (input.row_count - offset as f64 * groups).max(0.0);
+        let row_count = limit.map_or(row_count, |limit| row_count.min(limit as f64 * groups));
+        Self::subset_estimate(input, row_count)
+    }
+
+    /// The estimate of a relation returning the given number of rows out of the rows
+    /// of the given input.
+    /// The exact statistics of the given column of a relation made of rows of literals.
+    fn values_column_estimate(rows: &[Vec<Literal>], column: usize) -> ColumnEstimate {
+        let values = rows
+            .iter()
+            .map(|row| &row[column].value)
+            .filter(|value| !value.is_null())
+            .collect_vec();
+        ColumnEstimate {
+            distinct_values: Some(values.iter().unique().count() as f64),
+            null_fraction: if rows.is_empty() {
+                0.0
+            } else {
+                (rows.len() - values.len()) as f64 / rows.len() as f64
+            },
+            min_value: values
+                .iter()
+                .min_by(|a, b| compare_values(a, b))
+                .map(|value| (*value).clone()),
+            max_value: values
+                .iter()
+                .max_by(|a, b| compare_values(a, b))
+                .map(|value| (*value).clone()),
+            ..Default::default()
+        }
+    }
+
+    fn subset_estimate(input: &CardinalityEstimate, row_count: f64) -> CardinalityEstimate {
+        CardinalityEstimate {
+            row_count,
+            columns: input
+                .columns
+                .iter()
+                .map(|column| ColumnEstimate {
+                    distinct_values: column
+                        .distinct_values
+                        .map(|distinct_values| distinct_values.min(row_count)),
+                    ..column.clone()
+                })
+                .collect(),
+        }
+    }
+
+    /// Combines the estimates of the given column of the branches of a union.
+    fn union_column_estimate(
+        inputs: &[Rc<CardinalityEstimate>],
+        column: usize,
+        row_count: f64,
+    ) -> ColumnEstimate {
+        let columns = inputs
+            .iter()
+            .map(|input| &input.columns[column])
+            .collect_vec();
+        let null_fraction = if row_count > 0.0 {
+            inputs
+                .iter()
+                .zip(columns.iter())
+                .map(|(input, column)| input.row_count * column.null_fraction)
+                .sum::<f64>()
+                / row_count
+        } else {
+            0.0
+        };
+        let bound = |value: fn(&ColumnEstimate) -> &Option<Value>, ordering: Ordering| {
+            columns
+                .iter()
+                .map(|column| value(column).clone())
+                .collect::<Option<Vec<_>>>()?
+                .into_iter()
+                .reduce(|a, b| {
+                    if compare_values(&b, &a) == ordering {
+                        b
+                    } else {
+                        a
+                    }
+                })
+        };
+        ColumnEstimate {
+            distinct_values: columns.iter().map(|column| column.distinct_values).sum(),
+            null_fraction,
+            min_value: bound(|column| &column.min_value, Ordering::Less),
+            max_value: bound(|column| &column.max_value, Ordering::Greater),
+            ..Default::default()
+        }
+    }
+
+    /// Refines the given estimate with the keys of the given node: the row count must
+    /// be within the bounds of the empty key and the columns that form a unique key
+    /// contain a distinct value per row.
+    fn apply_keys(
+        query_graph: &QueryGraph,
+        node_id: NodeId,
+        mut estimate: CardinalityEstimate,
+    ) -> CardinalityEstimate {
+        let keys = keys(query_graph, node_id);
+        for key in keys.iter().filter(|key| key.key.is_empty()) {
+            estimate.row_count = estimate.row_count.max(key.lower_bound as f64);
+            if let Some(upper_bound) = key.upper_bound {
+                estimate.row_count = estimate.row_count.min(upper_bound as f64);
+            }
+        }
+        for key in keys.iter() {
+            if let (Some(1), [column]) = (key.upper_bound, &key.key[..]) {
+                if let ScalarExpr::InputRef { index } = column.as_ref() {
+                    let column = &mut estimate.columns[*index];
+                    column.distinct_values =
+                        Some(estimate.row_count * (1.0 - column.null_fraction));
+                }
+            }
+        }
+        let row_count = estimate.row_count;
+        for column in estimate.columns.iter_mut() {
+            column.distinct_values = column
+                .distinct_values
+                .map(|distinct_values| distinct_values.min(row_count));
+        }
+        estimate
+    }
+}
+
+impl QueryGraphPrePostVisitor for Cardinality {
+    fn visit_pre(&mut self, query_graph: &QueryGraph, node_id: NodeId) -> PreOrderVisitationResult {
+        if query_graph
+            .property_cache
+            .borrow_mut()
+            .node_bottom_up_properties(node_id)
+            .contains_key(&Self::metadata_type_id())
+        {
+            PreOrderVisitationResult::DoNotVisitInputs
+        } else {
+            PreOrderVisitationResult::VisitInputs
+        }
+    }
+
+    fn visit_post(&mut self, query_graph: &QueryGraph, node_id: NodeId) {
+        if !query_graph
+            .property_cache
+            .borrow_mut()
+            .node_bottom_up_properties(node_id)
+            .contains_key(&Self::metadata_type_id())
+        {
+            let estimate = self.compute_cardinality_estimate_for_node(query_graph, node_id);
+            query_graph
+                .property_cache
+                .borrow_mut()
+                .node_bottom_up_properties(node_id)
+                .insert(Self::metadata_type_id(), Box::new(estimate));
+        }
+    }
+}
@@ -4,7 +4,7 @@ use itertools::Itertools;
 
 use crate::{
     query_graph::{visitor::QueryGraphPrePostVisitor, *},
-    scalar_expr::{rewrite::lift_scalar_expr_2, ScalarExpr},
+    scalar_expr::{rewrite::rewrite_expr_pre, ScalarExpr},
     visitor_utils::PreOrderVisitationResult,
 };
 
@@ -112,23 +112,27 @@ impl ColumnProvenance {
             QueryNode::Project { input, outputs } => {
                 let input_prov = self.column_provenance_unchecked(query_graph, *input);
                 prov.extend(input_prov.iter().map(|prov_info| {
-                    let lifting_map = prov_info
-                        .column_expressions
-                        .iter()
-                        .enumerate()
-                        .filter_map(|(i, expr)| {
-                            if let Some(expr) = expr {
-                                Some((expr.clone(), i))
-                            } else {
-                                None
-                            }
-                        })
-                        .collect::<HashMap<_, _>>();
                     ColumnProvenanceInfo {
                         source_node: prov_info.source_node,
+                        // Replace the references to the input columns with their
+                        // expressions in terms of the source node
                         column_expressions: outputs
                             .iter()
-                            .map(|expr| lift_scalar_expr_2(expr, &lifting_map))
+                            .map(|expr| {
+                                rewrite_expr_pre(
+                                    &mut |expr: &ScalarExprRef| {
+                                        if let ScalarExpr::InputRef { index } = expr.as_ref() {
+                                            prov_info.column_expressions[*index]
+                                                .clone()
+                                                .map(Some)
+                                                .ok_or(())
+                                        } else {
+                                            Ok(None)
+                                        }
+                                    },
+                                    expr,
+                                )
+                            })
                             .collect_vec()
                             .into(),
                         filtered: prov_info.filtered,
@@ -154,8 +158,9 @@ impl ColumnProvenance {
                 prov.extend(input_prov.iter().map(|prov_info| {
                     ColumnProvenanceInfo {
                         source_node: prov_info.source_node,
-                        column_expressions: (0..group_key.len())
-                            .map(|i| prov_info.column_expressions[i].clone())
+                        column_expressions: group_key
+                            .iter()
+                            .map(|i| prov_info.column_expressions[*i].clone())
                             .chain(aggregates.iter().map(|_| None))
                             .collect_vec()
                             .into(),
@@ -270,3 +275,63 @@ impl QueryGraphPrePostVisitor for ColumnProvenance {
         }
     }
 }
+#[cfg(test)]
+mod tests {
+    use crate::{
+        query_graph::{QueryGraph, QueryNode},
+        scalar_expr::ScalarExpr,
+    };
+
+    use super::column_provenance;
+
+    #[test]
+    fn test_project_over_permutation() {
+        let mut query_graph = QueryGraph::new();
+        let table_scan = query_graph.table_scan(1, 3);
+        let permutation = query_graph.project(
+            table_scan,
+            vec![
+                ScalarExpr::input_ref(1).into(),
+                ScalarExpr::input_ref(2).into(),
+                ScalarExpr::input_ref(0).into(),
+            ],
+        );
+        let project = query_graph.project(permutation, vec![ScalarExpr::input_ref(0).into()]);
+        query_graph.set_entry_node(project);
+
+        let prov = column_provenance(&query_graph, project);
+        let table_scan_prov = prov
+            .iter()
+            .find(|prov_info| prov_info.source_node == table_scan)
+            .unwrap();
+        assert_eq!(
+            *table_scan_prov.column_expressions,
+            vec![Some(ScalarExpr::input_ref(1).into())]
+        );
+    }
+
+    #[test]
+    fn test_aggregate_group_key_columns() {
+        let mut query_graph = QueryGraph::new();
+        let table_scan = query_graph.table_scan(1, 3);
+        let aggregate = query_graph.add_node(QueryNode::Aggregate {
+            group_key: [1, 2].into(),
+            aggregates: Vec::new(),
+            input: table_scan,
+        });
+        query_graph.set_entry_node(aggregate);
+
+        let prov = column_provenance(&query_graph, aggregate);
+        let table_scan_prov = prov
+            .iter()
+            .find(|prov_info| prov_info.source_node == table_scan)
+            .unwrap();
+        assert_eq!(
+            *table_scan_prov.column_expressions,
+            vec![
+                Some(ScalarExpr::input_ref(1).into()),
+                Some(ScalarExpr::input_ref(2).into())
+            ]
+        );
+    }
+}
@@ -8,7 +8,7 @@ use itertools::Itertools;
 
 use crate::{
     query_graph::{visitor::QueryGraphPrePostVisitor, NodeId, QueryGraph, QueryNode},
-    scalar_expr::{visitor::visit_expr_pre, ScalarExpr},
+    scalar_expr::{visitor::visit_expr_pre, ScalarExpr, ScalarExprRef},
     visitor_utils::PreOrderVisitationResult,
 };
 
@@ -34,45 +34,7 @@ pub fn node_correlated_input_refs(
     let mut correlated_cols = HashMap::new();
     let query_node = query_graph.node(node_id);
     query_node.visit_scalar_expr(&mut |expr| {
-        visit_expr_pre(expr, &mut |curr_expr| {
-            match curr_expr.as_ref() {
-                ScalarExpr::CorrelatedInputRef {
-                    context_offset,
-                    index,
-                    ..
-                } => {
-                    correlated_cols
-                        .entry(*context_offset)
-                        .or_insert_with(|| BTreeSet::new())
-                        .insert(*index);
-                }
-                ScalarExpr::ExistsSubquery { subquery }
-                | ScalarExpr::ScalarSubquery { subquery }
-                | ScalarExpr::ScalarSubqueryCmp { subquery, .. } => {
-                    let subquery_correlated_input_refs =
-                        subgraph_correlated_input_refs(query_graph, subquery.root);
-                    if subquery.correlation.is_some() {
-                        let subquery_external_correlated_input_refs =
-                            subquery_correlated_input_refs
-                                .iter()
-                                .filter(|(offset, _)| **offset > 0)
-                                .map(|(offset, columns)| (offset - 1, columns.clone()))
-                                .collect::<HashMap<usize, BTreeSet<usize>>>();
-                        merge_correlated_maps(
-                            subquery_external_correlated_input_refs.iter(),
-                            &mut correlated_cols,
-                        );
-                    } else {
-                        merge_correlated_maps(
-                            subquery_correlated_input_refs.iter(),
-                            &mut correlated_cols,
-                        );
-                    }
-                }
-                _ => (),
-            }
-            PreOrderVisitationResult::VisitInputs
-        });
+        store_expr_correlated_input_refs(query_graph, expr, &mut correlated_cols);
     });
 
     // Store the property in the cache
@@ -85,6 +47,59 @@ pub fn node_correlated_input_refs(
     correlated_cols
 }
 
+/// Returns the correlated input refs the given expression contains, including the ones
+/// from the subqueries it contains that escape their context.
+pub fn expr_correlated_input_refs(
+    query_graph: &QueryGraph,
+    expr: &ScalarExprRef,
+) -> HashMap<usize, BTreeSet<usize>> {
+    let mut correlated_cols = HashMap::new();
+    store_expr_correlated_input_refs(query_graph, expr, &mut correlated_cols);
+    correlated_cols
+}
+
+fn store_expr_correlated_input_refs(
+    query_graph: &QueryGraph,
+    expr: &ScalarExprRef,
+    correlated_cols: &mut HashMap<usize, BTreeSet<usize>>,
+) {
+    visit_expr_pre(expr, &mut |curr_expr| {
+        match curr_expr.as_ref() {
+            ScalarExpr::CorrelatedInputRef {
+                context_offset,
+                index,
+                ..
+            } => {
+                correlated_cols
+                    .entry(*context_offset)
+                    .or_insert_with(|| BTreeSet::new())
+                    .insert(*index);
+            }
+            ScalarExpr::ExistsSubquery { subquery }
+            | ScalarExpr::ScalarSubquery { subquery }
+            | ScalarExpr::ScalarSubqueryCmp { subquery, .. } => {
+                let subquery_correlated_input_refs =
+                    subgraph_correlated_input_refs(query_graph, subquery.root);
+                if subquery.correlation.is_some() {
+                    let subquery_external_correlated_input_refs = subquery_correlated_input_refs
+                        .iter()
+                        .filter(|(offset, _)| **offset > 0)
+                        .map(|(offset, columns)| (offset - 1, columns.clone()))
+                        .collect::<HashMap<usize, BTreeSet<usize>>>();
+                    merge_correlated_maps(
+                        subquery_external_correlated_input_refs.iter(),
+                        correlated_cols,
+                    );
+                } else {
+                    merge_correlated_maps(subquery_correlated_input_refs.iter(), correlated_cols);
+                }
+            }
+            _ => (),
+        }
+        PreOrderVisitationResult::VisitInputs
+    });
+}
+
 /// Returns a set with the correlated input refs in the given subplan that escape
 /// the context of the subplan.
 pub fn subgraph_correlated_input_refs(
@@ -163,16 +178,21 @@ impl SubgraphCorrelatedInputRefs {
         for input in 0..query_node.num_inputs() {
             let input_correlated_cols = self
                 .subgraph_correlated_input_refs_unchecked(query_graph, query_node.get_input(input));
+            if let QueryNode::Apply { .. } = &query_node {
+                // ... but remove the ones in the correlation scope the apply defines for
+                // its right input.
+                if input == 1 {
+                    let outer_correlated_cols = input_correlated_cols
+                        .iter()
+                        .filter(|(offset, _)| **offset > 0)
+                        .map(|(offset, columns)| (offset - 1, columns.clone()))
+                        .collect::<HashMap<_, _>>();
+                    merge_correlated_maps(outer_correlated_cols.iter(), &mut correlated_cols);
+                    continue;
+                }
+            }
             merge_correlated_maps(input_correlated_cols.iter(), &mut correlated_cols);
         }
-        //... but remove ones in the correlation scope the node defines.
-        if let QueryNode::Apply { .. } = &query_node {
-            correlated_cols = correlated_cols
-                .into_iter()
-                .filter(|(offset, _)| *offset > 0)
-                .map(|(offset, columns)| (offset - 1, columns))
-                .collect();
-        }
         Rc::new(correlated_cols)
     }
 }
@@ -32,7 +32,9 @@ pub fn input_dependencies(query_graph: &QueryGraph, node_id: NodeId) -> Rc<HashS
         } => exprs
             .iter()
             .for_each(|e| store_input_dependencies(e, &mut dependencies)),
-        QueryNode::TableScan { .. } => {}
+        QueryNode::TableScan { .. }
+        | QueryNode::Values { .. }
+        | QueryNode::RecursiveReference { .. } => {}
         QueryNode::Aggregate {
             group_key,
             aggregates,
@@ -48,9 +50,17 @@ pub fn input_dependencies(query_graph: &QueryGraph, node_id: NodeId) -> Rc<HashS
         } => exprs
             .iter()
             .for_each(|e| store_input_dependencies(e, &mut dependencies)),
-        QueryNode::Union { .. } | QueryNode::SubqueryRoot { .. } | QueryNode::Apply { .. } => {
-            dependencies.extend(0..num_columns(query_graph, node_id))
-        }
+        QueryNode::Union { .. }
+        | QueryNode::Intersect { .. }
+        | QueryNode::Except { .. }
+        | QueryNode::RecursiveUnion { .. }
+        | QueryNode::SubqueryRoot { .. }
+        | QueryNode::Apply { .. }
+        | QueryNode::Sort { .. }
+        | QueryNode::Limit { .. }
+        | QueryNode::TopN { .. } => dependencies.extend(0..num_columns(query_graph, node_id)),
+        // All the input columns are passed through.
+        QueryNode::Window { input, .. } => dependencies.extend(0..num_columns(query_graph, *input)),
     }
     let dependencies = Rc::new(dependencies);
     query_graph
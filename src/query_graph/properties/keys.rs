@@ -10,7 +10,7 @@ use crate::{
     scalar_expr::{
         equivalence_class::{extract_equivalence_classes, find_class},
         rewrite::{lift_scalar_expr, normalize_scalar_expr, shift_right_input_refs},
-        ScalarExpr, ScalarExprRef,
+        ScalarExpr, ScalarExprRef, WindowFunction,
     },
     value::Value,
     visitor_utils::PreOrderVisitationResult,
@@ -131,6 +131,51 @@ impl Keys {
             .clone()
     }
 
+    /// Keys of a relation returning at most `limit` rows of the given input after
+    /// skipping the first `offset` ones.
+    fn limit_keys(
+        &self,
+        query_graph: &QueryGraph,
+        input: NodeId,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Vec<KeyBounds> {
+        let bounded = |bound: Option<usize>| match (bound, limit) {
+            (Some(bound), Some(limit)) => Some(min(bound, limit)),
+            (Some(bound), None) => Some(bound),
+            (None, limit) => limit,
+        };
+        let mut keys = self
+            .keys_unchecked(query_graph, input)
+            .iter()
+            .map(|key| {
+                if key.key.is_empty() {
+                    KeyBounds {
+                        key: key.key.clone(),
+                        lower_bound: bounded(Some(key.lower_bound.saturating_sub(offset))).unwrap(),
+                        upper_bound: bounded(key.upper_bound.map(|i| i.saturating_sub(offset))),
+                    }
+                } else {
+                    KeyBounds {
+                        key: key.key.clone(),
+                        lower_bound: 0,
+                        upper_bound: key.upper_bound,
+                    }
+                }
+            })
+            .collect_vec();
+        if let Some(limit) = limit {
+            if !keys.iter().any(|key| key.key.is_empty()) {
+                keys.push(KeyBounds {
+                    key: Default::default(),
+                    lower_bound: 0,
+                    upper_bound: Some(limit),
+                });
+            }
+        }
+        keys
+    }
+
     /// Used to tag the metadata in `QueryGraph::metadata_cache`
     fn metadata_type_id() -> TypeId {
         TypeId::of::<Self>()
@@ -195,9 +240,32 @@ impl Keys {
                     }));
                 }
             }
-            QueryNode::TableScan { .. } => {
-                // Here we could add the unique keys of the table if we had such information
-                // in some system catalog.
+            QueryNode::TableScan { table_id, .. } => {
+                if let Some(table) = query_graph.table_metadata(*table_id) {
+                    // Unique keys containing nullable columns are skipped, since several
+                    // rows may contain NULL values in them.
+                    keys.extend(
+                        table
+                            .keys()
+                            .filter(|key| key.iter().all(|col| !table.columns[*col].nullable))
+                            .map(|key| KeyBounds {
+                                key: key
+                                    .iter()
+                                    .sorted()
+                                    .map(|col| ScalarExpr::input_ref(*col).into())
+                                    .collect_vec()
+                                    .into(),
+                                lower_bound: 0,
+                                upper_bound: Some(1),
+                            }),
+                    );
+                    let (lower_bound, upper_bound) = table.row_count_bounds;
+                    keys.push(KeyBounds {
+                        key: Default::default(),
+                        lower_bound,
+                        upper_bound,
+                    });
+                }
             }
             QueryNode::Join {
                 join_type,
@@ -366,8 +434,176 @@ impl Keys {
                     }
                 }
             }
-            QueryNode::SubqueryRoot { input } => {
+            QueryNode::Intersect {
+                distinct,
+                left,
+                right,
+            } => {
+                // The rows returned are a subset of the rows of both inputs, so the
+                // tightest upper bound of either input applies.
+                for key in self
+                    .keys_unchecked(query_graph, *left)
+                    .iter()
+                    .chain(self.keys_unchecked(query_graph, *right).iter())
+                {
+                    match keys.iter_mut().find(|k: &&mut KeyBounds| k.key == key.key) {
+                        Some(existing) => {
+                            existing.upper_bound = match (existing.upper_bound, key.upper_bound) {
+                                (Some(a), Some(b)) => Some(min(a, b)),
+                                (a, b) => a.or(b),
+                            }
+                        }
+                        None => keys.push(KeyBounds {
+                            key: key.key.clone(),
+                            lower_bound: 0,
+                            upper_bound: key.upper_bound,
+                        }),
+                    }
+                }
+                if *distinct {
+                    keys.push(KeyBounds {
+                        key: column_key(0..num_columns(query_graph, node_id)),
+                        lower_bound: 0,
+                        upper_bound: Some(1),
+                    });
+                }
+            }
+            QueryNode::Except { distinct, left, .. } => {
+                // The rows returned are a subset of the rows of the left input.
+                keys.extend(
+                    self.keys_unchecked(query_graph, *left)
+                        .iter()
+                        .map(|key| KeyBounds {
+                            key: key.key.clone(),
+                            lower_bound: 0,
+                            upper_bound: key.upper_bound,
+                        }),
+                );
+                if *distinct {
+                    keys.push(KeyBounds {
+                        key: column_key(0..num_columns(query_graph, node_id)),
+                        lower_bound: 0,
+                        upper_bound: Some(1),
+                    });
+                }
+            }
+            QueryNode::RecursiveUnion { distinct, base, .. } => {
+                // The rows of the base input are always returned, but the number of
+                // rows returned by the recursive iterations is unknown.
+                if let Some(base_bounds) = self
+                    .keys_unchecked(query_graph, *base)
+                    .iter()
+                    .find(|key| key.key.is_empty())
+                {
+                    keys.push(KeyBounds {
+                        key: Rc::new(Vec::new()),
+                        lower_bound: if *distinct {
+                            min(base_bounds.lower_bound, 1)
+                        } else {
+                            base_bounds.lower_bound
+                        },
+                        upper_bound: None,
+                    });
+                }
+                if *distinct {
+                    keys.push(KeyBounds {
+                        key: column_key(0..num_columns(query_graph, node_id)),
+                        lower_bound: 0,
+                        upper_bound: Some(1),
+                    });
+                }
+            }
+            QueryNode::RecursiveReference { .. } => {}
+            QueryNode::Values { row_type, rows } => {
+                keys.push(KeyBounds {
+                    key: Default::default(),
+                    lower_bound: rows.len(),
+                    upper_bound: Some(rows.len()),
+                });
+                // Relations with a single row are already known to be unique.
+                if rows.len() > 1 {
+                    keys.extend(
+                        (0..row_type.len())
+                            .filter(|column| rows.iter().map(|row| &row[*column]).all_unique())
+                            .map(|column| KeyBounds {
+                                key: column_key(std::iter::once(column)),
+                                lower_bound: 0,
+                                upper_bound: Some(1),
+                            }),
+                    );
+                    if keys.len() == 1 && rows.iter().all_unique() {
+                        keys.push(KeyBounds {
+                            key: column_key(0..row_type.len()),
+                            lower_bound: 0,
+                            upper_bound: Some(1),
+                        });
+                    }
+                }
+            }
+            QueryNode::SubqueryRoot { input } | QueryNode::Sort { input, .. } => {
+                keys.extend(self.keys_unchecked(query_graph, *input).iter().cloned());
+            }
+            QueryNode::Limit {
+                offset,
+                limit,
+                input,
+            } => keys.extend(self.limit_keys(query_graph, *input, *offset, *limit)),
+            QueryNode::TopN {
+                partition_key,
+                offset,
+                limit,
+                input,
+                ..
+            } => {
+                if partition_key.is_empty() || *limit == 0 {
+                    keys.extend(self.limit_keys(query_graph, *input, *offset, Some(*limit)))
+                } else {
+                    // At most `limit` rows are returned per partition.
+                    let partition_key = column_key(partition_key.iter().cloned());
+                    keys.extend(self.keys_unchecked(query_graph, *input).iter().map(|key| {
+                        KeyBounds {
+                            key: key.key.clone(),
+                            lower_bound: 0,
+                            upper_bound: if key.key == partition_key {
+                                key.upper_bound.map(|bound| min(bound, *limit))
+                            } else {
+                                key.upper_bound
+                            },
+                        }
+                    }));
+                    if !keys.iter().any(|key| key.key == partition_key) {
+                        keys.push(KeyBounds {
+                            key: partition_key,
+                            lower_bound: 0,
+                            upper_bound: Some(*limit),
+                        });
+                    }
+                }
+            }
+            QueryNode::Window {
+                partition_key,
+                functions,
+                input,
+                ..
+            } => {
                 keys.extend(self.keys_unchecked(query_graph, *input).iter().cloned());
+                // The row number is unique within each partition.
+                let input_num_columns = num_columns(query_graph, *input);
+                keys.extend(
+                    functions
+                        .iter()
+                        .positions(|f| f.function == WindowFunction::RowNumber)
+                        .map(|i| KeyBounds {
+                            key: column_key(
+                                partition_key
+                                    .iter()
+                                    .cloned()
+                                    .chain(std::iter::once(input_num_columns + i)),
+                            ),
+                            lower_bound: 0,
+                            upper_bound: Some(1),
+                        }),
+                );
             }
             QueryNode::Apply {
                 left,
@@ -487,3 +723,13 @@ fn has_false_or_null_predicate(conditions: &Vec<ScalarExprRef>) -> bool {
         _ => false,
     })
 }
+
+/// Builds a key from the given columns, sorted as the rest of the keys.
+fn column_key(columns: impl Iterator<Item = usize>) -> Rc<Vec<ScalarExprRef>> {
+    columns
+        .sorted()
+        .dedup()
+        .map(|col| ScalarExpr::input_ref(col).into())
+        .collect_vec()
+        .into()
+}
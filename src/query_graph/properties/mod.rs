@@ -10,18 +10,29 @@ use std::{
 
 use crate::query_graph::NodeId;
 
+mod cardinality;
 mod column_provenance;
 mod correlated_input_refs;
 mod equivalence_classes;
 mod input_dependencies;
 mod keys;
+mod nullability;
 mod num_columns;
+mod ordering;
 mod pulled_up_predicates;
 mod row_type;
+mod selectivity;
 mod subqueries;
 
+pub use cardinality::cardinality_estimate;
+pub use cardinality::estimated_cardinality;
+pub use cardinality::estimated_cardinality_annotator;
+pub use cardinality::estimated_distinct_rows;
+pub use cardinality::CardinalityEstimate;
+pub use cardinality::ColumnEstimate;
 pub use column_provenance::column_provenance;
 pub use column_provenance::ColumnProvenanceInfo;
+pub use correlated_input_refs::expr_correlated_input_refs;
 pub use correlated_input_refs::node_correlated_input_refs;
 pub use correlated_input_refs::subgraph_correlated_input_refs;
 pub use correlated_input_refs::subgraph_correlated_input_refs_annotator;
@@ -32,13 +43,22 @@ pub use keys::is_empty_relation;
 pub use keys::keys;
 pub use keys::keys_annotator;
 pub use keys::unique_key;
+pub use nullability::is_null_if_input_is_null;
+pub use nullability::is_nullable_expr;
+pub use nullability::nullability;
+pub use nullability::nullability_annotator;
+pub use nullability::rejects_nulls_from_column;
 pub use num_columns::num_columns;
 pub use num_columns::num_columns_annotator;
+pub use ordering::ordering;
+pub use ordering::ordering_annotator;
+pub use ordering::partitioned_ordering;
 pub use pulled_up_predicates::pulled_up_predicates;
 pub use pulled_up_predicates::pulled_up_predicates_annotator;
 pub use row_type::cross_product_row_type;
 pub use row_type::row_type;
 pub use row_type::row_type_annotator;
+pub use selectivity::estimated_selectivity;
 pub use subqueries::subgraph_subqueries;
 pub use subqueries::subqueries;
 
@@ -49,8 +69,11 @@ pub fn default_annotators() -> Vec<&'static dyn Fn(&QueryGraph, NodeId) -> Optio
     vec![
         &num_columns_annotator,
         &row_type_annotator,
+        &nullability_annotator,
         &pulled_up_predicates_annotator,
         &keys_annotator,
+        &ordering_annotator,
+        &estimated_cardinality_annotator,
         &subgraph_correlated_input_refs_annotator,
     ]
 }
@@ -0,0 +1,388 @@
+use std::{any::TypeId, rc::Rc};
+
+use itertools::Itertools;
+
+use crate::{
+    query_graph::{visitor::QueryGraphPrePostVisitor, *},
+    scalar_expr::{
+        visitor::collect_input_dependencies, AggregateOp, NaryOp, ScalarExpr, UnaryOp,
+        WindowFunction,
+    },
+    value::Value,
+    visitor_utils::PreOrderVisitationResult,
+};
+
+use super::row_type;
+
+/// Returns, for each column projected by the given node, whether it may contain
+/// NULL values.
+///
+/// Columns from table scans are nullable unless the catalog says otherwise. Filters
+/// and inner joins make non-nullable the columns their conditions reject nulls from,
+/// while outer joins make nullable all the columns from their non-preserving side.
+pub fn nullability(query_graph: &QueryGraph, node_id: NodeId) -> Rc<Vec<bool>> {
+    Nullability::nullability(query_graph, node_id)
+}
+
+/// Helper function to include nullability information when explaining the plan.
+/// Only the non-nullable columns are listed.
+pub fn nullability_annotator(query_graph: &QueryGraph, node_id: NodeId) -> Option<String> {
+    let nullability = nullability(query_graph, node_id);
+    let non_nullable_columns = nullability
+        .iter()
+        .enumerate()
+        .filter(|(_, nullable)| !**nullable)
+        .map(|(i, _)| format!("{}", ScalarExpr::input_ref(i)))
+        .collect::<Vec<_>>();
+    if !non_nullable_columns.is_empty() {
+        Some(format!(
+            "Non-Nullable Columns: {}",
+            non_nullable_columns.join(", ")
+        ))
+    } else {
+        None
+    }
+}
+
+/// Returns whether the given expression may evaluate to NULL given the nullability of
+/// the columns of its input.
+pub fn is_nullable_expr(expr: &ScalarExprRef, input_nullability: &[bool]) -> bool {
+    match expr.as_ref() {
+        ScalarExpr::Literal(_) => expr.is_null(),
+        ScalarExpr::InputRef { index } => input_nullability[*index],
+        ScalarExpr::UnaryOp { op, operand } => {
+            op.propagates_null() && is_nullable_expr(operand, input_nullability)
+        }
+        ScalarExpr::BinaryOp { op, left, right } => {
+            op.propagates_null()
+                && (is_nullable_expr(left, input_nullability)
+                    || is_nullable_expr(right, input_nullability))
+        }
+        ScalarExpr::NaryOp { operands, .. } => operands
+            .iter()
+            .any(|operand| is_nullable_expr(operand, input_nullability)),
+        ScalarExpr::ExistsSubquery { .. } => false,
+        ScalarExpr::ScalarSubquery { .. }
+        | ScalarExpr::ScalarSubqueryCmp { .. }
+        | ScalarExpr::CorrelatedInputRef { .. } => true,
+    }
+}
+
+/// Returns whether the given condition never evaluates to TRUE when the given column
+/// is NULL.
+pub fn rejects_nulls_from_column(condition: &ScalarExprRef, column: usize) -> bool {
+    match condition.as_ref() {
+        ScalarExpr::NaryOp {
+            op: NaryOp::And,
+            operands,
+        } => operands
+            .iter()
+            .any(|operand| rejects_nulls_from_column(operand, column)),
+        ScalarExpr::NaryOp {
+            op: NaryOp::Or,
+            operands,
+        } => operands
+            .iter()
+            .all(|operand| rejects_nulls_from_column(operand, column)),
+        ScalarExpr::UnaryOp {
+            op: UnaryOp::IsTrue,
+            operand,
+        } => rejects_nulls_from_column(operand, column),
+        ScalarExpr::UnaryOp {
+            op: UnaryOp::IsNotNull,
+            operand,
+        } => is_null_if_column_is_null(operand, column),
+        _ => {
+            *condition.as_ref() == ScalarExpr::false_literal()
+                || is_null_if_column_is_null(condition, column)
+        }
+    }
+}
+
+/// Returns whether the given expression is guaranteed to evaluate to NULL when all the
+/// columns of its input are NULL, as in the rows padded with NULLs by outer joins.
+pub fn is_null_if_input_is_null(expr: &ScalarExprRef) -> bool {
+    expr.is_null()
+        || collect_input_dependencies(expr)
+            .into_iter()
+            .any(|column| is_null_if_column_is_null(expr, column))
+}
+
+/// Returns whether the given expression is guaranteed to evaluate to NULL when the
+/// given column is NULL.
+fn is_null_if_column_is_null(expr: &ScalarExprRef, column: usize) -> bool {
+    match expr.as_ref() {
+        ScalarExpr::Literal(_) => expr.is_null(),
+        ScalarExpr::InputRef { index } => *index == column,
+        ScalarExpr::UnaryOp { op, operand } => {
+            op.propagates_null() && is_null_if_column_is_null(operand, column)
+        }
+        ScalarExpr::BinaryOp { op, left, right } => {
+            op.propagates_null()
+                && (is_null_if_column_is_null(left, column)
+                    || is_null_if_column_is_null(right, column))
+        }
+        ScalarExpr::NaryOp {
+            op: NaryOp::Concat,
+            operands,
+        } => operands
+            .iter()
+            .any(|operand| is_null_if_column_is_null(operand, column)),
+        _ => false,
+    }
+}
+
+/// Marks as non-nullable the columns the given conditions reject nulls from.
+fn apply_null_rejecting_conditions(nullability: &mut [bool], conditions: &[ScalarExprRef]) {
+    for condition in conditions.iter() {
+        for column in collect_input_dependencies(condition) {
+            if nullability[column] && rejects_nulls_from_column(condition, column) {
+                nullability[column] = false;
+            }
+        }
+    }
+}
+
+struct Nullability {}
+
+impl Nullability {
+    fn nullability(query_graph: &QueryGraph, node_id: NodeId) -> Rc<Vec<bool>> {
+        let mut visitor = Nullability {};
+        query_graph.visit_subgraph(&mut visitor, node_id);
+        visitor.nullability_unchecked(query_graph, node_id)
+    }
+
+    fn nullability_unchecked(&self, query_graph: &QueryGraph, node_id: NodeId) -> Rc<Vec<bool>> {
+        query_graph
+            .property_cache
+            .borrow_mut()
+            .node_bottom_up_properties(node_id)
+            .get(&Self::metadata_type_id())
+            .unwrap()
+            .downcast_ref::<Rc<Vec<bool>>>()
+            .unwrap()
+            .clone()
+    }
+
+    fn metadata_type_id() -> TypeId {
+        TypeId::of::<Self>()
+    }
+
+    fn compute_nullability_for_node(
+        &self,
+        query_graph: &QueryGraph,
+        node_id: NodeId,
+    ) -> Rc<Vec<bool>> {
+        match query_graph.node(node_id) {
+            QueryNode::QueryRoot { input } => {
+                if let Some(input) = input {
+                    self.nullability_unchecked(query_graph, *input)
+                } else {
+                    Rc::new(Vec::new())
+                }
+            }
+            QueryNode::SubqueryRoot { input }
+            | QueryNode::Except { left: input, .. }
+            | QueryNode::Sort { input, .. }
+            | QueryNode::Limit { input, .. }
+            | QueryNode::TopN { input, .. } => self.nullability_unchecked(query_graph, *input),
+            QueryNode::TableScan { table_id, row_type } => {
+                if let Some(table) = query_graph.table_metadata(*table_id) {
+                    table
+                        .columns
+                        .iter()
+                        .map(|column| column.nullable)
+                        .collect_vec()
+                        .into()
+                } else {
+                    Rc::new(vec![true; row_type.len()])
+                }
+            }
+            QueryNode::Project { outputs, input } => {
+                let input_nullability = self.nullability_unchecked(query_graph, *input);
+                outputs
+                    .iter()
+                    .map(|e| is_nullable_expr(e, &input_nullability))
+                    .collect_vec()
+                    .into()
+            }
+            QueryNode::Filter { conditions, input } => {
+                let mut nullability = self.nullability_unchecked(query_graph, *input).to_vec();
+                apply_null_rejecting_conditions(&mut nullability, conditions);
+                nullability.into()
+            }
+            QueryNode::Join {
+                join_type,
+                conditions,
+                left,
+                right,
+            } => {
+                let left_nullability = self.nullability_unchecked(query_graph, *left);
+                let right_nullability = self.nullability_unchecked(query_graph, *right);
+                let mut nullability = left_nullability
+                    .iter()
+                    .chain(right_nullability.iter())
+                    .cloned()
+                    .collect_vec();
+                let left_num_columns = left_nullability.len();
+                match join_type {
+                    JoinType::Inner => {
+                        apply_null_rejecting_conditions(&mut nullability, conditions)
+                    }
+                    JoinType::LeftOuter => nullability[left_num_columns..].fill(true),
+                    JoinType::RightOuter => nullability[..left_num_columns].fill(true),
+                    JoinType::FullOuter => nullability.fill(true),
+                    JoinType::Semi => {
+                        // Only the left rows satisfying the join condition are returned
+                        apply_null_rejecting_conditions(&mut nullability, conditions);
+                        nullability.truncate(left_num_columns);
+                    }
+                    JoinType::Anti => nullability.truncate(left_num_columns),
+                }
+                nullability.into()
+            }
+            QueryNode::Aggregate {
+                group_key,
+                aggregates,
+                input,
+            } => {
+                let input_nullability = self.nullability_unchecked(query_graph, *input);
+                let input_row_type = row_type(query_graph, *input);
+                group_key
+                    .iter()
+                    .map(|col| input_nullability[*col])
+                    .chain(aggregates.iter().map(|aggregate| {
+                        // A global aggregation produces a row even if its input is empty
+                        (group_key.is_empty()
+                            && aggregate.on_empty_group(&input_row_type).value == Value::Null)
+                            || match aggregate.op {
+                                AggregateOp::Count => false,
+                                AggregateOp::Min | AggregateOp::Max => aggregate
+                                    .operands
+                                    .iter()
+                                    .any(|operand| input_nullability[*operand]),
+                            }
+                    }))
+                    .collect_vec()
+                    .into()
+            }
+            QueryNode::Window {
+                frame,
+                functions,
+                input,
+                ..
+            } => {
+                let input_nullability = self.nullability_unchecked(query_graph, *input);
+                input_nullability
+                    .iter()
+                    .cloned()
+                    .chain(functions.iter().map(|function| {
+                        match function.function {
+                            WindowFunction::RowNumber
+                            | WindowFunction::Rank
+                            | WindowFunction::DenseRank
+                            | WindowFunction::Count => false,
+                            // The offset row may be outside of the partition
+                            WindowFunction::Lag { .. } | WindowFunction::Lead { .. } => true,
+                            WindowFunction::Sum | WindowFunction::Min | WindowFunction::Max => {
+                                !frame.includes_current_row()
+                                    || function
+                                        .operands
+                                        .iter()
+                                        .any(|operand| input_nullability[*operand])
+                            }
+                        }
+                    }))
+                    .collect_vec()
+                    .into()
+            }
+            QueryNode::Intersect { left, right, .. } => {
+                // A column can only be NULL if it can be NULL in both inputs.
+                let left_nullability = self.nullability_unchecked(query_graph, *left);
+                let right_nullability = self.nullability_unchecked(query_graph, *right);
+                left_nullability
+                    .iter()
+                    .zip(right_nullability.iter())
+                    .map(|(left, right)| *left && *right)
+                    .collect_vec()
+                    .into()
+            }
+            QueryNode::RecursiveUnion { base, .. } => {
+                // The rows of the recursive input are derived from the rows of the
+                // previous iterations, whose nullability is unknown here.
+                let num_columns = self.nullability_unchecked(query_graph, *base).len();
+                Rc::new(vec![true; num_columns])
+            }
+            QueryNode::RecursiveReference { row_type, .. } => Rc::new(vec![true; row_type.len()]),
+            QueryNode::Values { row_type, rows } => (0..row_type.len())
+                .map(|column| rows.iter().any(|row| row[column].is_null()))
+                .collect_vec()
+                .into(),
+            QueryNode::Union { inputs } => {
+                let mut nullability = Vec::new();
+                for input in inputs.iter() {
+                    let input_nullability = self.nullability_unchecked(query_graph, *input);
+                    if nullability.is_empty() {
+                        nullability = input_nullability.to_vec();
+                    } else {
+                        for (nullable, input_nullable) in
+                            nullability.iter_mut().zip(input_nullability.iter())
+                        {
+                            *nullable = *nullable || *input_nullable;
+                        }
+                    }
+                }
+                nullability.into()
+            }
+            QueryNode::Apply {
+                left,
+                right,
+                apply_type,
+                ..
+            } => {
+                let left_nullability = self.nullability_unchecked(query_graph, *left);
+                let right_nullability = self.nullability_unchecked(query_graph, *right);
+                left_nullability
+                    .iter()
+                    .cloned()
+                    .chain(right_nullability.iter().map(|nullable| match apply_type {
+                        ApplyType::Inner => *nullable,
+                        ApplyType::LeftOuter => true,
+                    }))
+                    .collect_vec()
+                    .into()
+            }
+        }
+    }
+}
+
+impl QueryGraphPrePostVisitor for Nullability {
+    fn visit_pre(&mut self, query_graph: &QueryGraph, node_id: NodeId) -> PreOrderVisitationResult {
+        if query_graph
+            .property_cache
+            .borrow_mut()
+            .node_bottom_up_properties(node_id)
+            .contains_key(&Self::metadata_type_id())
+        {
+            PreOrderVisitationResult::DoNotVisitInputs
+        } else {
+            PreOrderVisitationResult::VisitInputs
+        }
+    }
+
+    fn visit_post(&mut self, query_graph: &QueryGraph, node_id: NodeId) {
+        if !query_graph
+            .property_cache
+            .borrow_mut()
+            .node_bottom_up_properties(node_id)
+            .contains_key(&Self::metadata_type_id())
+        {
+            let nullability = self.compute_nullability_for_node(query_graph, node_id);
+            query_graph
+                .property_cache
+                .borrow_mut()
+                .node_bottom_up_properties(node_id)
+                .insert(Self::metadata_type_id(), Box::new(nullability));
+        }
+    }
+}
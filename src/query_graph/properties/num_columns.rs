@@ -54,10 +54,17 @@ impl NumColumns {
                 }
             }
             QueryNode::Project { outputs, .. } => outputs.len(),
-            QueryNode::Filter { input, .. } | QueryNode::SubqueryRoot { input } => {
-                self.num_columns_unchecked(query_graph, *input)
-            }
-            QueryNode::TableScan { row_type, .. } => row_type.len(),
+            QueryNode::Filter { input, .. }
+            | QueryNode::Intersect { left: input, .. }
+            | QueryNode::Except { left: input, .. }
+            | QueryNode::RecursiveUnion { base: input, .. }
+            | QueryNode::SubqueryRoot { input }
+            | QueryNode::Sort { input, .. }
+            | QueryNode::Limit { input, .. }
+            | QueryNode::TopN { input, .. } => self.num_columns_unchecked(query_graph, *input),
+            QueryNode::TableScan { row_type, .. }
+            | QueryNode::Values { row_type, .. }
+            | QueryNode::RecursiveReference { row_type, .. } => row_type.len(),
             QueryNode::Join {
                 join_type,
                 left,
@@ -81,6 +88,9 @@ impl NumColumns {
                 aggregates,
                 ..
             } => group_key.len() + aggregates.len(),
+            QueryNode::Window {
+                functions, input, ..
+            } => self.num_columns_unchecked(query_graph, *input) + functions.len(),
             QueryNode::Union { inputs } => {
                 if inputs.is_empty() {
                     0
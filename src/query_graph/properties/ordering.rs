@@ -0,0 +1,145 @@
+use std::{any::TypeId, rc::Rc};
+
+use itertools::Itertools;
+
+use crate::{
+    query_graph::{visitor::QueryGraphPrePostVisitor, *},
+    visitor_utils::PreOrderVisitationResult,
+};
+
+/// Returns the order of the rows guaranteed by the given node, as a list of sort keys.
+/// Empty if the node doesn't guarantee any order.
+pub fn ordering(query_graph: &QueryGraph, node_id: NodeId) -> Rc<Vec<SortKey>> {
+    Ordering::ordering(query_graph, node_id)
+}
+
+/// Helper function to include the ordering information when explaining the plan.
+pub fn ordering_annotator(query_graph: &QueryGraph, node_id: NodeId) -> Option<String> {
+    let ordering = ordering(query_graph, node_id);
+    if ordering.is_empty() {
+        None
+    } else {
+        Some(format!("Ordering: [{}]", ordering.iter().join(", ")))
+    }
+}
+
+/// The ordering of the rows of a node that sorts them by the given partition key
+/// and then by the given keys.
+pub fn partitioned_ordering(partition_key: &[usize], keys: &[SortKey]) -> Vec<SortKey> {
+    partition_key
+        .iter()
+        .map(|column| SortKey::asc(*column))
+        .chain(keys.iter().cloned())
+        .collect_vec()
+}
+
+struct Ordering {}
+
+impl Ordering {
+    fn ordering(query_graph: &QueryGraph, node_id: NodeId) -> Rc<Vec<SortKey>> {
+        let mut visitor = Ordering {};
+        query_graph.visit_subgraph(&mut visitor, node_id);
+        visitor.ordering_unchecked(query_graph, node_id)
+    }
+
+    fn ordering_unchecked(&self, query_graph: &QueryGraph, node_id: NodeId) -> Rc<Vec<SortKey>> {
+        query_graph
+            .property_cache
+            .borrow_mut()
+            .node_bottom_up_properties(node_id)
+            .get(&Self::metadata_type_id())
+            .unwrap()
+            .downcast_ref::<Rc<Vec<SortKey>>>()
+            .unwrap()
+            .clone()
+    }
+
+    fn metadata_type_id() -> TypeId {
+        TypeId::of::<Self>()
+    }
+
+    fn compute_ordering_for_node(
+        &self,
+        query_graph: &QueryGraph,
+        node_id: NodeId,
+    ) -> Rc<Vec<SortKey>> {
+        match query_graph.node(node_id) {
+            QueryNode::QueryRoot { input: Some(input) }
+            | QueryNode::SubqueryRoot { input }
+            | QueryNode::Filter { input, .. }
+            | QueryNode::Limit { input, .. } => self.ordering_unchecked(query_graph, *input),
+            QueryNode::Sort { keys, .. } => Rc::new(keys.clone()),
+            QueryNode::TopN {
+                partition_key,
+                keys,
+                ..
+            } => partitioned_ordering(partition_key, keys).into(),
+            QueryNode::Window {
+                partition_key,
+                order_key,
+                ..
+            } => partitioned_ordering(partition_key, order_key).into(),
+            QueryNode::Project { outputs, input } => {
+                // Only the leading keys whose columns are passed through by the
+                // projection are preserved.
+                self.ordering_unchecked(query_graph, *input)
+                    .iter()
+                    .map_while(|key| {
+                        outputs
+                            .iter()
+                            .position(|output| {
+                                matches!(output.as_ref(), ScalarExpr::InputRef { index } if *index == key.column)
+                            })
+                            .map(|column| SortKey {
+                                column,
+                                ascending: key.ascending,
+                            })
+                    })
+                    .collect_vec()
+                    .into()
+            }
+            QueryNode::QueryRoot { input: None }
+            | QueryNode::TableScan { .. }
+            | QueryNode::Join { .. }
+            | QueryNode::Aggregate { .. }
+            | QueryNode::Union { .. }
+            | QueryNode::Intersect { .. }
+            | QueryNode::Except { .. }
+            | QueryNode::RecursiveUnion { .. }
+            | QueryNode::RecursiveReference { .. }
+            | QueryNode::Values { .. }
+            | QueryNode::Apply { .. } => Default::default(),
+        }
+    }
+}
+
+impl QueryGraphPrePostVisitor for Ordering {
+    fn visit_pre(&mut self, query_graph: &QueryGraph, node_id: NodeId) -> PreOrderVisitationResult {
+        if query_graph
+            .property_cache
+            .borrow_mut()
+            .node_bottom_up_properties(node_id)
+            .contains_key(&Self::metadata_type_id())
+        {
+            PreOrderVisitationResult::DoNotVisitInputs
+        } else {
+            PreOrderVisitationResult::VisitInputs
+        }
+    }
+
+    fn visit_post(&mut self, query_graph: &QueryGraph, node_id: NodeId) {
+        if !query_graph
+            .property_cache
+            .borrow_mut()
+            .node_bottom_up_properties(node_id)
+            .contains_key(&Self::metadata_type_id())
+        {
+            let ordering = self.compute_ordering_for_node(query_graph, node_id);
+            query_graph
+                .property_cache
+                .borrow_mut()
+                .node_bottom_up_properties(node_id)
+                .insert(Self::metadata_type_id(), Box::new(ordering));
+        }
+    }
+}
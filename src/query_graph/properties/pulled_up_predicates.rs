@@ -4,11 +4,12 @@ use itertools::Itertools;
 
 use crate::{
     query_graph::{visitor::QueryGraphPrePostVisitor, *},
-    scalar_expr::{rewrite::*, BinaryOp, ScalarExpr},
+    scalar_expr::{rewrite::*, visitor::collect_input_dependencies, BinaryOp, ScalarExpr},
+    value::Literal,
     visitor_utils::PreOrderVisitationResult,
 };
 
-use super::num_columns;
+use super::{expr_correlated_input_refs, num_columns, selectivity::compare_values};
 
 /// Returns the predicates that are known to evaluate to true on top of the given node.
 ///
@@ -30,8 +31,9 @@ use super::num_columns;
 /// pulled_up_predicates(1) = [ref_0 > 10, ref_0 raw= ref_1, ref_3 raw= ref_0 + ref_2, ref_4 raw= 'hello']
 /// ```
 ///
-/// Node that when CHECK constraints are supported `pulled_up_predicates(3)` will include
-/// the CHECK constraints from table `t` if it had any.
+/// If table `t` had CHECK constraints in the catalog, `pulled_up_predicates(3)` would
+/// include them, but only the ones referencing non-nullable columns, since a CHECK
+/// constraint is also satisfied by the rows where it evaluates to NULL.
 ///
 /// Filter nodes append the predicates they enforced to the list of predicates that are
 /// known to be true from its input.
@@ -161,7 +163,21 @@ impl PulledUpPredicates {
                 );
                 predicates.extend(conditions.iter().cloned());
             }
-            QueryNode::TableScan { .. } => {}
+            QueryNode::TableScan { table_id, .. } => {
+                if let Some(table) = query_graph.table_metadata(*table_id) {
+                    predicates.extend(
+                        table
+                            .check_constraints
+                            .iter()
+                            .filter(|constraint| {
+                                collect_input_dependencies(constraint)
+                                    .iter()
+                                    .all(|col| !table.columns[*col].nullable)
+                            })
+                            .cloned(),
+                    );
+                }
+            }
             QueryNode::Join {
                 join_type,
                 conditions,
@@ -185,12 +201,11 @@ impl PulledUpPredicates {
                         .filter(|x| left_predicates_filter(x))
                         .cloned(),
                 );
+                // Semi and anti joins don't project the columns from their right input
                 let right_predicates_filter = match join_type {
-                    JoinType::Semi | JoinType::Anti | JoinType::RightOuter | JoinType::Inner => {
-                        always_true
-                    }
+                    JoinType::RightOuter | JoinType::Inner => always_true,
                     JoinType::LeftOuter => is_raw_column_equivalence,
-                    JoinType::FullOuter => always_false,
+                    JoinType::Semi | JoinType::Anti | JoinType::FullOuter => always_false,
                 };
                 predicates.extend(
                     self.predicates_unchecked(query_graph, *right)
@@ -251,7 +266,32 @@ impl PulledUpPredicates {
                         .into_iter(),
                 );
             }
-            QueryNode::SubqueryRoot { input } => predicates.extend(
+            QueryNode::Values { row_type, rows } => {
+                predicates.extend((0..row_type.len()).flat_map(|column| {
+                    values_column_predicates(column, rows.iter().map(|row| &row[column]))
+                }));
+            }
+            // The rows of the recursive iterations are unknown.
+            QueryNode::RecursiveUnion { .. } | QueryNode::RecursiveReference { .. } => {}
+            QueryNode::Intersect { left, right, .. } => {
+                // The rows returned are present in both inputs.
+                predicates.extend(
+                    self.predicates_unchecked(query_graph, *left)
+                        .iter()
+                        .cloned(),
+                );
+                predicates.extend(
+                    self.predicates_unchecked(query_graph, *right)
+                        .iter()
+                        .cloned(),
+                );
+            }
+            QueryNode::SubqueryRoot { input }
+            | QueryNode::Except { left: input, .. }
+            | QueryNode::Sort { input, .. }
+            | QueryNode::Limit { input, .. }
+            | QueryNode::TopN { input, .. }
+            | QueryNode::Window { input, .. } => predicates.extend(
                 self.predicates_unchecked(query_graph, *input)
                     .iter()
                     .cloned(),
@@ -272,10 +312,15 @@ impl PulledUpPredicates {
                     ApplyType::Inner => always_true,
                     ApplyType::LeftOuter => is_raw_column_equivalence,
                 };
+                // The predicates from the right side referencing the correlation context
+                // of the apply are not valid outside of it
                 predicates.extend(
                     self.predicates_unchecked(query_graph, *right)
                         .iter()
-                        .filter(|x| right_predicates_filter(x))
+                        .filter(|x| {
+                            right_predicates_filter(x)
+                                && expr_correlated_input_refs(query_graph, x).is_empty()
+                        })
                         .map(|x| shift_right_input_refs(x, left_size)),
                 );
             }
@@ -349,3 +394,133 @@ fn is_raw_column_equivalence(predicate: &ScalarExprRef) -> bool {
     }
     false
 }
+
+/// Returns the predicates that hold for all the given values of a column of a
+/// relation made of rows of literals: an equality if all of them are the same, or
+/// the range they belong to if none of them is NULL.
+fn values_column_predicates<'a>(
+    column: usize,
+    values: impl Iterator<Item = &'a Literal> + Clone,
+) -> Vec<ScalarExprRef> {
+    let column_ref = ScalarExpr::input_ref(column);
+    let mut distinct_values = values.clone().unique();
+    match (distinct_values.next(), distinct_values.next()) {
+        (None, _) => Vec::new(),
+        (Some(value), None) => vec![column_ref
+            .binary(BinaryOp::RawEq, ScalarExpr::Literal(value.clone()).into())
+            .into()],
+        _ if values.clone().any(|value| value.is_null()) => Vec::new(),
+        _ => {
+            let min = values
+                .clone()
+                .min_by(|a, b| compare_values(&a.value, &b.value));
+            let max = values.max_by(|a, b| compare_values(&a.value, &b.value));
+            vec![
+                column_ref
+                    .clone()
+                    .binary(
+                        BinaryOp::Ge,
+                        ScalarExpr::Literal(min.unwrap().clone()).into(),
+                    )
+                    .into(),
+                column_ref
+                    .binary(
+                        BinaryOp::Le,
+                        ScalarExpr::Literal(max.unwrap().clone()).into(),
+                    )
+                    .into(),
+            ]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        data_type::DataType,
+        query_graph::{
+            properties::{expr_correlated_input_refs, num_columns},
+            ApplyType, CorrelationContext, JoinType, QueryGraph, QueryNode,
+        },
+        scalar_expr::{visitor::collect_input_dependencies, BinaryOp, ScalarExpr},
+    };
+
+    use super::pulled_up_predicates;
+
+    #[test]
+    fn test_semi_and_anti_joins_only_reference_left_columns() {
+        for join_type in [JoinType::Semi, JoinType::Anti] {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 2);
+            let table_scan_2 = query_graph.table_scan(2, 1);
+            let filter = query_graph.filter(
+                table_scan_2,
+                vec![ScalarExpr::input_ref(0)
+                    .binary(
+                        BinaryOp::Eq,
+                        ScalarExpr::string_literal("hello".to_string()).into(),
+                    )
+                    .into()],
+            );
+            let join = query_graph.join(
+                join_type,
+                table_scan_1,
+                filter,
+                vec![ScalarExpr::input_ref(0)
+                    .binary(BinaryOp::Eq, ScalarExpr::input_ref(2).into())
+                    .into()],
+            );
+            query_graph.set_entry_node(join);
+
+            let join_num_columns = num_columns(&query_graph, join);
+            for predicate in pulled_up_predicates(&query_graph, join).iter() {
+                assert!(
+                    collect_input_dependencies(predicate)
+                        .iter()
+                        .all(|i| *i < join_num_columns),
+                    "{} references columns not projected by the join",
+                    predicate
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_apply_doesnt_pull_up_its_correlated_predicates() {
+        let mut query_graph = QueryGraph::new();
+        let table_scan_1 = query_graph.table_scan(1, 2);
+        let table_scan_2 = query_graph.table_scan(2, 1);
+        let filter = query_graph.filter(
+            table_scan_2,
+            vec![ScalarExpr::input_ref(0)
+                .binary(
+                    BinaryOp::Eq,
+                    ScalarExpr::CorrelatedInputRef {
+                        context_offset: 0,
+                        index: 0,
+                        data_type: DataType::String,
+                    }
+                    .into(),
+                )
+                .into()],
+        );
+        let apply = query_graph.add_node(QueryNode::Apply {
+            correlation: CorrelationContext {
+                parameters: vec![ScalarExpr::input_ref(0).into()],
+            },
+            left: table_scan_1,
+            right: filter,
+            apply_type: ApplyType::Inner,
+        });
+        query_graph.set_entry_node(apply);
+
+        // On top of the apply, ctx_0 would refer to an outer context
+        for predicate in pulled_up_predicates(&query_graph, apply).iter() {
+            assert!(
+                expr_correlated_input_refs(&query_graph, predicate).is_empty(),
+                "{} references the correlation context of the apply",
+                predicate
+            );
+        }
+    }
+}
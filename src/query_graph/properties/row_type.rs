@@ -94,10 +94,17 @@ impl RowType {
                     .collect_vec()
                     .into()
             }
-            QueryNode::Filter { input, .. } | QueryNode::SubqueryRoot { input } => {
-                self.row_type_unchecked(query_graph, *input)
-            }
-            QueryNode::TableScan { row_type, .. } => row_type.clone(),
+            QueryNode::Filter { input, .. }
+            | QueryNode::Intersect { left: input, .. }
+            | QueryNode::Except { left: input, .. }
+            | QueryNode::RecursiveUnion { base: input, .. }
+            | QueryNode::SubqueryRoot { input }
+            | QueryNode::Sort { input, .. }
+            | QueryNode::Limit { input, .. }
+            | QueryNode::TopN { input, .. } => self.row_type_unchecked(query_graph, *input),
+            QueryNode::TableScan { row_type, .. }
+            | QueryNode::Values { row_type, .. }
+            | QueryNode::RecursiveReference { row_type, .. } => row_type.clone(),
             QueryNode::Join {
                 join_type,
                 left,
@@ -129,6 +136,17 @@ impl RowType {
                     .collect_vec()
                     .into()
             }
+            QueryNode::Window {
+                functions, input, ..
+            } => {
+                let input_row_type = self.row_type_unchecked(query_graph, *input);
+                input_row_type
+                    .iter()
+                    .cloned()
+                    .chain(functions.iter().map(|f| f.data_type(&input_row_type)))
+                    .collect_vec()
+                    .into()
+            }
             QueryNode::Union { inputs } => {
                 if inputs.is_empty() {
                     Default::default()
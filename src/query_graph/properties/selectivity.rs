@@ -0,0 +1,616 @@
+use std::{cmp::Ordering, collections::BTreeMap, ops::Bound};
+
+use crate::{
+    catalog::Histogram,
+    scalar_expr::{BinaryOp, NaryOp, ScalarExpr, ScalarExprRef, UnaryOp},
+    value::Value,
+};
+
+use super::ColumnEstimate;
+
+/// Selectivity assumed for equality predicates over columns without statistics.
+const EQUALITY_SELECTIVITY: f64 = 0.1;
+
+/// Selectivity assumed for each side of a range predicate over columns without
+/// statistics.
+const RANGE_SELECTIVITY: f64 = 1.0 / 3.0;
+
+/// Selectivity assumed for any other predicate.
+pub(super) const DEFAULT_SELECTIVITY: f64 = 0.5;
+
+/// Estimated fraction of the rows of a relation with the given column statistics that
+/// satisfy the given predicate.
+///
+/// The comparisons of a column with literals within a conjunction or a disjunction are
+/// combined into the set of ranges of values of the column that satisfy all or any of
+/// them, which are then estimated together against the most common values and the
+/// histogram of the column. Any other predicates are assumed to be independent.
+pub fn estimated_selectivity(predicate: &ScalarExprRef, columns: &[ColumnEstimate]) -> f64 {
+    SelectivityEstimator { columns }.selectivity(predicate)
+}
+
+/// Estimated fraction of the rows of a relation with the given column statistics that
+/// satisfy all the given predicates.
+pub(super) fn estimated_conjunction_selectivity(
+    predicates: &[ScalarExprRef],
+    columns: &[ColumnEstimate],
+) -> f64 {
+    SelectivityEstimator { columns }.conjunction_selectivity(predicates)
+}
+
+/// A range of values of a column.
+#[derive(Clone, Debug)]
+struct Interval {
+    lower: Bound<Value>,
+    upper: Bound<Value>,
+}
+
+/// A sorted list of disjoint ranges of values of a column.
+type ColumnRanges = Vec<Interval>;
+
+struct SelectivityEstimator<'a> {
+    columns: &'a [ColumnEstimate],
+}
+
+impl<'a> SelectivityEstimator<'a> {
+    fn selectivity(&self, predicate: &ScalarExprRef) -> f64 {
+        if let Some((column, ranges)) = column_ranges(predicate) {
+            return self.ranges_selectivity(&self.columns[column], &ranges);
+        }
+        match predicate.as_ref() {
+            ScalarExpr::Literal(literal) => {
+                if literal.value == Value::Bool(true) {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            ScalarExpr::BinaryOp { op, left, right } => match (left.as_ref(), right.as_ref()) {
+                (ScalarExpr::InputRef { index }, ScalarExpr::Literal(literal))
+                | (ScalarExpr::Literal(literal), ScalarExpr::InputRef { index })
+                    if literal.is_null() =>
+                {
+                    // Only `raw_eq(column, NULL)` may evaluate to TRUE
+                    match op {
+                        BinaryOp::RawEq => self.columns[*index].null_fraction,
+                        _ => 0.0,
+                    }
+                }
+                (ScalarExpr::InputRef { index }, ScalarExpr::Literal(_))
+                | (ScalarExpr::Literal(_), ScalarExpr::InputRef { index })
+                    if matches!(op, BinaryOp::Ne) =>
+                {
+                    // NULL values satisfy neither the equality nor the inequality
+                    let equality = ScalarExpr::BinaryOp {
+                        op: BinaryOp::Eq,
+                        left: left.clone(),
+                        right: right.clone(),
+                    }
+                    .into();
+                    (1.0 - self.columns[*index].null_fraction - self.selectivity(&equality))
+                        .max(0.0)
+                }
+                (ScalarExpr::InputRef { index: left }, ScalarExpr::InputRef { index: right }) => {
+                    column_column_selectivity(op, &self.columns[*left], &self.columns[*right])
+                }
+                _ => default_comparison_selectivity(op),
+            },
+            ScalarExpr::UnaryOp { op, operand } => match (op, operand.as_ref()) {
+                (UnaryOp::IsNull, ScalarExpr::InputRef { index }) => {
+                    self.columns[*index].null_fraction
+                }
+                (UnaryOp::IsNotNull, ScalarExpr::InputRef { index }) => {
+                    1.0 - self.columns[*index].null_fraction
+                }
+                (UnaryOp::IsTrue, _) => self.selectivity(operand),
+                (UnaryOp::Not, _) => 1.0 - self.selectivity(operand),
+                _ => DEFAULT_SELECTIVITY,
+            },
+            ScalarExpr::NaryOp {
+                op: NaryOp::And,
+                operands,
+            } => self.conjunction_selectivity(operands),
+            ScalarExpr::NaryOp {
+                op: NaryOp::Or,
+                operands,
+            } => {
+                let (ranges, others) = self.group_by_column(operands, union_ranges);
+                1.0 - ranges
+                    .iter()
+                    .chain(others.iter())
+                    .map(|selectivity| 1.0 - selectivity)
+                    .product::<f64>()
+            }
+            _ => DEFAULT_SELECTIVITY,
+        }
+    }
+
+    fn conjunction_selectivity(&self, operands: &[ScalarExprRef]) -> f64 {
+        let (ranges, others) = self.group_by_column(operands, intersect_ranges);
+        ranges.iter().chain(others.iter()).product()
+    }
+
+    /// Combines the ranges of the operands restricting the same column with the given
+    /// function. Returns the selectivities of the combined ranges of each column and
+    /// the selectivities of the rest of operands.
+    fn group_by_column(
+        &self,
+        operands: &[ScalarExprRef],
+        combine: fn(&ColumnRanges, &ColumnRanges) -> ColumnRanges,
+    ) -> (Vec<f64>, Vec<f64>) {
+        let mut ranges_per_column: BTreeMap<usize, ColumnRanges> = BTreeMap::new();
+        let mut others = Vec::new();
+        for operand in operands.iter() {
+            match column_ranges(operand) {
+                Some((column, ranges)) => {
+                    let combined = match ranges_per_column.get(&column) {
+                        Some(existing) => combine(existing, &ranges),
+                        None => ranges,
+                    };
+                    ranges_per_column.insert(column, combined);
+                }
+                None => others.push(self.selectivity(operand)),
+            }
+        }
+        let ranges = ranges_per_column
+            .iter()
+            .map(|(column, ranges)| self.ranges_selectivity(&self.columns[*column], ranges))
+            .collect();
+        (ranges, others)
+    }
+
+    fn ranges_selectivity(&self, column: &ColumnEstimate, ranges: &ColumnRanges) -> f64 {
+        ranges
+            .iter()
+            .map(|interval| interval_selectivity(column, interval))
+            .sum::<f64>()
+            .min(1.0)
+    }
+}
+
+/// Returns the column restricted by the given predicate and the ranges of values of
+/// the column that satisfy it, if the predicate only contains comparisons of a column
+/// with non-null literals.
+fn column_ranges(predicate: &ScalarExprRef) -> Option<(usize, ColumnRanges)> {
+    match predicate.as_ref() {
+        ScalarExpr::BinaryOp { op, left, right } if op.is_comparison() => {
+            let (column, op, value) = match (left.as_ref(), right.as_ref()) {
+                (ScalarExpr::InputRef { index }, ScalarExpr::Literal(literal)) => {
+                    (*index, op.clone(), literal)
+                }
+                (ScalarExpr::Literal(literal), ScalarExpr::InputRef { index }) => {
+                    (*index, commuted(op), literal)
+                }
+                _ => return None,
+            };
+            if value.is_null() {
+                return None;
+            }
+            let value = value.value.clone();
+            let interval = match op {
+                BinaryOp::Eq | BinaryOp::RawEq => Interval {
+                    lower: Bound::Included(value.clone()),
+                    upper: Bound::Included(value),
+                },
+                BinaryOp::Lt => Interval {
+                    lower: Bound::Unbounded,
+                    upper: Bound::Excluded(value),
+                },
+                BinaryOp::Le => Interval {
+                    lower: Bound::Unbounded,
+                    upper: Bound::Included(value),
+                },
+                BinaryOp::Gt => Interval {
+                    lower: Bound::Excluded(value),
+                    upper: Bound::Unbounded,
+                },
+                BinaryOp::Ge => Interval {
+                    lower: Bound::Included(value),
+                    upper: Bound::Unbounded,
+                },
+                // Estimated as the complement of the equality instead
+                BinaryOp::Ne => return None,
+                BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => {
+                    unreachable!()
+                }
+            };
+            Some((column, vec![interval]))
+        }
+        ScalarExpr::NaryOp {
+            op: op @ (NaryOp::And | NaryOp::Or),
+            operands,
+        } => {
+            let combine: fn(&ColumnRanges, &ColumnRanges) -> ColumnRanges = if let NaryOp::And = op
+            {
+                intersect_ranges
+            } else {
+                union_ranges
+            };
+            let mut result: Option<(usize, ColumnRanges)> = None;
+            for operand in operands.iter() {
+                let (column, ranges) = column_ranges(operand)?;
+                result = match result {
+                    Some((existing_column, existing)) if existing_column == column => {
+                        Some((column, combine(&existing, &ranges)))
+                    }
+                    Some(_) => return None,
+                    None => Some((column, ranges)),
+                };
+            }
+            result
+        }
+        _ => None,
+    }
+}
+
+/// The comparison resulting from swapping the operands of the given one.
+fn commuted(op: &BinaryOp) -> BinaryOp {
+    match op {
+        BinaryOp::Lt => BinaryOp::Gt,
+        BinaryOp::Le => BinaryOp::Ge,
+        BinaryOp::Gt => BinaryOp::Lt,
+        BinaryOp::Ge => BinaryOp::Le,
+        BinaryOp::Eq | BinaryOp::RawEq | BinaryOp::Ne => op.clone(),
+        BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => {
+            unreachable!()
+        }
+    }
+}
+
+fn default_comparison_selectivity(op: &BinaryOp) -> f64 {
+    match op {
+        BinaryOp::Eq | BinaryOp::RawEq => EQUALITY_SELECTIVITY,
+        BinaryOp::Ne => 1.0 - EQUALITY_SELECTIVITY,
+        BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => RANGE_SELECTIVITY,
+        BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => {
+            DEFAULT_SELECTIVITY
+        }
+    }
+}
+
+/// Selectivity of `left op right`, where both operands are columns of the same relation.
+fn column_column_selectivity(op: &BinaryOp, left: &ColumnEstimate, right: &ColumnEstimate) -> f64 {
+    match op {
+        BinaryOp::Eq | BinaryOp::RawEq => {
+            // Every value of the column with fewer distinct values is assumed to match
+            // some value of the other one.
+            let distinct_values = match (left.distinct_values, right.distinct_values) {
+                (Some(left), Some(right)) => left.max(right),
+                (Some(distinct_values), None) | (None, Some(distinct_values)) => distinct_values,
+                (None, None) => return EQUALITY_SELECTIVITY,
+            };
+            (1.0 - left.null_fraction) * (1.0 - right.null_fraction) / distinct_values.max(1.0)
+        }
+        _ => default_comparison_selectivity(op),
+    }
+}
+
+/// Fraction of the rows of the column whose value is within the given interval. The
+/// most common values of the column are accounted for individually, while the rest of
+/// non-null values are assumed to be distributed according to the histogram of the
+/// column or uniformly between its minimum and its maximum values.
+fn interval_selectivity(column: &ColumnEstimate, interval: &Interval) -> f64 {
+    let most_common_fraction = column
+        .most_common_values
+        .iter()
+        .map(|(_, frequency)| frequency)
+        .sum::<f64>();
+    let remaining_fraction = (1.0 - column.null_fraction - most_common_fraction).max(0.0);
+    let most_common_in_interval = column
+        .most_common_values
+        .iter()
+        .filter(|(value, _)| interval.contains(value))
+        .map(|(_, frequency)| frequency)
+        .sum::<f64>();
+
+    if let (Bound::Included(lower), Bound::Included(upper)) = (&interval.lower, &interval.upper) {
+        if compare_values(lower, upper) == Ordering::Equal {
+            if column
+                .most_common_values
+                .iter()
+                .any(|(value, _)| compare_values(value, lower) == Ordering::Equal)
+            {
+                return most_common_in_interval;
+            }
+            let out_of_range = column
+                .min_value
+                .as_ref()
+                .is_some_and(|min| compare_values(lower, min) == Ordering::Less)
+                || column
+                    .max_value
+                    .as_ref()
+                    .is_some_and(|max| compare_values(lower, max) == Ordering::Greater);
+            return if out_of_range {
+                0.0
+            } else if let Some(distinct_values) = column.distinct_values {
+                let remaining_distinct_values =
+                    distinct_values - column.most_common_values.len() as f64;
+                remaining_fraction / remaining_distinct_values.max(1.0)
+            } else {
+                EQUALITY_SELECTIVITY
+            };
+        }
+    }
+
+    let histogram =
+        column
+            .histogram
+            .clone()
+            .or_else(|| match (&column.min_value, &column.max_value) {
+                (Some(min), Some(max)) => Some(Histogram::new(vec![min.clone(), max.clone()])),
+                _ => None,
+            });
+    let fraction = match histogram {
+        Some(histogram) => {
+            let below_upper = match &interval.upper {
+                Bound::Included(value) | Bound::Excluded(value) => {
+                    fraction_below(&histogram, value)
+                }
+                Bound::Unbounded => 1.0,
+            };
+            let below_lower = match &interval.lower {
+                Bound::Included(value) | Bound::Excluded(value) => {
+                    fraction_below(&histogram, value)
+                }
+                Bound::Unbounded => 0.0,
+            };
+            (below_upper - below_lower).max(0.0)
+        }
+        None => [&interval.lower, &interval.upper]
+            .iter()
+            .filter(|bound| !matches!(bound, Bound::Unbounded))
+            .map(|_| RANGE_SELECTIVITY)
+            .product(),
+    };
+    most_common_in_interval + remaining_fraction * fraction
+}
+
+/// Fraction of the values described by the given histogram that are lower than the
+/// given value. Numeric values are assumed to be uniformly distributed within each
+/// bucket, while half of the bucket is assumed to be lower than any other value
+/// within it.
+fn fraction_below(histogram: &Histogram, value: &Value) -> f64 {
+    let bounds = &histogram.bounds;
+    if compare_values(value, &bounds[0]) != Ordering::Greater {
+        return 0.0;
+    }
+    let Some(bucket) = bounds
+        .windows(2)
+        .position(|pair| compare_values(value, &pair[1]) == Ordering::Less)
+    else {
+        return 1.0;
+    };
+    let fraction_within_bucket = match (
+        numeric_value(&bounds[bucket]),
+        numeric_value(&bounds[bucket + 1]),
+        numeric_value(value),
+    ) {
+        (Some(lower), Some(upper), Some(value)) if upper > lower => {
+            (value - lower) / (upper - lower)
+        }
+        _ => 0.5,
+    };
+    (bucket as f64 + fraction_within_bucket) / histogram.num_buckets() as f64
+}
+
+impl Interval {
+    fn contains(&self, value: &Value) -> bool {
+        let above_lower = match &self.lower {
+            Bound::Included(lower) => compare_values(value, lower) != Ordering::Less,
+            Bound::Excluded(lower) => compare_values(value, lower) == Ordering::Greater,
+            Bound::Unbounded => true,
+        };
+        let below_upper = match &self.upper {
+            Bound::Included(upper) => compare_values(value, upper) != Ordering::Greater,
+            Bound::Excluded(upper) => compare_values(value, upper) == Ordering::Less,
+            Bound::Unbounded => true,
+        };
+        above_lower && below_upper
+    }
+
+    fn is_empty(&self) -> bool {
+        match (&self.lower, &self.upper) {
+            (Bound::Included(lower), Bound::Included(upper)) => {
+                compare_values(lower, upper) == Ordering::Greater
+            }
+            (Bound::Excluded(lower), Bound::Included(upper))
+            | (Bound::Included(lower), Bound::Excluded(upper))
+            | (Bound::Excluded(lower), Bound::Excluded(upper)) => {
+                compare_values(lower, upper) != Ordering::Less
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Compares two lower bounds: the unbounded one is the lowest one, and for the same
+/// value, the included bound is lower than the excluded one.
+fn compare_lower_bounds(a: &Bound<Value>, b: &Bound<Value>) -> Ordering {
+    match (a, b) {
+        (Bound::Unbounded, Bound::Unbounded) => Ordering::Equal,
+        (Bound::Unbounded, _) => Ordering::Less,
+        (_, Bound::Unbounded) => Ordering::Greater,
+        (Bound::Included(a), Bound::Excluded(b)) => compare_values(a, b).then(Ordering::Less),
+        (Bound::Excluded(a), Bound::Included(b)) => compare_values(a, b).then(Ordering::Greater),
+        (Bound::Included(a), Bound::Included(b)) | (Bound::Excluded(a), Bound::Excluded(b)) => {
+            compare_values(a, b)
+        }
+    }
+}
+
+/// Compares two upper bounds: the unbounded one is the greatest one, and for the same
+/// value, the excluded bound is lower than the included one.
+fn compare_upper_bounds(a: &Bound<Value>, b: &Bound<Value>) -> Ordering {
+    match (a, b) {
+        (Bound::Unbounded, Bound::Unbounded) => Ordering::Equal,
+        (Bound::Unbounded, _) => Ordering::Greater,
+        (_, Bound::Unbounded) => Ordering::Less,
+        (Bound::Included(a), Bound::Excluded(b)) => compare_values(a, b).then(Ordering::Greater),
+        (Bound::Excluded(a), Bound::Included(b)) => compare_values(a, b).then(Ordering::Less),
+        (Bound::Included(a), Bound::Included(b)) | (Bound::Excluded(a), Bound::Excluded(b)) => {
+            compare_values(a, b)
+        }
+    }
+}
+
+/// Whether an interval ending at the given upper bound overlaps or is adjacent to an
+/// interval starting at the given lower bound.
+fn is_connected(upper: &Bound<Value>, lower: &Bound<Value>) -> bool {
+    match (upper, lower) {
+        (Bound::Unbounded, _) | (_, Bound::Unbounded) => true,
+        (Bound::Excluded(upper), Bound::Excluded(lower)) => {
+            compare_values(upper, lower) == Ordering::Greater
+        }
+        (
+            Bound::Included(upper) | Bound::Excluded(upper),
+            Bound::Included(lower) | Bound::Excluded(lower),
+        ) => compare_values(upper, lower) != Ordering::Less,
+    }
+}
+
+/// Sorts the given intervals, merging the ones that overlap.
+fn normalize_ranges(mut ranges: Vec<Interval>) -> ColumnRanges {
+    ranges.sort_by(|a, b| compare_lower_bounds(&a.lower, &b.lower));
+    let mut result: ColumnRanges = Vec::new();
+    for interval in ranges.into_iter().filter(|interval| !interval.is_empty()) {
+        if let Some(last) = result.last_mut() {
+            if is_connected(&last.upper, &interval.lower) {
+                if compare_upper_bounds(&interval.upper, &last.upper) == Ordering::Greater {
+                    last.upper = interval.upper;
+                }
+                continue;
+            }
+        }
+        result.push(interval);
+    }
+    result
+}
+
+/// The ranges of values contained in both given sets of ranges.
+fn intersect_ranges(a: &ColumnRanges, b: &ColumnRanges) -> ColumnRanges {
+    let mut ranges = Vec::new();
+    for (a, b) in a.iter().flat_map(|a| b.iter().map(move |b| (a, b))) {
+        let lower = if compare_lower_bounds(&a.lower, &b.lower) == Ordering::Less {
+            b.lower.clone()
+        } else {
+            a.lower.clone()
+        };
+        let upper = if compare_upper_bounds(&a.upper, &b.upper) == Ordering::Greater {
+            b.upper.clone()
+        } else {
+            a.upper.clone()
+        };
+        ranges.push(Interval { lower, upper });
+    }
+    normalize_ranges(ranges)
+}
+
+/// The ranges of values contained in any of the given sets of ranges.
+fn union_ranges(a: &ColumnRanges, b: &ColumnRanges) -> ColumnRanges {
+    normalize_ranges(a.iter().chain(b.iter()).cloned().collect())
+}
+
+fn numeric_value(value: &Value) -> Option<f64> {
+    match value {
+        Value::Int(value) => Some(*value as f64),
+        Value::BigInt(value) => Some(*value as f64),
+        _ => None,
+    }
+}
+
+/// Compares two values of a column, taking into account that integer values of
+/// different sizes may be compared.
+pub(super) fn compare_values(left: &Value, right: &Value) -> Ordering {
+    match (numeric_value(left), numeric_value(right)) {
+        (Some(left), Some(right)) => left.partial_cmp(&right).unwrap_or(Ordering::Equal),
+        _ => left.cmp(right),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        catalog::Histogram,
+        data_type::DataType,
+        query_graph::properties::ColumnEstimate,
+        scalar_expr::{BinaryOp, NaryOp, ScalarExpr, ScalarExprRef},
+        value::{Literal, Value},
+    };
+
+    use super::estimated_selectivity;
+
+    #[test]
+    fn test_range_selectivity() {
+        let comparison = |op: BinaryOp, value: i32| -> ScalarExprRef {
+            ScalarExpr::input_ref(0)
+                .binary(
+                    op,
+                    ScalarExpr::Literal(Literal::new(Value::Int(value), DataType::Int)).into(),
+                )
+                .into()
+        };
+        // 10% of NULLs, 20% of 5s and the rest uniformly distributed in [0, 100]
+        let columns = vec![ColumnEstimate {
+            distinct_values: Some(101.0),
+            null_fraction: 0.1,
+            min_value: Some(Value::Int(0)),
+            max_value: Some(Value::Int(100)),
+            most_common_values: vec![(Value::Int(5), 0.2)],
+            histogram: Some(Histogram::new(vec![
+                Value::Int(0),
+                Value::Int(50),
+                Value::Int(100),
+            ])),
+        }];
+        let assert_selectivity = |predicate: ScalarExprRef, expected: f64| {
+            let selectivity = estimated_selectivity(&predicate, &columns);
+            assert!(
+                (selectivity - expected).abs() < 1e-9,
+                "{}: {} != {}",
+                predicate,
+                selectivity,
+                expected
+            );
+        };
+
+        assert_selectivity(comparison(BinaryOp::Eq, 5), 0.2);
+        assert_selectivity(comparison(BinaryOp::Eq, 7), 0.7 / 100.0);
+        assert_selectivity(comparison(BinaryOp::Eq, 200), 0.0);
+        assert_selectivity(comparison(BinaryOp::Lt, 25), 0.2 + 0.7 * 0.25);
+        assert_selectivity(comparison(BinaryOp::Gt, 25), 0.7 * 0.75);
+        // The range is estimated as a whole rather than as independent predicates
+        assert_selectivity(
+            ScalarExpr::nary(
+                NaryOp::And,
+                vec![comparison(BinaryOp::Gt, 10), comparison(BinaryOp::Lt, 20)],
+            )
+            .into(),
+            0.7 * 0.1,
+        );
+        assert_selectivity(
+            ScalarExpr::nary(
+                NaryOp::And,
+                vec![comparison(BinaryOp::Gt, 20), comparison(BinaryOp::Lt, 10)],
+            )
+            .into(),
+            0.0,
+        );
+        // Overlapping ranges are not counted twice
+        assert_selectivity(
+            ScalarExpr::nary(
+                NaryOp::Or,
+                vec![comparison(BinaryOp::Lt, 60), comparison(BinaryOp::Gt, 40)],
+            )
+            .into(),
+            0.9,
+        );
+        assert_selectivity(
+            ScalarExpr::nary(
+                NaryOp::Or,
+                vec![comparison(BinaryOp::Lt, 0), comparison(BinaryOp::Gt, 75)],
+            )
+            .into(),
+            0.7 * 0.25,
+        );
+    }
+}
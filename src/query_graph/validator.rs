@@ -0,0 +1,1090 @@
+//! This module contains a validation pass that type checks the expressions in a query
+//! graph and verifies its structural invariants, so that bugs in the code building or
+//! rewriting the graph are reported as precise errors rather than as panics further
+//! down the line.
+use std::{collections::HashMap, fmt, rc::Rc};
+
+use itertools::Itertools;
+
+use crate::{
+    data_type::DataType,
+    query_graph::{JoinType, NodeId, QueryGraph, QueryNode},
+    scalar_expr::{
+        AggregateOp, NaryOp, ScalarExpr, ScalarExprRef, Subquery, UnaryOp, WindowFunction,
+    },
+};
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ValidationError {
+    /// A node points to an input that is not in the query graph.
+    MissingInput { node_id: NodeId, input_id: NodeId },
+    /// An expression or an aggregate within the node references a column beyond the
+    /// ones projected by its input.
+    ColumnOutOfBounds {
+        node_id: NodeId,
+        index: usize,
+        num_columns: usize,
+    },
+    /// The operands of a scalar or aggregate expression within the node have
+    /// incompatible types, or the wrong arity.
+    InvalidOperandTypes {
+        node_id: NodeId,
+        expr: String,
+        operand_types: Vec<DataType>,
+    },
+    /// A filter or join condition that is not a boolean expression.
+    NonBooleanCondition {
+        node_id: NodeId,
+        condition: String,
+        data_type: DataType,
+    },
+    /// A subquery used as a scalar value, or compared against a scalar value, that
+    /// doesn't project exactly one column.
+    InvalidSubqueryArity {
+        node_id: NodeId,
+        subquery_root: NodeId,
+        num_columns: usize,
+    },
+    /// A row of a values node whose literals don't match its row type.
+    ValuesRowTypeMismatch {
+        node_id: NodeId,
+        row_idx: usize,
+        expected: Vec<DataType>,
+        found: Vec<DataType>,
+    },
+    /// An input of a union whose row type is not compatible with the row type of
+    /// its first input.
+    UnionRowTypeMismatch {
+        node_id: NodeId,
+        input_idx: usize,
+        expected: Vec<DataType>,
+        found: Vec<DataType>,
+    },
+    /// The right input of an intersect or except node, or the recursive input of a
+    /// recursive union, whose row type is not compatible with the row type of its
+    /// left or base input.
+    SetOperationRowTypeMismatch {
+        node_id: NodeId,
+        expected: Vec<DataType>,
+        found: Vec<DataType>,
+    },
+    /// A correlated reference to a context or a parameter that is not in scope.
+    InvalidCorrelatedReference {
+        node_id: NodeId,
+        context_offset: usize,
+        index: usize,
+    },
+    /// A correlated reference whose type doesn't match the type of the parameter
+    /// it references.
+    CorrelatedReferenceTypeMismatch {
+        node_id: NodeId,
+        context_offset: usize,
+        index: usize,
+        expected: DataType,
+        found: DataType,
+    },
+    /// A scan of a catalog table whose row type doesn't match the columns of the
+    /// table.
+    TableScanRowTypeMismatch { node_id: NodeId, table_id: usize },
+    /// A window node with a malformed frame.
+    InvalidWindowFrame { node_id: NodeId, frame: String },
+    /// A recursive reference that is not under the recursive input of a recursive
+    /// union with the same ID.
+    UnboundRecursiveReference {
+        node_id: NodeId,
+        recursion_id: usize,
+    },
+    /// A recursive reference whose row type doesn't match the row type of the base
+    /// input of the recursive union it refers to.
+    RecursiveReferenceRowTypeMismatch {
+        node_id: NodeId,
+        expected: Vec<DataType>,
+        found: Vec<DataType>,
+    },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValidationError::MissingInput { node_id, input_id } => {
+                write!(f, "[{}] input {} not in graph", node_id, input_id)
+            }
+            ValidationError::ColumnOutOfBounds {
+                node_id,
+                index,
+                num_columns,
+            } => write!(
+                f,
+                "[{}] column ref_{} out of bounds, input has {} columns",
+                node_id, index, num_columns
+            ),
+            ValidationError::InvalidOperandTypes {
+                node_id,
+                expr,
+                operand_types,
+            } => write!(
+                f,
+                "[{}] invalid operand types for {}: {}",
+                node_id,
+                expr,
+                operand_types.iter().join(", ")
+            ),
+            ValidationError::NonBooleanCondition {
+                node_id,
+                condition,
+                data_type,
+            } => write!(
+                f,
+                "[{}] condition {} is not boolean, found {}",
+                node_id, condition, data_type
+            ),
+            ValidationError::InvalidSubqueryArity {
+                node_id,
+                subquery_root,
+                num_columns,
+            } => write!(
+                f,
+                "[{}] subquery {} must project one column, found {}",
+                node_id, subquery_root, num_columns
+            ),
+            ValidationError::ValuesRowTypeMismatch {
+                node_id,
+                row_idx,
+                expected,
+                found,
+            } => write!(
+                f,
+                "[{}] values row {} has row type [{}], expected [{}]",
+                node_id,
+                row_idx,
+                found.iter().join(", "),
+                expected.iter().join(", ")
+            ),
+            ValidationError::UnionRowTypeMismatch {
+                node_id,
+                input_idx,
+                expected,
+                found,
+            } => write!(
+                f,
+                "[{}] union input {} has row type [{}], expected [{}]",
+                node_id,
+                input_idx,
+                found.iter().join(", "),
+                expected.iter().join(", ")
+            ),
+            ValidationError::SetOperationRowTypeMismatch {
+                node_id,
+                expected,
+                found,
+            } => write!(
+                f,
+                "[{}] right input has row type [{}], expected [{}]",
+                node_id,
+                found.iter().join(", "),
+                expected.iter().join(", ")
+            ),
+            ValidationError::InvalidCorrelatedReference {
+                node_id,
+                context_offset,
+                index,
+            } => write!(
+                f,
+                "[{}] correlated reference ctx_{}.ref_{} not in scope",
+                node_id, context_offset, index
+            ),
+            ValidationError::CorrelatedReferenceTypeMismatch {
+                node_id,
+                context_offset,
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "[{}] correlated reference ctx_{}.ref_{} has type {}, expected {}",
+                node_id, context_offset, index, found, expected
+            ),
+            ValidationError::TableScanRowTypeMismatch { node_id, table_id } => write!(
+                f,
+                "[{}] row type doesn't match the columns of table {}",
+                node_id, table_id
+            ),
+            ValidationError::InvalidWindowFrame { node_id, frame } => {
+                write!(f, "[{}] invalid window frame {}", node_id, frame)
+            }
+            ValidationError::UnboundRecursiveReference {
+                node_id,
+                recursion_id,
+            } => write!(
+                f,
+                "[{}] recursive reference {} not in scope",
+                node_id, recursion_id
+            ),
+            ValidationError::RecursiveReferenceRowTypeMismatch {
+                node_id,
+                expected,
+                found,
+            } => write!(
+                f,
+                "[{}] recursive reference has row type [{}], expected [{}]",
+                node_id,
+                found.iter().join(", "),
+                expected.iter().join(", ")
+            ),
+        }
+    }
+}
+
+/// Validates the plan hanging from the root node of the given query graph, including
+/// the plans of the subqueries it references.
+///
+/// Returns all the errors found, without duplicates, in the order they were found.
+pub fn validate(query_graph: &QueryGraph) -> Result<(), Vec<ValidationError>> {
+    let mut validator = Validator {
+        query_graph,
+        row_types: HashMap::new(),
+        errors: Vec::new(),
+    };
+    validator.validate_node(QueryGraph::ROOT_NODE_ID, &[]);
+    for node_id in query_graph.unbound_recursive_references() {
+        if let QueryNode::RecursiveReference { recursion_id, .. } = query_graph.node(node_id) {
+            validator
+                .errors
+                .push(ValidationError::UnboundRecursiveReference {
+                    node_id,
+                    recursion_id: *recursion_id,
+                });
+        }
+    }
+    if validator.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(validator.errors.into_iter().unique().collect())
+    }
+}
+
+/// Whether values of the given types can be compared or used interchangeably.
+pub fn are_compatible_types(a: &DataType, b: &DataType) -> bool {
+    let is_numeric = |data_type: &DataType| matches!(data_type, DataType::Int | DataType::BigInt);
+    a == b
+        || matches!(a, DataType::Unknown | DataType::Any)
+        || matches!(b, DataType::Unknown | DataType::Any)
+        || (is_numeric(a) && is_numeric(b))
+}
+
+/// Whether the rows of the given row types can be compared or used interchangeably.
+fn are_compatible_row_types(a: &[DataType], b: &[DataType]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b.iter())
+            .all(|(a, b)| are_compatible_types(a, b))
+}
+
+type RowType = Rc<Vec<DataType>>;
+
+/// The types of the parameters of the correlation contexts in scope, with the
+/// innermost one last.
+type CorrelationScope = [RowType];
+
+struct Validator<'a> {
+    query_graph: &'a QueryGraph,
+    /// The row type of the nodes already validated under a given correlation scope,
+    /// if they could be determined.
+    row_types: HashMap<(NodeId, Vec<RowType>), Option<RowType>>,
+    errors: Vec<ValidationError>,
+}
+
+impl Validator<'_> {
+    /// Validates the subgraph under the given node, returning its row type if it could
+    /// be determined.
+    fn validate_node(&mut self, node_id: NodeId, scope: &CorrelationScope) -> Option<RowType> {
+        let cache_key = (node_id, scope.to_vec());
+        if let Some(row_type) = self.row_types.get(&cache_key) {
+            return row_type.clone();
+        }
+        let row_type = self.compute_row_type(node_id, scope);
+        self.row_types.insert(cache_key, row_type.clone());
+        row_type
+    }
+
+    fn validate_input(
+        &mut self,
+        node_id: NodeId,
+        input_id: NodeId,
+        scope: &CorrelationScope,
+    ) -> Option<RowType> {
+        if self.query_graph.nodes.contains_key(&input_id) {
+            self.validate_node(input_id, scope)
+        } else {
+            self.errors
+                .push(ValidationError::MissingInput { node_id, input_id });
+            None
+        }
+    }
+
+    fn compute_row_type(&mut self, node_id: NodeId, scope: &CorrelationScope) -> Option<RowType> {
+        match self.query_graph.node(node_id) {
+            QueryNode::QueryRoot { input } => match input {
+                Some(input) => self.validate_input(node_id, *input, scope),
+                None => Some(Rc::new(Vec::new())),
+            },
+            QueryNode::SubqueryRoot { input } | QueryNode::Limit { input, .. } => {
+                self.validate_input(node_id, *input, scope)
+            }
+            QueryNode::Sort { keys, input } => {
+                let input_row_type = self.validate_input(node_id, *input, scope)?;
+                let mut valid = true;
+                for key in keys.iter() {
+                    valid &= self.check_column(node_id, key.column, &input_row_type);
+                }
+                if !valid {
+                    return None;
+                }
+                Some(input_row_type)
+            }
+            QueryNode::TopN {
+                partition_key,
+                keys,
+                input,
+                ..
+            } => {
+                let input_row_type = self.validate_input(node_id, *input, scope)?;
+                let mut valid = true;
+                for column in partition_key
+                    .iter()
+                    .chain(keys.iter().map(|key| &key.column))
+                {
+                    valid &= self.check_column(node_id, *column, &input_row_type);
+                }
+                if !valid {
+                    return None;
+                }
+                Some(input_row_type)
+            }
+            QueryNode::TableScan { table_id, row_type } => {
+                if let Some(table) = self.query_graph.table_metadata(*table_id) {
+                    if table.row_type() != **row_type {
+                        self.errors.push(ValidationError::TableScanRowTypeMismatch {
+                            node_id,
+                            table_id: *table_id,
+                        });
+                    }
+                }
+                Some(row_type.clone())
+            }
+            QueryNode::Values { row_type, rows } => {
+                for (row_idx, row) in rows.iter().enumerate() {
+                    let literal_types = row
+                        .iter()
+                        .map(|literal| literal.data_type.clone())
+                        .collect_vec();
+                    if !are_compatible_row_types(&literal_types, row_type) {
+                        self.errors.push(ValidationError::ValuesRowTypeMismatch {
+                            node_id,
+                            row_idx,
+                            expected: row_type.to_vec(),
+                            found: literal_types,
+                        });
+                    }
+                }
+                Some(row_type.clone())
+            }
+            QueryNode::Project { outputs, input } => {
+                let input_row_type = self.validate_input(node_id, *input, scope)?;
+                let output_types = outputs
+                    .iter()
+                    .map(|expr| self.check_expr(node_id, expr, &input_row_type, scope))
+                    .collect_vec();
+                output_types
+                    .into_iter()
+                    .collect::<Option<Vec<_>>>()
+                    .map(Rc::new)
+            }
+            QueryNode::Filter { conditions, input } => {
+                let input_row_type = self.validate_input(node_id, *input, scope)?;
+                self.check_conditions(node_id, conditions, &input_row_type, scope);
+                Some(input_row_type)
+            }
+            QueryNode::Join {
+                join_type,
+                conditions,
+                left,
+                right,
+            } => {
+                let left_row_type = self.validate_input(node_id, *left, scope);
+                let right_row_type = self.validate_input(node_id, *right, scope);
+                let (left_row_type, right_row_type) = (left_row_type?, right_row_type?);
+                let join_row_type = left_row_type
+                    .iter()
+                    .chain(right_row_type.iter())
+                    .cloned()
+                    .collect_vec();
+                self.check_conditions(node_id, conditions, &join_row_type, scope);
+                match join_type {
+                    JoinType::Semi | JoinType::Anti => Some(left_row_type),
+                    _ => Some(Rc::new(join_row_type)),
+                }
+            }
+            QueryNode::Aggregate {
+                group_key,
+                aggregates,
+                input,
+            } => {
+                let input_row_type = self.validate_input(node_id, *input, scope)?;
+                let mut valid = true;
+                for column in group_key.iter().chain(
+                    aggregates
+                        .iter()
+                        .flat_map(|aggregate| aggregate.operands.iter()),
+                ) {
+                    valid &= self.check_column(node_id, *column, &input_row_type);
+                }
+                for aggregate in aggregates.iter() {
+                    let expected_operands = match aggregate.op {
+                        AggregateOp::Count => aggregate.operands.len(),
+                        AggregateOp::Min | AggregateOp::Max => 1,
+                    };
+                    if aggregate.operands.len() != expected_operands {
+                        valid = false;
+                        self.errors.push(ValidationError::InvalidOperandTypes {
+                            node_id,
+                            expr: aggregate.to_string(),
+                            operand_types: aggregate
+                                .operands
+                                .iter()
+                                .filter_map(|operand| input_row_type.get(*operand).cloned())
+                                .collect(),
+                        });
+                    }
+                }
+                if !valid {
+                    return None;
+                }
+                Some(Rc::new(
+                    group_key
+                        .iter()
+                        .map(|column| input_row_type[*column].clone())
+                        .chain(
+                            aggregates
+                                .iter()
+                                .map(|aggregate| aggregate.data_type(&input_row_type)),
+                        )
+                        .collect(),
+                ))
+            }
+            QueryNode::Window {
+                partition_key,
+                order_key,
+                frame,
+                functions,
+                input,
+            } => {
+                let input_row_type = self.validate_input(node_id, *input, scope)?;
+                let mut valid = true;
+                for column in partition_key
+                    .iter()
+                    .chain(order_key.iter().map(|key| &key.column))
+                    .chain(functions.iter().flat_map(|f| f.operands.iter()))
+                {
+                    valid &= self.check_column(node_id, *column, &input_row_type);
+                }
+                if !frame.is_valid() {
+                    valid = false;
+                    self.errors.push(ValidationError::InvalidWindowFrame {
+                        node_id,
+                        frame: frame.to_string(),
+                    });
+                }
+                if !valid {
+                    return None;
+                }
+                for function in functions.iter() {
+                    let operand_types = function
+                        .operands
+                        .iter()
+                        .map(|operand| input_row_type[*operand].clone())
+                        .collect_vec();
+                    let valid_operands = match function.function {
+                        WindowFunction::RowNumber
+                        | WindowFunction::Rank
+                        | WindowFunction::DenseRank => operand_types.is_empty(),
+                        WindowFunction::Count => operand_types.len() <= 1,
+                        WindowFunction::Lag { .. }
+                        | WindowFunction::Lead { .. }
+                        | WindowFunction::Min
+                        | WindowFunction::Max => operand_types.len() == 1,
+                        WindowFunction::Sum => {
+                            operand_types.len() == 1
+                                && are_compatible_types(&operand_types[0], &DataType::BigInt)
+                        }
+                    };
+                    if !valid_operands {
+                        valid = false;
+                        self.errors.push(ValidationError::InvalidOperandTypes {
+                            node_id,
+                            expr: function.to_string(),
+                            operand_types,
+                        });
+                    }
+                }
+                if !valid {
+                    return None;
+                }
+                Some(Rc::new(
+                    input_row_type
+                        .iter()
+                        .cloned()
+                        .chain(functions.iter().map(|f| f.data_type(&input_row_type)))
+                        .collect(),
+                ))
+            }
+            QueryNode::Union { inputs } => {
+                let input_row_types = inputs
+                    .iter()
+                    .map(|input| self.validate_input(node_id, *input, scope))
+                    .collect_vec();
+                let input_row_types = input_row_types.into_iter().collect::<Option<Vec<_>>>()?;
+                let Some(first_row_type) = input_row_types.first().cloned() else {
+                    return Some(Rc::new(Vec::new()));
+                };
+                for (input_idx, row_type) in input_row_types.iter().enumerate().skip(1) {
+                    if !are_compatible_row_types(row_type, &first_row_type) {
+                        self.errors.push(ValidationError::UnionRowTypeMismatch {
+                            node_id,
+                            input_idx,
+                            expected: first_row_type.to_vec(),
+                            found: row_type.to_vec(),
+                        });
+                    }
+                }
+                Some(first_row_type)
+            }
+            QueryNode::Intersect { left, right, .. } | QueryNode::Except { left, right, .. } => {
+                let left_row_type = self.validate_input(node_id, *left, scope);
+                let right_row_type = self.validate_input(node_id, *right, scope);
+                let (left_row_type, right_row_type) = (left_row_type?, right_row_type?);
+                if !are_compatible_row_types(&right_row_type, &left_row_type) {
+                    self.errors
+                        .push(ValidationError::SetOperationRowTypeMismatch {
+                            node_id,
+                            expected: left_row_type.to_vec(),
+                            found: right_row_type.to_vec(),
+                        });
+                }
+                Some(left_row_type)
+            }
+            QueryNode::RecursiveUnion {
+                base, recursive, ..
+            } => {
+                let base_row_type = self.validate_input(node_id, *base, scope);
+                let recursive_row_type = self.validate_input(node_id, *recursive, scope);
+                let base_row_type = base_row_type?;
+                for reference in self.query_graph.recursive_references(node_id) {
+                    if let QueryNode::RecursiveReference { row_type, .. } =
+                        self.query_graph.node(reference)
+                    {
+                        if !are_compatible_row_types(row_type, &base_row_type) {
+                            self.errors
+                                .push(ValidationError::RecursiveReferenceRowTypeMismatch {
+                                    node_id: reference,
+                                    expected: base_row_type.to_vec(),
+                                    found: row_type.to_vec(),
+                                });
+                        }
+                    }
+                }
+                let recursive_row_type = recursive_row_type?;
+                if !are_compatible_row_types(&recursive_row_type, &base_row_type) {
+                    self.errors
+                        .push(ValidationError::SetOperationRowTypeMismatch {
+                            node_id,
+                            expected: base_row_type.to_vec(),
+                            found: recursive_row_type.to_vec(),
+                        });
+                }
+                Some(base_row_type)
+            }
+            QueryNode::RecursiveReference { row_type, .. } => Some(row_type.clone()),
+            QueryNode::Apply {
+                correlation,
+                left,
+                right,
+                apply_type: _,
+            } => {
+                let left_row_type = self.validate_input(node_id, *left, scope)?;
+                let parameter_types =
+                    self.check_parameters(node_id, &correlation.parameters, &left_row_type, scope);
+                let inner_scope = scope
+                    .iter()
+                    .cloned()
+                    .chain(std::iter::once(parameter_types))
+                    .collect_vec();
+                let right_row_type = self.validate_input(node_id, *right, &inner_scope)?;
+                Some(Rc::new(
+                    left_row_type
+                        .iter()
+                        .chain(right_row_type.iter())
+                        .cloned()
+                        .collect(),
+                ))
+            }
+        }
+    }
+
+    fn check_column(&mut self, node_id: NodeId, index: usize, row_type: &[DataType]) -> bool {
+        if index < row_type.len() {
+            true
+        } else {
+            self.errors.push(ValidationError::ColumnOutOfBounds {
+                node_id,
+                index,
+                num_columns: row_type.len(),
+            });
+            false
+        }
+    }
+
+    fn check_conditions(
+        &mut self,
+        node_id: NodeId,
+        conditions: &[ScalarExprRef],
+        row_type: &[DataType],
+        scope: &CorrelationScope,
+    ) {
+        for condition in conditions.iter() {
+            if let Some(data_type) = self.check_expr(node_id, condition, row_type, scope) {
+                if !are_compatible_types(&data_type, &DataType::Bool) {
+                    self.errors.push(ValidationError::NonBooleanCondition {
+                        node_id,
+                        condition: condition.to_string(),
+                        data_type,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Type checks the parameters of a correlation context, returning their types.
+    /// Parameters whose type cannot be determined are given the unknown type, so that
+    /// the subgraph within the context can still be validated.
+    fn check_parameters(
+        &mut self,
+        node_id: NodeId,
+        parameters: &[ScalarExprRef],
+        row_type: &[DataType],
+        scope: &CorrelationScope,
+    ) -> RowType {
+        Rc::new(
+            parameters
+                .iter()
+                .map(|parameter| {
+                    self.check_expr(node_id, parameter, row_type, scope)
+                        .unwrap_or(DataType::Unknown)
+                })
+                .collect(),
+        )
+    }
+
+    /// Validates the plan of the given subquery, returning its row type if it could be
+    /// determined.
+    fn check_subquery(
+        &mut self,
+        node_id: NodeId,
+        subquery: &Subquery<ScalarExpr>,
+        row_type: &[DataType],
+        scope: &CorrelationScope,
+    ) -> Option<RowType> {
+        match &subquery.correlation {
+            Some(correlation) => {
+                let parameter_types =
+                    self.check_parameters(node_id, &correlation.parameters, row_type, scope);
+                let inner_scope = scope
+                    .iter()
+                    .cloned()
+                    .chain(std::iter::once(parameter_types))
+                    .collect_vec();
+                self.validate_input(node_id, subquery.root, &inner_scope)
+            }
+            None => self.validate_input(node_id, subquery.root, scope),
+        }
+    }
+
+    fn check_single_column_subquery(
+        &mut self,
+        node_id: NodeId,
+        subquery: &Subquery<ScalarExpr>,
+        row_type: &[DataType],
+        scope: &CorrelationScope,
+    ) -> Option<DataType> {
+        let subquery_row_type = self.check_subquery(node_id, subquery, row_type, scope)?;
+        if subquery_row_type.len() != 1 {
+            self.errors.push(ValidationError::InvalidSubqueryArity {
+                node_id,
+                subquery_root: subquery.root,
+                num_columns: subquery_row_type.len(),
+            });
+            return None;
+        }
+        Some(subquery_row_type[0].clone())
+    }
+
+    /// Type checks the given expression, returning its type if it could be determined.
+    fn check_expr(
+        &mut self,
+        node_id: NodeId,
+        expr: &ScalarExprRef,
+        row_type: &[DataType],
+        scope: &CorrelationScope,
+    ) -> Option<DataType> {
+        match expr.as_ref() {
+            ScalarExpr::Literal(literal) => Some(literal.data_type.clone()),
+            ScalarExpr::InputRef { index } => {
+                if self.check_column(node_id, *index, row_type) {
+                    Some(row_type[*index].clone())
+                } else {
+                    None
+                }
+            }
+            ScalarExpr::UnaryOp { op, operand } => {
+                let operand_types = vec![self.check_expr(node_id, operand, row_type, scope)?];
+                let expected_type = match op {
+                    UnaryOp::Not | UnaryOp::IsTrue => Some(DataType::Bool),
+                    UnaryOp::Neg => Some(DataType::BigInt),
+                    UnaryOp::IsNull | UnaryOp::IsNotNull => None,
+                };
+                if expected_type.is_some_and(|expected_type| {
+                    !are_compatible_types(&operand_types[0], &expected_type)
+                }) {
+                    self.errors.push(ValidationError::InvalidOperandTypes {
+                        node_id,
+                        expr: expr.to_string(),
+                        operand_types: operand_types.clone(),
+                    });
+                }
+                Some(op.return_type(&operand_types))
+            }
+            ScalarExpr::BinaryOp { op, left, right } => {
+                let left_type = self.check_expr(node_id, left, row_type, scope);
+                let right_type = self.check_expr(node_id, right, row_type, scope);
+                let operand_types = vec![left_type?, right_type?];
+                // Arithmetic operations are only supported over integer types
+                if !are_compatible_types(&operand_types[0], &operand_types[1])
+                    || (!op.is_comparison()
+                        && operand_types
+                            .iter()
+                            .any(|data_type| !are_compatible_types(data_type, &DataType::BigInt)))
+                {
+                    self.errors.push(ValidationError::InvalidOperandTypes {
+                        node_id,
+                        expr: expr.to_string(),
+                        operand_types: operand_types.clone(),
+                    });
+                }
+                Some(op.return_type(&operand_types))
+            }
+            ScalarExpr::NaryOp { op, operands } => {
+                let operand_types = operands
+                    .iter()
+                    .map(|operand| self.check_expr(node_id, operand, row_type, scope))
+                    .collect_vec();
+                let operand_types = operand_types.into_iter().collect::<Option<Vec<_>>>()?;
+                // Values of any type can be concatenated as strings
+                if matches!(op, NaryOp::And | NaryOp::Or)
+                    && operand_types
+                        .iter()
+                        .any(|data_type| !are_compatible_types(data_type, &DataType::Bool))
+                {
+                    self.errors.push(ValidationError::InvalidOperandTypes {
+                        node_id,
+                        expr: expr.to_string(),
+                        operand_types: operand_types.clone(),
+                    });
+                }
+                Some(op.return_type(&operand_types))
+            }
+            ScalarExpr::ScalarSubquery { subquery } => {
+                self.check_single_column_subquery(node_id, subquery, row_type, scope)
+            }
+            ScalarExpr::ExistsSubquery { subquery } => {
+                self.check_subquery(node_id, subquery, row_type, scope);
+                Some(DataType::Bool)
+            }
+            ScalarExpr::ScalarSubqueryCmp {
+                op: _,
+                scalar_operand,
+                subquery,
+            } => {
+                let scalar_type = self.check_expr(node_id, scalar_operand, row_type, scope);
+                let subquery_type =
+                    self.check_single_column_subquery(node_id, subquery, row_type, scope);
+                if let (Some(scalar_type), Some(subquery_type)) = (scalar_type, subquery_type) {
+                    if !are_compatible_types(&scalar_type, &subquery_type) {
+                        self.errors.push(ValidationError::InvalidOperandTypes {
+                            node_id,
+                            expr: expr.to_string(),
+                            operand_types: vec![scalar_type, subquery_type],
+                        });
+                    }
+                }
+                Some(DataType::Bool)
+            }
+            ScalarExpr::CorrelatedInputRef {
+                context_offset,
+                index,
+                data_type,
+            } => {
+                let parameter_type = scope
+                    .len()
+                    .checked_sub(context_offset + 1)
+                    .and_then(|position| scope[position].get(*index));
+                match parameter_type {
+                    Some(parameter_type) => {
+                        if !are_compatible_types(parameter_type, data_type) {
+                            self.errors
+                                .push(ValidationError::CorrelatedReferenceTypeMismatch {
+                                    node_id,
+                                    context_offset: *context_offset,
+                                    index: *index,
+                                    expected: parameter_type.clone(),
+                                    found: data_type.clone(),
+                                });
+                        }
+                    }
+                    None => self
+                        .errors
+                        .push(ValidationError::InvalidCorrelatedReference {
+                            node_id,
+                            context_offset: *context_offset,
+                            index: *index,
+                        }),
+                }
+                Some(data_type.clone())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        query_graph::{ApplyType, CorrelationContext},
+        scalar_expr::BinaryOp,
+        value::{Literal, Value},
+    };
+
+    use super::*;
+
+    fn int_literal(value: i32) -> ScalarExprRef {
+        ScalarExpr::Literal(Literal::new(Value::Int(value), DataType::Int)).into()
+    }
+
+    #[test]
+    fn test_valid_graph() {
+        let mut query_graph = QueryGraph::new();
+        let table_scan = query_graph.table_scan(0, 3);
+        let filter = query_graph.filter(
+            table_scan,
+            vec![ScalarExpr::input_ref(0)
+                .binary(BinaryOp::Eq, ScalarExpr::input_ref(2).into())
+                .into()],
+        );
+        let project = query_graph.project(filter, vec![ScalarExpr::input_ref(1).into()]);
+        query_graph.set_entry_node(project);
+        assert_eq!(validate(&query_graph), Ok(()));
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        let mut query_graph = QueryGraph::new();
+        let table_scan = query_graph.table_scan(0, 2);
+        let filter = query_graph.filter(
+            table_scan,
+            vec![
+                ScalarExpr::input_ref(0).into(),
+                ScalarExpr::input_ref(1)
+                    .binary(BinaryOp::Gt, int_literal(1))
+                    .into(),
+            ],
+        );
+        let project = query_graph.project(filter, vec![ScalarExpr::input_ref(2).into()]);
+        query_graph.set_entry_node(project);
+        assert_eq!(
+            validate(&query_graph),
+            Err(vec![
+                ValidationError::NonBooleanCondition {
+                    node_id: filter,
+                    condition: "ref_0".to_string(),
+                    data_type: DataType::String,
+                },
+                ValidationError::InvalidOperandTypes {
+                    node_id: filter,
+                    expr: "gt(ref_1, 1)".to_string(),
+                    operand_types: vec![DataType::String, DataType::Int],
+                },
+                ValidationError::ColumnOutOfBounds {
+                    node_id: project,
+                    index: 2,
+                    num_columns: 2,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_union_row_type_mismatch() {
+        let mut query_graph = QueryGraph::new();
+        let table_scan = query_graph.table_scan(0, 2);
+        let project = query_graph.project(table_scan, vec![int_literal(1), int_literal(2)]);
+        let union = query_graph.add_node(QueryNode::Union {
+            inputs: vec![table_scan, project],
+        });
+        query_graph.set_entry_node(union);
+        assert_eq!(
+            validate(&query_graph),
+            Err(vec![ValidationError::UnionRowTypeMismatch {
+                node_id: union,
+                input_idx: 1,
+                expected: vec![DataType::String, DataType::String],
+                found: vec![DataType::Int, DataType::Int],
+            }])
+        );
+    }
+
+    #[test]
+    fn test_set_operation_row_type_mismatch() {
+        let mut query_graph = QueryGraph::new();
+        let table_scan = query_graph.table_scan(0, 2);
+        let project = query_graph.project(table_scan, vec![int_literal(1)]);
+        let except = query_graph.except(table_scan, project, true);
+        query_graph.set_entry_node(except);
+        assert_eq!(
+            validate(&query_graph),
+            Err(vec![ValidationError::SetOperationRowTypeMismatch {
+                node_id: except,
+                expected: vec![DataType::String, DataType::String],
+                found: vec![DataType::Int],
+            }])
+        );
+    }
+
+    #[test]
+    fn test_recursive_references() {
+        let mut query_graph = QueryGraph::new();
+        let table_scan = query_graph.table_scan(0, 2);
+        // Reference in the base input, outside the scope of the recursive union
+        let unbound_reference = query_graph.recursive_reference(1, vec![DataType::String; 2]);
+        let base = query_graph.add_node(QueryNode::Union {
+            inputs: vec![table_scan, unbound_reference],
+        });
+        let reference = query_graph.recursive_reference(1, vec![DataType::String]);
+        let recursive = query_graph.project(
+            reference,
+            vec![
+                ScalarExpr::input_ref(0).into(),
+                ScalarExpr::input_ref(0).into(),
+            ],
+        );
+        let recursive_union = query_graph.recursive_union(1, base, recursive, false, None);
+        query_graph.set_entry_node(recursive_union);
+        assert_eq!(
+            validate(&query_graph),
+            Err(vec![
+                ValidationError::RecursiveReferenceRowTypeMismatch {
+                    node_id: reference,
+                    expected: vec![DataType::String, DataType::String],
+                    found: vec![DataType::String],
+                },
+                ValidationError::UnboundRecursiveReference {
+                    node_id: unbound_reference,
+                    recursion_id: 1,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_values_row_type_mismatch() {
+        let mut query_graph = QueryGraph::new();
+        let string = |value: &str| Literal::new(Value::String(value.to_string()), DataType::String);
+        let values = query_graph.values(
+            Rc::new(vec![DataType::String, DataType::Int]),
+            vec![
+                vec![string("a"), Literal::new(Value::Int(1), DataType::Int)],
+                vec![string("b"), string("c")],
+                vec![string("d")],
+                vec![string("e"), Literal::new(Value::Null, DataType::Unknown)],
+            ],
+        );
+        query_graph.set_entry_node(values);
+        assert_eq!(
+            validate(&query_graph),
+            Err(vec![
+                ValidationError::ValuesRowTypeMismatch {
+                    node_id: values,
+                    row_idx: 1,
+                    expected: vec![DataType::String, DataType::Int],
+                    found: vec![DataType::String, DataType::String],
+                },
+                ValidationError::ValuesRowTypeMismatch {
+                    node_id: values,
+                    row_idx: 2,
+                    expected: vec![DataType::String, DataType::Int],
+                    found: vec![DataType::String],
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_correlated_references() {
+        let mut query_graph = QueryGraph::new();
+        let left = query_graph.table_scan(0, 2);
+        let right = query_graph.table_scan(1, 2);
+        let correlated_ref = |context_offset, index| -> ScalarExprRef {
+            ScalarExpr::CorrelatedInputRef {
+                context_offset,
+                index,
+                data_type: DataType::String,
+            }
+            .into()
+        };
+        let filter = query_graph.filter(
+            right,
+            vec![
+                ScalarExpr::input_ref(0)
+                    .binary(BinaryOp::Eq, correlated_ref(0, 0))
+                    .into(),
+                ScalarExpr::input_ref(1)
+                    .binary(BinaryOp::Eq, correlated_ref(0, 1))
+                    .into(),
+                ScalarExpr::input_ref(1)
+                    .binary(BinaryOp::Eq, correlated_ref(1, 0))
+                    .into(),
+            ],
+        );
+        let apply = query_graph.add_node(QueryNode::Apply {
+            correlation: CorrelationContext {
+                parameters: vec![ScalarExpr::input_ref(1).into()],
+            },
+            left,
+            right: filter,
+            apply_type: ApplyType::Inner,
+        });
+        query_graph.set_entry_node(apply);
+        assert_eq!(
+            validate(&query_graph),
+            Err(vec![
+                ValidationError::InvalidCorrelatedReference {
+                    node_id: filter,
+                    context_offset: 0,
+                    index: 1,
+                },
+                ValidationError::InvalidCorrelatedReference {
+                    node_id: filter,
+                    context_offset: 1,
+                    index: 0,
+                },
+            ])
+        );
+    }
+}
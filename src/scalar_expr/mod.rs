@@ -7,7 +7,9 @@ use itertools::Itertools;
 
 use crate::{
     data_type::DataType,
-    query_graph::{CorrelationContext, NodeId, QueryGraph},
+    query_graph::{
+        CorrelationContext, NodeId, QueryGraph, WindowFrame, WindowFrameBound, WindowFrameUnits,
+    },
     value::{Literal, Value},
     visitor_utils::PostOrderVisitationResult,
 };
@@ -30,6 +32,25 @@ pub enum BinaryOp {
     RawEq,
     Lt,
     Le,
+    /// SQL inequality operator, that evaluates to NULL when any of its inputs is NULL.
+    Ne,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum UnaryOp {
+    Not,
+    /// Arithmetic negation.
+    Neg,
+    IsNull,
+    IsNotNull,
+    /// Evaluates to TRUE if its operand is TRUE, and to FALSE otherwise, ie. when it
+    /// is FALSE or NULL.
+    IsTrue,
 }
 
 #[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Debug)]
@@ -67,6 +88,10 @@ pub enum ScalarExpr {
     InputRef {
         index: usize,
     },
+    UnaryOp {
+        op: UnaryOp,
+        operand: Rc<ScalarExpr>,
+    },
     BinaryOp {
         op: BinaryOp,
         left: Rc<ScalarExpr>,
@@ -119,6 +144,36 @@ pub struct AggregateExpr {
 
 pub type AggregateExprRef = Rc<AggregateExpr>;
 
+#[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum WindowFunction {
+    RowNumber,
+    Rank,
+    DenseRank,
+    /// The value of the operand in the row `offset` rows before the current one
+    /// within the partition.
+    Lag {
+        offset: usize,
+    },
+    /// The value of the operand in the row `offset` rows after the current one
+    /// within the partition.
+    Lead {
+        offset: usize,
+    },
+    Sum,
+    Count,
+    Min,
+    Max,
+}
+
+/// A window function over the given columns of the input of a window node.
+#[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct WindowExpr {
+    pub function: WindowFunction,
+    pub operands: Vec<usize>,
+}
+
+pub type WindowExprRef = Rc<WindowExpr>;
+
 impl BinaryOp {
     pub fn function_name(&self) -> &str {
         match self {
@@ -128,17 +183,47 @@ impl BinaryOp {
             BinaryOp::Gt => "gt",
             BinaryOp::Le => "le",
             BinaryOp::Lt => "lt",
+            BinaryOp::Ne => "ne",
+            BinaryOp::Add => "add",
+            BinaryOp::Sub => "sub",
+            BinaryOp::Mul => "mul",
+            BinaryOp::Div => "div",
+            BinaryOp::Mod => "mod",
         }
     }
 
-    pub fn return_type(&self, _operand_types: &[DataType]) -> DataType {
+    pub fn return_type(&self, operand_types: &[DataType]) -> DataType {
+        match self {
+            BinaryOp::RawEq
+            | BinaryOp::Eq
+            | BinaryOp::Ge
+            | BinaryOp::Gt
+            | BinaryOp::Le
+            | BinaryOp::Lt
+            | BinaryOp::Ne => DataType::Bool,
+            // The result has the type of the widest operand. NULL literals, whose
+            // type is unknown, take the type of the other operand.
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => {
+                match (&operand_types[0], &operand_types[1]) {
+                    (DataType::BigInt, _) | (_, DataType::BigInt) => DataType::BigInt,
+                    (DataType::Unknown, other) => other.clone(),
+                    (left, _) => left.clone(),
+                }
+            }
+        }
+    }
+
+    /// Whether the operation compares its operands, returning a boolean value.
+    pub fn is_comparison(&self) -> bool {
         match self {
             BinaryOp::RawEq
             | BinaryOp::Eq
             | BinaryOp::Ge
             | BinaryOp::Gt
             | BinaryOp::Le
-            | BinaryOp::Lt => DataType::Bool,
+            | BinaryOp::Lt
+            | BinaryOp::Ne => true,
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => false,
         }
     }
 
@@ -146,7 +231,17 @@ impl BinaryOp {
     pub fn propagates_null(&self) -> bool {
         match self {
             BinaryOp::RawEq => false,
-            BinaryOp::Eq | BinaryOp::Ge | BinaryOp::Gt | BinaryOp::Le | BinaryOp::Lt => true,
+            BinaryOp::Eq
+            | BinaryOp::Ge
+            | BinaryOp::Gt
+            | BinaryOp::Le
+            | BinaryOp::Lt
+            | BinaryOp::Ne
+            | BinaryOp::Add
+            | BinaryOp::Sub
+            | BinaryOp::Mul
+            | BinaryOp::Div
+            | BinaryOp::Mod => true,
         }
     }
 }
@@ -157,6 +252,39 @@ impl fmt::Display for BinaryOp {
     }
 }
 
+impl UnaryOp {
+    pub fn function_name(&self) -> &str {
+        match self {
+            UnaryOp::Not => "not",
+            UnaryOp::Neg => "neg",
+            UnaryOp::IsNull => "is_null",
+            UnaryOp::IsNotNull => "is_not_null",
+            UnaryOp::IsTrue => "is_true",
+        }
+    }
+
+    pub fn return_type(&self, operand_types: &[DataType]) -> DataType {
+        match self {
+            UnaryOp::Neg => operand_types[0].clone(),
+            UnaryOp::Not | UnaryOp::IsNull | UnaryOp::IsNotNull | UnaryOp::IsTrue => DataType::Bool,
+        }
+    }
+
+    /// Whether the result of the operation is null if its operand is null.
+    pub fn propagates_null(&self) -> bool {
+        match self {
+            UnaryOp::Not | UnaryOp::Neg => true,
+            UnaryOp::IsNull | UnaryOp::IsNotNull | UnaryOp::IsTrue => false,
+        }
+    }
+}
+
+impl fmt::Display for UnaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.function_name())
+    }
+}
+
 impl NaryOp {
     pub fn function_name(&self) -> &str {
         match self {
@@ -195,6 +323,22 @@ impl ScalarSubqueryCmpOp {
             ScalarSubqueryCmpOp::GteAll => "gte_all",
         }
     }
+
+    /// Whether the comparison must hold for all the rows of the subquery.
+    pub fn is_all(&self) -> bool {
+        match self {
+            ScalarSubqueryCmpOp::EqAny
+            | ScalarSubqueryCmpOp::LtAny
+            | ScalarSubqueryCmpOp::LteAny
+            | ScalarSubqueryCmpOp::GtAny
+            | ScalarSubqueryCmpOp::GteAny => false,
+            ScalarSubqueryCmpOp::EqAll
+            | ScalarSubqueryCmpOp::LtAll
+            | ScalarSubqueryCmpOp::LteAll
+            | ScalarSubqueryCmpOp::GtAll
+            | ScalarSubqueryCmpOp::GteAll => true,
+        }
+    }
 }
 
 impl fmt::Display for ScalarSubqueryCmpOp {
@@ -237,6 +381,13 @@ impl ScalarExpr {
         ScalarExpr::InputRef { index }
     }
 
+    pub fn unary(self, op: UnaryOp) -> ScalarExpr {
+        ScalarExpr::UnaryOp {
+            op,
+            operand: self.into(),
+        }
+    }
+
     pub fn binary(self, op: BinaryOp, rhs: ScalarExprRef) -> ScalarExpr {
         ScalarExpr::BinaryOp {
             op,
@@ -299,6 +450,7 @@ impl ScalarExpr {
         match self {
             ScalarExpr::Literal(literal) => literal.data_type.clone(),
             ScalarExpr::InputRef { index } => row_type[*index].clone(),
+            ScalarExpr::UnaryOp { op, .. } => op.return_type(operand_types),
             ScalarExpr::BinaryOp { op, .. } => op.return_type(operand_types),
             ScalarExpr::NaryOp { op, .. } => op.return_type(operand_types),
             ScalarExpr::ExistsSubquery { .. } => DataType::Bool,
@@ -336,6 +488,7 @@ impl fmt::Display for ScalarExpr {
         match self {
             ScalarExpr::Literal(literal) => write!(f, "{}", literal),
             ScalarExpr::InputRef { index } => write!(f, "ref_{}", index),
+            ScalarExpr::UnaryOp { op, operand } => write!(f, "{}({})", op, operand),
             ScalarExpr::BinaryOp { op, left, right } => write!(f, "{}({}, {})", op, left, right),
             ScalarExpr::NaryOp { op, operands } => {
                 write!(f, "{}(", op)?;
@@ -433,6 +586,96 @@ impl fmt::Display for AggregateOp {
     }
 }
 
+impl WindowExpr {
+    pub fn data_type(&self, row_type: &[DataType]) -> DataType {
+        let operand_types = self
+            .operands
+            .iter()
+            .map(|o| row_type[*o].clone())
+            .collect_vec();
+        self.function.return_type(&operand_types)
+    }
+
+    /// Whether the value of the function for a row only depends on the rows before
+    /// it within its partition, given the frame of the window.
+    pub fn depends_only_on_preceding_rows(&self, frame: &WindowFrame) -> bool {
+        match self.function {
+            WindowFunction::RowNumber
+            | WindowFunction::Rank
+            | WindowFunction::DenseRank
+            | WindowFunction::Lag { .. } => true,
+            WindowFunction::Lead { .. } => false,
+            WindowFunction::Sum
+            | WindowFunction::Count
+            | WindowFunction::Min
+            | WindowFunction::Max => {
+                // The peers of the current row may follow it in RANGE frames.
+                frame.units == WindowFrameUnits::Rows
+                    && matches!(
+                        frame.end,
+                        WindowFrameBound::UnboundedPreceding
+                            | WindowFrameBound::Preceding(_)
+                            | WindowFrameBound::CurrentRow
+                    )
+            }
+        }
+    }
+}
+
+impl fmt::Display for WindowExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}(", self.function)?;
+        let mut sep = "";
+        for operand in self.operands.iter() {
+            write!(f, "{}ref_{}", sep, operand)?;
+            sep = ", ";
+        }
+        match self.function {
+            WindowFunction::Lag { offset } | WindowFunction::Lead { offset } => {
+                write!(f, "{}{}", sep, offset)?;
+            }
+            _ => {}
+        }
+        write!(f, ")")
+    }
+}
+
+impl WindowFunction {
+    pub fn return_type(&self, operand_types: &[DataType]) -> DataType {
+        match self {
+            WindowFunction::RowNumber
+            | WindowFunction::Rank
+            | WindowFunction::DenseRank
+            | WindowFunction::Sum
+            | WindowFunction::Count => DataType::BigInt,
+            WindowFunction::Lag { .. }
+            | WindowFunction::Lead { .. }
+            | WindowFunction::Min
+            | WindowFunction::Max => operand_types[0].clone(),
+        }
+    }
+
+    pub fn function_name(&self) -> &str {
+        match self {
+            WindowFunction::RowNumber => "row_number",
+            WindowFunction::Rank => "rank",
+            WindowFunction::DenseRank => "dense_rank",
+            WindowFunction::Lag { .. } => "lag",
+            WindowFunction::Lead { .. } => "lead",
+            WindowFunction::Sum => "sum",
+            WindowFunction::Count => "count",
+            WindowFunction::Min => "min",
+            WindowFunction::Max => "max",
+        }
+    }
+}
+
+impl fmt::Display for WindowFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.function_name())
+    }
+}
+
 /// Representation for working with expressions that may contain aggregate expressions
 /// and other expressions that are not allowed in the query graph.
 #[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Debug)]
@@ -441,6 +684,10 @@ pub enum ExtendedScalarExpr {
     InputRef {
         index: usize,
     },
+    UnaryOp {
+        op: UnaryOp,
+        operand: Rc<ExtendedScalarExpr>,
+    },
     BinaryOp {
         op: BinaryOp,
         left: Rc<ExtendedScalarExpr>,
@@ -512,6 +759,7 @@ impl ExtendedScalarExpr {
         match self {
             ExtendedScalarExpr::Literal(literal) => literal.data_type.clone(),
             ExtendedScalarExpr::InputRef { index } => row_type[*index].clone(),
+            ExtendedScalarExpr::UnaryOp { op, .. } => op.return_type(operand_types),
             ExtendedScalarExpr::BinaryOp { op, .. } => op.return_type(operand_types),
             ExtendedScalarExpr::NaryOp { op, .. } => op.return_type(operand_types),
             ExtendedScalarExpr::Aggregate { op, .. } => op.return_type(operand_types),
@@ -537,6 +785,10 @@ impl ToScalarExpr for Rc<ExtendedScalarExpr> {
             let extended_expr = match expr.as_ref() {
                 ExtendedScalarExpr::Literal(literal) => ScalarExpr::Literal(literal.clone()),
                 ExtendedScalarExpr::InputRef { index } => ScalarExpr::InputRef { index: *index },
+                ExtendedScalarExpr::UnaryOp { op, operand: _ } => ScalarExpr::UnaryOp {
+                    op: op.clone(),
+                    operand: stack.pop().unwrap(),
+                },
                 ExtendedScalarExpr::BinaryOp {
                     op,
                     left: _,
@@ -666,6 +918,10 @@ impl ToExtendedExpr for Rc<ScalarExpr> {
             let extended_expr = match expr.as_ref() {
                 ScalarExpr::Literal(literal) => ExtendedScalarExpr::Literal(literal.clone()),
                 ScalarExpr::InputRef { index } => ExtendedScalarExpr::InputRef { index: *index },
+                ScalarExpr::UnaryOp { op, operand: _ } => ExtendedScalarExpr::UnaryOp {
+                    op: op.clone(),
+                    operand: stack.pop().unwrap(),
+                },
                 ScalarExpr::BinaryOp {
                     op,
                     left: _,
@@ -1,5 +1,7 @@
 //! Utilities for reducing scalar expression
 
+use std::cmp::Ordering;
+
 use itertools::Itertools;
 
 use crate::{
@@ -7,7 +9,9 @@ use crate::{
     query_graph::{properties::num_columns, QueryGraph},
 };
 
-use super::{rewrite::rewrite_expr_pre_post, NaryOp, ScalarExpr, ScalarExprRef, Subquery};
+use super::{
+    rewrite::rewrite_expr_pre_post, BinaryOp, NaryOp, ScalarExpr, ScalarExprRef, Subquery, UnaryOp,
+};
 
 /// Reduce the given expression recursively. Keeps trying until the expression cannot
 /// be reduced any further.
@@ -71,10 +75,92 @@ pub fn reduce_expr(
             });
         }
     }
+    if let ScalarExpr::UnaryOp { op, operand } = expr.as_ref() {
+        if op.propagates_null() && operand.is_null() {
+            return Some(ScalarExpr::null_literal(expr.data_type(query_graph, row_type)).into());
+        }
+        if let ScalarExpr::Literal(literal) = operand.as_ref() {
+            let result = match op {
+                UnaryOp::IsNull => Some(literal.is_null()),
+                UnaryOp::IsNotNull => Some(!literal.is_null()),
+                UnaryOp::IsTrue => Some(**operand == ScalarExpr::true_literal()),
+                UnaryOp::Not if literal.data_type == DataType::Bool => {
+                    Some(**operand == ScalarExpr::false_literal())
+                }
+                UnaryOp::Not | UnaryOp::Neg => None,
+            };
+            if let Some(result) = result {
+                return Some(if result {
+                    ScalarExpr::true_literal().into()
+                } else {
+                    ScalarExpr::false_literal().into()
+                });
+            }
+        }
+        if let (
+            UnaryOp::Not,
+            ScalarExpr::UnaryOp {
+                op: inner_op,
+                operand,
+            },
+        ) = (op, operand.as_ref())
+        {
+            match inner_op {
+                // not(not(x)) -> x
+                UnaryOp::Not => return Some(operand.clone()),
+                // not(is_null(x)) -> is_not_null(x) and vice versa
+                UnaryOp::IsNull | UnaryOp::IsNotNull => {
+                    let op = if let UnaryOp::IsNull = inner_op {
+                        UnaryOp::IsNotNull
+                    } else {
+                        UnaryOp::IsNull
+                    };
+                    return Some(
+                        ScalarExpr::UnaryOp {
+                            op,
+                            operand: operand.clone(),
+                        }
+                        .into(),
+                    );
+                }
+                UnaryOp::Neg | UnaryOp::IsTrue => {}
+            }
+        }
+    }
     if let ScalarExpr::BinaryOp { op, left, right } = expr.as_ref() {
         if op.propagates_null() && (left.is_null() || right.is_null()) {
             return Some(ScalarExpr::null_literal(expr.data_type(query_graph, row_type)).into());
         }
+        // Comparisons between non-null literals of the same type can be evaluated
+        if let (ScalarExpr::Literal(left), ScalarExpr::Literal(right)) =
+            (left.as_ref(), right.as_ref())
+        {
+            if op.is_comparison()
+                && left.data_type == right.data_type
+                && !left.is_null()
+                && !right.is_null()
+            {
+                let ordering = left.value.cmp(&right.value);
+                let result = match op {
+                    BinaryOp::Eq | BinaryOp::RawEq => ordering == Ordering::Equal,
+                    BinaryOp::Lt => ordering == Ordering::Less,
+                    BinaryOp::Le => ordering != Ordering::Greater,
+                    BinaryOp::Gt => ordering == Ordering::Greater,
+                    BinaryOp::Ge => ordering != Ordering::Less,
+                    BinaryOp::Ne => ordering != Ordering::Equal,
+                    BinaryOp::Add
+                    | BinaryOp::Sub
+                    | BinaryOp::Mul
+                    | BinaryOp::Div
+                    | BinaryOp::Mod => unreachable!(),
+                };
+                return Some(if result {
+                    ScalarExpr::true_literal().into()
+                } else {
+                    ScalarExpr::false_literal().into()
+                });
+            }
+        }
     }
     None
 }
@@ -118,3 +204,101 @@ pub fn prune_exists_subplan(
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        data_type::DataType,
+        query_graph::QueryGraph,
+        scalar_expr::{BinaryOp, NaryOp, ScalarExpr},
+        value::{Literal, Value},
+    };
+
+    use super::{reduce_expr, reduce_expr_recursively};
+
+    fn int_literal(value: i32) -> ScalarExpr {
+        ScalarExpr::Literal(Literal {
+            value: Value::Int(value),
+            data_type: DataType::Int,
+        })
+    }
+
+    #[test]
+    fn test_literal_comparisons() {
+        let query_graph = QueryGraph::new();
+        let tests = vec![
+            (BinaryOp::Eq, 1, 1, true),
+            (BinaryOp::Eq, 1, 2, false),
+            (BinaryOp::RawEq, 2, 2, true),
+            (BinaryOp::Lt, 1, 2, true),
+            (BinaryOp::Lt, 2, 2, false),
+            (BinaryOp::Le, 2, 2, true),
+            (BinaryOp::Le, 3, 2, false),
+            (BinaryOp::Gt, 3, 2, true),
+            (BinaryOp::Gt, 2, 2, false),
+            (BinaryOp::Ge, 2, 2, true),
+            (BinaryOp::Ge, 1, 2, false),
+        ];
+        for (op, left, right, expected) in tests {
+            let expr = int_literal(left)
+                .binary(op, int_literal(right).into())
+                .into();
+            let expected = if expected {
+                ScalarExpr::true_literal()
+            } else {
+                ScalarExpr::false_literal()
+            };
+            assert_eq!(reduce_expr(&expr, &query_graph, &[]), Some(expected.into()));
+        }
+
+        let expr = ScalarExpr::string_literal("hello".to_string())
+            .binary(
+                BinaryOp::Eq,
+                ScalarExpr::string_literal("hello".to_string()).into(),
+            )
+            .into();
+        assert_eq!(
+            reduce_expr(&expr, &query_graph, &[]),
+            Some(ScalarExpr::true_literal().into())
+        );
+    }
+
+    #[test]
+    fn test_non_literal_comparisons_are_not_folded() {
+        let query_graph = QueryGraph::new();
+        // Literals of different types
+        let expr = int_literal(1)
+            .binary(
+                BinaryOp::Eq,
+                ScalarExpr::string_literal("1".to_string()).into(),
+            )
+            .into();
+        assert_eq!(reduce_expr(&expr, &query_graph, &[]), None);
+        // Comparisons with a column
+        let expr = ScalarExpr::input_ref(0)
+            .binary(BinaryOp::Eq, int_literal(1).into())
+            .into();
+        assert_eq!(reduce_expr(&expr, &query_graph, &[DataType::Int]), None);
+    }
+
+    #[test]
+    fn test_folded_comparison_within_conjunction() {
+        let query_graph = QueryGraph::new();
+        let expr = ScalarExpr::nary(
+            NaryOp::And,
+            vec![
+                ScalarExpr::input_ref(0)
+                    .binary(BinaryOp::Eq, int_literal(1).into())
+                    .into(),
+                int_literal(1)
+                    .binary(BinaryOp::Eq, int_literal(2).into())
+                    .into(),
+            ],
+        )
+        .into();
+        assert_eq!(
+            reduce_expr_recursively(&expr, &query_graph, &[DataType::Int]),
+            ScalarExpr::false_literal().into()
+        );
+    }
+}
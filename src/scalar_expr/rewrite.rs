@@ -212,9 +212,15 @@ where
 
 /// Tries to lift the given expression through the given projection.
 /// Fails if an input ref expression not included in the projection is reached.
+///
+/// Sub-expressions not depending on any input column, such as literals, are left
+/// as they are, since they are valid on top of the projection.
 pub fn lift_scalar_expr(expr: &ScalarExprRef, proj: &Vec<ScalarExprRef>) -> Option<ScalarExprRef> {
     rewrite_expr_pre(
         &mut |expr: &ScalarExprRef| {
+            if collect_input_dependencies(expr).is_empty() {
+                return Ok(Some(expr.clone()));
+            }
             if let Some(proj_col) = proj
                 .iter()
                 .enumerate()
@@ -412,6 +418,10 @@ impl RewritableExpr for ScalarExpr {
     fn clone_with_new_inputs(&self, inputs: &[ScalarExprRef]) -> ScalarExprRef {
         assert!(inputs.len() == self.num_inputs());
         match self {
+            ScalarExpr::UnaryOp { op, .. } => ScalarExpr::UnaryOp {
+                op: op.clone(),
+                operand: inputs[0].clone(),
+            },
             ScalarExpr::BinaryOp { op, .. } => ScalarExpr::BinaryOp {
                 op: op.clone(),
                 left: inputs[0].clone(),
@@ -463,6 +473,10 @@ impl RewritableExpr for ExtendedScalarExpr {
     fn clone_with_new_inputs(&self, inputs: &[ExtendedScalarExprRef]) -> ExtendedScalarExprRef {
         assert!(inputs.len() == self.num_inputs());
         match self {
+            ExtendedScalarExpr::UnaryOp { op, .. } => ExtendedScalarExpr::UnaryOp {
+                op: op.clone(),
+                operand: inputs[0].clone(),
+            },
             ExtendedScalarExpr::BinaryOp { op, .. } => ExtendedScalarExpr::BinaryOp {
                 op: op.clone(),
                 left: inputs[0].clone(),
@@ -575,4 +589,27 @@ mod tests {
             assert_eq!(lifted_expr, expected);
         }
     }
+
+    #[test]
+    fn test_lift_scalar_expr_keeps_literals() {
+        // Project [ref_0, ref_1, TRUE]
+        let proj = vec![
+            ScalarExpr::input_ref(0).into(),
+            ScalarExpr::input_ref(1).into(),
+            ScalarExpr::true_literal().into(),
+        ];
+        // ge(concat('2', TRUE), ref_0) must not become ge(concat('2', ref_2), ref_0),
+        // otherwise it cannot be lifted through a projection that doesn't project
+        // ref_2.
+        let expr: ScalarExprRef = ScalarExpr::nary(
+            NaryOp::Concat,
+            vec![
+                ScalarExpr::string_literal("2".to_string()).into(),
+                ScalarExpr::true_literal().into(),
+            ],
+        )
+        .binary(BinaryOp::Ge, ScalarExpr::input_ref(0).into())
+        .into();
+        assert_eq!(lift_scalar_expr(&expr, &proj), Some(expr.clone()));
+    }
 }
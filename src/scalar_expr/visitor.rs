@@ -152,6 +152,7 @@ impl VisitableExpr for ScalarExpr {
         match self {
             ScalarExpr::Literal { .. } => 0,
             ScalarExpr::InputRef { .. } => 0,
+            ScalarExpr::UnaryOp { .. } => 1,
             ScalarExpr::BinaryOp { .. } => 2,
             ScalarExpr::NaryOp { operands, .. } => operands.len(),
             ScalarExpr::CorrelatedInputRef { .. } => 0,
@@ -178,6 +179,7 @@ impl VisitableExpr for ScalarExpr {
     fn get_input(&self, input_idx: usize) -> ScalarExprRef {
         assert!(input_idx < self.num_inputs());
         match self {
+            ScalarExpr::UnaryOp { operand, .. } => operand.clone(),
             ScalarExpr::BinaryOp { left, right, .. } => {
                 if input_idx == 0 {
                     left.clone()
@@ -213,6 +215,7 @@ impl VisitableExpr for ExtendedScalarExpr {
         match self {
             ExtendedScalarExpr::Literal { .. } => 0,
             ExtendedScalarExpr::InputRef { .. } => 0,
+            ExtendedScalarExpr::UnaryOp { .. } => 1,
             ExtendedScalarExpr::BinaryOp { .. } => 2,
             ExtendedScalarExpr::Aggregate { operands, .. }
             | ExtendedScalarExpr::NaryOp { operands, .. } => operands.len(),
@@ -240,6 +243,7 @@ impl VisitableExpr for ExtendedScalarExpr {
     fn get_input(&self, input_idx: usize) -> ExtendedScalarExprRef {
         assert!(input_idx < self.num_inputs());
         match self {
+            ExtendedScalarExpr::UnaryOp { operand, .. } => operand.clone(),
             ExtendedScalarExpr::BinaryOp { left, right, .. } => {
                 if input_idx == 0 {
                     left.clone()
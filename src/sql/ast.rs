@@ -0,0 +1,162 @@
+//! Abstract syntax tree produced by the SQL parser.
+
+/// A query expression: either a SELECT block or a set operation between
+/// two query expressions.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Query {
+    Select(Box<Select>),
+    SetOperation {
+        op: SetOperator,
+        /// Whether duplicated rows must be preserved, ie. `UNION ALL`.
+        all: bool,
+        left: Box<Query>,
+        right: Box<Query>,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SetOperator {
+    Union,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Select {
+    pub distinct: bool,
+    pub projection: Vec<SelectItem>,
+    /// Comma-separated list of relations in the FROM clause. Empty if the
+    /// query has no FROM clause.
+    pub from: Vec<TableRef>,
+    pub selection: Option<Expr>,
+    pub group_by: Vec<Expr>,
+    pub having: Option<Expr>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SelectItem {
+    /// `*`
+    Wildcard,
+    /// `t.*`
+    QualifiedWildcard(String),
+    Expr {
+        expr: Expr,
+        alias: Option<String>,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum JoinKind {
+    Inner,
+    LeftOuter,
+    RightOuter,
+    FullOuter,
+    Cross,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TableRef {
+    Table {
+        name: String,
+        alias: Option<String>,
+    },
+    /// A subquery in the FROM clause.
+    Derived {
+        query: Box<Query>,
+        alias: Option<String>,
+    },
+    Join {
+        kind: JoinKind,
+        left: Box<TableRef>,
+        right: Box<TableRef>,
+        on: Option<Expr>,
+    },
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Literal {
+    Null,
+    Boolean(bool),
+    Integer(i64),
+    String(String),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BinaryOperator {
+    And,
+    Or,
+    Eq,
+    NotEq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// `IS NOT DISTINCT FROM`
+    NotDistinctFrom,
+    Concat,
+    Plus,
+    Minus,
+    Multiply,
+    Divide,
+    Modulo,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum UnaryOperator {
+    Not,
+    Minus,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Quantifier {
+    Any,
+    All,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Expr {
+    /// A possibly qualified column reference.
+    Column {
+        qualifier: Option<String>,
+        name: String,
+    },
+    Literal(Literal),
+    Binary {
+        op: BinaryOperator,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    Unary {
+        op: UnaryOperator,
+        expr: Box<Expr>,
+    },
+    IsNull {
+        expr: Box<Expr>,
+        negated: bool,
+    },
+    /// A function call. `COUNT(*)` is represented with `star` set to true and
+    /// no arguments.
+    Function {
+        name: String,
+        args: Vec<Expr>,
+        star: bool,
+        distinct: bool,
+    },
+    Exists {
+        query: Box<Query>,
+        negated: bool,
+    },
+    /// A subquery in a scalar position.
+    Subquery(Box<Query>),
+    /// `expr [NOT] IN (subquery)`
+    InSubquery {
+        expr: Box<Expr>,
+        query: Box<Query>,
+        negated: bool,
+    },
+    /// `expr op ANY|SOME|ALL (subquery)`
+    Quantified {
+        op: BinaryOperator,
+        quantifier: Quantifier,
+        expr: Box<Expr>,
+        query: Box<Query>,
+    },
+}
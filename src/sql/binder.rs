@@ -0,0 +1,843 @@
+//! Name resolution and translation of the AST into a query graph.
+use std::{collections::BTreeSet, rc::Rc};
+
+use itertools::Itertools;
+
+use crate::{
+    catalog::Catalog,
+    data_type::DataType,
+    query_graph::{
+        properties::row_type, CorrelationContext, JoinType, NodeId, QueryGraph, QueryNode,
+    },
+    scalar_expr::{
+        AggregateExpr, AggregateOp, BinaryOp, NaryOp, ScalarExpr, ScalarExprRef,
+        ScalarSubqueryCmpOp, Subquery, UnaryOp,
+    },
+    value::{Literal, Value},
+};
+
+use super::{ast, SqlError};
+
+/// Builds a query graph for the given query, resolving the table names against
+/// the given catalog.
+pub fn bind_query(catalog: &Rc<Catalog>, query: &ast::Query) -> Result<QueryGraph, SqlError> {
+    let mut binder = Binder {
+        catalog,
+        query_graph: QueryGraph::with_catalog(catalog.clone()),
+        correlation_levels: Vec::new(),
+    };
+    let relation = binder.bind_query(query)?;
+    binder.query_graph.set_entry_node(relation.node_id);
+    Ok(binder.query_graph)
+}
+
+#[derive(Clone, Debug)]
+struct ScopeColumn {
+    qualifier: Option<String>,
+    name: Option<String>,
+    data_type: DataType,
+}
+
+/// The columns visible to the expressions of a query block, in the same order
+/// as they are projected by the node they belong to.
+#[derive(Clone, Debug, Default)]
+struct Scope {
+    columns: Vec<ScopeColumn>,
+}
+
+impl Scope {
+    /// Returns the position of the column with the given name. Fails if the name
+    /// is ambiguous.
+    fn resolve(&self, qualifier: Option<&str>, name: &str) -> Result<Option<usize>, SqlError> {
+        let matches = self
+            .columns
+            .iter()
+            .positions(|column| {
+                column.name.as_deref() == Some(name)
+                    && (qualifier.is_none() || column.qualifier.as_deref() == qualifier)
+            })
+            .collect_vec();
+        match matches.len() {
+            0 => Ok(None),
+            1 => Ok(Some(matches[0])),
+            _ => Err(SqlError::Bind(format!(
+                "column reference {} is ambiguous",
+                column_name(qualifier, name)
+            ))),
+        }
+    }
+
+    fn with_qualifier(mut self, qualifier: Option<&String>) -> Self {
+        for column in self.columns.iter_mut() {
+            column.qualifier = qualifier.cloned();
+        }
+        self
+    }
+}
+
+/// A bound relation: a node in the query graph together with the names of its
+/// columns.
+struct Relation {
+    node_id: NodeId,
+    scope: Scope,
+}
+
+/// Information about the grouping performed by a query block, used for binding
+/// the expressions evaluated on top of the aggregation.
+struct Grouping {
+    /// The expressions in the GROUP BY clause. They are projected by the aggregate
+    /// node in the same order, before the aggregate expressions.
+    group_exprs: Vec<ast::Expr>,
+    /// The grouping expressions bound against the scope of the input of the
+    /// aggregation.
+    bound_group_exprs: Vec<ScalarExprRef>,
+    /// The aggregate function calls in the SELECT list and in the HAVING clause.
+    aggregates: Vec<ast::Expr>,
+    /// The scope of the input of the aggregation.
+    input_scope: Scope,
+}
+
+/// The context in which an expression is bound.
+struct ExprContext<'a> {
+    scope: &'a Scope,
+    grouping: Option<&'a Grouping>,
+    /// The clause the expression belongs to, for error reporting purposes.
+    clause: &'static str,
+}
+
+/// The state of a subquery being bound: the scope of the query block containing
+/// it and the outer columns it references, which become the parameters of its
+/// correlation context.
+struct CorrelationLevel {
+    outer_scope: Scope,
+    parameters: Vec<ScalarExprRef>,
+}
+
+struct Binder<'a> {
+    catalog: &'a Catalog,
+    query_graph: QueryGraph,
+    /// One level per subquery being bound, the innermost one being the last one.
+    correlation_levels: Vec<CorrelationLevel>,
+}
+
+impl<'a> Binder<'a> {
+    fn bind_query(&mut self, query: &ast::Query) -> Result<Relation, SqlError> {
+        match query {
+            ast::Query::Select(select) => self.bind_select(select),
+            ast::Query::SetOperation {
+                op: ast::SetOperator::Union,
+                all,
+                left,
+                right,
+            } => {
+                let left = self.bind_query(left)?;
+                let right = self.bind_query(right)?;
+                if left.scope.columns.len() != right.scope.columns.len() {
+                    return Err(SqlError::Bind(format!(
+                        "each UNION query must have the same number of columns, found {} and {}",
+                        left.scope.columns.len(),
+                        right.scope.columns.len()
+                    )));
+                }
+                let union = self.query_graph.add_node(QueryNode::Union {
+                    inputs: vec![left.node_id, right.node_id],
+                });
+                let node_id = if *all {
+                    union
+                } else {
+                    self.distinct(union, left.scope.columns.len())
+                };
+                Ok(self.relation(node_id, left.scope.with_qualifier(None)))
+            }
+        }
+    }
+
+    fn bind_select(&mut self, select: &ast::Select) -> Result<Relation, SqlError> {
+        let mut relation = self.bind_from(&select.from)?;
+
+        if let Some(selection) = &select.selection {
+            let context = ExprContext {
+                scope: &relation.scope,
+                grouping: None,
+                clause: "WHERE",
+            };
+            let conditions = self.bind_conjunction(selection, &context)?;
+            relation.node_id = self.query_graph.filter(relation.node_id, conditions);
+        }
+
+        let mut aggregates = Vec::new();
+        for item in select.projection.iter() {
+            if let ast::SelectItem::Expr { expr, .. } = item {
+                collect_aggregates(expr, &mut aggregates);
+            }
+        }
+        if let Some(having) = &select.having {
+            collect_aggregates(having, &mut aggregates);
+        }
+
+        let grouping = if !select.group_by.is_empty() || !aggregates.is_empty() {
+            let (node_id, grouping, scope) =
+                self.bind_aggregation(relation, &select.group_by, aggregates)?;
+            relation = Relation { node_id, scope };
+            Some(grouping)
+        } else if select.having.is_some() {
+            return Err(SqlError::Bind(
+                "HAVING clause requires GROUP BY or aggregate functions".to_string(),
+            ));
+        } else {
+            None
+        };
+
+        if let Some(having) = &select.having {
+            let context = ExprContext {
+                scope: &relation.scope,
+                grouping: grouping.as_ref(),
+                clause: "HAVING",
+            };
+            let conditions = self.bind_conjunction(having, &context)?;
+            relation.node_id = self.query_graph.filter(relation.node_id, conditions);
+        }
+
+        let mut outputs = Vec::new();
+        let mut columns = Vec::new();
+        for item in select.projection.iter() {
+            match item {
+                ast::SelectItem::Wildcard | ast::SelectItem::QualifiedWildcard(_) => {
+                    if grouping.is_some() {
+                        return Err(SqlError::Bind(
+                            "wildcards are not supported in grouping queries".to_string(),
+                        ));
+                    }
+                    let qualifier = match item {
+                        ast::SelectItem::QualifiedWildcard(qualifier) => Some(qualifier),
+                        _ => None,
+                    };
+                    let mut found = false;
+                    for (index, column) in relation.scope.columns.iter().enumerate() {
+                        if qualifier.is_none() || column.qualifier.as_ref() == qualifier {
+                            found = true;
+                            outputs.push(ScalarExpr::input_ref(index).into());
+                            columns.push(column.clone());
+                        }
+                    }
+                    if let Some(qualifier) = qualifier {
+                        if !found {
+                            return Err(SqlError::Bind(format!(
+                                "relation {} not found",
+                                qualifier
+                            )));
+                        }
+                    }
+                }
+                ast::SelectItem::Expr { expr, alias } => {
+                    let context = ExprContext {
+                        scope: &relation.scope,
+                        grouping: grouping.as_ref(),
+                        clause: "SELECT",
+                    };
+                    let bound_expr = self.bind_expr(expr, &context)?;
+                    let name = match (alias, expr) {
+                        (Some(alias), _) => Some(alias.clone()),
+                        (None, ast::Expr::Column { name, .. }) => Some(name.clone()),
+                        (None, _) => None,
+                    };
+                    outputs.push(bound_expr);
+                    columns.push(ScopeColumn {
+                        qualifier: None,
+                        name,
+                        data_type: DataType::Unknown,
+                    });
+                }
+            }
+        }
+        let num_columns = outputs.len();
+        let mut node_id = self.query_graph.project(relation.node_id, outputs);
+        if select.distinct {
+            node_id = self.distinct(node_id, num_columns);
+        }
+        Ok(self.relation(node_id, Scope { columns }.with_qualifier(None)))
+    }
+
+    /// Binds the relations in the FROM clause as a tree of cross joins.
+    fn bind_from(&mut self, from: &[ast::TableRef]) -> Result<Relation, SqlError> {
+        let mut relations = from
+            .iter()
+            .map(|table_ref| self.bind_table_ref(table_ref))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter();
+        let Some(mut result) = relations.next() else {
+            return Err(SqlError::Bind(
+                "queries without FROM clause are not supported".to_string(),
+            ));
+        };
+        for relation in relations {
+            let node_id = self
+                .query_graph
+                .inner_join(result.node_id, relation.node_id, Vec::new());
+            result.scope.columns.extend(relation.scope.columns);
+            result.node_id = node_id;
+        }
+        Ok(result)
+    }
+
+    fn bind_table_ref(&mut self, table_ref: &ast::TableRef) -> Result<Relation, SqlError> {
+        match table_ref {
+            ast::TableRef::Table { name, alias } => {
+                let Some(table) = self.catalog.table_by_name(name) else {
+                    return Err(SqlError::Bind(format!("table {} not found", name)));
+                };
+                let node_id = self.query_graph.catalog_table_scan(table.id);
+                let qualifier = alias.as_ref().unwrap_or(name);
+                let scope = Scope {
+                    columns: table
+                        .columns
+                        .iter()
+                        .map(|column| ScopeColumn {
+                            qualifier: Some(qualifier.clone()),
+                            name: Some(column.name.clone()),
+                            data_type: DataType::Unknown,
+                        })
+                        .collect(),
+                };
+                Ok(self.relation(node_id, scope))
+            }
+            ast::TableRef::Derived { query, alias } => {
+                let relation = self.bind_query(query)?;
+                Ok(Relation {
+                    node_id: relation.node_id,
+                    scope: relation.scope.with_qualifier(alias.as_ref()),
+                })
+            }
+            ast::TableRef::Join {
+                kind,
+                left,
+                right,
+                on,
+            } => {
+                let left = self.bind_table_ref(left)?;
+                let right = self.bind_table_ref(right)?;
+                let mut scope = left.scope;
+                scope.columns.extend(right.scope.columns);
+                let conditions = if let Some(on) = on {
+                    let context = ExprContext {
+                        scope: &scope,
+                        grouping: None,
+                        clause: "JOIN",
+                    };
+                    self.bind_conjunction(on, &context)?
+                } else {
+                    Vec::new()
+                };
+                let join_type = match kind {
+                    ast::JoinKind::Inner | ast::JoinKind::Cross => JoinType::Inner,
+                    ast::JoinKind::LeftOuter => JoinType::LeftOuter,
+                    ast::JoinKind::RightOuter => JoinType::RightOuter,
+                    ast::JoinKind::FullOuter => JoinType::FullOuter,
+                };
+                let node_id =
+                    self.query_graph
+                        .join(join_type, left.node_id, right.node_id, conditions);
+                Ok(Relation { node_id, scope })
+            }
+        }
+    }
+
+    /// Adds a projection with the grouping expressions followed by the arguments
+    /// of the aggregate functions, and an aggregate node on top of it.
+    fn bind_aggregation(
+        &mut self,
+        relation: Relation,
+        group_by: &[ast::Expr],
+        aggregates: Vec<ast::Expr>,
+    ) -> Result<(NodeId, Grouping, Scope), SqlError> {
+        let mut outputs: Vec<ScalarExprRef> = Vec::new();
+        let mut columns = Vec::new();
+        let context = ExprContext {
+            scope: &relation.scope,
+            grouping: None,
+            clause: "GROUP BY",
+        };
+        for expr in group_by.iter() {
+            let bound_expr = self.bind_expr(expr, &context)?;
+            // Grouping columns can still be referenced by name after the aggregation
+            let column = match &*bound_expr {
+                ScalarExpr::InputRef { index } => relation.scope.columns[*index].clone(),
+                _ => ScopeColumn {
+                    qualifier: None,
+                    name: None,
+                    data_type: DataType::Unknown,
+                },
+            };
+            outputs.push(bound_expr);
+            columns.push(column);
+        }
+        let bound_group_exprs = outputs.clone();
+        let group_key: BTreeSet<usize> = (0..outputs.len()).collect();
+        let mut aggregate_exprs = Vec::new();
+        for aggregate in aggregates.iter() {
+            let ast::Expr::Function {
+                name,
+                args,
+                star,
+                distinct,
+            } = aggregate
+            else {
+                unreachable!()
+            };
+            if *distinct {
+                return Err(SqlError::Bind(format!(
+                    "DISTINCT is not supported in aggregate function {}",
+                    name
+                )));
+            }
+            let op = aggregate_op(name).unwrap();
+            let operands = if *star {
+                if op != AggregateOp::Count {
+                    return Err(SqlError::Bind(format!("{}(*) is not supported", name)));
+                }
+                Vec::new()
+            } else {
+                if args.len() != 1 {
+                    return Err(SqlError::Bind(format!(
+                        "aggregate function {} expects one argument",
+                        name
+                    )));
+                }
+                let context = ExprContext {
+                    scope: &relation.scope,
+                    grouping: None,
+                    clause: "aggregate function arguments",
+                };
+                let bound_expr = self.bind_expr(&args[0], &context)?;
+                let index = match outputs.iter().position(|e| *e == bound_expr) {
+                    Some(index) => index,
+                    None => {
+                        outputs.push(bound_expr);
+                        outputs.len() - 1
+                    }
+                };
+                vec![index]
+            };
+            aggregate_exprs.push(AggregateExpr { op, operands }.into());
+            columns.push(ScopeColumn {
+                qualifier: None,
+                name: None,
+                data_type: DataType::Unknown,
+            });
+        }
+        let project = self.query_graph.project(relation.node_id, outputs);
+        let node_id = self.query_graph.add_node(QueryNode::Aggregate {
+            group_key,
+            aggregates: aggregate_exprs,
+            input: project,
+        });
+        let grouping = Grouping {
+            group_exprs: group_by.to_vec(),
+            bound_group_exprs,
+            aggregates,
+            input_scope: relation.scope,
+        };
+        let relation = self.relation(node_id, Scope { columns });
+        Ok((relation.node_id, grouping, relation.scope))
+    }
+
+    /// Adds an aggregate node removing the duplicated rows of the given input.
+    fn distinct(&mut self, input: NodeId, num_columns: usize) -> NodeId {
+        self.query_graph.add_node(QueryNode::Aggregate {
+            group_key: (0..num_columns).collect(),
+            aggregates: Vec::new(),
+            input,
+        })
+    }
+
+    /// Fills the data types of the columns in the scope from the row type of the
+    /// given node.
+    fn relation(&self, node_id: NodeId, mut scope: Scope) -> Relation {
+        let row_type = row_type(&self.query_graph, node_id);
+        for (column, data_type) in scope.columns.iter_mut().zip(row_type.iter()) {
+            column.data_type = data_type.clone();
+        }
+        Relation { node_id, scope }
+    }
+
+    /// Binds a boolean expression splitting it into its conjuncts.
+    fn bind_conjunction(
+        &mut self,
+        expr: &ast::Expr,
+        context: &ExprContext,
+    ) -> Result<Vec<ScalarExprRef>, SqlError> {
+        match expr {
+            ast::Expr::Binary {
+                op: ast::BinaryOperator::And,
+                left,
+                right,
+            } => {
+                let mut conditions = self.bind_conjunction(left, context)?;
+                conditions.extend(self.bind_conjunction(right, context)?);
+                Ok(conditions)
+            }
+            _ => Ok(vec![self.bind_expr(expr, context)?]),
+        }
+    }
+
+    fn bind_expr(
+        &mut self,
+        expr: &ast::Expr,
+        context: &ExprContext,
+    ) -> Result<ScalarExprRef, SqlError> {
+        if let Some(grouping) = context.grouping {
+            if let Some(index) = grouping.group_exprs.iter().position(|e| e == expr) {
+                return Ok(ScalarExpr::input_ref(index).into());
+            }
+            if let Some(index) = grouping.aggregates.iter().position(|e| e == expr) {
+                return Ok(ScalarExpr::input_ref(grouping.group_exprs.len() + index).into());
+            }
+            // Grouping expressions may be spelled differently, ie. using qualified
+            // column names, so compare them once bound.
+            if !matches!(expr, ast::Expr::Column { .. }) && !contains_subqueries_or_functions(expr)
+            {
+                let input_context = ExprContext {
+                    scope: &grouping.input_scope,
+                    grouping: None,
+                    clause: context.clause,
+                };
+                if let Ok(bound_expr) = self.bind_expr(expr, &input_context) {
+                    if let Some(index) = grouping
+                        .bound_group_exprs
+                        .iter()
+                        .position(|e| *e == bound_expr)
+                    {
+                        return Ok(ScalarExpr::input_ref(index).into());
+                    }
+                }
+            }
+        }
+        let bound_expr = match expr {
+            ast::Expr::Column { qualifier, name } => {
+                return self.bind_column(qualifier.as_deref(), name, context)
+            }
+            ast::Expr::Literal(literal) => ScalarExpr::Literal(match literal {
+                ast::Literal::Null => Literal::build_null(DataType::Unknown),
+                ast::Literal::Boolean(value) => Literal::new(Value::Bool(*value), DataType::Bool),
+                ast::Literal::Integer(value) => match i32::try_from(*value) {
+                    Ok(value) => Literal::new(Value::Int(value), DataType::Int),
+                    Err(_) => Literal::new(Value::BigInt(*value), DataType::BigInt),
+                },
+                ast::Literal::String(value) => {
+                    Literal::new(Value::String(value.clone()), DataType::String)
+                }
+            }),
+            ast::Expr::Binary { op, left, right } => {
+                let left = self.bind_expr(left, context)?;
+                let right = self.bind_expr(right, context)?;
+                match op {
+                    ast::BinaryOperator::And => ScalarExpr::nary(NaryOp::And, vec![left, right]),
+                    ast::BinaryOperator::Or => ScalarExpr::nary(NaryOp::Or, vec![left, right]),
+                    ast::BinaryOperator::Concat => {
+                        ScalarExpr::nary(NaryOp::Concat, vec![left, right])
+                    }
+                    _ => match binary_op(*op) {
+                        Some(op) => ScalarExpr::BinaryOp { op, left, right },
+                        None => {
+                            return Err(SqlError::Bind(format!(
+                                "binary operator {:?} is not supported",
+                                op
+                            )))
+                        }
+                    },
+                }
+            }
+            ast::Expr::Unary { op, expr } => {
+                let operand = self.bind_expr(expr, context)?;
+                let op = match op {
+                    ast::UnaryOperator::Not => UnaryOp::Not,
+                    ast::UnaryOperator::Minus => UnaryOp::Neg,
+                };
+                ScalarExpr::UnaryOp { op, operand }
+            }
+            ast::Expr::IsNull { expr, negated } => {
+                let operand = self.bind_expr(expr, context)?;
+                let op = if *negated {
+                    UnaryOp::IsNotNull
+                } else {
+                    UnaryOp::IsNull
+                };
+                ScalarExpr::UnaryOp { op, operand }
+            }
+            ast::Expr::Function { name, .. } => {
+                return Err(if aggregate_op(name).is_some() {
+                    SqlError::Bind(format!(
+                        "aggregate functions are not allowed in {}",
+                        context.clause
+                    ))
+                } else {
+                    SqlError::Bind(format!("function {} not found", name))
+                })
+            }
+            ast::Expr::Exists { query, negated } => {
+                let (subquery, _) = self.bind_subquery(query, context)?;
+                let exists = ScalarExpr::ExistsSubquery { subquery };
+                if *negated {
+                    exists.unary(UnaryOp::Not)
+                } else {
+                    exists
+                }
+            }
+            ast::Expr::Subquery(query) => {
+                let (subquery, num_columns) = self.bind_subquery(query, context)?;
+                check_single_column(num_columns)?;
+                ScalarExpr::ScalarSubquery { subquery }
+            }
+            ast::Expr::InSubquery {
+                expr,
+                query,
+                negated,
+            } => {
+                let scalar_operand = self.bind_expr(expr, context)?;
+                let (subquery, num_columns) = self.bind_subquery(query, context)?;
+                check_single_column(num_columns)?;
+                let in_subquery = ScalarExpr::ScalarSubqueryCmp {
+                    op: ScalarSubqueryCmpOp::EqAny,
+                    scalar_operand,
+                    subquery,
+                };
+                // x NOT IN (S) is equivalent to NOT (x = ANY (S)), which evaluates to
+                // NULL if x is NULL or S contains a NULL value but not x
+                if *negated {
+                    in_subquery.unary(UnaryOp::Not)
+                } else {
+                    in_subquery
+                }
+            }
+            ast::Expr::Quantified {
+                op,
+                quantifier,
+                expr,
+                query,
+            } => {
+                let Some(op) = subquery_cmp_op(*op, *quantifier) else {
+                    return Err(SqlError::Bind(format!(
+                        "operator {:?} {:?} is not supported",
+                        op, quantifier
+                    )));
+                };
+                let scalar_operand = self.bind_expr(expr, context)?;
+                let (subquery, num_columns) = self.bind_subquery(query, context)?;
+                check_single_column(num_columns)?;
+                ScalarExpr::ScalarSubqueryCmp {
+                    op,
+                    scalar_operand,
+                    subquery,
+                }
+            }
+        };
+        Ok(bound_expr.into())
+    }
+
+    fn bind_column(
+        &mut self,
+        qualifier: Option<&str>,
+        name: &str,
+        context: &ExprContext,
+    ) -> Result<ScalarExprRef, SqlError> {
+        if let Some(index) = context.scope.resolve(qualifier, name)? {
+            return Ok(ScalarExpr::input_ref(index).into());
+        }
+        if let Some(grouping) = context.grouping {
+            if grouping.input_scope.resolve(qualifier, name)?.is_some() {
+                return Err(SqlError::Bind(format!(
+                    "column {} must appear in the GROUP BY clause or be used in an aggregate function",
+                    column_name(qualifier, name)
+                )));
+            }
+        }
+        self.bind_outer_column(qualifier, name, self.correlation_levels.len())?
+            .ok_or_else(|| {
+                SqlError::Bind(format!("column {} not found", column_name(qualifier, name)))
+            })
+    }
+
+    /// Resolves a column from the scope of the query blocks containing the subquery
+    /// at the given depth, adding it as a parameter of the correlation context
+    /// of the subquery. The parameter is resolved recursively, so that every
+    /// subquery in between also forwards the referenced column.
+    fn bind_outer_column(
+        &mut self,
+        qualifier: Option<&str>,
+        name: &str,
+        depth: usize,
+    ) -> Result<Option<ScalarExprRef>, SqlError> {
+        if depth == 0 {
+            return Ok(None);
+        }
+        let level = &self.correlation_levels[depth - 1];
+        let (parameter, data_type) = match level.outer_scope.resolve(qualifier, name)? {
+            Some(index) => (
+                Rc::new(ScalarExpr::input_ref(index)),
+                level.outer_scope.columns[index].data_type.clone(),
+            ),
+            None => match self.bind_outer_column(qualifier, name, depth - 1)? {
+                Some(parameter) => {
+                    let ScalarExpr::CorrelatedInputRef { data_type, .. } = &*parameter else {
+                        unreachable!()
+                    };
+                    let data_type = data_type.clone();
+                    (parameter, data_type)
+                }
+                None => return Ok(None),
+            },
+        };
+        let parameters = &mut self.correlation_levels[depth - 1].parameters;
+        let index = match parameters.iter().position(|p| *p == parameter) {
+            Some(index) => index,
+            None => {
+                parameters.push(parameter);
+                parameters.len() - 1
+            }
+        };
+        Ok(Some(
+            ScalarExpr::CorrelatedInputRef {
+                context_offset: 0,
+                index,
+                data_type,
+            }
+            .into(),
+        ))
+    }
+
+    /// Binds a subquery within the given context, returning the subquery and
+    /// the number of columns it projects.
+    fn bind_subquery(
+        &mut self,
+        query: &ast::Query,
+        context: &ExprContext,
+    ) -> Result<(Subquery<ScalarExpr>, usize), SqlError> {
+        self.correlation_levels.push(CorrelationLevel {
+            outer_scope: context.scope.clone(),
+            parameters: Vec::new(),
+        });
+        let relation = self.bind_query(query);
+        let level = self.correlation_levels.pop().unwrap();
+        let relation = relation?;
+        let root = self.query_graph.add_subquery(relation.node_id);
+        let correlation = if level.parameters.is_empty() {
+            None
+        } else {
+            Some(CorrelationContext {
+                parameters: level.parameters,
+            })
+        };
+        Ok((Subquery { root, correlation }, relation.scope.columns.len()))
+    }
+}
+
+fn column_name(qualifier: Option<&str>, name: &str) -> String {
+    match qualifier {
+        Some(qualifier) => format!("{}.{}", qualifier, name),
+        None => name.to_string(),
+    }
+}
+
+fn check_single_column(num_columns: usize) -> Result<(), SqlError> {
+    if num_columns != 1 {
+        return Err(SqlError::Bind(format!(
+            "subquery must return only one column, found {}",
+            num_columns
+        )));
+    }
+    Ok(())
+}
+
+fn aggregate_op(name: &str) -> Option<AggregateOp> {
+    match name {
+        "count" => Some(AggregateOp::Count),
+        "min" => Some(AggregateOp::Min),
+        "max" => Some(AggregateOp::Max),
+        _ => None,
+    }
+}
+
+fn binary_op(op: ast::BinaryOperator) -> Option<BinaryOp> {
+    match op {
+        ast::BinaryOperator::Eq => Some(BinaryOp::Eq),
+        ast::BinaryOperator::NotDistinctFrom => Some(BinaryOp::RawEq),
+        ast::BinaryOperator::Lt => Some(BinaryOp::Lt),
+        ast::BinaryOperator::Le => Some(BinaryOp::Le),
+        ast::BinaryOperator::Gt => Some(BinaryOp::Gt),
+        ast::BinaryOperator::Ge => Some(BinaryOp::Ge),
+        ast::BinaryOperator::NotEq => Some(BinaryOp::Ne),
+        ast::BinaryOperator::Plus => Some(BinaryOp::Add),
+        ast::BinaryOperator::Minus => Some(BinaryOp::Sub),
+        ast::BinaryOperator::Multiply => Some(BinaryOp::Mul),
+        ast::BinaryOperator::Divide => Some(BinaryOp::Div),
+        ast::BinaryOperator::Modulo => Some(BinaryOp::Mod),
+        _ => None,
+    }
+}
+
+fn subquery_cmp_op(
+    op: ast::BinaryOperator,
+    quantifier: ast::Quantifier,
+) -> Option<ScalarSubqueryCmpOp> {
+    use ast::{BinaryOperator, Quantifier};
+    match (op, quantifier) {
+        (BinaryOperator::Eq, Quantifier::Any) => Some(ScalarSubqueryCmpOp::EqAny),
+        (BinaryOperator::Lt, Quantifier::Any) => Some(ScalarSubqueryCmpOp::LtAny),
+        (BinaryOperator::Le, Quantifier::Any) => Some(ScalarSubqueryCmpOp::LteAny),
+        (BinaryOperator::Gt, Quantifier::Any) => Some(ScalarSubqueryCmpOp::GtAny),
+        (BinaryOperator::Ge, Quantifier::Any) => Some(ScalarSubqueryCmpOp::GteAny),
+        (BinaryOperator::Eq, Quantifier::All) => Some(ScalarSubqueryCmpOp::EqAll),
+        (BinaryOperator::Lt, Quantifier::All) => Some(ScalarSubqueryCmpOp::LtAll),
+        (BinaryOperator::Le, Quantifier::All) => Some(ScalarSubqueryCmpOp::LteAll),
+        (BinaryOperator::Gt, Quantifier::All) => Some(ScalarSubqueryCmpOp::GtAll),
+        (BinaryOperator::Ge, Quantifier::All) => Some(ScalarSubqueryCmpOp::GteAll),
+        _ => None,
+    }
+}
+
+/// Collects the aggregate function calls in the given expression, without
+/// descending into subqueries, which are bound independently.
+fn collect_aggregates(expr: &ast::Expr, aggregates: &mut Vec<ast::Expr>) {
+    match expr {
+        ast::Expr::Function { name, args, .. } => {
+            if aggregate_op(name).is_some() {
+                if !aggregates.contains(expr) {
+                    aggregates.push(expr.clone());
+                }
+            } else {
+                for arg in args.iter() {
+                    collect_aggregates(arg, aggregates);
+                }
+            }
+        }
+        ast::Expr::Binary { left, right, .. } => {
+            collect_aggregates(left, aggregates);
+            collect_aggregates(right, aggregates);
+        }
+        ast::Expr::Unary { expr, .. }
+        | ast::Expr::IsNull { expr, .. }
+        | ast::Expr::InSubquery { expr, .. }
+        | ast::Expr::Quantified { expr, .. } => collect_aggregates(expr, aggregates),
+        ast::Expr::Column { .. }
+        | ast::Expr::Literal(_)
+        | ast::Expr::Exists { .. }
+        | ast::Expr::Subquery(_) => {}
+    }
+}
+
+fn contains_subqueries_or_functions(expr: &ast::Expr) -> bool {
+    match expr {
+        ast::Expr::Column { .. } | ast::Expr::Literal(_) => false,
+        ast::Expr::Binary { left, right, .. } => {
+            contains_subqueries_or_functions(left) || contains_subqueries_or_functions(right)
+        }
+        ast::Expr::Unary { expr, .. } | ast::Expr::IsNull { expr, .. } => {
+            contains_subqueries_or_functions(expr)
+        }
+        ast::Expr::Function { .. }
+        | ast::Expr::Exists { .. }
+        | ast::Expr::Subquery(_)
+        | ast::Expr::InSubquery { .. }
+        | ast::Expr::Quantified { .. } => true,
+    }
+}
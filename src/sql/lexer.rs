@@ -0,0 +1,196 @@
+//! Tokenizer for the SQL dialect supported by the parser.
+use core::fmt;
+
+use super::SqlError;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Token {
+    /// Unquoted identifiers are lowercased. Keywords are also returned as
+    /// identifiers, it is up to the parser to decide whether an identifier
+    /// is a keyword in a given position.
+    Identifier(String),
+    /// Double-quoted identifiers preserve their case and are never treated as
+    /// keywords.
+    QuotedIdentifier(String),
+    Number(String),
+    String(String),
+    Comma,
+    Dot,
+    LeftParen,
+    RightParen,
+    Star,
+    Plus,
+    Minus,
+    Slash,
+    Percent,
+    Eq,
+    NotEq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Concat,
+    Semicolon,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Identifier(name) => write!(f, "{}", name),
+            Token::QuotedIdentifier(name) => write!(f, "\"{}\"", name),
+            Token::Number(value) => write!(f, "{}", value),
+            Token::String(value) => write!(f, "'{}'", value),
+            Token::Comma => write!(f, ","),
+            Token::Dot => write!(f, "."),
+            Token::LeftParen => write!(f, "("),
+            Token::RightParen => write!(f, ")"),
+            Token::Star => write!(f, "*"),
+            Token::Plus => write!(f, "+"),
+            Token::Minus => write!(f, "-"),
+            Token::Slash => write!(f, "/"),
+            Token::Percent => write!(f, "%"),
+            Token::Eq => write!(f, "="),
+            Token::NotEq => write!(f, "<>"),
+            Token::Lt => write!(f, "<"),
+            Token::Le => write!(f, "<="),
+            Token::Gt => write!(f, ">"),
+            Token::Ge => write!(f, ">="),
+            Token::Concat => write!(f, "||"),
+            Token::Semicolon => write!(f, ";"),
+        }
+    }
+}
+
+/// Splits the given SQL text into tokens. Comments starting with `--` are skipped.
+pub fn tokenize(sql: &str) -> Result<Vec<Token>, SqlError> {
+    let chars = sql.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while pos < chars.len() {
+        let c = chars[pos];
+        if c.is_whitespace() {
+            pos += 1;
+            continue;
+        }
+        if c == '-' && chars.get(pos + 1) == Some(&'-') {
+            while pos < chars.len() && chars[pos] != '\n' {
+                pos += 1;
+            }
+            continue;
+        }
+        if c.is_ascii_alphabetic() || c == '_' {
+            let start = pos;
+            while pos < chars.len() && (chars[pos].is_ascii_alphanumeric() || chars[pos] == '_') {
+                pos += 1;
+            }
+            let identifier = chars[start..pos].iter().collect::<String>();
+            tokens.push(Token::Identifier(identifier.to_lowercase()));
+            continue;
+        }
+        if c.is_ascii_digit() {
+            let start = pos;
+            while pos < chars.len() && chars[pos].is_ascii_digit() {
+                pos += 1;
+            }
+            tokens.push(Token::Number(chars[start..pos].iter().collect()));
+            continue;
+        }
+        if c == '\'' || c == '"' {
+            // Both string literals and quoted identifiers escape the quote
+            // character by duplicating it.
+            let quote = c;
+            let mut value = String::new();
+            pos += 1;
+            loop {
+                match chars.get(pos) {
+                    None => {
+                        return Err(SqlError::Parse(format!(
+                            "unterminated {}",
+                            if quote == '\'' {
+                                "string literal"
+                            } else {
+                                "quoted identifier"
+                            }
+                        )))
+                    }
+                    Some(c) if *c == quote => {
+                        if chars.get(pos + 1) == Some(&quote) {
+                            value.push(quote);
+                            pos += 2;
+                        } else {
+                            pos += 1;
+                            break;
+                        }
+                    }
+                    Some(c) => {
+                        value.push(*c);
+                        pos += 1;
+                    }
+                }
+            }
+            tokens.push(if quote == '\'' {
+                Token::String(value)
+            } else {
+                Token::QuotedIdentifier(value)
+            });
+            continue;
+        }
+        let next = chars.get(pos + 1).cloned();
+        let (token, len) = match (c, next) {
+            ('<', Some('=')) => (Token::Le, 2),
+            ('<', Some('>')) => (Token::NotEq, 2),
+            ('>', Some('=')) => (Token::Ge, 2),
+            ('!', Some('=')) => (Token::NotEq, 2),
+            ('|', Some('|')) => (Token::Concat, 2),
+            (',', _) => (Token::Comma, 1),
+            ('.', _) => (Token::Dot, 1),
+            ('(', _) => (Token::LeftParen, 1),
+            (')', _) => (Token::RightParen, 1),
+            ('*', _) => (Token::Star, 1),
+            ('+', _) => (Token::Plus, 1),
+            ('-', _) => (Token::Minus, 1),
+            ('/', _) => (Token::Slash, 1),
+            ('%', _) => (Token::Percent, 1),
+            ('=', _) => (Token::Eq, 1),
+            ('<', _) => (Token::Lt, 1),
+            ('>', _) => (Token::Gt, 1),
+            (';', _) => (Token::Semicolon, 1),
+            _ => {
+                return Err(SqlError::Parse(format!(
+                    "unexpected character '{}' at position {}",
+                    c, pos
+                )))
+            }
+        };
+        tokens.push(token);
+        pos += len;
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{tokenize, Token};
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("SELECT a.b, 'it''s' FROM \"T\" -- comment\nWHERE x <= 10").unwrap(),
+            vec![
+                Token::Identifier("select".to_string()),
+                Token::Identifier("a".to_string()),
+                Token::Dot,
+                Token::Identifier("b".to_string()),
+                Token::Comma,
+                Token::String("it's".to_string()),
+                Token::Identifier("from".to_string()),
+                Token::QuotedIdentifier("T".to_string()),
+                Token::Identifier("where".to_string()),
+                Token::Identifier("x".to_string()),
+                Token::Le,
+                Token::Number("10".to_string()),
+            ]
+        );
+        assert!(tokenize("SELECT 'unterminated").is_err());
+    }
+}
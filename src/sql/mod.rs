@@ -0,0 +1,44 @@
+//! A small SQL front-end: a parser for a subset of the SQL language and a binder
+//! that translates the parsed queries into query graphs, resolving the table
+//! references against a catalog.
+//!
+//! Supported constructs: SELECT with WHERE, GROUP BY and HAVING clauses, joins,
+//! derived tables, UNION [ALL], and subqueries in scalar positions, including
+//! EXISTS, IN and quantified comparisons, which may be correlated.
+use core::fmt;
+use std::rc::Rc;
+
+use crate::{catalog::Catalog, query_graph::QueryGraph};
+
+pub mod ast;
+pub mod binder;
+pub mod lexer;
+pub mod parser;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SqlError {
+    /// The SQL text is not syntactically valid.
+    Parse(String),
+    /// The query is syntactically valid but refers to unknown objects or uses
+    /// unsupported features.
+    Bind(String),
+}
+
+impl fmt::Display for SqlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SqlError::Parse(msg) => write!(f, "parse error: {}", msg),
+            SqlError::Bind(msg) => write!(f, "bind error: {}", msg),
+        }
+    }
+}
+
+/// Parses the given SQL text.
+pub fn parse(sql: &str) -> Result<ast::Query, SqlError> {
+    parser::parse_query(sql)
+}
+
+/// Parses the given SQL text and builds a query graph for it.
+pub fn build_query_graph(catalog: &Rc<Catalog>, sql: &str) -> Result<QueryGraph, SqlError> {
+    binder::bind_query(catalog, &parse(sql)?)
+}
@@ -0,0 +1,629 @@
+//! Recursive descent parser for the supported subset of SQL.
+use super::{ast::*, lexer::tokenize, lexer::Token, SqlError};
+
+/// Words that cannot be used as implicit aliases.
+const RESERVED_KEYWORDS: &[&str] = &[
+    "all",
+    "and",
+    "any",
+    "as",
+    "by",
+    "cross",
+    "distinct",
+    "except",
+    "exists",
+    "false",
+    "from",
+    "full",
+    "group",
+    "having",
+    "in",
+    "inner",
+    "intersect",
+    "is",
+    "join",
+    "left",
+    "limit",
+    "not",
+    "null",
+    "offset",
+    "on",
+    "or",
+    "order",
+    "outer",
+    "right",
+    "select",
+    "some",
+    "true",
+    "union",
+    "where",
+];
+
+/// Parses a single query, optionally terminated by a semicolon.
+pub fn parse_query(sql: &str) -> Result<Query, SqlError> {
+    let mut parser = Parser {
+        tokens: tokenize(sql)?,
+        pos: 0,
+    };
+    let query = parser.parse_query()?;
+    parser.consume_token(&Token::Semicolon);
+    if let Some(token) = parser.peek() {
+        return Err(SqlError::Parse(format!("unexpected token '{}'", token)));
+    }
+    Ok(query)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + n)
+    }
+
+    fn is_keyword(token: Option<&Token>, keyword: &str) -> bool {
+        matches!(token, Some(Token::Identifier(name)) if name == keyword)
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        Self::is_keyword(self.peek(), keyword)
+    }
+
+    /// Consumes the next token if it is the given keyword.
+    fn consume_keyword(&mut self, keyword: &str) -> bool {
+        if self.peek_keyword(keyword) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn consume_keywords(&mut self, keywords: &[&str]) -> bool {
+        if keywords
+            .iter()
+            .enumerate()
+            .all(|(i, keyword)| Self::is_keyword(self.peek_nth(i), keyword))
+        {
+            self.pos += keywords.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), SqlError> {
+        if self.consume_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&keyword.to_uppercase()))
+        }
+    }
+
+    fn consume_token(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_token(&mut self, token: &Token) -> Result<(), SqlError> {
+        if self.consume_token(token) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("'{}'", token)))
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> SqlError {
+        match self.peek() {
+            Some(token) => SqlError::Parse(format!("expected {}, found '{}'", expected, token)),
+            None => SqlError::Parse(format!("expected {}, found end of input", expected)),
+        }
+    }
+
+    fn parse_identifier(&mut self) -> Result<String, SqlError> {
+        match self.peek() {
+            Some(Token::Identifier(name)) if !RESERVED_KEYWORDS.contains(&name.as_str()) => {
+                let name = name.clone();
+                self.pos += 1;
+                Ok(name)
+            }
+            Some(Token::QuotedIdentifier(name)) => {
+                let name = name.clone();
+                self.pos += 1;
+                Ok(name)
+            }
+            _ => Err(self.unexpected("identifier")),
+        }
+    }
+
+    /// Parses an optional alias, either `AS alias` or just `alias`.
+    fn parse_optional_alias(&mut self) -> Result<Option<String>, SqlError> {
+        if self.consume_keyword("as") {
+            return self.parse_identifier().map(Some);
+        }
+        match self.peek() {
+            Some(Token::Identifier(name)) if !RESERVED_KEYWORDS.contains(&name.as_str()) => {
+                self.parse_identifier().map(Some)
+            }
+            Some(Token::QuotedIdentifier(_)) => self.parse_identifier().map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    fn parse_query(&mut self) -> Result<Query, SqlError> {
+        let mut left = self.parse_query_primary()?;
+        loop {
+            let op = if self.consume_keyword("union") {
+                SetOperator::Union
+            } else {
+                break;
+            };
+            let all = if self.consume_keyword("all") {
+                true
+            } else {
+                self.consume_keyword("distinct");
+                false
+            };
+            let right = self.parse_query_primary()?;
+            left = Query::SetOperation {
+                op,
+                all,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+        }
+        Ok(left)
+    }
+
+    fn parse_query_primary(&mut self) -> Result<Query, SqlError> {
+        if self.consume_token(&Token::LeftParen) {
+            let query = self.parse_query()?;
+            self.expect_token(&Token::RightParen)?;
+            Ok(query)
+        } else {
+            Ok(Query::Select(Box::new(self.parse_select()?)))
+        }
+    }
+
+    fn parse_select(&mut self) -> Result<Select, SqlError> {
+        self.expect_keyword("select")?;
+        let distinct = if self.consume_keyword("distinct") {
+            true
+        } else {
+            self.consume_keyword("all");
+            false
+        };
+        let mut projection = vec![self.parse_select_item()?];
+        while self.consume_token(&Token::Comma) {
+            projection.push(self.parse_select_item()?);
+        }
+        let mut from = Vec::new();
+        if self.consume_keyword("from") {
+            from.push(self.parse_table_ref()?);
+            while self.consume_token(&Token::Comma) {
+                from.push(self.parse_table_ref()?);
+            }
+        }
+        let selection = if self.consume_keyword("where") {
+            Some(self.parse_expr()?)
+        } else {
+            None
+        };
+        let mut group_by = Vec::new();
+        if self.consume_keywords(&["group", "by"]) {
+            group_by.push(self.parse_expr()?);
+            while self.consume_token(&Token::Comma) {
+                group_by.push(self.parse_expr()?);
+            }
+        }
+        let having = if self.consume_keyword("having") {
+            Some(self.parse_expr()?)
+        } else {
+            None
+        };
+        Ok(Select {
+            distinct,
+            projection,
+            from,
+            selection,
+            group_by,
+            having,
+        })
+    }
+
+    fn parse_select_item(&mut self) -> Result<SelectItem, SqlError> {
+        if self.consume_token(&Token::Star) {
+            return Ok(SelectItem::Wildcard);
+        }
+        if let (
+            Some(Token::Identifier(_) | Token::QuotedIdentifier(_)),
+            Some(Token::Dot),
+            Some(Token::Star),
+        ) = (self.peek(), self.peek_nth(1), self.peek_nth(2))
+        {
+            let qualifier = self.parse_identifier()?;
+            self.pos += 2;
+            return Ok(SelectItem::QualifiedWildcard(qualifier));
+        }
+        let expr = self.parse_expr()?;
+        let alias = self.parse_optional_alias()?;
+        Ok(SelectItem::Expr { expr, alias })
+    }
+
+    fn parse_table_ref(&mut self) -> Result<TableRef, SqlError> {
+        let mut left = self.parse_table_factor()?;
+        loop {
+            let kind = if self.consume_keyword("join") || self.consume_keywords(&["inner", "join"])
+            {
+                JoinKind::Inner
+            } else if self.consume_keywords(&["left", "join"])
+                || self.consume_keywords(&["left", "outer", "join"])
+            {
+                JoinKind::LeftOuter
+            } else if self.consume_keywords(&["right", "join"])
+                || self.consume_keywords(&["right", "outer", "join"])
+            {
+                JoinKind::RightOuter
+            } else if self.consume_keywords(&["full", "join"])
+                || self.consume_keywords(&["full", "outer", "join"])
+            {
+                JoinKind::FullOuter
+            } else if self.consume_keywords(&["cross", "join"]) {
+                JoinKind::Cross
+            } else {
+                break;
+            };
+            let right = self.parse_table_factor()?;
+            let on = if kind == JoinKind::Cross {
+                None
+            } else {
+                self.expect_keyword("on")?;
+                Some(self.parse_expr()?)
+            };
+            left = TableRef::Join {
+                kind,
+                left: Box::new(left),
+                right: Box::new(right),
+                on,
+            };
+        }
+        Ok(left)
+    }
+
+    fn parse_table_factor(&mut self) -> Result<TableRef, SqlError> {
+        if self.consume_token(&Token::LeftParen) {
+            if self.peek_keyword("select") || self.peek() == Some(&Token::LeftParen) {
+                let query = self.parse_query()?;
+                self.expect_token(&Token::RightParen)?;
+                let alias = self.parse_optional_alias()?;
+                return Ok(TableRef::Derived {
+                    query: Box::new(query),
+                    alias,
+                });
+            }
+            let table_ref = self.parse_table_ref()?;
+            self.expect_token(&Token::RightParen)?;
+            return Ok(table_ref);
+        }
+        let name = self.parse_identifier()?;
+        let alias = self.parse_optional_alias()?;
+        Ok(TableRef::Table { name, alias })
+    }
+
+    pub fn parse_expr(&mut self) -> Result<Expr, SqlError> {
+        self.parse_or()
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, SqlError> {
+        let mut left = self.parse_and()?;
+        while self.consume_keyword("or") {
+            let right = self.parse_and()?;
+            left = binary(BinaryOperator::Or, left, right);
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, SqlError> {
+        let mut left = self.parse_not()?;
+        while self.consume_keyword("and") {
+            let right = self.parse_not()?;
+            left = binary(BinaryOperator::And, left, right);
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, SqlError> {
+        if self.peek_keyword("not") && !Self::is_keyword(self.peek_nth(1), "exists") {
+            self.pos += 1;
+            let expr = self.parse_not()?;
+            return Ok(Expr::Unary {
+                op: UnaryOperator::Not,
+                expr: Box::new(expr),
+            });
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, SqlError> {
+        let left = self.parse_concat()?;
+        if self.consume_keyword("is") {
+            let negated = self.consume_keyword("not");
+            if self.consume_keyword("null") {
+                return Ok(Expr::IsNull {
+                    expr: Box::new(left),
+                    negated,
+                });
+            }
+            self.expect_keyword("distinct")?;
+            self.expect_keyword("from")?;
+            let right = self.parse_concat()?;
+            let expr = binary(BinaryOperator::NotDistinctFrom, left, right);
+            // IS DISTINCT FROM is the negation of IS NOT DISTINCT FROM
+            return Ok(if negated {
+                expr
+            } else {
+                Expr::Unary {
+                    op: UnaryOperator::Not,
+                    expr: Box::new(expr),
+                }
+            });
+        }
+        let negated_in = self.peek_keyword("not") && Self::is_keyword(self.peek_nth(1), "in");
+        if negated_in || self.peek_keyword("in") {
+            self.pos += if negated_in { 2 } else { 1 };
+            self.expect_token(&Token::LeftParen)?;
+            let query = self.parse_query()?;
+            self.expect_token(&Token::RightParen)?;
+            return Ok(Expr::InSubquery {
+                expr: Box::new(left),
+                query: Box::new(query),
+                negated: negated_in,
+            });
+        }
+        let op = match self.peek() {
+            Some(Token::Eq) => BinaryOperator::Eq,
+            Some(Token::NotEq) => BinaryOperator::NotEq,
+            Some(Token::Lt) => BinaryOperator::Lt,
+            Some(Token::Le) => BinaryOperator::Le,
+            Some(Token::Gt) => BinaryOperator::Gt,
+            Some(Token::Ge) => BinaryOperator::Ge,
+            _ => return Ok(left),
+        };
+        self.pos += 1;
+        let quantifier = if self.consume_keyword("any") || self.consume_keyword("some") {
+            Some(Quantifier::Any)
+        } else if self.consume_keyword("all") {
+            Some(Quantifier::All)
+        } else {
+            None
+        };
+        if let Some(quantifier) = quantifier {
+            self.expect_token(&Token::LeftParen)?;
+            let query = self.parse_query()?;
+            self.expect_token(&Token::RightParen)?;
+            return Ok(Expr::Quantified {
+                op,
+                quantifier,
+                expr: Box::new(left),
+                query: Box::new(query),
+            });
+        }
+        let right = self.parse_concat()?;
+        Ok(binary(op, left, right))
+    }
+
+    fn parse_concat(&mut self) -> Result<Expr, SqlError> {
+        let mut left = self.parse_additive()?;
+        while self.consume_token(&Token::Concat) {
+            let right = self.parse_additive()?;
+            left = binary(BinaryOperator::Concat, left, right);
+        }
+        Ok(left)
+    }
+
+    fn parse_additive(&mut self) -> Result<Expr, SqlError> {
+        let mut left = self.parse_multiplicative()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Plus) => BinaryOperator::Plus,
+                Some(Token::Minus) => BinaryOperator::Minus,
+                _ => break,
+            };
+            self.pos += 1;
+            let right = self.parse_multiplicative()?;
+            left = binary(op, left, right);
+        }
+        Ok(left)
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Expr, SqlError> {
+        let mut left = self.parse_unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Star) => BinaryOperator::Multiply,
+                Some(Token::Slash) => BinaryOperator::Divide,
+                Some(Token::Percent) => BinaryOperator::Modulo,
+                _ => break,
+            };
+            self.pos += 1;
+            let right = self.parse_unary()?;
+            left = binary(op, left, right);
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, SqlError> {
+        if self.consume_token(&Token::Minus) {
+            let expr = self.parse_unary()?;
+            // Fold negative integer literals
+            if let Expr::Literal(Literal::Integer(value)) = expr {
+                return Ok(Expr::Literal(Literal::Integer(-value)));
+            }
+            return Ok(Expr::Unary {
+                op: UnaryOperator::Minus,
+                expr: Box::new(expr),
+            });
+        }
+        self.consume_token(&Token::Plus);
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, SqlError> {
+        match self.peek().cloned() {
+            Some(Token::Number(value)) => {
+                self.pos += 1;
+                value
+                    .parse::<i64>()
+                    .map(|value| Expr::Literal(Literal::Integer(value)))
+                    .map_err(|_| SqlError::Parse(format!("invalid number {}", value)))
+            }
+            Some(Token::String(value)) => {
+                self.pos += 1;
+                Ok(Expr::Literal(Literal::String(value)))
+            }
+            Some(Token::LeftParen) => {
+                self.pos += 1;
+                if self.peek_keyword("select") {
+                    let query = self.parse_query()?;
+                    self.expect_token(&Token::RightParen)?;
+                    return Ok(Expr::Subquery(Box::new(query)));
+                }
+                let expr = self.parse_expr()?;
+                self.expect_token(&Token::RightParen)?;
+                Ok(expr)
+            }
+            Some(Token::Identifier(name)) => match name.as_str() {
+                "null" => {
+                    self.pos += 1;
+                    Ok(Expr::Literal(Literal::Null))
+                }
+                "true" | "false" => {
+                    self.pos += 1;
+                    Ok(Expr::Literal(Literal::Boolean(name == "true")))
+                }
+                "exists" | "not" => {
+                    let negated = self.consume_keyword("not");
+                    self.expect_keyword("exists")?;
+                    self.expect_token(&Token::LeftParen)?;
+                    let query = self.parse_query()?;
+                    self.expect_token(&Token::RightParen)?;
+                    Ok(Expr::Exists {
+                        query: Box::new(query),
+                        negated,
+                    })
+                }
+                _ => self.parse_column_or_function(),
+            },
+            Some(Token::QuotedIdentifier(_)) => self.parse_column_or_function(),
+            _ => Err(self.unexpected("expression")),
+        }
+    }
+
+    fn parse_column_or_function(&mut self) -> Result<Expr, SqlError> {
+        let name = self.parse_identifier()?;
+        if self.consume_token(&Token::LeftParen) {
+            if self.consume_token(&Token::Star) {
+                self.expect_token(&Token::RightParen)?;
+                return Ok(Expr::Function {
+                    name,
+                    args: Vec::new(),
+                    star: true,
+                    distinct: false,
+                });
+            }
+            let distinct = self.consume_keyword("distinct");
+            let mut args = Vec::new();
+            if !self.consume_token(&Token::RightParen) {
+                args.push(self.parse_expr()?);
+                while self.consume_token(&Token::Comma) {
+                    args.push(self.parse_expr()?);
+                }
+                self.expect_token(&Token::RightParen)?;
+            }
+            return Ok(Expr::Function {
+                name,
+                args,
+                star: false,
+                distinct,
+            });
+        }
+        if self.consume_token(&Token::Dot) {
+            let column = self.parse_identifier()?;
+            return Ok(Expr::Column {
+                qualifier: Some(name),
+                name: column,
+            });
+        }
+        Ok(Expr::Column {
+            qualifier: None,
+            name,
+        })
+    }
+}
+
+fn binary(op: BinaryOperator, left: Expr, right: Expr) -> Expr {
+    Expr::Binary {
+        op,
+        left: Box::new(left),
+        right: Box::new(right),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(name: &str) -> Expr {
+        Expr::Column {
+            qualifier: None,
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_operator_precedence() {
+        let query = parse_query("select a from t where a = 1 or b = 2 and c").unwrap();
+        let Query::Select(select) = query else {
+            panic!("expected select")
+        };
+        assert_eq!(
+            select.selection.unwrap(),
+            binary(
+                BinaryOperator::Or,
+                binary(
+                    BinaryOperator::Eq,
+                    column("a"),
+                    Expr::Literal(Literal::Integer(1))
+                ),
+                binary(
+                    BinaryOperator::And,
+                    binary(
+                        BinaryOperator::Eq,
+                        column("b"),
+                        Expr::Literal(Literal::Integer(2))
+                    ),
+                    column("c")
+                ),
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_query("select").is_err());
+        assert!(parse_query("select a from").is_err());
+        assert!(parse_query("select a from t where").is_err());
+        assert!(parse_query("select a from t t2 t3").is_err());
+        assert!(parse_query("select a from t join u").is_err());
+    }
+}
@@ -1,22 +1,35 @@
 use std::collections::{BTreeSet, HashMap, HashSet};
+use std::rc::Rc;
 
 use datadriven::walk;
+use itertools::Itertools;
+use rust_sql::data_type::DataType;
+use rust_sql::interpreter::{execute, Database, ExecutionError, Row};
 use rust_sql::query_graph::explain::Explainer;
 use rust_sql::query_graph::json::JsonSerializer;
 use rust_sql::query_graph::optimizer::{
     build_rule, Optimizer, OptimizerContext, OptimizerListener, Replacement, DEFAULT_OPTIMIZER,
 };
-use rust_sql::query_graph::{JoinType, QueryGraph, QueryNode};
+use rust_sql::query_graph::validator::validate;
+use rust_sql::query_graph::{JoinType, NodeId, QueryGraph, QueryNode};
 use rust_sql::scalar_expr::BinaryOp;
 use rust_sql::scalar_expr::NaryOp;
 use rust_sql::scalar_expr::ScalarExpr;
+use rust_sql::value::Value;
 
 mod test_queries {
     use itertools::Itertools;
     use rust_sql::{
         data_type::DataType,
-        query_graph::{ApplyType, CorrelationContext},
-        scalar_expr::{AggregateExpr, AggregateOp, ScalarExprRef, ScalarSubqueryCmpOp, Subquery},
+        query_graph::{
+            ApplyType, CorrelationContext, NodeId, SortKey, WindowFrame, WindowFrameBound,
+            WindowFrameUnits,
+        },
+        scalar_expr::{
+            AggregateExpr, AggregateOp, ScalarExprRef, ScalarSubqueryCmpOp, Subquery, UnaryOp,
+            WindowExpr, WindowExprRef, WindowFunction,
+        },
+        value::Literal,
     };
 
     use super::*;
@@ -741,6 +754,466 @@ mod test_queries {
         });
     }
 
+    pub(crate) fn recursive_union(queries: &mut HashMap<String, QueryGraph>) {
+        // Pairs of nodes connected through a path of edges of table 1.
+        let transitive_closure =
+            |query_graph: &mut QueryGraph, distinct: bool, max_iterations: Option<usize>| {
+                let edges = query_graph.table_scan(1, 2);
+                let reference = query_graph.recursive_reference(1, vec![DataType::String; 2]);
+                let join = query_graph.inner_join(
+                    reference,
+                    edges,
+                    vec![ScalarExpr::input_ref(1)
+                        .binary(BinaryOp::Eq, ScalarExpr::input_ref(2).into())
+                        .into()],
+                );
+                let recursive = query_graph.project(
+                    join,
+                    vec![
+                        ScalarExpr::input_ref(0).into(),
+                        ScalarExpr::input_ref(3).into(),
+                    ],
+                );
+                query_graph.recursive_union(1, edges, recursive, distinct, max_iterations)
+            };
+        queries.insert("recursive_union_distinct".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let recursive_union = transitive_closure(&mut query_graph, true, None);
+            query_graph.set_entry_node(recursive_union);
+            query_graph
+        });
+        queries.insert("recursive_union_all_max_iterations".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let recursive_union = transitive_closure(&mut query_graph, false, Some(3));
+            query_graph.set_entry_node(recursive_union);
+            query_graph
+        });
+        queries.insert("recursive_union_redundant_aggregate".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let recursive_union = transitive_closure(&mut query_graph, true, None);
+            let aggregate = query_graph.add_node(QueryNode::Aggregate {
+                group_key: BTreeSet::from([0, 1]),
+                aggregates: Vec::new(),
+                input: recursive_union,
+            });
+            query_graph.set_entry_node(aggregate);
+            query_graph
+        });
+        queries.insert("recursive_union_filter".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let recursive_union = transitive_closure(&mut query_graph, true, None);
+            let filter = query_graph.filter(
+                recursive_union,
+                vec![ScalarExpr::input_ref(0)
+                    .binary(
+                        BinaryOp::Eq,
+                        ScalarExpr::string_literal("1".to_string()).into(),
+                    )
+                    .into()],
+            );
+            query_graph.set_entry_node(filter);
+            query_graph
+        });
+    }
+
+    pub(crate) fn values(queries: &mut HashMap<String, QueryGraph>) {
+        let string = |value: Option<&str>| match value {
+            Some(value) => Literal::new(Value::String(value.to_string()), DataType::String),
+            None => Literal::new(Value::Null, DataType::String),
+        };
+        let row_type = Rc::new(vec![DataType::String; 2]);
+        queries.insert("values_scan".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let values = query_graph.values(
+                row_type.clone(),
+                vec![
+                    vec![string(Some("1")), string(Some("a"))],
+                    vec![string(Some("2")), string(Some("a"))],
+                    vec![string(Some("3")), string(None)],
+                ],
+            );
+            query_graph.set_entry_node(values);
+            query_graph
+        });
+        queries.insert("values_filter".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let values = query_graph.values(
+                row_type.clone(),
+                vec![
+                    vec![string(Some("1")), string(Some("a"))],
+                    vec![string(Some("2")), string(Some("b"))],
+                ],
+            );
+            let filter = query_graph.filter(
+                values,
+                vec![ScalarExpr::input_ref(0)
+                    .binary(
+                        BinaryOp::Eq,
+                        ScalarExpr::string_literal("1".to_string()).into(),
+                    )
+                    .into()],
+            );
+            query_graph.set_entry_node(filter);
+            query_graph
+        });
+        queries.insert("values_identity_join".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 2);
+            let values = query_graph.values(
+                row_type.clone(),
+                vec![vec![string(Some("1")), string(Some("a"))]],
+            );
+            let join = query_graph.inner_join(
+                values,
+                table_scan_1,
+                vec![ScalarExpr::input_ref(0)
+                    .binary(BinaryOp::Eq, ScalarExpr::input_ref(2).into())
+                    .into()],
+            );
+            query_graph.set_entry_node(join);
+            query_graph
+        });
+        queries.insert("values_empty_filter".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 2);
+            let filter = query_graph.filter(table_scan_1, vec![ScalarExpr::false_literal().into()]);
+            let project = query_graph.project(
+                filter,
+                vec![
+                    ScalarExpr::input_ref(1).into(),
+                    ScalarExpr::input_ref(0).into(),
+                ],
+            );
+            query_graph.set_entry_node(project);
+            query_graph
+        });
+    }
+
+    pub(crate) fn set_operations(queries: &mut HashMap<String, QueryGraph>) {
+        let distinct_rows = |query_graph: &mut QueryGraph, input: NodeId| {
+            query_graph.add_node(QueryNode::Aggregate {
+                group_key: BTreeSet::from([0, 1]),
+                aggregates: Vec::new(),
+                input,
+            })
+        };
+        queries.insert("intersect_distinct".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 2);
+            let table_scan_2 = query_graph.table_scan(2, 2);
+            let intersect = query_graph.intersect(table_scan_1, table_scan_2, true);
+            query_graph.set_entry_node(intersect);
+            query_graph
+        });
+        queries.insert("except_distinct".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 2);
+            let table_scan_2 = query_graph.table_scan(2, 2);
+            let except = query_graph.except(table_scan_1, table_scan_2, true);
+            query_graph.set_entry_node(except);
+            query_graph
+        });
+        queries.insert("intersect_all".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 2);
+            let table_scan_2 = query_graph.table_scan(2, 2);
+            let intersect = query_graph.intersect(table_scan_1, table_scan_2, false);
+            query_graph.set_entry_node(intersect);
+            query_graph
+        });
+        queries.insert("except_all".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 2);
+            let table_scan_2 = query_graph.table_scan(2, 2);
+            let except = query_graph.except(table_scan_1, table_scan_2, false);
+            query_graph.set_entry_node(except);
+            query_graph
+        });
+        queries.insert("intersect_all_unique_left".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 2);
+            let aggregate = distinct_rows(&mut query_graph, table_scan_1);
+            let table_scan_2 = query_graph.table_scan(2, 2);
+            let intersect = query_graph.intersect(aggregate, table_scan_2, false);
+            query_graph.set_entry_node(intersect);
+            query_graph
+        });
+        queries.insert("except_all_unique_left".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 2);
+            let aggregate = distinct_rows(&mut query_graph, table_scan_1);
+            let table_scan_2 = query_graph.table_scan(2, 2);
+            let except = query_graph.except(aggregate, table_scan_2, false);
+            query_graph.set_entry_node(except);
+            query_graph
+        });
+        // Both the join and the aggregation are possible for distinct set operations
+        // with a unique left input: the join is cheaper unless the right input is much
+        // larger than the left one and contains many duplicated rows.
+        queries.insert("intersect_distinct_unique_left".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 2);
+            let aggregate = distinct_rows(&mut query_graph, table_scan_1);
+            let table_scan_2 = query_graph.table_scan(2, 2);
+            let intersect = query_graph.intersect(aggregate, table_scan_2, true);
+            query_graph.set_entry_node(intersect);
+            query_graph
+        });
+        queries.insert(
+            "intersect_distinct_unique_left_duplicated_right".to_string(),
+            {
+                let string =
+                    |value: &str| Literal::new(Value::String(value.to_string()), DataType::String);
+                let row_type = Rc::new(vec![DataType::String; 2]);
+                let mut query_graph = QueryGraph::new();
+                let left = query_graph.values(
+                    row_type.clone(),
+                    vec![
+                        vec![string("1"), string("a")],
+                        vec![string("2"), string("b")],
+                    ],
+                );
+                let right = query_graph.values(
+                    row_type,
+                    (0..30)
+                        .map(|i| {
+                            if i % 2 == 0 {
+                                vec![string("1"), string("a")]
+                            } else {
+                                vec![string("3"), string("c")]
+                            }
+                        })
+                        .collect(),
+                );
+                let intersect = query_graph.intersect(left, right, true);
+                query_graph.set_entry_node(intersect);
+                query_graph
+            },
+        );
+        queries.insert("intersect_empty_right".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 2);
+            let table_scan_2 = query_graph.table_scan(2, 2);
+            let filter = query_graph.filter(table_scan_2, vec![ScalarExpr::false_literal().into()]);
+            let intersect = query_graph.intersect(table_scan_1, filter, false);
+            query_graph.set_entry_node(intersect);
+            query_graph
+        });
+        queries.insert("except_empty_right".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 2);
+            let table_scan_2 = query_graph.table_scan(2, 2);
+            let filter = query_graph.filter(table_scan_2, vec![ScalarExpr::false_literal().into()]);
+            let except = query_graph.except(table_scan_1, filter, true);
+            query_graph.set_entry_node(except);
+            query_graph
+        });
+    }
+
+    pub(crate) fn sort_limit(queries: &mut HashMap<String, QueryGraph>) {
+        queries.insert("limit_union_transpose".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 3);
+            let table_scan_2 = query_graph.table_scan(2, 3);
+            let union_ = query_graph.add_node(QueryNode::Union {
+                inputs: vec![table_scan_1, table_scan_2],
+            });
+            let limit = query_graph.limit(union_, 1, Some(2));
+            query_graph.set_entry_node(limit);
+            query_graph
+        });
+        queries.insert("limit_project_transpose".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 3);
+            let sort = query_graph.sort(table_scan_1, vec![SortKey::asc(0), SortKey::asc(1)]);
+            let project = query_graph.project(
+                sort,
+                vec![
+                    ScalarExpr::input_ref(1).into(),
+                    ScalarExpr::nary(
+                        NaryOp::Concat,
+                        vec![
+                            ScalarExpr::input_ref(0).into(),
+                            ScalarExpr::input_ref(2).into(),
+                        ],
+                    )
+                    .into(),
+                ],
+            );
+            let limit = query_graph.limit(project, 0, Some(2));
+            query_graph.set_entry_node(limit);
+            query_graph
+        });
+        queries.insert("limit_sort_to_top_n".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 3);
+            let sort = query_graph.sort(
+                table_scan_1,
+                vec![SortKey {
+                    column: 1,
+                    ascending: false,
+                }],
+            );
+            let limit = query_graph.limit(sort, 1, Some(3));
+            query_graph.set_entry_node(limit);
+            query_graph
+        });
+        queries.insert("sort_remove".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 3);
+            let sort_1 = query_graph.sort(table_scan_1, vec![SortKey::asc(2)]);
+            let aggregate = query_graph.add_node(QueryNode::Aggregate {
+                group_key: BTreeSet::from([0]),
+                aggregates: vec![AggregateExpr {
+                    op: AggregateOp::Count,
+                    operands: vec![1],
+                }
+                .into()],
+                input: sort_1,
+            });
+            let sort_2 = query_graph.sort(aggregate, vec![SortKey::asc(0), SortKey::asc(1)]);
+            let sort_3 = query_graph.sort(sort_2, vec![SortKey::asc(0)]);
+            query_graph.set_entry_node(sort_3);
+            query_graph
+        });
+    }
+
+    pub(crate) fn window(queries: &mut HashMap<String, QueryGraph>) {
+        let int_literal = |value: i64| -> ScalarExprRef {
+            ScalarExpr::Literal(Literal::new(Value::BigInt(value), DataType::BigInt)).into()
+        };
+        let window_function = |function: WindowFunction, operands: Vec<usize>| -> WindowExprRef {
+            WindowExpr { function, operands }.into()
+        };
+        queries.insert("window_functions".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 3);
+            let project = query_graph.project(
+                table_scan_1,
+                (0..3)
+                    .map(|i| ScalarExpr::input_ref(i).into())
+                    .chain(std::iter::once(int_literal(1)))
+                    .collect_vec(),
+            );
+            let window = query_graph.window(
+                project,
+                vec![0],
+                vec![SortKey::asc(1)],
+                WindowFrame::default(),
+                vec![
+                    window_function(WindowFunction::RowNumber, vec![]),
+                    window_function(WindowFunction::Rank, vec![]),
+                    window_function(WindowFunction::DenseRank, vec![]),
+                    window_function(WindowFunction::Lag { offset: 1 }, vec![2]),
+                    window_function(WindowFunction::Count, vec![2]),
+                    window_function(WindowFunction::Sum, vec![3]),
+                ],
+            );
+            query_graph.set_entry_node(window);
+            query_graph
+        });
+        queries.insert("window_rows_frame".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 3);
+            let window = query_graph.window(
+                table_scan_1,
+                vec![],
+                vec![SortKey::asc(0), SortKey::asc(1), SortKey::asc(2)],
+                WindowFrame {
+                    units: WindowFrameUnits::Rows,
+                    start: WindowFrameBound::Preceding(1),
+                    end: WindowFrameBound::Following(1),
+                },
+                vec![
+                    window_function(WindowFunction::Min, vec![2]),
+                    window_function(WindowFunction::Max, vec![2]),
+                    window_function(WindowFunction::Lead { offset: 2 }, vec![2]),
+                ],
+            );
+            query_graph.set_entry_node(window);
+            query_graph
+        });
+        queries.insert("filter_window_transpose".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 3);
+            let window = query_graph.window(
+                table_scan_1,
+                vec![0],
+                vec![SortKey::asc(1)],
+                WindowFrame::default(),
+                vec![window_function(WindowFunction::RowNumber, vec![])],
+            );
+            let filter = query_graph.filter(
+                window,
+                vec![
+                    ScalarExpr::input_ref(0)
+                        .binary(
+                            BinaryOp::Eq,
+                            ScalarExpr::string_literal("hello".to_string()).into(),
+                        )
+                        .into(),
+                    ScalarExpr::input_ref(2)
+                        .binary(
+                            BinaryOp::Eq,
+                            ScalarExpr::string_literal("world".to_string()).into(),
+                        )
+                        .into(),
+                ],
+            );
+            query_graph.set_entry_node(filter);
+            query_graph
+        });
+        queries.insert("filter_window_to_top_n".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 3);
+            let window = query_graph.window(
+                table_scan_1,
+                vec![0],
+                vec![SortKey {
+                    column: 1,
+                    ascending: false,
+                }],
+                WindowFrame::default(),
+                vec![
+                    window_function(WindowFunction::RowNumber, vec![]),
+                    window_function(WindowFunction::Lag { offset: 1 }, vec![2]),
+                ],
+            );
+            let filter = query_graph.filter(
+                window,
+                vec![ScalarExpr::input_ref(3)
+                    .binary(BinaryOp::Le, int_literal(2))
+                    .into()],
+            );
+            query_graph.set_entry_node(filter);
+            query_graph
+        });
+        queries.insert("filter_window_to_top_n_range_frame".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 3);
+            let window = query_graph.window(
+                table_scan_1,
+                vec![0],
+                vec![SortKey::asc(1)],
+                WindowFrame::default(),
+                vec![
+                    window_function(WindowFunction::RowNumber, vec![]),
+                    window_function(WindowFunction::Count, vec![]),
+                ],
+            );
+            let filter = query_graph.filter(
+                window,
+                vec![
+                    ScalarExpr::Literal(Literal::new(Value::BigInt(3), DataType::BigInt))
+                        .binary(BinaryOp::Gt, ScalarExpr::input_ref(3).into())
+                        .into(),
+                ],
+            );
+            query_graph.set_entry_node(filter);
+            query_graph
+        });
+    }
+
     pub(crate) fn join_project_transpose(queries: &mut HashMap<String, QueryGraph>) {
         queries.insert("join_project_transpose_left".to_string(), {
             let mut query_graph = QueryGraph::new();
@@ -1630,34 +2103,129 @@ mod test_queries {
         });
     }
 
-    pub(crate) fn expression_reduction(queries: &mut HashMap<String, QueryGraph>) {
-        queries.insert("expression_reduction_1".to_string(), {
+    pub(crate) fn empty_relation_propagation(queries: &mut HashMap<String, QueryGraph>) {
+        let empty_filter = |query_graph: &mut QueryGraph, table_id: usize| {
+            let table_scan = query_graph.table_scan(table_id, 2);
+            query_graph.filter(table_scan, vec![ScalarExpr::false_literal().into()])
+        };
+        let join_condition: ScalarExprRef = ScalarExpr::input_ref(0)
+            .binary(BinaryOp::Eq, ScalarExpr::input_ref(2).into())
+            .into();
+        queries.insert("empty_relation_inner_join".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 2);
+            let filter = empty_filter(&mut query_graph, 2);
+            let join = query_graph.inner_join(table_scan_1, filter, vec![join_condition.clone()]);
+            query_graph.set_entry_node(join);
+            query_graph
+        });
+        for (name, join_type) in [
+            ("left_outer", JoinType::LeftOuter),
+            ("right_outer", JoinType::RightOuter),
+            ("full_outer", JoinType::FullOuter),
+        ] {
+            queries.insert(format!("empty_relation_{}_join_empty_right", name), {
+                let mut query_graph = QueryGraph::new();
+                let table_scan_1 = query_graph.table_scan(1, 2);
+                let filter = empty_filter(&mut query_graph, 2);
+                let join = query_graph.join(
+                    join_type,
+                    table_scan_1,
+                    filter,
+                    vec![join_condition.clone()],
+                );
+                query_graph.set_entry_node(join);
+                query_graph
+            });
+        }
+        queries.insert("empty_relation_union".to_string(), {
             let mut query_graph = QueryGraph::new();
-            let table_scan_1 = query_graph.table_scan(1, 5);
-            let join = query_graph.join(
-                JoinType::LeftOuter,
-                table_scan_1,
-                table_scan_1,
-                vec![ScalarExpr::null_literal(DataType::String)
-                    .binary(BinaryOp::Eq, ScalarExpr::input_ref(5).into())
-                    .into()],
-            );
-            let filter_1 = query_graph.filter(
-                join,
-                vec![ScalarExpr::null_literal(DataType::String)
-                    .binary(BinaryOp::Lt, ScalarExpr::input_ref(1).into())
-                    .into()],
-            );
-            query_graph.set_entry_node(filter_1);
+            let table_scan_1 = query_graph.table_scan(1, 2);
+            let filter = empty_filter(&mut query_graph, 2);
+            let table_scan_3 = query_graph.table_scan(3, 2);
+            let union_ = query_graph.add_node(QueryNode::Union {
+                inputs: vec![filter, table_scan_1, table_scan_3],
+            });
+            query_graph.set_entry_node(union_);
             query_graph
         });
-    }
-
-    pub(crate) fn cte_discovery(queries: &mut HashMap<String, QueryGraph>) {
-        queries.insert("cte_discovery_1".to_string(), {
+        queries.insert("empty_relation_union_single_branch".to_string(), {
             let mut query_graph = QueryGraph::new();
-            let table_scan_1 = query_graph.table_scan(1, 5);
-            let filter_1 = query_graph.filter(
+            let table_scan_1 = query_graph.table_scan(1, 2);
+            let filter = empty_filter(&mut query_graph, 2);
+            let union_ = query_graph.add_node(QueryNode::Union {
+                inputs: vec![table_scan_1, filter],
+            });
+            query_graph.set_entry_node(union_);
+            query_graph
+        });
+        queries.insert("empty_relation_global_aggregate".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let filter = empty_filter(&mut query_graph, 1);
+            let aggregate = query_graph.add_node(QueryNode::Aggregate {
+                group_key: BTreeSet::new(),
+                aggregates: vec![
+                    AggregateExpr {
+                        op: AggregateOp::Count,
+                        operands: vec![0],
+                    }
+                    .into(),
+                    AggregateExpr {
+                        op: AggregateOp::Max,
+                        operands: vec![1],
+                    }
+                    .into(),
+                ],
+                input: filter,
+            });
+            query_graph.set_entry_node(aggregate);
+            query_graph
+        });
+        queries.insert("empty_relation_grouped_aggregate".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let filter = empty_filter(&mut query_graph, 1);
+            let aggregate = query_graph.add_node(QueryNode::Aggregate {
+                group_key: BTreeSet::from([0]),
+                aggregates: vec![AggregateExpr {
+                    op: AggregateOp::Count,
+                    operands: vec![1],
+                }
+                .into()],
+                input: filter,
+            });
+            query_graph.set_entry_node(aggregate);
+            query_graph
+        });
+    }
+
+    pub(crate) fn expression_reduction(queries: &mut HashMap<String, QueryGraph>) {
+        queries.insert("expression_reduction_1".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 5);
+            let join = query_graph.join(
+                JoinType::LeftOuter,
+                table_scan_1,
+                table_scan_1,
+                vec![ScalarExpr::null_literal(DataType::String)
+                    .binary(BinaryOp::Eq, ScalarExpr::input_ref(5).into())
+                    .into()],
+            );
+            let filter_1 = query_graph.filter(
+                join,
+                vec![ScalarExpr::null_literal(DataType::String)
+                    .binary(BinaryOp::Lt, ScalarExpr::input_ref(1).into())
+                    .into()],
+            );
+            query_graph.set_entry_node(filter_1);
+            query_graph
+        });
+    }
+
+    pub(crate) fn cte_discovery(queries: &mut HashMap<String, QueryGraph>) {
+        queries.insert("cte_discovery_1".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 5);
+            let filter_1 = query_graph.filter(
                 table_scan_1,
                 vec![ScalarExpr::input_ref(0)
                     .binary(BinaryOp::Lt, ScalarExpr::input_ref(1).into())
@@ -1756,6 +2324,288 @@ mod test_queries {
         });
     }
 
+    pub(crate) fn subquery_unnesting(queries: &mut HashMap<String, QueryGraph>) {
+        let correlated_subquery = |subquery_root: NodeId| Subquery {
+            root: subquery_root,
+            correlation: Some(CorrelationContext {
+                parameters: vec![ScalarExpr::input_ref(1).into()],
+            }),
+        };
+        // select * from t2 where c4 > (select max(c2) from t1 where c0 = t2.c1)
+        queries.insert("scalar_subquery_unnesting_1".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 5);
+            let filter_1 = query_graph.filter(
+                table_scan_1,
+                vec![ScalarExpr::input_ref(0)
+                    .binary(
+                        BinaryOp::Eq,
+                        ScalarExpr::CorrelatedInputRef {
+                            context_offset: 0,
+                            index: 0,
+                            data_type: DataType::String,
+                        }
+                        .into(),
+                    )
+                    .into()],
+            );
+            let aggregate_1 = query_graph.add_node(QueryNode::Aggregate {
+                group_key: BTreeSet::new(),
+                aggregates: vec![AggregateExpr {
+                    op: AggregateOp::Max,
+                    operands: vec![2],
+                }
+                .into()],
+                input: filter_1,
+            });
+            let subquery_root = query_graph.add_subquery(aggregate_1);
+            let table_scan_2 = query_graph.table_scan(2, 5);
+            let filter_2 = query_graph.filter(
+                table_scan_2,
+                vec![ScalarExpr::input_ref(4)
+                    .binary(
+                        BinaryOp::Gt,
+                        ScalarExpr::ScalarSubquery {
+                            subquery: correlated_subquery(subquery_root),
+                        }
+                        .into(),
+                    )
+                    .into()],
+            );
+            query_graph.set_entry_node(filter_2);
+            query_graph
+        });
+        // select c0, (select count(*) from t1 where c0 = t2.c1) from t2
+        queries.insert("scalar_subquery_unnesting_2".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 5);
+            let filter_1 = query_graph.filter(
+                table_scan_1,
+                vec![ScalarExpr::input_ref(0)
+                    .binary(
+                        BinaryOp::Eq,
+                        ScalarExpr::CorrelatedInputRef {
+                            context_offset: 0,
+                            index: 0,
+                            data_type: DataType::String,
+                        }
+                        .into(),
+                    )
+                    .into()],
+            );
+            let aggregate_1 = query_graph.add_node(QueryNode::Aggregate {
+                group_key: BTreeSet::new(),
+                aggregates: vec![AggregateExpr {
+                    op: AggregateOp::Count,
+                    operands: vec![],
+                }
+                .into()],
+                input: filter_1,
+            });
+            let subquery_root = query_graph.add_subquery(aggregate_1);
+            let table_scan_2 = query_graph.table_scan(2, 5);
+            let project_1 = query_graph.project(
+                table_scan_2,
+                vec![
+                    ScalarExpr::input_ref(0).into(),
+                    ScalarExpr::ScalarSubquery {
+                        subquery: correlated_subquery(subquery_root),
+                    }
+                    .into(),
+                ],
+            );
+            query_graph.set_entry_node(project_1);
+            query_graph
+        });
+        // select * from t2 where not exists (select * from t1 where c0 = t2.c1)
+        //
+        // The negation is expressed either as `raw_eq(exists(S), FALSE)` or as
+        // `not(exists(S))`.
+        for (name, use_not) in [
+            ("not_exists_unnesting_1", false),
+            ("not_exists_unnesting_2", true),
+        ] {
+            queries.insert(name.to_string(), {
+                let mut query_graph = QueryGraph::new();
+                let table_scan_1 = query_graph.table_scan(1, 5);
+                let filter_1 = query_graph.filter(
+                    table_scan_1,
+                    vec![ScalarExpr::input_ref(0)
+                        .binary(
+                            BinaryOp::Eq,
+                            ScalarExpr::CorrelatedInputRef {
+                                context_offset: 0,
+                                index: 0,
+                                data_type: DataType::String,
+                            }
+                            .into(),
+                        )
+                        .into()],
+                );
+                let subquery_root = query_graph.add_subquery(filter_1);
+                let table_scan_2 = query_graph.table_scan(2, 5);
+                let exists = ScalarExpr::ExistsSubquery {
+                    subquery: correlated_subquery(subquery_root),
+                };
+                let filter_2 = query_graph.filter(
+                    table_scan_2,
+                    vec![if use_not {
+                        exists.unary(UnaryOp::Not)
+                    } else {
+                        exists.binary(BinaryOp::RawEq, ScalarExpr::false_literal().into())
+                    }
+                    .into()],
+                );
+                query_graph.set_entry_node(filter_2);
+                query_graph
+            });
+        }
+        // select * from t2 where c0 in (select c3 from t1 where c2 = t2.c1)
+        // select * from t2 where c0 < all (select c3 from t1 where c2 = t2.c1)
+        // select * from t2 where c0 = all (select c3 from t1)
+        for (name, op, correlated) in [
+            ("eq_any_unnesting_1", ScalarSubqueryCmpOp::EqAny, true),
+            ("lt_all_unnesting_1", ScalarSubqueryCmpOp::LtAll, true),
+            ("eq_all_unnesting_1", ScalarSubqueryCmpOp::EqAll, false),
+        ] {
+            queries.insert(name.to_string(), {
+                let mut query_graph = QueryGraph::new();
+                let table_scan_1 = query_graph.table_scan(1, 5);
+                let filter_1 = query_graph.filter(
+                    table_scan_1,
+                    if correlated {
+                        vec![ScalarExpr::input_ref(2)
+                            .binary(
+                                BinaryOp::Eq,
+                                ScalarExpr::CorrelatedInputRef {
+                                    context_offset: 0,
+                                    index: 0,
+                                    data_type: DataType::String,
+                                }
+                                .into(),
+                            )
+                            .into()]
+                    } else {
+                        Vec::new()
+                    },
+                );
+                let project_1 =
+                    query_graph.project(filter_1, vec![ScalarExpr::input_ref(3).into()]);
+                let subquery_root = query_graph.add_subquery(project_1);
+                let table_scan_2 = query_graph.table_scan(2, 5);
+                let filter_2 = query_graph.filter(
+                    table_scan_2,
+                    vec![ScalarExpr::ScalarSubqueryCmp {
+                        op,
+                        scalar_operand: ScalarExpr::input_ref(0).into(),
+                        subquery: if correlated {
+                            correlated_subquery(subquery_root)
+                        } else {
+                            Subquery {
+                                root: subquery_root,
+                                correlation: None,
+                            }
+                        },
+                    }
+                    .into()],
+                );
+                query_graph.set_entry_node(filter_2);
+                query_graph
+            });
+        }
+    }
+
+    pub(crate) fn join_reordering(queries: &mut HashMap<String, QueryGraph>) {
+        let eq = |left: usize, right: usize| -> ScalarExprRef {
+            ScalarExpr::input_ref(left)
+                .binary(BinaryOp::Eq, ScalarExpr::input_ref(right).into())
+                .into()
+        };
+        let selective_filter = |query_graph: &mut QueryGraph, input: NodeId| {
+            query_graph.filter(
+                input,
+                vec![ScalarExpr::input_ref(4)
+                    .binary(
+                        BinaryOp::Eq,
+                        ScalarExpr::string_literal("hello".to_string()).into(),
+                    )
+                    .into()],
+            )
+        };
+        // The filtered relation should be joined first
+        queries.insert("join_reordering_chain_1".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 5);
+            let table_scan_2 = query_graph.table_scan(2, 5);
+            let table_scan_3 = query_graph.table_scan(3, 5);
+            let filter_3 = selective_filter(&mut query_graph, table_scan_3);
+            let join_1 = query_graph.inner_join(table_scan_1, table_scan_2, vec![eq(1, 5)]);
+            let join_2 = query_graph.inner_join(join_1, filter_3, vec![eq(6, 10)]);
+            query_graph.set_entry_node(join_2);
+            query_graph
+        });
+        // The predicates of the filters in between joins are part of the join graph
+        queries.insert("join_reordering_filter_1".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 5);
+            let table_scan_2 = query_graph.table_scan(2, 5);
+            let table_scan_3 = query_graph.table_scan(3, 5);
+            let filter_3 = selective_filter(&mut query_graph, table_scan_3);
+            let join_1 = query_graph.inner_join(table_scan_1, table_scan_2, Vec::new());
+            let filter_1 = query_graph.filter(join_1, vec![eq(0, 5)]);
+            let join_2 = query_graph.inner_join(filter_1, filter_3, vec![eq(1, 10)]);
+            let filter_2 = query_graph.filter(join_2, vec![eq(7, 12)]);
+            let join_3 = query_graph.inner_join(filter_2, table_scan_1, vec![eq(14, 15)]);
+            query_graph.set_entry_node(join_3);
+            query_graph
+        });
+        // Predicates referencing more than two relations
+        queries.insert("join_reordering_hyperedge_1".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 5);
+            let table_scan_2 = query_graph.table_scan(2, 5);
+            let table_scan_3 = query_graph.table_scan(3, 5);
+            let filter_3 = selective_filter(&mut query_graph, table_scan_3);
+            let join_1 = query_graph.inner_join(table_scan_1, table_scan_2, vec![eq(0, 5)]);
+            let join_2 = query_graph.inner_join(
+                join_1,
+                filter_3,
+                vec![ScalarExpr::nary(
+                    NaryOp::Concat,
+                    vec![
+                        ScalarExpr::input_ref(1).into(),
+                        ScalarExpr::input_ref(6).into(),
+                    ],
+                )
+                .binary(BinaryOp::Eq, ScalarExpr::input_ref(11).into())
+                .into()],
+            );
+            let join_3 = query_graph.inner_join(join_2, table_scan_3, vec![eq(12, 15)]);
+            query_graph.set_entry_node(join_3);
+            query_graph
+        });
+        // Cross products are only considered between disconnected components
+        queries.insert("join_reordering_cross_product_1".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 5);
+            let table_scan_2 = query_graph.table_scan(2, 5);
+            let table_scan_3 = query_graph.table_scan(3, 5);
+            let aggregate_3 = query_graph.add_node(QueryNode::Aggregate {
+                group_key: BTreeSet::new(),
+                aggregates: vec![AggregateExpr {
+                    op: AggregateOp::Max,
+                    operands: vec![0],
+                }
+                .into()],
+                input: table_scan_3,
+            });
+            let join_1 = query_graph.inner_join(table_scan_1, table_scan_2, vec![eq(0, 5)]);
+            let join_2 = query_graph.inner_join(join_1, aggregate_3, Vec::new());
+            query_graph.set_entry_node(join_2);
+            query_graph
+        });
+    }
+
     pub(crate) fn apply(queries: &mut HashMap<String, QueryGraph>) {
         queries.insert("left_apply_1".to_string(), {
             let mut query_graph = QueryGraph::new();
@@ -1954,6 +2804,151 @@ mod test_queries {
         });
     }
 
+    pub(crate) fn apply_decorrelation(queries: &mut HashMap<String, QueryGraph>) {
+        let correlated_filter = |query_graph: &mut QueryGraph, table_scan: NodeId| {
+            query_graph.filter(
+                table_scan,
+                vec![ScalarExpr::input_ref(0)
+                    .binary(
+                        BinaryOp::Eq,
+                        ScalarExpr::CorrelatedInputRef {
+                            context_offset: 0,
+                            index: 0,
+                            data_type: DataType::String,
+                        }
+                        .into(),
+                    )
+                    .into()],
+            )
+        };
+        let apply =
+            |query_graph: &mut QueryGraph, left: NodeId, right: NodeId, apply_type: ApplyType| {
+                query_graph.add_node(QueryNode::Apply {
+                    correlation: CorrelationContext {
+                        parameters: vec![ScalarExpr::input_ref(1).into()],
+                    },
+                    left,
+                    right,
+                    apply_type,
+                })
+            };
+        queries.insert("apply_count_bug_1".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 5);
+            let filter_1 = correlated_filter(&mut query_graph, table_scan_1);
+            let aggregate_1 = query_graph.add_node(QueryNode::Aggregate {
+                group_key: BTreeSet::new(),
+                aggregates: vec![
+                    AggregateExpr {
+                        op: AggregateOp::Count,
+                        operands: vec![],
+                    }
+                    .into(),
+                    AggregateExpr {
+                        op: AggregateOp::Max,
+                        operands: vec![2],
+                    }
+                    .into(),
+                ],
+                input: filter_1,
+            });
+            let table_scan_2 = query_graph.table_scan(2, 5);
+            let apply_1 = apply(
+                &mut query_graph,
+                table_scan_2,
+                aggregate_1,
+                ApplyType::Inner,
+            );
+            query_graph.set_entry_node(apply_1);
+            query_graph
+        });
+        queries.insert("left_apply_grouped_aggregate_1".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 5);
+            let filter_1 = correlated_filter(&mut query_graph, table_scan_1);
+            let aggregate_1 = query_graph.add_node(QueryNode::Aggregate {
+                group_key: BTreeSet::from([1]),
+                aggregates: vec![AggregateExpr {
+                    op: AggregateOp::Min,
+                    operands: vec![2],
+                }
+                .into()],
+                input: filter_1,
+            });
+            let table_scan_2 = query_graph.table_scan(2, 5);
+            let apply_1 = apply(
+                &mut query_graph,
+                table_scan_2,
+                aggregate_1,
+                ApplyType::LeftOuter,
+            );
+            query_graph.set_entry_node(apply_1);
+            query_graph
+        });
+        queries.insert("apply_union_1".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 5);
+            let filter_1 = correlated_filter(&mut query_graph, table_scan_1);
+            let table_scan_3 = query_graph.table_scan(3, 5);
+            let union_1 = query_graph.add_node(QueryNode::Union {
+                inputs: vec![filter_1, table_scan_3],
+            });
+            let table_scan_2 = query_graph.table_scan(2, 5);
+            let apply_1 = apply(&mut query_graph, table_scan_2, union_1, ApplyType::Inner);
+            query_graph.set_entry_node(apply_1);
+            query_graph
+        });
+        for (name, apply_type) in [
+            ("apply_join_1", ApplyType::Inner),
+            ("left_apply_join_1", ApplyType::LeftOuter),
+        ] {
+            queries.insert(name.to_string(), {
+                let mut query_graph = QueryGraph::new();
+                let table_scan_1 = query_graph.table_scan(1, 5);
+                let filter_1 = correlated_filter(&mut query_graph, table_scan_1);
+                let table_scan_3 = query_graph.table_scan(3, 5);
+                let join_1 = query_graph.join(
+                    JoinType::RightOuter,
+                    table_scan_3,
+                    filter_1,
+                    vec![ScalarExpr::input_ref(0)
+                        .binary(BinaryOp::Eq, ScalarExpr::input_ref(6).into())
+                        .into()],
+                );
+                let table_scan_2 = query_graph.table_scan(2, 5);
+                let apply_1 = apply(&mut query_graph, table_scan_2, join_1, apply_type);
+                query_graph.set_entry_node(apply_1);
+                query_graph
+            });
+        }
+        queries.insert("left_apply_project_1".to_string(), {
+            let mut query_graph = QueryGraph::new();
+            let table_scan_1 = query_graph.table_scan(1, 5);
+            let filter_1 = correlated_filter(&mut query_graph, table_scan_1);
+            let project_1 = query_graph.project(
+                filter_1,
+                vec![
+                    ScalarExpr::input_ref(2).into(),
+                    ScalarExpr::CorrelatedInputRef {
+                        context_offset: 0,
+                        index: 0,
+                        data_type: DataType::String,
+                    }
+                    .into(),
+                ],
+            );
+            let table_scan_2 = query_graph.table_scan(2, 5);
+            let apply_1 = apply(
+                &mut query_graph,
+                table_scan_2,
+                project_1,
+                ApplyType::LeftOuter,
+            );
+            query_graph.set_entry_node(apply_1);
+            query_graph
+        });
+    }
+
     pub(crate) fn correlated_filter(queries: &mut HashMap<String, QueryGraph>) {
         queries.insert("correlated_filter_1".to_string(), {
             let mut query_graph = QueryGraph::new();
@@ -2440,10 +3435,12 @@ fn static_queries() -> HashMap<String, QueryGraph> {
     test_queries::aggregate_pruning(&mut queries);
     test_queries::aggregate_remove(&mut queries);
     test_queries::apply(&mut queries);
+    test_queries::apply_decorrelation(&mut queries);
     test_queries::common_aggregate_discovery(&mut queries);
     test_queries::correlated_filter(&mut queries);
     test_queries::correlated_project(&mut queries);
     test_queries::cte_discovery(&mut queries);
+    test_queries::empty_relation_propagation(&mut queries);
     test_queries::expression_reduction(&mut queries);
     test_queries::filter_aggregate_transpose(&mut queries);
     test_queries::filter_join_transpose(&mut queries);
@@ -2458,9 +3455,16 @@ fn static_queries() -> HashMap<String, QueryGraph> {
     test_queries::outer_to_inner_join(&mut queries);
     test_queries::project_normalization(&mut queries);
     test_queries::pulled_up_predicates(&mut queries);
+    test_queries::recursive_union(&mut queries);
+    test_queries::set_operations(&mut queries);
+    test_queries::sort_limit(&mut queries);
     test_queries::subqueries(&mut queries);
+    test_queries::subquery_unnesting(&mut queries);
+    test_queries::join_reordering(&mut queries);
     test_queries::union_merge(&mut queries);
+    test_queries::values(&mut queries);
     test_queries::union_pruning(&mut queries);
+    test_queries::window(&mut queries);
 
     queries
 }
@@ -2555,6 +3559,7 @@ fn test_explain_properties() {
             let query_graph = static_queries
                 .get(&test_case.input.trim().to_string())
                 .unwrap();
+            validate(query_graph).unwrap();
 
             let mut serializer = JsonSerializer::new_with_all_annotators();
             serializer.add_subgraph(query_graph, QueryGraph::ROOT_NODE_ID);
@@ -2568,15 +3573,20 @@ fn test_explain_properties() {
                 .add_subgraph(&cloned_query_graph, QueryGraph::ROOT_NODE_ID);
             let mut listener = DebugOptimizerListener::default();
             let mut opt_context = OptimizerContext::new();
+            opt_context.set_validate_after_each_rule(true);
             opt_context.append_listener(&mut listener);
             opt_context.append_listener(&mut listener2);
 
             if let Some(rules) = test_case.args.get("rules") {
                 let optimizer =
                     Optimizer::new(rules.iter().map(|rule| build_rule(rule).unwrap()).collect());
-                optimizer.optimize(&mut opt_context, &mut cloned_query_graph);
+                optimizer
+                    .optimize(&mut opt_context, &mut cloned_query_graph)
+                    .unwrap();
             } else {
-                optimizer.optimize(&mut opt_context, &mut cloned_query_graph);
+                optimizer
+                    .optimize(&mut opt_context, &mut cloned_query_graph)
+                    .unwrap();
             }
 
             let mut serializer = JsonSerializer::new_with_all_annotators();
@@ -2598,3 +3608,172 @@ fn test_explain_properties() {
         })
     });
 }
+
+/// Listener that executes the plan after every rule application, recording the first
+/// rule that changes the results of the query.
+struct DifferentialListener<'a> {
+    database: &'a Database,
+    expected_rows: Vec<Row>,
+    failure: Option<String>,
+}
+
+impl OptimizerListener for DifferentialListener<'_> {
+    fn node_replacements(
+        &mut self,
+        rule: &dyn rust_sql::query_graph::optimizer::Rule,
+        query_graph: &QueryGraph,
+        replacements: &Vec<Replacement>,
+    ) {
+        if self.failure.is_some() {
+            return;
+        }
+        // Replay the replacements on a copy of the graph, since the listener is invoked
+        // before they are applied.
+        let mut next_query_graph = query_graph.clone();
+        next_query_graph.replace_nodes(replacements);
+        let result = execute(&next_query_graph, self.database).map(sorted_rows);
+        if result.as_ref() != Ok(&self.expected_rows) {
+            self.failure = Some(format!(
+                "{} changed the results of the query\nBefore:\n{}\nAfter:\n{}\nExpected {} rows: {:?}\nFound: {:?}",
+                rule.name(),
+                query_graph.explain(),
+                next_query_graph.explain(),
+                self.expected_rows.len(),
+                self.expected_rows.iter().take(5).collect_vec(),
+                result.map(|rows| (rows.len(), rows.into_iter().take(5).collect_vec())),
+            ));
+        }
+    }
+}
+
+fn sorted_rows(rows: Vec<Row>) -> Vec<Row> {
+    rows.into_iter().sorted().collect()
+}
+
+/// Returns the nodes under the root node and under the subquery roots of the given
+/// query graph.
+fn collect_all_nodes(query_graph: &QueryGraph) -> HashSet<NodeId> {
+    let mut nodes = query_graph.collect_nodes_under(QueryGraph::ROOT_NODE_ID);
+    for subquery_root in query_graph.subquery_roots() {
+        nodes.extend(query_graph.collect_nodes_under(subquery_root));
+    }
+    nodes
+}
+
+/// The same table may be scanned with different row types, ie. projecting only its
+/// leading columns. Replaces such scans with a projection over a scan of the widest
+/// row type, so that all the scans of a table match the width of its rows.
+fn widen_table_scans(query_graph: &mut QueryGraph) {
+    let nodes = collect_all_nodes(query_graph);
+    let mut tables: HashMap<usize, Rc<Vec<DataType>>> = HashMap::new();
+    for node_id in nodes.iter() {
+        if let QueryNode::TableScan { table_id, row_type } = query_graph.node(*node_id) {
+            let table_row_type = tables.entry(*table_id).or_insert_with(|| row_type.clone());
+            if row_type.len() > table_row_type.len() {
+                *table_row_type = row_type.clone();
+            }
+        }
+    }
+    let mut replacements = Vec::new();
+    for node_id in nodes.iter().sorted() {
+        if let QueryNode::TableScan { table_id, row_type } = query_graph.node(*node_id) {
+            let table_row_type = tables[table_id].clone();
+            if row_type.len() < table_row_type.len() {
+                let num_columns = row_type.len();
+                let table_scan = query_graph.add_node(QueryNode::TableScan {
+                    table_id: *table_id,
+                    row_type: table_row_type,
+                });
+                let project = query_graph.project(
+                    table_scan,
+                    (0..num_columns)
+                        .map(|i| ScalarExpr::input_ref(i).into())
+                        .collect(),
+                );
+                replacements.push((*node_id, project));
+            }
+        }
+    }
+    query_graph.replace_nodes(&replacements);
+}
+
+/// Generates the contents of the tables scanned by the given query graph, whose scans
+/// of the same table must all have the same row type. Values are drawn from small
+/// domains, with NULLs, so that joins find matches, and the last row of every table
+/// is a duplicate of the first one.
+fn generate_database(query_graph: &QueryGraph, seed: u64) -> Database {
+    let mut state = seed;
+    let mut next_random = move |bound: u64| {
+        // Linear congruential generator, so that the data is deterministic
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 33) % bound
+    };
+    let mut database = Database::new();
+    let mut tables: HashMap<usize, Rc<Vec<DataType>>> = HashMap::new();
+    for node_id in collect_all_nodes(query_graph).iter() {
+        if let QueryNode::TableScan { table_id, row_type } = query_graph.node(*node_id) {
+            tables.insert(*table_id, row_type.clone());
+        }
+    }
+    for (table_id, row_type) in tables.iter().sorted_by_key(|(table_id, _)| **table_id) {
+        let mut rows = (0..5)
+            .map(|_| {
+                row_type
+                    .iter()
+                    .map(|data_type| {
+                        let value = next_random(4);
+                        if value == 0 {
+                            return Value::Null;
+                        }
+                        match data_type {
+                            DataType::Bool => Value::Bool(value % 2 == 0),
+                            DataType::Int => Value::Int(value as i32),
+                            DataType::BigInt => Value::BigInt(value as i64),
+                            _ => Value::String(format!("{}", value)),
+                        }
+                    })
+                    .collect_vec()
+            })
+            .collect_vec();
+        rows.push(rows[0].clone());
+        database.insert_table(*table_id, rows);
+    }
+    database
+}
+
+#[test]
+fn test_optimized_results() {
+    let mut failures = Vec::new();
+    for (name, query_graph) in static_queries().iter().sorted_by_key(|(name, _)| *name) {
+        let mut query_graph = query_graph.clone();
+        widen_table_scans(&mut query_graph);
+        for seed in 0..3 {
+            let database = generate_database(&query_graph, seed);
+            // Queries that fail on the generated data, ie. scalar subqueries returning
+            // more than one row, are skipped.
+            let expected_rows = match execute(&query_graph, &database) {
+                Ok(rows) => sorted_rows(rows),
+                Err(ExecutionError::ScalarSubqueryMultipleRows(_)) => continue,
+                Err(err) => panic!("{}: {}", name, err),
+            };
+            let mut listener = DifferentialListener {
+                database: &database,
+                expected_rows,
+                failure: None,
+            };
+            let mut optimized_query_graph = query_graph.clone();
+            let mut opt_context = OptimizerContext::new();
+            opt_context.append_listener(&mut listener);
+            DEFAULT_OPTIMIZER
+                .optimize(&mut opt_context, &mut optimized_query_graph)
+                .unwrap();
+            if let Some(failure) = listener.failure {
+                failures.push(format!("{} (seed {}): {}", name, seed, failure));
+                break;
+            }
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n\n"));
+}
@@ -0,0 +1,1013 @@
+use std::cell::Cell;
+use std::collections::BTreeSet;
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+use std::sync::Once;
+
+use itertools::Itertools;
+use rust_sql::data_type::DataType;
+use rust_sql::physical_plan::{lower, DefaultCostModel};
+use rust_sql::query_graph::optimizer::memo::DEFAULT_MEMO_OPTIMIZER;
+use rust_sql::query_graph::optimizer::{OptimizerContext, OptimizerError, DEFAULT_OPTIMIZER};
+use rust_sql::query_graph::validator::validate;
+use rust_sql::query_graph::{
+    ApplyType, CorrelationContext, JoinType, NodeId, QueryGraph, QueryNode,
+};
+use rust_sql::scalar_expr::{
+    AggregateExpr, AggregateOp, BinaryOp, NaryOp, ScalarExpr, ScalarExprRef, ScalarSubqueryCmpOp,
+    Subquery,
+};
+use rust_sql::value::{Literal, Value};
+
+/// The row types of the tables the generated plans scan.
+const TABLES: [&[DataType]; 4] = [
+    &[DataType::Int, DataType::Int, DataType::String],
+    &[DataType::Int, DataType::String],
+    &[DataType::Int, DataType::Int],
+    &[DataType::String, DataType::Int, DataType::Bool],
+];
+
+/// Number of rule applications after which the optimizer is considered not to
+/// terminate.
+const MAX_RULE_APPLICATIONS: usize = 1000;
+
+/// Simple linear congruential generator, so that the generated plans only depend on
+/// the seed.
+struct Rng(u64);
+
+impl Rng {
+    fn below(&mut self, bound: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((self.0 >> 33) % bound as u64) as usize
+    }
+
+    fn chance(&mut self, percent: usize) -> bool {
+        self.below(100) < percent
+    }
+
+    fn choose<T: Clone>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len())].clone()
+    }
+}
+
+/// Tree representation of the generated plans, which is lowered into a query graph.
+/// Shrinking is performed over this representation.
+#[derive(Clone)]
+enum Plan {
+    Scan {
+        table_id: usize,
+    },
+    Filter {
+        input: Box<Plan>,
+        conditions: Vec<Expr>,
+    },
+    Project {
+        input: Box<Plan>,
+        outputs: Vec<Expr>,
+    },
+    Join {
+        join_type: JoinType,
+        left: Box<Plan>,
+        right: Box<Plan>,
+        conditions: Vec<Expr>,
+    },
+    Aggregate {
+        input: Box<Plan>,
+        group_key: BTreeSet<usize>,
+        aggregates: Vec<AggregateExpr>,
+    },
+    Union {
+        inputs: Vec<Plan>,
+    },
+    Apply {
+        apply_type: ApplyType,
+        parameters: Vec<Expr>,
+        left: Box<Plan>,
+        right: Box<Plan>,
+    },
+}
+
+#[derive(Clone)]
+enum Expr {
+    Column(usize),
+    Literal(Literal),
+    Correlated {
+        context_offset: usize,
+        index: usize,
+        data_type: DataType,
+    },
+    Compare(BinaryOp, Box<Expr>, Box<Expr>),
+    Nary(NaryOp, Vec<Expr>),
+    Exists(SubquerySpec),
+    Scalar(SubquerySpec),
+    SubqueryCmp(ScalarSubqueryCmpOp, Box<Expr>, SubquerySpec),
+}
+
+#[derive(Clone)]
+struct SubquerySpec {
+    /// The parameters of the correlation context of the subquery, if correlated.
+    parameters: Option<Vec<Expr>>,
+    plan: Box<Plan>,
+}
+
+impl Plan {
+    fn row_type(&self) -> Vec<DataType> {
+        match self {
+            Plan::Scan { table_id } => TABLES[*table_id].to_vec(),
+            Plan::Filter { input, .. } => input.row_type(),
+            Plan::Project { input, outputs } => {
+                let input_row_type = input.row_type();
+                outputs
+                    .iter()
+                    .map(|e| e.data_type(&input_row_type))
+                    .collect()
+            }
+            Plan::Join {
+                join_type,
+                left,
+                right,
+                ..
+            } => match join_type {
+                JoinType::Semi | JoinType::Anti => left.row_type(),
+                _ => concat(left.row_type(), right.row_type()),
+            },
+            Plan::Aggregate {
+                input,
+                group_key,
+                aggregates,
+            } => {
+                let input_row_type = input.row_type();
+                group_key
+                    .iter()
+                    .map(|col| input_row_type[*col].clone())
+                    .chain(aggregates.iter().map(|a| a.data_type(&input_row_type)))
+                    .collect()
+            }
+            Plan::Union { inputs } => inputs[0].row_type(),
+            Plan::Apply { left, right, .. } => concat(left.row_type(), right.row_type()),
+        }
+    }
+
+    fn size(&self) -> usize {
+        let expr_size = |exprs: &Vec<Expr>| exprs.iter().map(|e| e.size()).sum::<usize>();
+        1 + match self {
+            Plan::Scan { .. } => 0,
+            Plan::Filter {
+                input,
+                conditions: exprs,
+            }
+            | Plan::Project {
+                input,
+                outputs: exprs,
+            } => input.size() + expr_size(exprs),
+            Plan::Join {
+                left,
+                right,
+                conditions,
+                ..
+            } => left.size() + right.size() + expr_size(conditions),
+            Plan::Aggregate {
+                input, aggregates, ..
+            } => input.size() + aggregates.len(),
+            Plan::Union { inputs } => inputs.iter().map(|i| i.size()).sum(),
+            Plan::Apply {
+                parameters,
+                left,
+                right,
+                ..
+            } => left.size() + right.size() + expr_size(parameters),
+        }
+    }
+
+    fn to_query_graph(&self) -> QueryGraph {
+        let mut query_graph = QueryGraph::new();
+        let entry_node = self.lower(&mut query_graph);
+        query_graph.set_entry_node(entry_node);
+        query_graph
+    }
+
+    fn lower(&self, query_graph: &mut QueryGraph) -> NodeId {
+        match self {
+            Plan::Scan { table_id } => query_graph.add_node(QueryNode::TableScan {
+                table_id: *table_id,
+                row_type: Rc::new(TABLES[*table_id].to_vec()),
+            }),
+            Plan::Filter { input, conditions } => {
+                let input = input.lower(query_graph);
+                let conditions = lower_exprs(conditions, query_graph);
+                query_graph.filter(input, conditions)
+            }
+            Plan::Project { input, outputs } => {
+                let input = input.lower(query_graph);
+                let outputs = lower_exprs(outputs, query_graph);
+                query_graph.project(input, outputs)
+            }
+            Plan::Join {
+                join_type,
+                left,
+                right,
+                conditions,
+            } => {
+                let left = left.lower(query_graph);
+                let right = right.lower(query_graph);
+                let conditions = lower_exprs(conditions, query_graph);
+                query_graph.join(*join_type, left, right, conditions)
+            }
+            Plan::Aggregate {
+                input,
+                group_key,
+                aggregates,
+            } => {
+                let input = input.lower(query_graph);
+                query_graph.add_node(QueryNode::Aggregate {
+                    group_key: group_key.clone(),
+                    aggregates: aggregates.iter().map(|a| Rc::new(a.clone())).collect(),
+                    input,
+                })
+            }
+            Plan::Union { inputs } => {
+                let inputs = inputs.iter().map(|i| i.lower(query_graph)).collect();
+                query_graph.add_node(QueryNode::Union { inputs })
+            }
+            Plan::Apply {
+                apply_type,
+                parameters,
+                left,
+                right,
+            } => {
+                let left = left.lower(query_graph);
+                let right = right.lower(query_graph);
+                let parameters = lower_exprs(parameters, query_graph);
+                query_graph.add_node(QueryNode::Apply {
+                    correlation: CorrelationContext { parameters },
+                    left,
+                    right,
+                    apply_type: *apply_type,
+                })
+            }
+        }
+    }
+}
+
+impl Expr {
+    fn data_type(&self, row_type: &[DataType]) -> DataType {
+        match self {
+            Expr::Column(index) => row_type[*index].clone(),
+            Expr::Literal(literal) => literal.data_type.clone(),
+            Expr::Correlated { data_type, .. } => data_type.clone(),
+            Expr::Compare(..) | Expr::Exists(..) | Expr::SubqueryCmp(..) => DataType::Bool,
+            Expr::Nary(op, _) => match op {
+                NaryOp::And | NaryOp::Or => DataType::Bool,
+                NaryOp::Concat => DataType::String,
+            },
+            Expr::Scalar(subquery) => subquery.plan.row_type()[0].clone(),
+        }
+    }
+
+    fn size(&self) -> usize {
+        1 + match self {
+            Expr::Column(_) | Expr::Literal(_) | Expr::Correlated { .. } => 0,
+            Expr::Compare(_, left, right) => left.size() + right.size(),
+            Expr::Nary(_, operands) => operands.iter().map(|o| o.size()).sum(),
+            Expr::Exists(subquery) | Expr::Scalar(subquery) => subquery.size(),
+            Expr::SubqueryCmp(_, operand, subquery) => operand.size() + subquery.size(),
+        }
+    }
+
+    fn lower(&self, query_graph: &mut QueryGraph) -> ScalarExprRef {
+        match self {
+            Expr::Column(index) => ScalarExpr::input_ref(*index).into(),
+            Expr::Literal(literal) => ScalarExpr::Literal(literal.clone()).into(),
+            Expr::Correlated {
+                context_offset,
+                index,
+                data_type,
+            } => ScalarExpr::CorrelatedInputRef {
+                context_offset: *context_offset,
+                index: *index,
+                data_type: data_type.clone(),
+            }
+            .into(),
+            Expr::Compare(op, left, right) => ScalarExpr::BinaryOp {
+                op: op.clone(),
+                left: left.lower(query_graph),
+                right: right.lower(query_graph),
+            }
+            .into(),
+            Expr::Nary(op, operands) => {
+                ScalarExpr::nary(op.clone(), lower_exprs(operands, query_graph)).into()
+            }
+            Expr::Exists(subquery) => ScalarExpr::ExistsSubquery {
+                subquery: subquery.lower(query_graph),
+            }
+            .into(),
+            Expr::Scalar(subquery) => ScalarExpr::ScalarSubquery {
+                subquery: subquery.lower(query_graph),
+            }
+            .into(),
+            Expr::SubqueryCmp(op, operand, subquery) => ScalarExpr::ScalarSubqueryCmp {
+                op: op.clone(),
+                scalar_operand: operand.lower(query_graph),
+                subquery: subquery.lower(query_graph),
+            }
+            .into(),
+        }
+    }
+}
+
+impl SubquerySpec {
+    fn size(&self) -> usize {
+        self.plan.size()
+            + self
+                .parameters
+                .iter()
+                .flatten()
+                .map(|p| p.size())
+                .sum::<usize>()
+    }
+
+    fn lower(&self, query_graph: &mut QueryGraph) -> Subquery<ScalarExpr> {
+        let correlation = self
+            .parameters
+            .as_ref()
+            .map(|parameters| CorrelationContext {
+                parameters: lower_exprs(parameters, query_graph),
+            });
+        let input = self.plan.lower(query_graph);
+        Subquery {
+            root: query_graph.add_subquery(input),
+            correlation,
+        }
+    }
+}
+
+fn lower_exprs(exprs: &[Expr], query_graph: &mut QueryGraph) -> Vec<ScalarExprRef> {
+    exprs.iter().map(|e| e.lower(query_graph)).collect()
+}
+
+fn concat(left: Vec<DataType>, right: Vec<DataType>) -> Vec<DataType> {
+    left.into_iter().chain(right).collect()
+}
+
+/// Generates random well-typed plans. Correlated references may point to any of the
+/// correlation contexts in scope, given by the types of their parameters, with the
+/// innermost one last.
+struct Generator {
+    rng: Rng,
+}
+
+impl Generator {
+    fn new(seed: u64) -> Self {
+        Self { rng: Rng(seed) }
+    }
+
+    fn plan(&mut self, depth: usize, scope: &[Vec<DataType>]) -> Plan {
+        if depth == 0 || self.rng.chance(15) {
+            return Plan::Scan {
+                table_id: self.rng.below(TABLES.len()),
+            };
+        }
+        match self.rng.below(7) {
+            0 => {
+                let input = self.plan(depth - 1, scope);
+                let row_type = input.row_type();
+                let conditions = (0..1 + self.rng.below(2))
+                    .map(|_| self.expr(&DataType::Bool, &row_type, scope, depth - 1))
+                    .collect();
+                Plan::Filter {
+                    input: Box::new(input),
+                    conditions,
+                }
+            }
+            1 => {
+                let input = self.plan(depth - 1, scope);
+                let row_type = input.row_type();
+                let outputs = (0..1 + self.rng.below(4))
+                    .map(|_| {
+                        let data_type = self.data_type();
+                        self.expr(&data_type, &row_type, scope, depth - 1)
+                    })
+                    .collect();
+                Plan::Project {
+                    input: Box::new(input),
+                    outputs,
+                }
+            }
+            2 | 3 => {
+                let left = self.plan(depth - 1, scope);
+                let right = self.plan(depth - 1, scope);
+                let row_type = concat(left.row_type(), right.row_type());
+                let conditions = (0..self.rng.below(3))
+                    .map(|_| self.expr(&DataType::Bool, &row_type, scope, 0))
+                    .collect();
+                Plan::Join {
+                    join_type: self.rng.choose(&[
+                        JoinType::Inner,
+                        JoinType::LeftOuter,
+                        JoinType::RightOuter,
+                        JoinType::FullOuter,
+                        JoinType::Semi,
+                        JoinType::Anti,
+                    ]),
+                    left: Box::new(left),
+                    right: Box::new(right),
+                    conditions,
+                }
+            }
+            4 => {
+                let input = self.plan(depth - 1, scope);
+                let num_columns = input.row_type().len();
+                let group_key = (0..num_columns).filter(|_| self.rng.chance(40)).collect();
+                let aggregates = (0..self.rng.below(3))
+                    .map(|_| {
+                        let op = self.rng.choose(&[
+                            AggregateOp::Count,
+                            AggregateOp::Min,
+                            AggregateOp::Max,
+                        ]);
+                        if num_columns == 0 || (op == AggregateOp::Count && self.rng.chance(30)) {
+                            // count(*)
+                            return AggregateExpr {
+                                op: AggregateOp::Count,
+                                operands: Vec::new(),
+                            };
+                        }
+                        AggregateExpr {
+                            op,
+                            operands: vec![self.rng.below(num_columns)],
+                        }
+                    })
+                    .collect();
+                Plan::Aggregate {
+                    input: Box::new(input),
+                    group_key,
+                    aggregates,
+                }
+            }
+            5 => {
+                let first = self.plan(depth - 1, scope);
+                let row_type = first.row_type();
+                let mut inputs = vec![first];
+                for _ in 0..1 + self.rng.below(2) {
+                    let input = self.plan(depth - 1, scope);
+                    inputs.push(self.conform(input, &row_type, scope));
+                }
+                Plan::Union { inputs }
+            }
+            _ => {
+                let left = self.plan(depth - 1, scope);
+                let left_row_type = left.row_type();
+                let num_parameters = if left_row_type.is_empty() {
+                    0
+                } else {
+                    1 + self.rng.below(2)
+                };
+                let parameters = (0..num_parameters)
+                    .map(|_| Expr::Column(self.rng.below(left_row_type.len())))
+                    .collect_vec();
+                let inner_scope = self.inner_scope(scope, &parameters, &left_row_type);
+                let right = self.plan(depth - 1, &inner_scope);
+                Plan::Apply {
+                    apply_type: self.rng.choose(&[ApplyType::Inner, ApplyType::LeftOuter]),
+                    parameters,
+                    left: Box::new(left),
+                    right: Box::new(right),
+                }
+            }
+        }
+    }
+
+    fn data_type(&mut self) -> DataType {
+        self.rng
+            .choose(&[DataType::Int, DataType::String, DataType::Bool])
+    }
+
+    fn inner_scope(
+        &mut self,
+        scope: &[Vec<DataType>],
+        parameters: &[Expr],
+        row_type: &[DataType],
+    ) -> Vec<Vec<DataType>> {
+        let mut inner_scope = scope.to_vec();
+        inner_scope.push(parameters.iter().map(|p| p.data_type(row_type)).collect());
+        inner_scope
+    }
+
+    /// Wraps the given plan with a projection that returns the given row type.
+    fn conform(&mut self, plan: Plan, row_type: &[DataType], scope: &[Vec<DataType>]) -> Plan {
+        if plan.row_type() == row_type {
+            return plan;
+        }
+        let input_row_type = plan.row_type();
+        let outputs = row_type
+            .iter()
+            .map(|data_type| self.expr(data_type, &input_row_type, scope, 0))
+            .collect();
+        Plan::Project {
+            input: Box::new(plan),
+            outputs,
+        }
+    }
+
+    fn literal(&mut self, data_type: &DataType) -> Expr {
+        let value = self.rng.below(4);
+        Expr::Literal(if value == 0 {
+            Literal::new(Value::Null, data_type.clone())
+        } else {
+            match data_type {
+                DataType::Int => Literal::new(Value::Int(value as i32), DataType::Int),
+                DataType::BigInt => Literal::new(Value::BigInt(value as i64), DataType::BigInt),
+                DataType::Bool => {
+                    Literal::new(Value::Bool(value.is_multiple_of(2)), DataType::Bool)
+                }
+                _ => Literal::new(Value::String(format!("{}", value)), DataType::String),
+            }
+        })
+    }
+
+    fn expr(
+        &mut self,
+        data_type: &DataType,
+        row_type: &[DataType],
+        scope: &[Vec<DataType>],
+        depth: usize,
+    ) -> Expr {
+        let columns = row_type
+            .iter()
+            .positions(|t| t == data_type)
+            .map(Expr::Column)
+            .collect_vec();
+        let correlated_refs = scope
+            .iter()
+            .rev()
+            .enumerate()
+            .flat_map(|(context_offset, parameter_types)| {
+                parameter_types
+                    .iter()
+                    .positions(|t| t == data_type)
+                    .map(move |index| Expr::Correlated {
+                        context_offset,
+                        index,
+                        data_type: data_type.clone(),
+                    })
+            })
+            .collect_vec();
+        let choice = self.rng.below(10);
+        if choice < 5 && !columns.is_empty() {
+            return self.rng.choose(&columns);
+        }
+        if choice < 6 && !correlated_refs.is_empty() {
+            return self.rng.choose(&correlated_refs);
+        }
+        if choice == 9 && depth > 0 {
+            // Subquery expressions
+            if *data_type == DataType::Bool && self.rng.chance(60) {
+                let subquery = self.subquery(row_type, scope, depth - 1, None);
+                return if self.rng.chance(50) {
+                    Expr::Exists(subquery)
+                } else {
+                    let operand_type = subquery.plan.row_type()[0].clone();
+                    let operand = self.expr(&operand_type, row_type, scope, 0);
+                    Expr::SubqueryCmp(
+                        self.rng.choose(&[
+                            ScalarSubqueryCmpOp::EqAny,
+                            ScalarSubqueryCmpOp::LtAny,
+                            ScalarSubqueryCmpOp::GteAny,
+                            ScalarSubqueryCmpOp::EqAll,
+                            ScalarSubqueryCmpOp::GtAll,
+                            ScalarSubqueryCmpOp::LteAll,
+                        ]),
+                        Box::new(operand),
+                        subquery,
+                    )
+                };
+            }
+            // Scalar subqueries must return one row at most
+            let subquery = self.subquery(row_type, scope, depth - 1, Some(data_type));
+            return Expr::Scalar(subquery);
+        }
+        match data_type {
+            DataType::Bool if choice >= 6 => {
+                if self.rng.chance(70) {
+                    let operand_type = self.rng.choose(&[DataType::Int, DataType::String]);
+                    Expr::Compare(
+                        self.rng.choose(&[
+                            BinaryOp::Eq,
+                            BinaryOp::RawEq,
+                            BinaryOp::Lt,
+                            BinaryOp::Le,
+                            BinaryOp::Gt,
+                            BinaryOp::Ge,
+                        ]),
+                        Box::new(self.expr(&operand_type, row_type, scope, 0)),
+                        Box::new(self.expr(&operand_type, row_type, scope, 0)),
+                    )
+                } else {
+                    let operands = (0..2)
+                        .map(|_| self.expr(&DataType::Bool, row_type, scope, 0))
+                        .collect();
+                    Expr::Nary(self.rng.choose(&[NaryOp::And, NaryOp::Or]), operands)
+                }
+            }
+            DataType::String if choice >= 6 && self.rng.chance(50) => {
+                let operands = (0..2)
+                    .map(|_| {
+                        let operand_type = self.data_type();
+                        self.expr(&operand_type, row_type, scope, 0)
+                    })
+                    .collect();
+                Expr::Nary(NaryOp::Concat, operands)
+            }
+            _ => self.literal(data_type),
+        }
+    }
+
+    /// Generates a subquery, correlated or not. If a type is given, the subquery returns
+    /// a single row with a single column of that type.
+    fn subquery(
+        &mut self,
+        row_type: &[DataType],
+        scope: &[Vec<DataType>],
+        depth: usize,
+        scalar_type: Option<&DataType>,
+    ) -> SubquerySpec {
+        let (parameters, inner_scope) = if self.rng.chance(70) && !row_type.is_empty() {
+            let parameters = (0..1 + self.rng.below(2))
+                .map(|_| Expr::Column(self.rng.below(row_type.len())))
+                .collect_vec();
+            let inner_scope = self.inner_scope(scope, &parameters, row_type);
+            (Some(parameters), inner_scope)
+        } else {
+            (None, scope.to_vec())
+        };
+        let mut plan = self.plan(depth, &inner_scope);
+        match scalar_type {
+            Some(scalar_type) => {
+                // A global aggregation over a single column returns exactly one row
+                let plan_row_type = plan.row_type();
+                let output = self.expr(scalar_type, &plan_row_type, &inner_scope, 0);
+                plan = Plan::Aggregate {
+                    input: Box::new(Plan::Project {
+                        input: Box::new(plan),
+                        outputs: vec![output],
+                    }),
+                    group_key: BTreeSet::new(),
+                    aggregates: vec![AggregateExpr {
+                        op: self.rng.choose(&[AggregateOp::Min, AggregateOp::Max]),
+                        operands: vec![0],
+                    }],
+                };
+            }
+            None => {
+                let plan_row_type = plan.row_type();
+                if plan_row_type.len() > 1 || self.rng.chance(30) {
+                    let data_type = self.data_type();
+                    plan = Plan::Project {
+                        input: Box::new(plan),
+                        outputs: vec![self.expr(&data_type, &plan_row_type, &inner_scope, 0)],
+                    };
+                }
+            }
+        }
+        SubquerySpec {
+            parameters,
+            plan: Box::new(plan),
+        }
+    }
+}
+
+/// Returns the simplifications of the given plan that can be obtained in a single step,
+/// preserving its row type.
+fn shrink_plan(plan: &Plan) -> Vec<Plan> {
+    let row_type = plan.row_type();
+    let mut candidates = Vec::new();
+    // Replace the node with one of its inputs with the same row type
+    let inputs: Vec<&Plan> = match plan {
+        Plan::Scan { .. } => vec![],
+        Plan::Filter { input, .. }
+        | Plan::Project { input, .. }
+        | Plan::Aggregate { input, .. } => vec![input],
+        Plan::Join { left, right, .. } | Plan::Apply { left, right, .. } => vec![left, right],
+        Plan::Union { inputs } => inputs.iter().collect(),
+    };
+    candidates.extend(
+        inputs
+            .into_iter()
+            .filter(|input| input.row_type() == row_type)
+            .cloned(),
+    );
+    match plan {
+        Plan::Scan { .. } => {}
+        Plan::Filter { input, conditions } => {
+            candidates.extend(shrink_vec(conditions, shrink_expr, 1).into_iter().map(
+                |conditions| Plan::Filter {
+                    input: input.clone(),
+                    conditions,
+                },
+            ));
+            candidates.extend(shrink_plan(input).into_iter().map(|input| Plan::Filter {
+                input: Box::new(input),
+                conditions: conditions.clone(),
+            }));
+        }
+        Plan::Project { input, outputs } => {
+            candidates.extend(
+                shrink_vec(outputs, shrink_expr, usize::MAX)
+                    .into_iter()
+                    .map(|outputs| Plan::Project {
+                        input: input.clone(),
+                        outputs,
+                    }),
+            );
+            candidates.extend(shrink_plan(input).into_iter().map(|input| Plan::Project {
+                input: Box::new(input),
+                outputs: outputs.clone(),
+            }));
+        }
+        Plan::Join {
+            join_type,
+            left,
+            right,
+            conditions,
+        } => {
+            let join = |left: &Plan, right: &Plan, conditions: Vec<Expr>| Plan::Join {
+                join_type: *join_type,
+                left: Box::new(left.clone()),
+                right: Box::new(right.clone()),
+                conditions,
+            };
+            candidates.extend(
+                shrink_vec(conditions, shrink_expr, 0)
+                    .into_iter()
+                    .map(|conditions| join(left, right, conditions)),
+            );
+            candidates.extend(
+                shrink_plan(left)
+                    .iter()
+                    .map(|left| join(left, right, conditions.clone())),
+            );
+            candidates.extend(
+                shrink_plan(right)
+                    .iter()
+                    .map(|right| join(left, right, conditions.clone())),
+            );
+        }
+        Plan::Aggregate {
+            input,
+            group_key,
+            aggregates,
+        } => {
+            candidates.extend(shrink_plan(input).into_iter().map(|input| Plan::Aggregate {
+                input: Box::new(input),
+                group_key: group_key.clone(),
+                aggregates: aggregates.clone(),
+            }));
+        }
+        Plan::Union { inputs } => {
+            for (i, input) in inputs.iter().enumerate() {
+                if inputs.len() > 2 {
+                    let mut new_inputs = inputs.clone();
+                    new_inputs.remove(i);
+                    candidates.push(Plan::Union { inputs: new_inputs });
+                }
+                for new_input in shrink_plan(input) {
+                    let mut new_inputs = inputs.clone();
+                    new_inputs[i] = new_input;
+                    candidates.push(Plan::Union { inputs: new_inputs });
+                }
+            }
+        }
+        Plan::Apply {
+            apply_type,
+            parameters,
+            left,
+            right,
+        } => {
+            let apply = |left: &Plan, right: &Plan| Plan::Apply {
+                apply_type: *apply_type,
+                parameters: parameters.clone(),
+                left: Box::new(left.clone()),
+                right: Box::new(right.clone()),
+            };
+            candidates.extend(shrink_plan(left).iter().map(|left| apply(left, right)));
+            candidates.extend(shrink_plan(right).iter().map(|right| apply(left, right)));
+        }
+    }
+    candidates
+}
+
+/// Returns the simplifications of the given vector obtained by either removing one of
+/// its elements, keeping at least `min_len` of them, or by shrinking one of them.
+/// Elements are not removed if `min_len` is `usize::MAX`.
+fn shrink_vec<T: Clone>(items: &[T], shrink: fn(&T) -> Vec<T>, min_len: usize) -> Vec<Vec<T>> {
+    let mut candidates = Vec::new();
+    for i in 0..items.len() {
+        if items.len() > min_len {
+            let mut new_items = items.to_vec();
+            new_items.remove(i);
+            candidates.push(new_items);
+        }
+        for new_item in shrink(&items[i]) {
+            let mut new_items = items.to_vec();
+            new_items[i] = new_item;
+            candidates.push(new_items);
+        }
+    }
+    candidates
+}
+
+fn shrink_subquery(subquery: &SubquerySpec) -> Vec<SubquerySpec> {
+    shrink_plan(&subquery.plan)
+        .into_iter()
+        .map(|plan| SubquerySpec {
+            parameters: subquery.parameters.clone(),
+            plan: Box::new(plan),
+        })
+        .collect()
+}
+
+/// Returns the simplifications of the given expression that preserve its type.
+fn shrink_expr(expr: &Expr) -> Vec<Expr> {
+    let null_literal = |data_type: DataType| Expr::Literal(Literal::new(Value::Null, data_type));
+    match expr {
+        Expr::Column(_) | Expr::Literal(_) | Expr::Correlated { .. } => Vec::new(),
+        Expr::Compare(op, left, right) => {
+            let mut candidates = vec![null_literal(DataType::Bool)];
+            candidates.extend(
+                shrink_expr(left)
+                    .into_iter()
+                    .map(|left| Expr::Compare(op.clone(), Box::new(left), right.clone())),
+            );
+            candidates.extend(
+                shrink_expr(right)
+                    .into_iter()
+                    .map(|right| Expr::Compare(op.clone(), left.clone(), Box::new(right))),
+            );
+            candidates
+        }
+        Expr::Nary(op, operands) => {
+            let mut candidates = Vec::new();
+            if *op != NaryOp::Concat {
+                candidates.extend(operands.iter().cloned());
+            }
+            candidates.extend(
+                shrink_vec(operands, shrink_expr, usize::MAX)
+                    .into_iter()
+                    .map(|operands| Expr::Nary(op.clone(), operands)),
+            );
+            candidates
+        }
+        Expr::Exists(subquery) => std::iter::once(null_literal(DataType::Bool))
+            .chain(shrink_subquery(subquery).into_iter().map(Expr::Exists))
+            .collect(),
+        Expr::Scalar(subquery) => {
+            std::iter::once(null_literal(subquery.plan.row_type()[0].clone()))
+                .chain(shrink_subquery(subquery).into_iter().map(Expr::Scalar))
+                .collect()
+        }
+        Expr::SubqueryCmp(op, operand, subquery) => std::iter::once(null_literal(DataType::Bool))
+            .chain(
+                shrink_subquery(subquery)
+                    .into_iter()
+                    .map(|subquery| Expr::SubqueryCmp(op.clone(), operand.clone(), subquery)),
+            )
+            .collect(),
+    }
+}
+
+#[derive(PartialEq, Eq)]
+enum FailureKind {
+    /// The optimizer or the lowering panicked, including failed `check_detached_nodes`
+    /// assertions.
+    Panic,
+    /// Some rule led to an invalid query graph.
+    InvalidQueryGraph,
+    /// The optimizer didn't reach a fix-point, either because it detected a cycle or
+    /// because it gave up after `MAX_RULE_APPLICATIONS`.
+    NonTermination,
+}
+
+struct Failure {
+    kind: FailureKind,
+    message: String,
+}
+
+thread_local! {
+    static SILENCE_PANICS: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Installs a panic hook that doesn't report the panics caught by `check_plan`, while
+/// still reporting the ones from other tests running concurrently.
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !SILENCE_PANICS.with(|silence| silence.get()) {
+                default_hook(info);
+            }
+        }));
+    });
+}
+
+/// Optimizes the plan and lowers it into a physical plan, both directly and through
+/// the memo, returning the failure found if any. Returns None as well if the plan is
+/// not valid, which may happen with shrunk plans.
+fn check_plan(plan: &Plan) -> Option<Failure> {
+    let mut query_graph = plan.to_query_graph();
+    if validate(&query_graph).is_err() {
+        return None;
+    }
+    install_panic_hook();
+    SILENCE_PANICS.with(|silence| silence.set(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut opt_context = OptimizerContext::new();
+        opt_context.set_validate_after_each_rule(true);
+        opt_context.set_max_rule_applications(MAX_RULE_APPLICATIONS);
+        DEFAULT_OPTIMIZER
+            .optimize(&mut opt_context, &mut query_graph)
+            .map(|()| lower(&query_graph, &DefaultCostModel::new()))?;
+        DEFAULT_MEMO_OPTIMIZER.optimize(
+            &mut opt_context,
+            &mut query_graph,
+            &DefaultCostModel::new(),
+        )
+    }));
+    SILENCE_PANICS.with(|silence| silence.set(false));
+    match result {
+        Ok(Ok(_)) => None,
+        Ok(Err(err @ OptimizerError::InvalidQueryGraph { .. })) => Some(Failure {
+            kind: FailureKind::InvalidQueryGraph,
+            message: err.to_string(),
+        }),
+        Ok(Err(err)) => Some(Failure {
+            kind: FailureKind::NonTermination,
+            message: err.to_string(),
+        }),
+        Err(payload) => Some(Failure {
+            kind: FailureKind::Panic,
+            message: payload
+                .downcast_ref::<String>()
+                .cloned()
+                .or_else(|| payload.downcast_ref::<&str>().map(|s| s.to_string()))
+                .unwrap_or_default(),
+        }),
+    }
+}
+
+/// Greedily applies the simplifications that preserve the kind of failure, until
+/// none of them does.
+fn shrink(mut plan: Plan, mut failure: Failure) -> (Plan, Failure) {
+    'outer: loop {
+        for candidate in shrink_plan(&plan)
+            .into_iter()
+            .sorted_by_key(|candidate| candidate.size())
+        {
+            if let Some(candidate_failure) = check_plan(&candidate) {
+                if candidate_failure.kind == failure.kind {
+                    plan = candidate;
+                    failure = candidate_failure;
+                    continue 'outer;
+                }
+            }
+        }
+        return (plan, failure);
+    }
+}
+
+#[test]
+fn test_generated_plans_are_valid() {
+    for seed in 0..200 {
+        let plan = Generator::new(seed).plan(4, &[]);
+        let query_graph = plan.to_query_graph();
+        if let Err(errors) = validate(&query_graph) {
+            panic!(
+                "seed {}: {}\n{}",
+                seed,
+                errors.iter().join("\n"),
+                query_graph.explain()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_fuzz_optimizer() {
+    let mut failures = Vec::new();
+    for seed in 0..500 {
+        let plan = Generator::new(seed).plan(4, &[]);
+        if let Some(failure) = check_plan(&plan) {
+            let (plan, failure) = shrink(plan, failure);
+            failures.push(format!(
+                "seed {}: {}\nMinimal plan:\n{}",
+                seed,
+                failure.message,
+                plan.to_query_graph().explain()
+            ));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n\n"));
+}
@@ -0,0 +1,161 @@
+use std::rc::Rc;
+
+use datadriven::walk;
+use rust_sql::catalog::{Catalog, ColumnMetadata, ColumnStatistics, Histogram, TableStatistics};
+use rust_sql::data_type::DataType;
+use rust_sql::physical_plan::{lower, DefaultCostModel};
+use rust_sql::query_graph::optimizer::memo::DEFAULT_MEMO_OPTIMIZER;
+use rust_sql::query_graph::optimizer::{OptimizerContext, DEFAULT_OPTIMIZER};
+use rust_sql::query_graph::validator::validate;
+use rust_sql::query_graph::QueryGraph;
+use rust_sql::scalar_expr::{BinaryOp, ScalarExpr};
+use rust_sql::sql::build_query_graph;
+use rust_sql::value::{Literal, Value};
+
+fn test_catalog() -> Rc<Catalog> {
+    let mut catalog = Catalog::new();
+    let t1 = catalog.add_table(
+        "t1",
+        vec![
+            ColumnMetadata::new("a", DataType::Int, false),
+            ColumnMetadata::new("b", DataType::String, true),
+            ColumnMetadata::new("c", DataType::Int, true),
+        ],
+    );
+    catalog.set_primary_key(t1, vec![0]);
+    catalog.set_table_statistics(
+        t1,
+        TableStatistics {
+            row_count: 1000,
+            column_statistics: vec![
+                ColumnStatistics::new(1000, 0.0).with_range(Value::Int(1), Value::Int(1000)),
+                ColumnStatistics::new(100, 0.1).with_most_common_values(vec![
+                    (Value::String("hello".to_string()), 0.3),
+                    (Value::String("world".to_string()), 0.1),
+                ]),
+                ColumnStatistics::new(50, 0.2).with_range(Value::Int(0), Value::Int(100)),
+            ],
+        },
+    );
+    let t2 = catalog.add_table(
+        "t2",
+        vec![
+            ColumnMetadata::new("a", DataType::Int, false),
+            ColumnMetadata::new("b", DataType::String, false),
+            ColumnMetadata::new("c", DataType::Int, false),
+        ],
+    );
+    catalog.set_primary_key(t2, vec![0]);
+    catalog.add_unique_key(t2, vec![1]);
+    catalog.add_foreign_key(t2, vec![2], t1, vec![0]);
+    catalog.set_table_statistics(
+        t2,
+        TableStatistics {
+            row_count: 100,
+            column_statistics: vec![
+                ColumnStatistics::new(100, 0.0).with_range(Value::Int(1), Value::Int(100)),
+                ColumnStatistics::new(100, 0.0),
+                ColumnStatistics::new(80, 0.0)
+                    .with_range(Value::Int(1), Value::Int(1000))
+                    .with_histogram(Histogram::new(vec![
+                        Value::Int(1),
+                        Value::Int(10),
+                        Value::Int(20),
+                        Value::Int(100),
+                        Value::Int(1000),
+                    ])),
+            ],
+        },
+    );
+    let t3 = catalog.add_table(
+        "t3",
+        vec![
+            ColumnMetadata::new("d", DataType::Int, true),
+            ColumnMetadata::new("e", DataType::String, true),
+        ],
+    );
+    catalog.add_unique_key(t3, vec![1]);
+    catalog.add_check_constraint(
+        t3,
+        ScalarExpr::input_ref(0)
+            .binary(
+                BinaryOp::Gt,
+                ScalarExpr::Literal(Literal::new(Value::Int(0), DataType::Int)).into(),
+            )
+            .into(),
+    );
+    // Table known to contain exactly one row
+    let t4 = catalog.add_table(
+        "t4",
+        vec![
+            ColumnMetadata::new("f", DataType::Int, true),
+            ColumnMetadata::new("g", DataType::Int, false),
+        ],
+    );
+    catalog.set_row_count_bounds(t4, 1, Some(1));
+    catalog.add_check_constraint(
+        t4,
+        ScalarExpr::input_ref(1)
+            .binary(
+                BinaryOp::Eq,
+                ScalarExpr::Literal(Literal::new(Value::Int(1), DataType::Int)).into(),
+            )
+            .into(),
+    );
+    Rc::new(catalog)
+}
+
+#[test]
+fn test_sql() {
+    let catalog = test_catalog();
+    walk("tests/testdata/sql", |f| {
+        f.run(|test_case| -> String {
+            println!("{}", test_case.input);
+            let mut query_graph = match build_query_graph(&catalog, &test_case.input) {
+                Ok(query_graph) => query_graph,
+                Err(err) => return format!("{}\n", err),
+            };
+            validate(&query_graph).unwrap();
+            let explain = query_graph.fully_annotated_explain();
+            let optimize = |query_graph: &mut QueryGraph| {
+                let mut opt_context = OptimizerContext::new();
+                opt_context.set_validate_after_each_rule(true);
+                DEFAULT_OPTIMIZER
+                    .optimize(&mut opt_context, query_graph)
+                    .unwrap();
+            };
+            match test_case.directive.as_str() {
+                "optimize" => {
+                    optimize(&mut query_graph);
+                    format!(
+                        "{}\n\nOptimized:\n{}\n",
+                        explain,
+                        query_graph.fully_annotated_explain()
+                    )
+                }
+                "physical" => {
+                    optimize(&mut query_graph);
+                    let plan = lower(&query_graph, &DefaultCostModel::new());
+                    format!(
+                        "{}\n\nPhysical plan:\n{}\n",
+                        query_graph.explain(),
+                        plan.fully_annotated_explain()
+                    )
+                }
+                "memo" => {
+                    let mut opt_context = OptimizerContext::new();
+                    opt_context.set_validate_after_each_rule(true);
+                    let plan = DEFAULT_MEMO_OPTIMIZER
+                        .optimize(&mut opt_context, &mut query_graph, &DefaultCostModel::new())
+                        .unwrap();
+                    format!(
+                        "{}\n\nPhysical plan:\n{}\n",
+                        query_graph.explain(),
+                        plan.fully_annotated_explain()
+                    )
+                }
+                _ => format!("{}\n", explain),
+            }
+        })
+    });
+}